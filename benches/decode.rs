@@ -0,0 +1,37 @@
+//! Decoding a large JPEG at full size vs at hashing size.
+//!
+//! Run with `cargo bench --bench decode`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use duplicate_photo_cleaner::core::hasher::{DecodeOptions, FastDecoder, HASH_DECODE_SIZE};
+use image::RgbImage;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Write a 24 megapixel JPEG, about the size of a modern camera photo
+fn write_large_jpeg(dir: &Path) -> std::path::PathBuf {
+    let image = RgbImage::from_fn(6000, 4000, |x, y| {
+        image::Rgb([(x % 256) as u8, (y % 256) as u8, ((x ^ y) % 256) as u8])
+    });
+    let path = dir.join("large.jpg");
+    image.save(&path).unwrap();
+    path
+}
+
+fn bench_decode(c: &mut Criterion) {
+    let dir = TempDir::new().unwrap();
+    let path = write_large_jpeg(dir.path());
+
+    let mut group = c.benchmark_group("decode_24mp_jpeg");
+    group.sample_size(10);
+    group.bench_function("full_size", |b| {
+        b.iter(|| FastDecoder::decode_with(&path, &DecodeOptions::upright()).unwrap())
+    });
+    group.bench_function("for_hash", |b| {
+        b.iter(|| FastDecoder::decode_for_hash(&path, HASH_DECODE_SIZE).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_decode);
+criterion_main!(benches);
@@ -1,8 +1,9 @@
 //! Tauri commands for the duplicate photo finder.
 
+use duplicate_photo_cleaner::core::analysis::PhotoDetails;
 use duplicate_photo_cleaner::core::cache::{CacheBackend, SqliteCache};
 use duplicate_photo_cleaner::core::comparator::DuplicateGroup;
-use duplicate_photo_cleaner::core::hasher::HashAlgorithmKind;
+use duplicate_photo_cleaner::core::hasher::{HashAlgorithmKind, DEFAULT_HASH_SIZE};
 use duplicate_photo_cleaner::core::history::{
     HistoryRepository, ModuleType as HistoryModuleType, ScanHistoryEntry, ScanHistoryResult,
     ScanStatus,
@@ -12,7 +13,10 @@ use duplicate_photo_cleaner::core::organize::{
     OperationMode, OrganizeConfig, OrganizeExecutor, OrganizePlan, OrganizePlanner, OrganizeResult,
 };
 use duplicate_photo_cleaner::core::pipeline::{CancellationToken, Pipeline, PipelineResult};
-use duplicate_photo_cleaner::core::reporter::{export_csv, export_html};
+use duplicate_photo_cleaner::core::reporter::{export_csv, export_html, DetailedReporter};
+use duplicate_photo_cleaner::core::scanner::{
+    rename_to_content_extension, FormatMismatch, ScanLimits,
+};
 use duplicate_photo_cleaner::core::similar::{SimilarConfig, SimilarResult, SimilarScanner};
 use duplicate_photo_cleaner::core::unorganized::{
     UnorganizedConfig, UnorganizedResult, UnorganizedScanner,
@@ -22,7 +26,7 @@ use duplicate_photo_cleaner::core::watcher::{
 };
 use duplicate_photo_cleaner::events::{Event, EventSender, PipelineEvent, WatcherEvent};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Emitter, Manager, State};
@@ -62,6 +66,23 @@ pub struct ScanConfig {
     pub paths: Vec<String>,
     pub threshold: u32,
     pub algorithm: Option<String>,
+    #[serde(default)]
+    pub match_rotations: bool,
+    /// Hash size: 8 (default), 16 or 32
+    #[serde(default)]
+    pub hash_size: Option<u32>,
+    /// Gitignore-style patterns for files and directories to skip
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Gitignore-style patterns a photo must match to be scanned
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Detect formats from file contents instead of extensions
+    #[serde(default)]
+    pub sniff_content: bool,
+    /// Size, date and dimension limits a photo must be within
+    #[serde(default)]
+    pub limits: ScanLimits,
 }
 
 /// Duplicate group for frontend
@@ -71,12 +92,21 @@ pub struct DuplicateGroupDto {
     pub photos: Vec<String>,
     pub representative: String,
     pub match_type: String,
+    pub confidence: Option<String>,
+    pub max_distance: Option<u32>,
+    pub transform: Option<String>,
+    pub crop: Option<String>,
     pub duplicate_count: usize,
     pub duplicate_size_bytes: u64,
+    /// One-line summary of why these are duplicates
+    pub summary: String,
+    /// Longer explanation of why these are duplicates
+    pub explanation: String,
 }
 
-impl From<&DuplicateGroup> for DuplicateGroupDto {
-    fn from(group: &DuplicateGroup) -> Self {
+impl DuplicateGroupDto {
+    fn new(group: &DuplicateGroup, reporter: &DetailedReporter) -> Self {
+        let explanation = reporter.explain_group(group);
         Self {
             id: group.id.to_string(),
             photos: group
@@ -86,12 +116,28 @@ impl From<&DuplicateGroup> for DuplicateGroupDto {
                 .collect(),
             representative: group.representative.display().to_string(),
             match_type: format!("{:?}", group.match_type),
+            confidence: group.confidence.map(|c| format!("{:?}", c)),
+            max_distance: group.max_distance,
+            transform: group.transform.map(|t| t.to_string()),
+            crop: group.crop.as_ref().map(|c| c.to_string()),
             duplicate_count: group.duplicate_count(),
             duplicate_size_bytes: group.duplicate_size_bytes,
+            summary: explanation.summary,
+            explanation: explanation.human_readable,
         }
     }
 }
 
+/// Convert the groups of a scan for the frontend, explaining each
+fn group_dtos(result: &PipelineResult) -> Vec<DuplicateGroupDto> {
+    let reporter = DetailedReporter::new(result.algorithm).hash_size(result.hash_size);
+    result
+        .groups
+        .iter()
+        .map(|group| DuplicateGroupDto::new(group, &reporter))
+        .collect()
+}
+
 /// Scan results for frontend
 #[derive(Debug, Serialize)]
 pub struct ScanResultDto {
@@ -102,6 +148,7 @@ pub struct ScanResultDto {
     pub duration_ms: u64,
     pub groups: Vec<DuplicateGroupDto>,
     pub errors: Vec<String>,
+    pub format_mismatches: Vec<FormatMismatch>,
 }
 
 /// Start a scan
@@ -129,6 +176,9 @@ pub async fn start_scan(
         Some("average") => HashAlgorithmKind::Average,
         Some("perceptual") => HashAlgorithmKind::Perceptual,
         Some("fusion") => HashAlgorithmKind::Fusion,
+        Some("wavelet") => HashAlgorithmKind::Wavelet,
+        Some("block-mean") => HashAlgorithmKind::BlockMean,
+        Some("crop") => HashAlgorithmKind::CropResistant,
         _ => HashAlgorithmKind::Difference,
     };
 
@@ -139,6 +189,12 @@ pub async fn start_scan(
         .paths(paths)
         .algorithm(algorithm)
         .threshold(config.threshold)
+        .hash_size(config.hash_size.unwrap_or(DEFAULT_HASH_SIZE))
+        .match_rotations(config.match_rotations)
+        .exclude(config.exclude)
+        .include(config.include)
+        .sniff_content(config.sniff_content)
+        .limits(config.limits)
         .build();
 
     // Create event sender that emits to frontend
@@ -199,8 +255,9 @@ pub async fn start_scan(
         duplicate_count: result.groups.iter().map(|g| g.duplicate_count()).sum(),
         potential_savings_bytes: result.groups.iter().map(|g| g.duplicate_size_bytes).sum(),
         duration_ms: result.duration_ms,
-        groups: result.groups.iter().map(DuplicateGroupDto::from).collect(),
+        groups: group_dtos(&result),
         errors: result.errors.clone(),
+        format_mismatches: result.format_mismatches.clone(),
     };
 
     Ok(dto)
@@ -229,8 +286,9 @@ pub fn get_results(state: State<'_, AppState>) -> Result<Option<ScanResultDto>,
         duplicate_count: result.groups.iter().map(|g| g.duplicate_count()).sum(),
         potential_savings_bytes: result.groups.iter().map(|g| g.duplicate_size_bytes).sum(),
         duration_ms: result.duration_ms,
-        groups: result.groups.iter().map(DuplicateGroupDto::from).collect(),
+        groups: group_dtos(result),
         errors: result.errors.clone(),
+        format_mismatches: result.format_mismatches.clone(),
     }))
 }
 
@@ -248,6 +306,47 @@ pub struct TrashResult {
     pub errors: Vec<String>,
 }
 
+/// Result of renaming mislabeled files
+#[derive(Debug, Serialize)]
+pub struct RenameResult {
+    /// Old and new path of each renamed file
+    pub renamed: Vec<(String, String)>,
+    pub errors: Vec<String>,
+}
+
+/// Rename the mislabeled files of the last scan to the extension of their
+/// real format, updating the stored results to the new paths
+#[tauri::command]
+pub fn fix_extensions(state: State<'_, AppState>) -> Result<RenameResult, String> {
+    let mut results = state.results.lock().map_err(|e| e.to_string())?;
+    let Some(result) = results.as_mut() else {
+        return Ok(RenameResult {
+            renamed: Vec::new(),
+            errors: Vec::new(),
+        });
+    };
+
+    let mut renamed = Vec::new();
+    let mut errors = Vec::new();
+    for mismatch in std::mem::take(&mut result.format_mismatches) {
+        match rename_to_content_extension(&mismatch) {
+            Ok(path) => {
+                result.rename_photo(&mismatch.path, &path);
+                renamed.push((
+                    mismatch.path.display().to_string(),
+                    path.display().to_string(),
+                ));
+            }
+            Err(e) => {
+                errors.push(format!("{}: {}", mismatch.path.display(), e));
+                result.format_mismatches.push(mismatch);
+            }
+        }
+    }
+
+    Ok(RenameResult { renamed, errors })
+}
+
 /// File information for comparison view
 #[derive(Debug, Serialize)]
 pub struct FileInfo {
@@ -260,7 +359,7 @@ pub struct FileInfo {
 
 /// Get file information for comparison
 #[tauri::command]
-pub async fn get_file_info(path: String) -> Result<FileInfo, String> {
+pub async fn get_file_info(path: String, state: State<'_, AppState>) -> Result<FileInfo, String> {
     let path_buf = PathBuf::from(&path);
 
     if !path_buf.exists() {
@@ -280,8 +379,16 @@ pub async fn get_file_info(path: String) -> Result<FileInfo, String> {
         datetime.format("%Y-%m-%d %H:%M").to_string()
     });
 
-    // Try to get image dimensions
-    let dimensions = image::image_dimensions(&path_buf).ok();
+    // Use the dimensions from the last scan when it analyzed this photo.
+    // Otherwise read them; the image crate can't read HEIC, so fall back to
+    // the metadata reader
+    let scanned = cached_details(&state, &path_buf)?.map(|details| (details.width, details.height));
+    let dimensions = scanned
+        .or_else(|| image::image_dimensions(&path_buf).ok())
+        .or_else(|| {
+            let metadata = duplicate_photo_cleaner::core::metadata::extract_metadata(&path_buf);
+            metadata.width.zip(metadata.height)
+        });
 
     Ok(FileInfo {
         path,
@@ -435,9 +542,26 @@ pub struct QualityScoreDto {
     pub overall: f64,
 }
 
+/// Details of a photo from the last scan, if it analyzed it
+fn cached_details(
+    state: &State<'_, AppState>,
+    path: &Path,
+) -> Result<Option<PhotoDetails>, String> {
+    let results = state.results.lock().map_err(|e| e.to_string())?;
+    Ok(results
+        .as_ref()
+        .and_then(|result| result.details.get(path).cloned()))
+}
+
 /// Get quality score for an image (sharpness, contrast, etc.)
+///
+/// Photos in the last scan's results were analyzed while hashing, so only
+/// other photos are decoded here.
 #[tauri::command]
-pub async fn get_quality_score(path: String) -> Result<QualityScoreDto, String> {
+pub async fn get_quality_score(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<QualityScoreDto, String> {
     use duplicate_photo_cleaner::core::quality::QualityAnalyzer;
 
     let path_buf = PathBuf::from(&path);
@@ -446,10 +570,12 @@ pub async fn get_quality_score(path: String) -> Result<QualityScoreDto, String>
         return Err(format!("File not found: {}", path));
     }
 
-    let analyzer = QualityAnalyzer::default();
-    let score = analyzer
-        .analyze_file(&path_buf)
-        .map_err(|e| format!("Failed to analyze: {:?}", e))?;
+    let score = match cached_details(&state, &path_buf)? {
+        Some(details) => details.quality,
+        None => QualityAnalyzer::default()
+            .analyze_file(&path_buf)
+            .map_err(|e| format!("Failed to analyze: {:?}", e))?,
+    };
 
     Ok(QualityScoreDto {
         path,
@@ -858,6 +984,9 @@ pub async fn scan_screenshots(
         Some("average") => HashAlgorithmKind::Average,
         Some("perceptual") => HashAlgorithmKind::Perceptual,
         Some("fusion") => HashAlgorithmKind::Fusion,
+        Some("wavelet") => HashAlgorithmKind::Wavelet,
+        Some("block-mean") => HashAlgorithmKind::BlockMean,
+        Some("crop") => HashAlgorithmKind::CropResistant,
         _ => HashAlgorithmKind::Difference,
     };
 
@@ -865,8 +994,13 @@ pub async fn scan_screenshots(
     let paths: Vec<PathBuf> = config.paths.iter().map(PathBuf::from).collect();
 
     // Scan for all photos first
-    let scanner =
-        WalkDirScanner::new(duplicate_photo_cleaner::core::scanner::ScanConfig::default());
+    let scanner = WalkDirScanner::new(duplicate_photo_cleaner::core::scanner::ScanConfig {
+        exclude: config.exclude.clone(),
+        include: config.include.clone(),
+        sniff_content: config.sniff_content,
+        limits: config.limits,
+        ..Default::default()
+    });
     let scan_result = match scanner.scan(&paths) {
         Ok(result) => result,
         Err(e) => {
@@ -954,8 +1088,7 @@ pub async fn scan_screenshots(
             .await
             {
                 Ok(Ok(result)) => {
-                    let groups: Vec<DuplicateGroupDto> =
-                        result.groups.iter().map(DuplicateGroupDto::from).collect();
+                    let groups = group_dtos(&result);
                     let dup_count: usize = groups.iter().map(|g| g.duplicate_count).sum();
                     let savings: u64 = groups.iter().map(|g| g.duplicate_size_bytes).sum();
                     (groups, dup_count, savings)
@@ -1006,6 +1139,7 @@ pub async fn scan_large_files(
     paths: Vec<String>,
     min_size_mb: Option<u64>,
     max_results: Option<usize>,
+    limits: Option<ScanLimits>,
     state: State<'_, AppState>,
 ) -> Result<LargeFileScanResult, String> {
     // Check if already scanning
@@ -1029,7 +1163,8 @@ pub async fn scan_large_files(
     let max_results = max_results.unwrap_or(50);
 
     // Create scanner with optional overrides
-    let scanner = LargeFileScanner::new(min_size_mb, max_results);
+    let scanner =
+        LargeFileScanner::new(min_size_mb, max_results).with_limits(limits.unwrap_or_default());
 
     // Emit initial progress
     let _ = app.emit(
@@ -15,6 +15,7 @@ pub fn run() {
             commands::get_results,
             commands::is_scanning,
             commands::trash_files,
+            commands::fix_extensions,
             commands::get_file_info,
             commands::get_quality_score,
             commands::restore_from_trash,
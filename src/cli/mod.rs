@@ -10,6 +10,21 @@
 //! # With custom threshold
 //! photo-dedup scan ~/Photos --threshold 5
 //!
+//! # Also find rotated and mirrored copies
+//! photo-dedup scan ~/Photos --rotations
+//!
+//! # Keep burst shots from chaining into one big group
+//! photo-dedup scan ~/Photos --grouping complete-linkage --max-diameter 6
+//!
+//! # Quick preview of a huge library from EXIF thumbnails
+//! photo-dedup scan ~/Photos --scan-mode preview
+//!
+//! # Only photos over 500KB modified since 2020, ignoring icons
+//! photo-dedup scan ~/Photos --min-size 500KB --modified-after 2020-01-01 --min-dimension 200
+//!
+//! # Find photos by their contents and fix wrong extensions
+//! photo-dedup scan ~/Downloads --fix-extensions
+//!
 //! # Verbose output
 //! photo-dedup scan ~/Photos --verbose
 //!
@@ -18,8 +33,16 @@
 //! ```
 
 use duplicate_photo_cleaner::core::cache::SqliteCache;
+use duplicate_photo_cleaner::core::comparator::{GroupingStrategy, MatchType};
 use duplicate_photo_cleaner::core::hasher::HashAlgorithmKind;
-use duplicate_photo_cleaner::core::pipeline::{Pipeline, PipelineResult};
+use duplicate_photo_cleaner::core::metadata::extract_metadata;
+use duplicate_photo_cleaner::core::pipeline::{
+    Pipeline, PipelineBuilder, PipelineResult, ScanMode,
+};
+use duplicate_photo_cleaner::core::reporter::DetailedReporter;
+use duplicate_photo_cleaner::core::scanner::{
+    rename_to_content_extension, ImageFormat, ScanLimits,
+};
 use duplicate_photo_cleaner::error::Result;
 use duplicate_photo_cleaner::events::{Event, EventChannel, HashEvent, PipelineEvent, ScanEvent};
 use clap::{Parser, Subcommand, ValueEnum};
@@ -27,6 +50,7 @@ use console::{style, Term};
 use indicatif::{ProgressBar, ProgressStyle};
 use std::path::PathBuf;
 use std::thread;
+use std::time::SystemTime;
 
 /// Duplicate Photo Cleaner - Find duplicates without fear
 #[derive(Parser, Debug)]
@@ -45,14 +69,40 @@ enum Commands {
         #[arg(required = true)]
         paths: Vec<PathBuf>,
 
-        /// Comparison threshold (lower = stricter, 0-64)
+        /// Comparison threshold (lower = stricter, 0-64), in bits of a 64-bit
+        /// hash and scaled up for larger hash sizes
         #[arg(short, long, default_value = "8")]
         threshold: u32,
 
+        /// Hash size: 8, 16 or 32 (size² bits; the threshold scales with it)
+        #[arg(long, default_value = "8")]
+        hash_size: u32,
+
         /// Hash algorithm to use
         #[arg(short, long, default_value = "difference")]
         algorithm: Algorithm,
 
+        /// How matching photos are clustered into groups
+        #[arg(short, long, default_value = "transitive")]
+        grouping: Grouping,
+
+        /// Largest distance allowed between any two photos in a group
+        /// (implies complete-linkage grouping)
+        #[arg(long)]
+        max_diameter: Option<u32>,
+
+        /// Also match rotated and mirrored copies
+        #[arg(long)]
+        rotations: bool,
+
+        /// Extra hashes that vote in fusion (comma-separated)
+        #[arg(long, value_delimiter = ',')]
+        fusion_voters: Vec<Voter>,
+
+        /// Decode every photo, or hash EXIF thumbnails and only decode matches
+        #[arg(long, default_value = "full")]
+        scan_mode: Mode,
+
         /// Output format
         #[arg(short, long, default_value = "pretty")]
         output: OutputFormat,
@@ -61,6 +111,54 @@ enum Commands {
         #[arg(long)]
         include_hidden: bool,
 
+        /// Skip files and directories matching a gitignore-style pattern
+        /// (repeatable; .photodedupignore files are always honored)
+        #[arg(long)]
+        exclude: Vec<String>,
+
+        /// Only scan photos matching a gitignore-style pattern (repeatable)
+        #[arg(long)]
+        include: Vec<String>,
+
+        /// Skip photos smaller than this (bytes, or with KB, MB or GB)
+        #[arg(long, value_parser = parse_size)]
+        min_size: Option<u64>,
+
+        /// Skip photos larger than this (bytes, or with KB, MB or GB)
+        #[arg(long, value_parser = parse_size)]
+        max_size: Option<u64>,
+
+        /// Skip photos last modified before this date (YYYY-MM-DD)
+        #[arg(long, value_parser = parse_date)]
+        modified_after: Option<SystemTime>,
+
+        /// Skip photos last modified after the start of this date (YYYY-MM-DD)
+        #[arg(long, value_parser = parse_date)]
+        modified_before: Option<SystemTime>,
+
+        /// Skip photos narrower or shorter than this many pixels, such as icons
+        #[arg(long)]
+        min_dimension: Option<u32>,
+
+        /// Detect formats from file contents instead of extensions, and list
+        /// photos whose extension is wrong
+        #[arg(long)]
+        sniff: bool,
+
+        /// Rename photos whose extension is wrong to the extension of their
+        /// real format (implies --sniff)
+        #[arg(long)]
+        fix_extensions: bool,
+
+        /// Read directories on all cores (for huge trees on network mounts)
+        #[arg(long)]
+        parallel_scan: bool,
+
+        /// Only read changed directories and compare changed photos,
+        /// reusing the rest from the last incremental scan
+        #[arg(long)]
+        incremental: bool,
+
         /// Verbose output
         #[arg(short, long)]
         verbose: bool,
@@ -79,6 +177,14 @@ enum Algorithm {
     Difference,
     /// Perceptual Hash - Most robust to edits
     Perceptual,
+    /// Wavelet Hash - Follows coarse structure, ignores fine noise
+    Wavelet,
+    /// Block Mean Hash - Tolerates small shifts
+    BlockMean,
+    /// aHash + dHash + pHash (+ --fusion-voters) with majority voting
+    Fusion,
+    /// Window hashes that also find cropped copies
+    Crop,
 }
 
 impl From<Algorithm> for HashAlgorithmKind {
@@ -87,6 +193,65 @@ impl From<Algorithm> for HashAlgorithmKind {
             Algorithm::Average => HashAlgorithmKind::Average,
             Algorithm::Difference => HashAlgorithmKind::Difference,
             Algorithm::Perceptual => HashAlgorithmKind::Perceptual,
+            Algorithm::Wavelet => HashAlgorithmKind::Wavelet,
+            Algorithm::BlockMean => HashAlgorithmKind::BlockMean,
+            Algorithm::Fusion => HashAlgorithmKind::Fusion,
+            Algorithm::Crop => HashAlgorithmKind::CropResistant,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Voter {
+    /// Wavelet Hash
+    Wavelet,
+    /// Block Mean Hash
+    BlockMean,
+}
+
+impl From<Voter> for HashAlgorithmKind {
+    fn from(voter: Voter) -> Self {
+        match voter {
+            Voter::Wavelet => HashAlgorithmKind::Wavelet,
+            Voter::BlockMean => HashAlgorithmKind::BlockMean,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Grouping {
+    /// Chain matches together (A~B and B~C gives one group)
+    Transitive,
+    /// Every photo in a group must match every other
+    CompleteLinkage,
+    /// Group photos around the largest file they match
+    Star,
+}
+
+impl Grouping {
+    fn into_strategy(self, max_diameter: Option<u32>) -> GroupingStrategy {
+        match (self, max_diameter) {
+            (_, Some(diameter)) => GroupingStrategy::MaxDiameter(diameter),
+            (Grouping::Transitive, None) => GroupingStrategy::Transitive,
+            (Grouping::CompleteLinkage, None) => GroupingStrategy::CompleteLinkage,
+            (Grouping::Star, None) => GroupingStrategy::Star,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Mode {
+    /// Decode every photo (default)
+    Full,
+    /// Hash EXIF thumbnails, decode photos only to confirm matches
+    Preview,
+}
+
+impl From<Mode> for ScanMode {
+    fn from(mode: Mode) -> Self {
+        match mode {
+            Mode::Full => ScanMode::Full,
+            Mode::Preview => ScanMode::Preview,
         }
     }
 }
@@ -109,31 +274,63 @@ pub fn run() -> Result<()> {
         Commands::Scan {
             paths,
             threshold,
+            hash_size,
             algorithm,
+            grouping,
+            max_diameter,
+            rotations,
+            fusion_voters,
+            scan_mode,
             output,
             include_hidden,
+            exclude,
+            include,
+            min_size,
+            max_size,
+            modified_after,
+            modified_before,
+            min_dimension,
+            sniff,
+            fix_extensions,
+            parallel_scan,
+            incremental,
             verbose,
             cache,
-        } => run_scan(
-            paths,
-            threshold,
-            algorithm.into(),
-            output,
-            include_hidden,
-            verbose,
-            cache,
-        ),
+        } => {
+            let builder = Pipeline::builder()
+                .paths(paths)
+                .algorithm(algorithm.into())
+                .threshold(threshold)
+                .hash_size(hash_size)
+                .grouping(grouping.into_strategy(max_diameter))
+                .match_rotations(rotations)
+                .fusion_voters(fusion_voters.into_iter().map(Into::into).collect())
+                .scan_mode(scan_mode.into())
+                .include_hidden(include_hidden)
+                .exclude(exclude)
+                .include(include)
+                .limits(ScanLimits {
+                    min_size,
+                    max_size,
+                    modified_after,
+                    modified_before,
+                    min_dimension,
+                })
+                .sniff_content(sniff || fix_extensions)
+                .parallel_scan(parallel_scan)
+                .incremental(incremental);
+
+            run_scan(builder, output, verbose, cache, fix_extensions)
+        }
     }
 }
 
 fn run_scan(
-    paths: Vec<PathBuf>,
-    threshold: u32,
-    algorithm: HashAlgorithmKind,
+    builder: PipelineBuilder,
     output: OutputFormat,
-    include_hidden: bool,
     verbose: bool,
     cache_path: Option<PathBuf>,
+    fix_extensions: bool,
 ) -> Result<()> {
     let term = Term::stderr();
 
@@ -159,14 +356,7 @@ fn run_scan(
     let cache = SqliteCache::open(&cache_path)?;
 
     // Build pipeline
-    let builder = Pipeline::builder()
-        .paths(paths.clone())
-        .algorithm(algorithm)
-        .threshold(threshold)
-        .include_hidden(include_hidden)
-        .cache(Box::new(cache));
-
-    let pipeline = builder.build();
+    let pipeline = builder.cache(Box::new(cache)).build();
 
     // Set up event handling
     let (sender, receiver) = EventChannel::new();
@@ -197,9 +387,19 @@ fn run_scan(
                         pb.set_message(format!("{}", phase));
                     }
                 }
-                Event::Scan(ScanEvent::Completed { total_photos }) => {
+                Event::Scan(ScanEvent::Completed {
+                    total_photos,
+                    excluded_files,
+                    excluded_directories,
+                }) => {
                     if let Some(ref pb) = progress_clone {
                         pb.set_length(total_photos as u64);
+                        if verbose_clone && excluded_files + excluded_directories > 0 {
+                            pb.println(format!(
+                                "Excluded {} photos and {} directories",
+                                excluded_files, excluded_directories
+                            ));
+                        }
                     }
                 }
                 Event::Hash(HashEvent::Progress(p)) => {
@@ -238,9 +438,34 @@ fn run_scan(
         OutputFormat::Minimal => print_minimal_results(&result),
     }
 
+    if fix_extensions {
+        fix_mislabeled_files(&term, &result);
+    }
+
     Ok(())
 }
 
+/// Rename the mislabeled photos of a scan, reporting each on stderr
+fn fix_mislabeled_files(term: &Term, result: &PipelineResult) {
+    for mismatch in &result.format_mismatches {
+        match rename_to_content_extension(mismatch) {
+            Ok(renamed) => term.write_line(&format!(
+                "{} {} → {}",
+                style("Renamed").green(),
+                format_path(&mismatch.path),
+                format_path(&renamed)
+            )),
+            Err(e) => term.write_line(&format!(
+                "{} {}: {}",
+                style("Couldn't rename").red(),
+                format_path(&mismatch.path),
+                e
+            )),
+        }
+        .ok();
+    }
+}
+
 /// Formats results for pretty terminal output
 struct PrettyFormatter<'a> {
     term: &'a Term,
@@ -265,6 +490,12 @@ impl<'a> PrettyFormatter<'a> {
     fn print_summary(&self, result: &PipelineResult) {
         let duplicate_count: usize = result.groups.iter().map(|g| g.duplicate_count()).sum();
         let savings: u64 = result.groups.iter().map(|g| g.duplicate_size_bytes).sum();
+        let hardlinked: usize = result
+            .groups
+            .iter()
+            .filter(|g| g.match_type == MatchType::Hardlinked)
+            .map(|g| g.photos.len() - 1)
+            .sum();
 
         self.write(&format!(
             "  {} photos scanned in {:.1}s",
@@ -276,6 +507,12 @@ impl<'a> PrettyFormatter<'a> {
             style(result.groups.len()).cyan()
         ));
         self.write(&format!("  {} duplicate photos", style(duplicate_count).cyan()));
+        if hardlinked > 0 {
+            self.write(&format!(
+                "  {} hardlinks already deduplicated",
+                style(hardlinked).dim()
+            ));
+        }
         self.write(&format!(
             "  {} potential space savings",
             style(format_bytes(savings)).yellow()
@@ -284,10 +521,27 @@ impl<'a> PrettyFormatter<'a> {
         if result.cache_hits > 0 {
             self.write(&format!("  {} cache hits", style(result.cache_hits).dim()));
         }
+        if let Some(preview) = &result.preview {
+            self.write(&format!(
+                "  {} hashed from thumbnails only, {} decoded without one",
+                style(preview.thumbnails_only).dim(),
+                style(preview.no_thumbnail).dim()
+            ));
+            self.write(&format!(
+                "  {} decoded to confirm matches, {} thumbnail matches rejected",
+                style(preview.confirmed).dim(),
+                style(preview.rejected_pairs).dim()
+            ));
+        }
         self.write("");
     }
 
-    fn print_group(&self, index: usize, group: &duplicate_photo_cleaner::core::comparator::DuplicateGroup) {
+    fn print_group(
+        &self,
+        index: usize,
+        group: &duplicate_photo_cleaner::core::comparator::DuplicateGroup,
+        result: &PipelineResult,
+    ) {
         self.write(&format!(
             "  {} {} ({} photos, {})",
             style(format!("Group {}:", index + 1)).bold(),
@@ -296,6 +550,42 @@ impl<'a> PrettyFormatter<'a> {
             format_bytes(group.duplicate_size_bytes)
         ));
 
+        let explanation = DetailedReporter::new(result.algorithm)
+            .hash_size(result.hash_size)
+            .explain_group(group);
+        self.write(&format!("    {}", style(&explanation.summary).dim()));
+
+        if group.match_type == MatchType::Hardlinked {
+            self.write(&format!(
+                "    {}",
+                style("One file on disk; deleting a path frees no space").dim()
+            ));
+        }
+
+        if let Some(crop) = &group.crop {
+            self.write(&format!(
+                "    {} {}",
+                style("Cropped copy:").dim(),
+                style(crop).dim()
+            ));
+        }
+
+        if let Some(max_distance) = group.max_distance {
+            self.write(&format!(
+                "    {} {}",
+                style("Max distance:").dim(),
+                style(max_distance).dim()
+            ));
+        }
+
+        if let Some(confidence) = group.confidence {
+            self.write(&format!(
+                "    {} {}",
+                style("Confidence:").dim(),
+                style(confidence).dim()
+            ));
+        }
+
         for photo in &group.photos {
             let marker = if photo == &group.representative {
                 style("★").green().to_string()
@@ -305,7 +595,19 @@ impl<'a> PrettyFormatter<'a> {
             self.write(&format!("    {} {}", marker, format_path(photo)));
         }
 
-        if self.verbose && group.photos.len() > 1 {
+        if group.match_type == MatchType::SamePixelsDifferentMetadata {
+            self.print_metadata_differences(group, result);
+        }
+
+        if self.verbose {
+            self.write(&format!(
+                "    {} {}",
+                style("Why:").dim(),
+                style(&explanation.human_readable).dim()
+            ));
+        }
+
+        if self.verbose && group.duplicate_count() > 0 {
             self.write(&format!(
                 "    {} {}",
                 style("Recommended:").dim(),
@@ -315,6 +617,39 @@ impl<'a> PrettyFormatter<'a> {
         self.write("");
     }
 
+    /// List how each copy's metadata differs from the starred photo, using
+    /// the metadata read while hashing where the scan kept it
+    fn print_metadata_differences(
+        &self,
+        group: &duplicate_photo_cleaner::core::comparator::DuplicateGroup,
+        result: &PipelineResult,
+    ) {
+        let metadata = |path: &PathBuf| match result.details.get(path) {
+            Some(details) => details.metadata.clone(),
+            None => extract_metadata(path),
+        };
+        let reporter = DetailedReporter::new(result.algorithm);
+        let representative = metadata(&group.representative);
+        for photo in group.photos.iter().filter(|p| *p != &group.representative) {
+            let explanation = reporter.explain_same_pixels(&representative, &metadata(photo));
+            let summary = if explanation.metadata_differences.is_empty() {
+                "no differences in the fields we read".to_string()
+            } else {
+                explanation
+                    .metadata_differences
+                    .iter()
+                    .map(|d| d.to_string())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            };
+            self.write(&format!(
+                "    {} {}",
+                style(format!("Metadata ({}):", format_path(photo))).dim(),
+                style(summary).dim()
+            ));
+        }
+    }
+
     fn print_groups(&self, result: &PipelineResult) {
         if result.groups.is_empty() {
             self.write(&format!("  {} No duplicates found!", style("🎉").green()));
@@ -325,8 +660,30 @@ impl<'a> PrettyFormatter<'a> {
         self.write("");
 
         for (i, group) in result.groups.iter().enumerate() {
-            self.print_group(i, group);
+            self.print_group(i, group, result);
+        }
+    }
+
+    fn print_mismatches(&self, result: &PipelineResult) {
+        if result.format_mismatches.is_empty() {
+            return;
+        }
+
+        self.write(&format!("{}", style("Mislabeled Files:").bold().underlined()));
+        self.write("");
+
+        for mismatch in &result.format_mismatches {
+            let named = match mismatch.named_format {
+                ImageFormat::Unknown => "no image extension".to_string(),
+                format => format!("named {:?}", format),
+            };
+            self.write(&format!(
+                "  {} {}",
+                format_path(&mismatch.path),
+                style(format!("({}, contains {:?})", named, mismatch.content_format)).dim()
+            ));
         }
+        self.write("");
     }
 
     fn print_footer(&self) {
@@ -351,10 +708,12 @@ fn print_pretty_results(term: &Term, result: &PipelineResult, verbose: bool) {
     formatter.print_header();
     formatter.print_summary(result);
     formatter.print_groups(result);
+    formatter.print_mismatches(result);
     formatter.print_footer();
 }
 
 fn print_json_results(result: &PipelineResult) {
+    let reporter = DetailedReporter::new(result.algorithm).hash_size(result.hash_size);
     let output = serde_json::json!({
         "total_photos": result.total_photos,
         "duplicate_groups": result.groups.len(),
@@ -362,15 +721,22 @@ fn print_json_results(result: &PipelineResult) {
         "potential_savings_bytes": result.groups.iter().map(|g| g.duplicate_size_bytes).sum::<u64>(),
         "duration_ms": result.duration_ms,
         "cache_hits": result.cache_hits,
+        "preview": result.preview,
         "groups": result.groups.iter().map(|g| {
             serde_json::json!({
                 "id": g.id.to_string(),
                 "match_type": format!("{}", g.match_type),
+                "confidence": g.confidence.map(|c| format!("{:?}", c)),
+                "max_distance": g.max_distance,
+                "transform": g.transform.map(|t| t.to_string()),
+                "crop": g.crop,
                 "photos": g.photos,
                 "representative": g.representative,
                 "duplicate_size_bytes": g.duplicate_size_bytes,
+                "explanation": reporter.explain_group(g),
             })
-        }).collect::<Vec<_>>()
+        }).collect::<Vec<_>>(),
+        "format_mismatches": result.format_mismatches,
     });
 
     println!("{}", serde_json::to_string_pretty(&output).unwrap());
@@ -386,6 +752,38 @@ fn print_minimal_results(result: &PipelineResult) {
     }
 }
 
+/// Parse a size such as `500KB` or `1.5GB`, in the units of [`format_bytes`]
+fn parse_size(value: &str) -> std::result::Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let unit = unit.trim();
+    let multiplier: u64 = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" => 1024,
+        "M" | "MB" => 1024 * 1024,
+        "G" | "GB" => 1024 * 1024 * 1024,
+        _ => return Err(format!("unknown size unit '{}' (use KB, MB or GB)", unit)),
+    };
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("'{}' isn't a size", value))?;
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// Parse a `YYYY-MM-DD` date as the start of that day in local time
+fn parse_date(value: &str) -> std::result::Result<SystemTime, String> {
+    let date = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|e| format!("'{}' isn't a YYYY-MM-DD date: {}", value, e))?;
+    date.and_time(chrono::NaiveTime::MIN)
+        .and_local_timezone(chrono::Local)
+        .earliest()
+        .map(SystemTime::from)
+        .ok_or_else(|| format!("'{}' doesn't exist in the local time zone", value))
+}
+
 fn format_bytes(bytes: u64) -> String {
     const KB: u64 = 1024;
     const MB: u64 = KB * 1024;
@@ -0,0 +1,218 @@
+//! # Analysis Module
+//!
+//! Reads and decodes each photo once, and derives everything the app needs
+//! from that one decode.
+//!
+//! ## What a Photo Analysis Holds
+//! - The perceptual hash, plus rotation and mirror variants when enabled
+//! - Quality score (sharpness, contrast, brightness)
+//! - Full-size dimensions, read from the header since the decode is scaled
+//! - EXIF metadata, parsed from the bytes already in memory
+//!
+//! Everything but the hashes is kept in [`PhotoDetails`], which the hash
+//! cache stores next to the hash, so later scans, representative selection
+//! and the UI get quality data without touching the file again.
+
+use crate::core::hasher::fast_decode::{FastDecoder, ImageFormat, HASH_DECODE_SIZE};
+use crate::core::hasher::{
+    read_file_bytes, DihedralHasher, HashAlgorithm, HashVariants, ImageHashValue,
+};
+use crate::core::metadata::{extract_metadata_from_bytes, PhotoMetadata};
+use crate::core::quality::{QualityAnalyzer, QualityScore};
+use crate::core::raw::{self, MIN_PREVIEW_SIZE};
+use crate::error::HashError;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::path::Path;
+
+/// What one decode of a photo says about it, apart from its hashes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhotoDetails {
+    /// Full-size width in pixels, as stored
+    pub width: u32,
+    /// Full-size height in pixels, as stored
+    pub height: u32,
+    /// Sharpness, contrast and brightness of the upright image
+    pub quality: QualityScore,
+    /// EXIF metadata
+    pub metadata: PhotoMetadata,
+}
+
+impl PhotoDetails {
+    /// Number of pixels in the full-size image
+    pub fn pixels(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+}
+
+/// Everything derived from one decode of a photo
+#[derive(Debug, Clone)]
+pub struct PhotoAnalysis {
+    /// Perceptual hash of the upright image
+    pub hash: ImageHashValue,
+    /// Rotation and mirror variants, when enabled
+    pub variants: Option<HashVariants>,
+    /// Quality, dimensions and metadata
+    pub details: PhotoDetails,
+}
+
+/// Decodes photos once for hashing, quality and metadata
+pub struct PhotoAnalyzer<'a> {
+    hasher: &'a dyn HashAlgorithm,
+    quality: QualityAnalyzer,
+    match_rotations: bool,
+    sniff_content: bool,
+}
+
+impl<'a> PhotoAnalyzer<'a> {
+    /// Create an analyzer that hashes with `hasher`
+    pub fn new(hasher: &'a dyn HashAlgorithm) -> Self {
+        Self {
+            hasher,
+            quality: QualityAnalyzer::default(),
+            match_rotations: false,
+            sniff_content: false,
+        }
+    }
+
+    /// Also hash the rotation and mirror variants
+    pub fn match_rotations(mut self, enabled: bool) -> Self {
+        self.match_rotations = enabled;
+        self
+    }
+
+    /// Decode photos as the format their magic bytes say, rather than their
+    /// extension
+    pub fn sniff_content(mut self, enabled: bool) -> Self {
+        self.sniff_content = enabled;
+        self
+    }
+
+    /// Read and decode a photo once, and analyze it.
+    ///
+    /// The hash and quality score are the same as [`HashAlgorithm::hash_file`]
+    /// and [`QualityAnalyzer::analyze_file`] give, since they decode the same
+    /// way.
+    pub fn analyze_file(&self, path: &Path) -> Result<PhotoAnalysis, HashError> {
+        let bytes = read_file_bytes(path)?;
+        let format = ImageFormat::from_header_if(path, &bytes, self.sniff_content);
+        let metadata = extract_metadata_from_bytes(path, &bytes);
+        let image = FastDecoder::decode_bytes_for_hash(
+            path,
+            &bytes,
+            format,
+            HASH_DECODE_SIZE,
+            metadata.orientation,
+        )?;
+
+        let hash = self.hasher.hash_image(&image)?;
+        let variants = if self.match_rotations {
+            Some(DihedralHasher::new(self.hasher).hash_upright(&image)?)
+        } else {
+            None
+        };
+        let quality = self.quality.analyze_image(&image);
+        let (width, height) = full_dimensions(format, &bytes, &metadata, &image);
+
+        Ok(PhotoAnalysis {
+            hash,
+            variants,
+            details: PhotoDetails {
+                width,
+                height,
+                quality,
+                metadata,
+            },
+        })
+    }
+}
+
+/// Full-size dimensions of a photo whose decode may have been scaled.
+///
+/// Read from the file header where the image crate can, from the embedded
+/// preview of RAW files, and otherwise from EXIF or the decode itself.
+fn full_dimensions(
+    format: ImageFormat,
+    bytes: &[u8],
+    metadata: &PhotoMetadata,
+    image: &DynamicImage,
+) -> (u32, u32) {
+    let from_header = match format {
+        ImageFormat::Raw => raw::embedded_jpeg(bytes)
+            .filter(|jpeg| jpeg.width.max(jpeg.height) >= MIN_PREVIEW_SIZE)
+            .map(|jpeg| (jpeg.width, jpeg.height)),
+        ImageFormat::Heic | ImageFormat::Avif | ImageFormat::Jxl => None,
+        _ => image::ImageReader::new(Cursor::new(bytes))
+            .with_guessed_format()
+            .ok()
+            .and_then(|reader| reader.into_dimensions().ok()),
+    };
+
+    from_header
+        .or(metadata.width.zip(metadata.height))
+        .unwrap_or((image.width(), image.height()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hasher::{DifferenceHasher, PerceptualHash};
+    use image::RgbImage;
+    use tempfile::TempDir;
+
+    fn write_image(dir: &TempDir, name: &str, width: u32, height: u32) -> std::path::PathBuf {
+        let image = RgbImage::from_fn(width, height, |x, y| {
+            let value = ((x / 16 + y / 16) % 2 * 160 + (x % 64)) as u8;
+            image::Rgb([value, value / 2, 255 - value])
+        });
+        let path = dir.path().join(name);
+        image.save(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn analysis_matches_separate_passes() {
+        let dir = TempDir::new().unwrap();
+        let path = write_image(&dir, "photo.jpg", 1600, 1200);
+        let hasher = DifferenceHasher::new(8);
+
+        let analysis = PhotoAnalyzer::new(&hasher).analyze_file(&path).unwrap();
+
+        let hash = hasher.hash_file(&path).unwrap();
+        assert_eq!(analysis.hash.distance(&hash), 0);
+        let quality = QualityAnalyzer::default().analyze_file(&path).unwrap();
+        assert_eq!(analysis.details.quality, quality);
+        assert!(analysis.variants.is_none());
+    }
+
+    #[test]
+    fn reports_full_size_of_scaled_decode() {
+        let dir = TempDir::new().unwrap();
+        let jpeg = write_image(&dir, "large.jpg", 4096, 2048);
+        let png = write_image(&dir, "large.png", 640, 480);
+        let hasher = DifferenceHasher::new(8);
+        let analyzer = PhotoAnalyzer::new(&hasher);
+
+        let jpeg = analyzer.analyze_file(&jpeg).unwrap().details;
+        let png = analyzer.analyze_file(&png).unwrap().details;
+
+        assert_eq!((jpeg.width, jpeg.height), (4096, 2048));
+        assert_eq!((png.width, png.height), (640, 480));
+        assert_eq!(png.pixels(), 640 * 480);
+    }
+
+    #[test]
+    fn hashes_variants_when_matching_rotations() {
+        let dir = TempDir::new().unwrap();
+        let path = write_image(&dir, "photo.png", 320, 240);
+        let hasher = DifferenceHasher::new(8);
+
+        let analysis = PhotoAnalyzer::new(&hasher)
+            .match_rotations(true)
+            .analyze_file(&path)
+            .unwrap();
+
+        assert!(analysis.variants.is_some());
+    }
+}
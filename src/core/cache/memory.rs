@@ -1,17 +1,24 @@
 //! In-memory cache backend for testing.
 
-use super::{CacheBackend, CacheEntry, CacheStats};
+use super::{CacheBackend, CacheEntry, CacheStats, ContentHash, MatchState, ScanState};
+use crate::core::hasher::HashAlgorithmKind;
 use crate::error::CacheError;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::RwLock;
 use std::time::SystemTime;
 
+/// Entries are keyed by path, algorithm and hash size
+type CacheKey = (PathBuf, HashAlgorithmKind, u32);
+
 /// In-memory cache backend
 ///
 /// Useful for testing and scenarios where persistence isn't needed.
 pub struct InMemoryCache {
-    entries: RwLock<HashMap<PathBuf, CacheEntry>>,
+    entries: RwLock<HashMap<CacheKey, CacheEntry>>,
+    scan_states: RwLock<HashMap<PathBuf, ScanState>>,
+    match_states: RwLock<HashMap<String, MatchState>>,
+    content_hashes: RwLock<HashMap<PathBuf, ContentHash>>,
 }
 
 impl InMemoryCache {
@@ -19,6 +26,9 @@ impl InMemoryCache {
     pub fn new() -> Self {
         Self {
             entries: RwLock::new(HashMap::new()),
+            scan_states: RwLock::new(HashMap::new()),
+            match_states: RwLock::new(HashMap::new()),
+            content_hashes: RwLock::new(HashMap::new()),
         }
     }
 }
@@ -33,6 +43,8 @@ impl CacheBackend for InMemoryCache {
     fn get(
         &self,
         path: &Path,
+        algorithm: HashAlgorithmKind,
+        hash_size: u32,
         current_size: u64,
         current_modified: SystemTime,
     ) -> Result<Option<CacheEntry>, CacheError> {
@@ -40,7 +52,8 @@ impl CacheBackend for InMemoryCache {
             path: PathBuf::from("memory"),
         })?;
 
-        if let Some(entry) = entries.get(path) {
+        let key = (path.to_path_buf(), algorithm, hash_size);
+        if let Some(entry) = entries.get(&key) {
             if entry.is_valid_for(current_size, current_modified) {
                 return Ok(Some(entry.clone()));
             }
@@ -54,7 +67,10 @@ impl CacheBackend for InMemoryCache {
             path: PathBuf::from("memory"),
         })?;
 
-        entries.insert(entry.path.clone(), entry);
+        entries.insert(
+            (entry.path.clone(), entry.algorithm, entry.hash_size),
+            entry,
+        );
         Ok(())
     }
 
@@ -63,7 +79,15 @@ impl CacheBackend for InMemoryCache {
             path: PathBuf::from("memory"),
         })?;
 
-        entries.remove(path);
+        entries.retain(|(entry_path, _, _), _| entry_path != path);
+        drop(entries);
+
+        self.content_hashes
+            .write()
+            .map_err(|_| CacheError::Corrupted {
+                path: PathBuf::from("memory"),
+            })?
+            .remove(path);
         Ok(())
     }
 
@@ -73,6 +97,26 @@ impl CacheBackend for InMemoryCache {
         })?;
 
         entries.clear();
+        drop(entries);
+
+        self.scan_states
+            .write()
+            .map_err(|_| CacheError::Corrupted {
+                path: PathBuf::from("memory"),
+            })?
+            .clear();
+        self.match_states
+            .write()
+            .map_err(|_| CacheError::Corrupted {
+                path: PathBuf::from("memory"),
+            })?
+            .clear();
+        self.content_hashes
+            .write()
+            .map_err(|_| CacheError::Corrupted {
+                path: PathBuf::from("memory"),
+            })?
+            .clear();
         Ok(())
     }
 
@@ -101,17 +145,99 @@ impl CacheBackend for InMemoryCache {
         })?;
 
         let before = entries.len();
-        entries.retain(|path, _| path.exists());
+        entries.retain(|(path, _, _), _| path.exists());
         let after = entries.len();
 
+        self.content_hashes
+            .write()
+            .map_err(|_| CacheError::Corrupted {
+                path: PathBuf::from("memory"),
+            })?
+            .retain(|path, _| path.exists());
+
         Ok(before - after)
     }
+
+    fn get_scan_state(&self, directory: &Path) -> Result<Option<ScanState>, CacheError> {
+        let states = self.scan_states.read().map_err(|_| CacheError::Corrupted {
+            path: PathBuf::from("memory"),
+        })?;
+
+        Ok(states.get(directory).cloned())
+    }
+
+    fn set_scan_state(&self, state: &ScanState) -> Result<(), CacheError> {
+        let mut states = self
+            .scan_states
+            .write()
+            .map_err(|_| CacheError::Corrupted {
+                path: PathBuf::from("memory"),
+            })?;
+
+        states.insert(state.directory.clone(), state.clone());
+        Ok(())
+    }
+
+    fn get_match_state(&self, settings: &str) -> Result<Option<MatchState>, CacheError> {
+        let states = self
+            .match_states
+            .read()
+            .map_err(|_| CacheError::Corrupted {
+                path: PathBuf::from("memory"),
+            })?;
+
+        Ok(states.get(settings).cloned())
+    }
+
+    fn set_match_state(&self, state: &MatchState) -> Result<(), CacheError> {
+        let mut states = self
+            .match_states
+            .write()
+            .map_err(|_| CacheError::Corrupted {
+                path: PathBuf::from("memory"),
+            })?;
+
+        states.insert(state.settings.clone(), state.clone());
+        Ok(())
+    }
+
+    fn get_content_hash(
+        &self,
+        path: &Path,
+        current_size: u64,
+        current_modified: SystemTime,
+    ) -> Result<Option<u128>, CacheError> {
+        let hashes = self
+            .content_hashes
+            .read()
+            .map_err(|_| CacheError::Corrupted {
+                path: PathBuf::from("memory"),
+            })?;
+
+        Ok(hashes
+            .get(path)
+            .filter(|h| h.file_size == current_size && h.file_modified == current_modified)
+            .map(|h| h.hash))
+    }
+
+    fn set_content_hashes(&self, hashes: &[ContentHash]) -> Result<(), CacheError> {
+        let mut stored = self
+            .content_hashes
+            .write()
+            .map_err(|_| CacheError::Corrupted {
+                path: PathBuf::from("memory"),
+            })?;
+
+        for hash in hashes {
+            stored.insert(hash.path.clone(), hash.clone());
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::hasher::HashAlgorithmKind;
 
     fn create_entry(path: &str) -> CacheEntry {
         let now = SystemTime::now();
@@ -119,9 +245,13 @@ mod tests {
             path: PathBuf::from(path),
             hash: vec![0xDE, 0xAD, 0xBE, 0xEF],
             algorithm: HashAlgorithmKind::Difference,
+            hash_size: 8,
             file_size: 1000,
             file_modified: now,
             cached_at: now,
+            pixel_hash: None,
+            details: None,
+            variants: None,
         }
     }
 
@@ -130,7 +260,15 @@ mod tests {
         let cache = InMemoryCache::new();
         let now = SystemTime::now();
 
-        let result = cache.get(Path::new("/nonexistent.jpg"), 1000, now).unwrap();
+        let result = cache
+            .get(
+                Path::new("/nonexistent.jpg"),
+                HashAlgorithmKind::Difference,
+                8,
+                1000,
+                now,
+            )
+            .unwrap();
 
         assert!(result.is_none());
     }
@@ -143,7 +281,15 @@ mod tests {
 
         cache.set(entry).unwrap();
 
-        let result = cache.get(Path::new("/test.jpg"), 1000, modified).unwrap();
+        let result = cache
+            .get(
+                Path::new("/test.jpg"),
+                HashAlgorithmKind::Difference,
+                8,
+                1000,
+                modified,
+            )
+            .unwrap();
 
         assert!(result.is_some());
         assert_eq!(result.unwrap().hash, vec![0xDE, 0xAD, 0xBE, 0xEF]);
@@ -159,7 +305,15 @@ mod tests {
 
         // Try to get with different modification time
         let later = original_modified + std::time::Duration::from_secs(60);
-        let result = cache.get(Path::new("/test.jpg"), 1000, later).unwrap();
+        let result = cache
+            .get(
+                Path::new("/test.jpg"),
+                HashAlgorithmKind::Difference,
+                8,
+                1000,
+                later,
+            )
+            .unwrap();
 
         assert!(result.is_none());
     }
@@ -199,7 +353,46 @@ mod tests {
         cache.set(entry).unwrap();
         cache.remove(Path::new("/test.jpg")).unwrap();
 
-        let result = cache.get(Path::new("/test.jpg"), 1000, modified).unwrap();
+        let result = cache
+            .get(
+                Path::new("/test.jpg"),
+                HashAlgorithmKind::Difference,
+                8,
+                1000,
+                modified,
+            )
+            .unwrap();
         assert!(result.is_none());
     }
+
+    #[test]
+    fn entries_are_keyed_by_algorithm_and_size() {
+        let cache = InMemoryCache::new();
+        let entry = create_entry("/test.jpg");
+        let modified = entry.file_modified;
+
+        cache.set(entry.clone()).unwrap();
+        cache
+            .set(CacheEntry {
+                hash: vec![0x01; 32],
+                hash_size: 16,
+                ..entry
+            })
+            .unwrap();
+
+        let path = Path::new("/test.jpg");
+        let dhash8 = cache
+            .get(path, HashAlgorithmKind::Difference, 8, 1000, modified)
+            .unwrap();
+        let dhash16 = cache
+            .get(path, HashAlgorithmKind::Difference, 16, 1000, modified)
+            .unwrap();
+        let phash8 = cache
+            .get(path, HashAlgorithmKind::Perceptual, 8, 1000, modified)
+            .unwrap();
+
+        assert_eq!(dhash8.unwrap().hash, vec![0xDE, 0xAD, 0xBE, 0xEF]);
+        assert_eq!(dhash16.unwrap().hash.len(), 32);
+        assert!(phash8.is_none());
+    }
 }
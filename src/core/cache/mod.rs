@@ -6,6 +6,12 @@
 //! - Subsequent scans are much faster
 //! - Only new or modified photos need hashing
 //! - Cache invalidation based on file modification time
+//! - One entry per (path, algorithm, hash size), so switching algorithms
+//!   never reuses the wrong hash bytes
+//! - Incremental scans also keep what each directory held ([`ScanState`])
+//!   and the matches found with each setting ([`MatchState`])
+//! - Digests of file contents ([`ContentHash`]), so byte-identical copies
+//!   are found without reading unchanged files again
 //!
 //! ## Backends
 //! - `SqliteCache` - Persistent storage using SQLite
@@ -16,10 +22,13 @@ mod sqlite;
 mod traits;
 
 pub use memory::InMemoryCache;
-pub use sqlite::{ScanState, SqliteCache};
+pub use sqlite::SqliteCache;
 pub use traits::CacheBackend;
 
-use crate::core::hasher::HashAlgorithmKind;
+use crate::core::analysis::PhotoDetails;
+use crate::core::comparator::MatchResult;
+use crate::core::hasher::{HashAlgorithmKind, HashVariants};
+use crate::core::scanner::PhotoFile;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::time::SystemTime;
@@ -33,12 +42,25 @@ pub struct CacheEntry {
     pub hash: Vec<u8>,
     /// Algorithm used to compute the hash
     pub algorithm: HashAlgorithmKind,
+    /// Hash size the hasher was configured with (8 = 64 bits)
+    pub hash_size: u32,
     /// File size at time of hashing
     pub file_size: u64,
     /// File modification time at time of hashing
     pub file_modified: SystemTime,
     /// When the entry was cached
     pub cached_at: SystemTime,
+    /// Hash of the decoded pixels (see [`PixelHash`]), if it was computed
+    ///
+    /// [`PixelHash`]: crate::core::hasher::PixelHash
+    #[serde(default)]
+    pub pixel_hash: Option<Vec<u8>>,
+    /// Quality, dimensions and metadata from the hashing decode
+    #[serde(default)]
+    pub details: Option<PhotoDetails>,
+    /// Rotation and mirror hashes, if rotations were matched
+    #[serde(default)]
+    pub variants: Option<HashVariants>,
 }
 
 impl CacheEntry {
@@ -72,6 +94,52 @@ pub struct CacheStats {
     pub newest_entry: Option<SystemTime>,
 }
 
+/// What a directory held when an incremental scan last read it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanState {
+    /// Directory that was scanned
+    pub directory: PathBuf,
+    /// When the scan was performed
+    pub last_scan_time: SystemTime,
+    /// Number of photos found
+    pub file_count: usize,
+    /// Modification time of the directory itself when it was read
+    pub modified: SystemTime,
+    /// Fingerprint of the file filter the photos were picked with
+    pub filter: String,
+    /// Photos directly in the directory
+    pub photos: Vec<PhotoFile>,
+    /// Subdirectories to descend into, sorted by name
+    pub subdirectories: Vec<PathBuf>,
+    /// Whether the directory has an ignore file, which is read on every scan
+    #[serde(default)]
+    pub ignore_file: bool,
+}
+
+/// Digest of a file's full contents, for finding byte-identical copies
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentHash {
+    /// Path to the file
+    pub path: PathBuf,
+    /// File size when it was read
+    pub file_size: u64,
+    /// File modification time when it was read, compared exactly
+    pub file_modified: SystemTime,
+    /// xxh3-128 digest of the file's bytes
+    pub hash: u128,
+}
+
+/// The matches a scan found between photos, for reuse by the next scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchState {
+    /// Fingerprint of the hash and comparison settings
+    pub settings: String,
+    /// Every compared photo, with a fingerprint of its hash
+    pub photos: Vec<(PathBuf, u64)>,
+    /// Matches between the compared photos
+    pub matches: Vec<MatchResult>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -83,9 +151,13 @@ mod tests {
             path: PathBuf::from("/test.jpg"),
             hash: vec![0xFF],
             algorithm: HashAlgorithmKind::Difference,
+            hash_size: 8,
             file_size: 1000,
             file_modified: now,
             cached_at: now,
+            pixel_hash: None,
+            details: None,
+            variants: None,
         };
 
         assert!(entry.is_valid_for(1000, now));
@@ -98,9 +170,13 @@ mod tests {
             path: PathBuf::from("/test.jpg"),
             hash: vec![0xFF],
             algorithm: HashAlgorithmKind::Difference,
+            hash_size: 8,
             file_size: 1000,
             file_modified: now,
             cached_at: now,
+            pixel_hash: None,
+            details: None,
+            variants: None,
         };
 
         assert!(!entry.is_valid_for(2000, now)); // Different size
@@ -115,9 +191,13 @@ mod tests {
             path: PathBuf::from("/test.jpg"),
             hash: vec![0xFF],
             algorithm: HashAlgorithmKind::Difference,
+            hash_size: 8,
             file_size: 1000,
             file_modified: now,
             cached_at: now,
+            pixel_hash: None,
+            details: None,
+            variants: None,
         };
 
         assert!(!entry.is_valid_for(1000, later)); // Different time
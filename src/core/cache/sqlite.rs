@@ -1,13 +1,26 @@
 //! SQLite cache backend for persistent storage.
 
-use super::{CacheBackend, CacheEntry, CacheStats};
+use super::{CacheBackend, CacheEntry, CacheStats, ContentHash, MatchState, ScanState};
 use crate::core::hasher::HashAlgorithmKind;
 use crate::error::CacheError;
 use rusqlite::{params, Connection};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+/// Current schema version, stored in `PRAGMA user_version`.
+///
+/// - 0: legacy layout, one row per path
+/// - 1: one row per (path, algorithm, hash_size)
+/// - 2: same layout, hashes computed after applying EXIF orientation
+/// - 3: adds the nullable `pixel_hash` column
+/// - 4: same layout, hashes computed from DCT-scaled JPEG decodes
+/// - 5: adds the nullable `details` column, as JSON
+/// - 6: adds the nullable `variants` column, as JSON
+const SCHEMA_VERSION: i32 = 6;
+
 /// SQLite-backed persistent cache
 ///
 /// Uses WAL (Write-Ahead Logging) mode for better concurrent access.
@@ -28,7 +41,7 @@ impl SqliteCache {
             })?;
         }
 
-        let conn = Connection::open(path).map_err(|e| CacheError::OpenFailed {
+        let mut conn = Connection::open(path).map_err(|e| CacheError::OpenFailed {
             path: path.to_path_buf(),
             reason: e.to_string(),
         })?;
@@ -38,39 +51,127 @@ impl SqliteCache {
         conn.execute_batch("PRAGMA journal_mode=WAL;")
             .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
 
-        // Create table if it doesn't exist
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS hashes (
-                path TEXT PRIMARY KEY,
-                hash BLOB NOT NULL,
+        // Create or upgrade the hashes table
+        Self::migrate(&mut conn)?;
+
+        // Create state tables for incremental scanning, and content hashes
+        Self::create_state_tables(&conn)?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+            db_path: path.to_path_buf(),
+        })
+    }
+
+    /// Bring the hashes table up to [`SCHEMA_VERSION`].
+    ///
+    /// Hashes from before v4 were computed from other decodes (without EXIF
+    /// orientation before v2, at full size before v4), and v4 entries lack
+    /// the details the pipeline now expects, so older tables are dropped
+    /// rather than migrated; photos are rehashed on the next scan. v5
+    /// tables only lack the variants column, so it is added.
+    fn migrate(conn: &mut Connection) -> Result<(), CacheError> {
+        let version: i32 = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
+
+        if version >= SCHEMA_VERSION {
+            return Ok(());
+        }
+
+        let tx = conn
+            .transaction()
+            .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
+
+        if version == 5 {
+            tx.execute_batch(&format!(
+                "ALTER TABLE hashes ADD COLUMN variants TEXT;
+                 PRAGMA user_version = {};",
+                SCHEMA_VERSION
+            ))
+            .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
+            return tx
+                .commit()
+                .map_err(|e| CacheError::QueryFailed(e.to_string()));
+        }
+
+        let has_old_table: bool = tx
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'hashes'",
+                [],
+                |row| row.get::<_, i64>(0).map(|count| count > 0),
+            )
+            .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
+        if has_old_table {
+            tracing::info!("Discarding hash cache from schema v{}", version);
+        }
+
+        tx.execute_batch(&format!(
+            "DROP TABLE IF EXISTS hashes;
+             CREATE TABLE hashes (
+                path TEXT NOT NULL,
                 algorithm TEXT NOT NULL,
+                hash_size INTEGER NOT NULL,
+                hash BLOB NOT NULL,
                 file_size INTEGER NOT NULL,
                 file_modified INTEGER NOT NULL,
-                cached_at INTEGER NOT NULL
-            )",
-            [],
-        )
+                cached_at INTEGER NOT NULL,
+                pixel_hash BLOB,
+                details TEXT,
+                variants TEXT,
+                PRIMARY KEY (path, algorithm, hash_size)
+             );
+             PRAGMA user_version = {};",
+            SCHEMA_VERSION
+        ))
         .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
 
-        // Create index for faster lookups
-        conn.execute("CREATE INDEX IF NOT EXISTS idx_path ON hashes(path)", [])
+        tx.commit()
+            .map_err(|e| CacheError::QueryFailed(e.to_string()))
+    }
+
+    /// Create the tables incremental scans keep their state in, and the
+    /// content hashes of files.
+    ///
+    /// Scan state from before directory listings (and whether they have an
+    /// ignore file) were stored can't be reused, so that table is recreated.
+    fn create_state_tables(conn: &Connection) -> Result<(), CacheError> {
+        let has_listings: bool = conn
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('scan_state') WHERE name = 'ignore_file'",
+                [],
+                |row| row.get::<_, i64>(0).map(|count| count > 0),
+            )
             .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
+        if !has_listings {
+            conn.execute("DROP TABLE IF EXISTS scan_state", [])
+                .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
+        }
 
-        // Create scan state table for incremental scanning
-        conn.execute(
+        conn.execute_batch(
             "CREATE TABLE IF NOT EXISTS scan_state (
                 directory TEXT PRIMARY KEY,
                 last_scan_time INTEGER NOT NULL,
-                file_count INTEGER NOT NULL
-            )",
-            [],
+                file_count INTEGER NOT NULL,
+                modified INTEGER NOT NULL,
+                filter TEXT NOT NULL,
+                photos TEXT NOT NULL,
+                subdirectories TEXT NOT NULL,
+                ignore_file INTEGER NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS match_state (
+                settings TEXT PRIMARY KEY,
+                photos TEXT NOT NULL,
+                matches TEXT NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS content_hashes (
+                path TEXT PRIMARY KEY,
+                file_size INTEGER NOT NULL,
+                file_modified INTEGER NOT NULL,
+                hash BLOB NOT NULL
+             );",
         )
-        .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
-
-        Ok(Self {
-            conn: Mutex::new(conn),
-            db_path: path.to_path_buf(),
-        })
+        .map_err(|e| CacheError::QueryFailed(e.to_string()))
     }
 
     /// Open a cache database and prune orphaned entries
@@ -96,90 +197,61 @@ impl SqliteCache {
         UNIX_EPOCH + Duration::from_secs(timestamp as u64)
     }
 
-    /// Convert algorithm to string for storage
-    fn algorithm_to_string(algo: HashAlgorithmKind) -> &'static str {
-        match algo {
-            HashAlgorithmKind::Average => "average",
-            HashAlgorithmKind::Difference => "difference",
-            HashAlgorithmKind::Perceptual => "perceptual",
-            HashAlgorithmKind::Fusion => "fusion",
-        }
+    /// Convert SystemTime to nanoseconds since the epoch.
+    ///
+    /// Directory times are compared exactly, so they keep full precision.
+    fn to_nanos(time: SystemTime) -> i64 {
+        time.duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO)
+            .as_nanos() as i64
     }
 
-    /// Convert string to algorithm
-    fn string_to_algorithm(s: &str) -> HashAlgorithmKind {
-        match s {
-            "average" => HashAlgorithmKind::Average,
-            "perceptual" => HashAlgorithmKind::Perceptual,
-            "fusion" => HashAlgorithmKind::Fusion,
-            _ => HashAlgorithmKind::Difference,
-        }
+    fn from_nanos(nanos: i64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_nanos(nanos as u64)
     }
 
-    /// Get the last scan time for a directory
-    pub fn get_scan_state(&self, directory: &Path) -> Result<Option<ScanState>, CacheError> {
-        let conn = self.conn.lock().map_err(|_| CacheError::Corrupted {
-            path: self.db_path.clone(),
-        })?;
-
-        let dir_str = directory.to_string_lossy();
-
-        let result: Result<ScanState, _> = conn.query_row(
-            "SELECT last_scan_time, file_count FROM scan_state WHERE directory = ?",
-            [&dir_str],
-            |row| {
-                Ok(ScanState {
-                    directory: directory.to_path_buf(),
-                    last_scan_time: Self::from_timestamp(row.get(0)?),
-                    file_count: row.get::<_, i64>(1)? as usize,
-                })
-            },
-        );
-
-        match result {
-            Ok(state) => Ok(Some(state)),
-            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-            Err(e) => Err(CacheError::QueryFailed(e.to_string())),
-        }
+    fn to_json<T: Serialize>(value: &T) -> Result<String, CacheError> {
+        serde_json::to_string(value).map_err(|e| CacheError::QueryFailed(e.to_string()))
     }
 
-    /// Update the scan state for a directory
-    pub fn set_scan_state(&self, state: &ScanState) -> Result<(), CacheError> {
-        let conn = self.conn.lock().map_err(|_| CacheError::Corrupted {
-            path: self.db_path.clone(),
-        })?;
-
-        let dir_str = state.directory.to_string_lossy();
+    fn from_json<T: DeserializeOwned>(json: &str) -> rusqlite::Result<T> {
+        serde_json::from_str(json).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e))
+        })
+    }
 
-        conn.execute(
-            "INSERT OR REPLACE INTO scan_state (directory, last_scan_time, file_count) VALUES (?, ?, ?)",
-            params![
-                dir_str,
-                Self::to_timestamp(state.last_scan_time),
-                state.file_count as i64,
-            ],
-        )
-        .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
+    /// Serialize an optional value, such as a photo's details, for storage
+    fn optional_to_json<T: Serialize>(value: &Option<T>) -> Option<String> {
+        value
+            .as_ref()
+            .and_then(|value| serde_json::to_string(value).ok())
+    }
 
-        Ok(())
+    /// Read an optional stored value, treating an unreadable one as missing
+    fn optional_from_json<T: DeserializeOwned>(json: Option<String>) -> Option<T> {
+        json.and_then(|json| serde_json::from_str(&json).ok())
     }
-}
 
-/// Represents the state of a previous scan
-#[derive(Debug, Clone)]
-pub struct ScanState {
-    /// Directory that was scanned
-    pub directory: PathBuf,
-    /// When the scan was performed
-    pub last_scan_time: SystemTime,
-    /// Number of photos found
-    pub file_count: usize,
+    /// Convert algorithm to string for storage
+    fn algorithm_to_string(algo: HashAlgorithmKind) -> &'static str {
+        match algo {
+            HashAlgorithmKind::Average => "average",
+            HashAlgorithmKind::Difference => "difference",
+            HashAlgorithmKind::Perceptual => "perceptual",
+            HashAlgorithmKind::Fusion => "fusion",
+            HashAlgorithmKind::Wavelet => "wavelet",
+            HashAlgorithmKind::BlockMean => "block_mean",
+            HashAlgorithmKind::CropResistant => "crop_resistant",
+        }
+    }
 }
 
 impl CacheBackend for SqliteCache {
     fn get(
         &self,
         path: &Path,
+        algorithm: HashAlgorithmKind,
+        hash_size: u32,
         current_size: u64,
         current_modified: SystemTime,
     ) -> Result<Option<CacheEntry>, CacheError> {
@@ -189,18 +261,27 @@ impl CacheBackend for SqliteCache {
 
         let path_str = path.to_string_lossy();
 
-        let result: Result<CacheEntry, _> = conn.query_row(
-            "SELECT hash, algorithm, file_size, file_modified, cached_at
-             FROM hashes WHERE path = ?",
-            [&path_str],
+        let mut stmt = conn
+            .prepare_cached(
+                "SELECT hash, file_size, file_modified, cached_at, pixel_hash, details, variants
+                 FROM hashes WHERE path = ? AND algorithm = ? AND hash_size = ?",
+            )
+            .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
+
+        let result: Result<CacheEntry, _> = stmt.query_row(
+            params![path_str, Self::algorithm_to_string(algorithm), hash_size],
             |row| {
                 Ok(CacheEntry {
                     path: path.to_path_buf(),
                     hash: row.get(0)?,
-                    algorithm: Self::string_to_algorithm(&row.get::<_, String>(1)?),
-                    file_size: row.get::<_, i64>(2)? as u64,
-                    file_modified: Self::from_timestamp(row.get(3)?),
-                    cached_at: Self::from_timestamp(row.get(4)?),
+                    algorithm,
+                    hash_size,
+                    file_size: row.get::<_, i64>(1)? as u64,
+                    file_modified: Self::from_timestamp(row.get(2)?),
+                    cached_at: Self::from_timestamp(row.get(3)?),
+                    pixel_hash: row.get(4)?,
+                    details: Self::optional_from_json(row.get(5)?),
+                    variants: Self::optional_from_json(row.get(6)?),
                 })
             },
         );
@@ -227,15 +308,19 @@ impl CacheBackend for SqliteCache {
 
         conn.execute(
             "INSERT OR REPLACE INTO hashes
-             (path, hash, algorithm, file_size, file_modified, cached_at)
-             VALUES (?, ?, ?, ?, ?, ?)",
+             (path, hash, algorithm, hash_size, file_size, file_modified, cached_at, pixel_hash, details, variants)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
             params![
                 path_str,
                 entry.hash,
                 Self::algorithm_to_string(entry.algorithm),
+                entry.hash_size,
                 entry.file_size as i64,
                 Self::to_timestamp(entry.file_modified),
                 Self::to_timestamp(entry.cached_at),
+                entry.pixel_hash,
+                Self::optional_to_json(&entry.details),
+                Self::optional_to_json(&entry.variants),
             ],
         )
         .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
@@ -261,8 +346,8 @@ impl CacheBackend for SqliteCache {
             let mut stmt = tx
                 .prepare_cached(
                     "INSERT OR REPLACE INTO hashes
-                     (path, hash, algorithm, file_size, file_modified, cached_at)
-                     VALUES (?, ?, ?, ?, ?, ?)",
+                     (path, hash, algorithm, hash_size, file_size, file_modified, cached_at, pixel_hash, details, variants)
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
                 )
                 .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
 
@@ -272,9 +357,13 @@ impl CacheBackend for SqliteCache {
                     path_str,
                     &entry.hash,
                     Self::algorithm_to_string(entry.algorithm),
+                    entry.hash_size,
                     entry.file_size as i64,
                     Self::to_timestamp(entry.file_modified),
                     Self::to_timestamp(entry.cached_at),
+                    &entry.pixel_hash,
+                    Self::optional_to_json(&entry.details),
+                    Self::optional_to_json(&entry.variants),
                 ])
                 .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
             }
@@ -295,6 +384,8 @@ impl CacheBackend for SqliteCache {
 
         conn.execute("DELETE FROM hashes WHERE path = ?", [&path_str])
             .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
+        conn.execute("DELETE FROM content_hashes WHERE path = ?", [&path_str])
+            .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
 
         Ok(())
     }
@@ -304,8 +395,13 @@ impl CacheBackend for SqliteCache {
             path: self.db_path.clone(),
         })?;
 
-        conn.execute("DELETE FROM hashes", [])
-            .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
+        conn.execute_batch(
+            "DELETE FROM hashes;
+             DELETE FROM scan_state;
+             DELETE FROM match_state;
+             DELETE FROM content_hashes;",
+        )
+        .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
 
         Ok(())
     }
@@ -357,7 +453,7 @@ impl CacheBackend for SqliteCache {
         })?;
 
         let mut stmt = conn
-            .prepare("SELECT path FROM hashes")
+            .prepare("SELECT path FROM hashes UNION SELECT path FROM content_hashes")
             .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
 
         let paths: Vec<String> = stmt
@@ -373,17 +469,214 @@ impl CacheBackend for SqliteCache {
             if !Path::new(&path).exists() {
                 conn.execute("DELETE FROM hashes WHERE path = ?", [&path])
                     .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
+                conn.execute("DELETE FROM content_hashes WHERE path = ?", [&path])
+                    .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
                 count += 1;
             }
         }
 
         Ok(count)
     }
+
+    fn get_scan_state(&self, directory: &Path) -> Result<Option<ScanState>, CacheError> {
+        let conn = self.conn.lock().map_err(|_| CacheError::Corrupted {
+            path: self.db_path.clone(),
+        })?;
+
+        let dir_str = directory.to_string_lossy();
+
+        let result: Result<ScanState, _> = conn.query_row(
+            "SELECT last_scan_time, file_count, modified, filter, photos, subdirectories,
+                    ignore_file
+             FROM scan_state WHERE directory = ?",
+            [&dir_str],
+            |row| {
+                Ok(ScanState {
+                    directory: directory.to_path_buf(),
+                    last_scan_time: Self::from_timestamp(row.get(0)?),
+                    file_count: row.get::<_, i64>(1)? as usize,
+                    modified: Self::from_nanos(row.get(2)?),
+                    filter: row.get(3)?,
+                    photos: Self::from_json(&row.get::<_, String>(4)?)?,
+                    subdirectories: Self::from_json(&row.get::<_, String>(5)?)?,
+                    ignore_file: row.get(6)?,
+                })
+            },
+        );
+
+        match result {
+            Ok(state) => Ok(Some(state)),
+            // A listing that no longer parses is read again
+            Err(rusqlite::Error::QueryReturnedNoRows)
+            | Err(rusqlite::Error::FromSqlConversionFailure(..)) => Ok(None),
+            Err(e) => Err(CacheError::QueryFailed(e.to_string())),
+        }
+    }
+
+    fn set_scan_state(&self, state: &ScanState) -> Result<(), CacheError> {
+        self.set_scan_states(std::slice::from_ref(state))
+    }
+
+    fn set_scan_states(&self, states: &[ScanState]) -> Result<(), CacheError> {
+        if states.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = self.conn.lock().map_err(|_| CacheError::Corrupted {
+            path: self.db_path.clone(),
+        })?;
+
+        let tx = conn
+            .transaction()
+            .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
+
+        {
+            let mut stmt = tx
+                .prepare_cached(
+                    "INSERT OR REPLACE INTO scan_state
+                     (directory, last_scan_time, file_count, modified, filter, photos,
+                      subdirectories, ignore_file)
+                     VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+                )
+                .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
+
+            for state in states {
+                stmt.execute(params![
+                    state.directory.to_string_lossy(),
+                    Self::to_timestamp(state.last_scan_time),
+                    state.file_count as i64,
+                    Self::to_nanos(state.modified),
+                    &state.filter,
+                    Self::to_json(&state.photos)?,
+                    Self::to_json(&state.subdirectories)?,
+                    state.ignore_file,
+                ])
+                .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
+            }
+        }
+
+        tx.commit()
+            .map_err(|e| CacheError::QueryFailed(e.to_string()))
+    }
+
+    fn get_match_state(&self, settings: &str) -> Result<Option<MatchState>, CacheError> {
+        let conn = self.conn.lock().map_err(|_| CacheError::Corrupted {
+            path: self.db_path.clone(),
+        })?;
+
+        let result: Result<MatchState, _> = conn.query_row(
+            "SELECT photos, matches FROM match_state WHERE settings = ?",
+            [settings],
+            |row| {
+                Ok(MatchState {
+                    settings: settings.to_string(),
+                    photos: Self::from_json(&row.get::<_, String>(0)?)?,
+                    matches: Self::from_json(&row.get::<_, String>(1)?)?,
+                })
+            },
+        );
+
+        match result {
+            Ok(state) => Ok(Some(state)),
+            Err(rusqlite::Error::QueryReturnedNoRows)
+            | Err(rusqlite::Error::FromSqlConversionFailure(..)) => Ok(None),
+            Err(e) => Err(CacheError::QueryFailed(e.to_string())),
+        }
+    }
+
+    fn set_match_state(&self, state: &MatchState) -> Result<(), CacheError> {
+        let conn = self.conn.lock().map_err(|_| CacheError::Corrupted {
+            path: self.db_path.clone(),
+        })?;
+
+        conn.execute(
+            "INSERT OR REPLACE INTO match_state (settings, photos, matches) VALUES (?, ?, ?)",
+            params![
+                &state.settings,
+                Self::to_json(&state.photos)?,
+                Self::to_json(&state.matches)?,
+            ],
+        )
+        .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
+
+        Ok(())
+    }
+
+    fn get_content_hash(
+        &self,
+        path: &Path,
+        current_size: u64,
+        current_modified: SystemTime,
+    ) -> Result<Option<u128>, CacheError> {
+        let conn = self.conn.lock().map_err(|_| CacheError::Corrupted {
+            path: self.db_path.clone(),
+        })?;
+
+        let result: Result<(i64, i64, Vec<u8>), _> = conn.query_row(
+            "SELECT file_size, file_modified, hash FROM content_hashes WHERE path = ?",
+            [path.to_string_lossy()],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        );
+
+        match result {
+            Ok((size, modified, hash)) => {
+                let unchanged =
+                    size as u64 == current_size && modified == Self::to_nanos(current_modified);
+                Ok(hash
+                    .try_into()
+                    .ok()
+                    .filter(|_| unchanged)
+                    .map(u128::from_be_bytes))
+            }
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(CacheError::QueryFailed(e.to_string())),
+        }
+    }
+
+    fn set_content_hashes(&self, hashes: &[ContentHash]) -> Result<(), CacheError> {
+        if hashes.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = self.conn.lock().map_err(|_| CacheError::Corrupted {
+            path: self.db_path.clone(),
+        })?;
+
+        let tx = conn
+            .transaction()
+            .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
+
+        {
+            let mut stmt = tx
+                .prepare_cached(
+                    "INSERT OR REPLACE INTO content_hashes (path, file_size, file_modified, hash)
+                     VALUES (?, ?, ?, ?)",
+                )
+                .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
+
+            for hash in hashes {
+                stmt.execute(params![
+                    hash.path.to_string_lossy(),
+                    hash.file_size as i64,
+                    Self::to_nanos(hash.file_modified),
+                    hash.hash.to_be_bytes().as_slice(),
+                ])
+                .map_err(|e| CacheError::QueryFailed(e.to_string()))?;
+            }
+        }
+
+        tx.commit()
+            .map_err(|e| CacheError::QueryFailed(e.to_string()))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::analysis::PhotoDetails;
+    use crate::core::hasher::{DihedralTransform, ImageHashValue, PerceptualHash};
+    use crate::core::metadata::PhotoMetadata;
+    use crate::core::quality::QualityScore;
     use tempfile::TempDir;
 
     fn create_entry(path: &str) -> CacheEntry {
@@ -392,9 +685,13 @@ mod tests {
             path: PathBuf::from(path),
             hash: vec![0xDE, 0xAD, 0xBE, 0xEF],
             algorithm: HashAlgorithmKind::Difference,
+            hash_size: 8,
             file_size: 1000,
             file_modified: now,
             cached_at: now,
+            pixel_hash: None,
+            details: None,
+            variants: None,
         }
     }
 
@@ -435,7 +732,15 @@ mod tests {
 
         cache.set(entry).unwrap();
 
-        let result = cache.get(Path::new("/test.jpg"), size, modified).unwrap();
+        let result = cache
+            .get(
+                Path::new("/test.jpg"),
+                HashAlgorithmKind::Difference,
+                8,
+                size,
+                modified,
+            )
+            .unwrap();
 
         assert!(result.is_some());
         assert_eq!(result.unwrap().hash, vec![0xDE, 0xAD, 0xBE, 0xEF]);
@@ -454,7 +759,15 @@ mod tests {
 
         // Check with different modification time
         let later = original_modified + Duration::from_secs(60);
-        let result = cache.get(Path::new("/test.jpg"), 1000, later).unwrap();
+        let result = cache
+            .get(
+                Path::new("/test.jpg"),
+                HashAlgorithmKind::Difference,
+                8,
+                1000,
+                later,
+            )
+            .unwrap();
 
         assert!(result.is_none());
     }
@@ -505,4 +818,380 @@ mod tests {
         let stats = cache.stats().unwrap();
         assert_eq!(stats.total_entries, 0);
     }
+
+    #[test]
+    fn sqlite_cache_keys_by_algorithm_and_hash_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("cache.db");
+
+        let cache = SqliteCache::open(&db_path).unwrap();
+        let modified = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let dhash = CacheEntry {
+            file_modified: modified,
+            ..create_entry("/test.jpg")
+        };
+        let phash = CacheEntry {
+            hash: vec![0x01, 0x02],
+            algorithm: HashAlgorithmKind::Perceptual,
+            ..dhash.clone()
+        };
+
+        cache.set_batch(&[dhash, phash]).unwrap();
+
+        let path = Path::new("/test.jpg");
+        let got_dhash = cache
+            .get(path, HashAlgorithmKind::Difference, 8, 1000, modified)
+            .unwrap()
+            .unwrap();
+        let got_phash = cache
+            .get(path, HashAlgorithmKind::Perceptual, 8, 1000, modified)
+            .unwrap()
+            .unwrap();
+        let wrong_size = cache
+            .get(path, HashAlgorithmKind::Difference, 16, 1000, modified)
+            .unwrap();
+
+        assert_eq!(got_dhash.hash, vec![0xDE, 0xAD, 0xBE, 0xEF]);
+        assert_eq!(got_phash.hash, vec![0x01, 0x02]);
+        assert_eq!(got_phash.algorithm, HashAlgorithmKind::Perceptual);
+        assert!(wrong_size.is_none());
+        assert_eq!(cache.stats().unwrap().total_entries, 2);
+
+        // Removing a path drops every algorithm's entry
+        cache.remove(path).unwrap();
+        assert_eq!(cache.stats().unwrap().total_entries, 0);
+    }
+
+    #[test]
+    fn sqlite_cache_discards_legacy_schema() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("cache.db");
+
+        // Layout written by earlier versions: one row per path
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE hashes (
+                    path TEXT PRIMARY KEY,
+                    hash BLOB NOT NULL,
+                    algorithm TEXT NOT NULL,
+                    file_size INTEGER NOT NULL,
+                    file_modified INTEGER NOT NULL,
+                    cached_at INTEGER NOT NULL
+                );
+                CREATE INDEX idx_path ON hashes(path);
+                INSERT INTO hashes VALUES ('/old.jpg', x'CAFE', 'difference', 1000, 1700000000, 1700000000);",
+            )
+            .unwrap();
+        }
+
+        let cache = SqliteCache::open(&db_path).unwrap();
+        let modified = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let path = Path::new("/old.jpg");
+
+        // Legacy hashes ignored EXIF orientation, so they are rehashed
+        let old = cache
+            .get(path, HashAlgorithmKind::Difference, 8, 1000, modified)
+            .unwrap();
+        assert!(old.is_none());
+
+        // The new table is keyed by algorithm and hash size
+        let mut entry = create_entry("/old.jpg");
+        cache.set(entry.clone()).unwrap();
+        entry.hash_size = 16;
+        cache.set(entry).unwrap();
+        assert_eq!(cache.stats().unwrap().total_entries, 2);
+
+        // Reopening an upgraded database is a no-op
+        drop(cache);
+        let cache = SqliteCache::open(&db_path).unwrap();
+        assert_eq!(cache.stats().unwrap().total_entries, 2);
+    }
+
+    #[test]
+    fn sqlite_cache_discards_unoriented_hashes() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("cache.db");
+
+        {
+            let cache = SqliteCache::open(&db_path).unwrap();
+            cache.set(create_entry("/photo.jpg")).unwrap();
+        }
+
+        // Pretend the database was written before orientation was applied
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute_batch("PRAGMA user_version = 1;").unwrap();
+        }
+
+        let cache = SqliteCache::open(&db_path).unwrap();
+        assert_eq!(cache.stats().unwrap().total_entries, 0);
+    }
+
+    #[test]
+    fn sqlite_cache_discards_full_size_hashes() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("cache.db");
+
+        {
+            let cache = SqliteCache::open(&db_path).unwrap();
+            cache.set(create_entry("/photo.jpg")).unwrap();
+        }
+
+        // Pretend the database was written before JPEGs were decoded scaled
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute_batch("PRAGMA user_version = 3;").unwrap();
+        }
+
+        let cache = SqliteCache::open(&db_path).unwrap();
+        assert_eq!(cache.stats().unwrap().total_entries, 0);
+
+        // Pixel hashes are stored alongside the perceptual hash
+        let modified = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let entry = CacheEntry {
+            file_modified: modified,
+            pixel_hash: Some(vec![7; 16]),
+            ..create_entry("/new.jpg")
+        };
+        cache.set_batch(&[entry]).unwrap();
+        let new = cache
+            .get(
+                Path::new("/new.jpg"),
+                HashAlgorithmKind::Difference,
+                8,
+                1000,
+                modified,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(new.pixel_hash, Some(vec![7; 16]));
+    }
+
+    #[test]
+    fn sqlite_cache_adds_variants_to_v5_tables() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("cache.db");
+
+        {
+            let cache = SqliteCache::open(&db_path).unwrap();
+            cache.set(create_entry("/photo.jpg")).unwrap();
+        }
+
+        // Pretend the database was written before variants were stored
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute_batch("ALTER TABLE hashes DROP COLUMN variants; PRAGMA user_version = 5;")
+                .unwrap();
+        }
+
+        let cache = SqliteCache::open(&db_path).unwrap();
+        assert_eq!(cache.stats().unwrap().total_entries, 1);
+
+        let variants = vec![
+            (
+                DihedralTransform::Identity,
+                ImageHashValue::from_bytes(&[1; 8], HashAlgorithmKind::Difference),
+            ),
+            (
+                DihedralTransform::Rotate90,
+                ImageHashValue::from_bytes(&[2; 8], HashAlgorithmKind::Difference),
+            ),
+        ];
+        let modified = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let entry = CacheEntry {
+            file_modified: modified,
+            variants: Some(variants),
+            ..create_entry("/new.jpg")
+        };
+        cache.set(entry).unwrap();
+        let new = cache
+            .get(
+                Path::new("/new.jpg"),
+                HashAlgorithmKind::Difference,
+                8,
+                1000,
+                modified,
+            )
+            .unwrap()
+            .unwrap();
+        let stored = new.variants.unwrap();
+        assert_eq!(stored.len(), 2);
+        assert_eq!(stored[1].0, DihedralTransform::Rotate90);
+        assert_eq!(stored[1].1.as_bytes(), &[2; 8]);
+    }
+
+    #[test]
+    fn sqlite_cache_stores_details() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("cache.db");
+
+        {
+            let cache = SqliteCache::open(&db_path).unwrap();
+            cache.set(create_entry("/photo.jpg")).unwrap();
+        }
+
+        // Pretend the database was written before details were stored
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute_batch("PRAGMA user_version = 4;").unwrap();
+        }
+
+        let cache = SqliteCache::open(&db_path).unwrap();
+        assert_eq!(cache.stats().unwrap().total_entries, 0);
+
+        let details = PhotoDetails {
+            width: 4032,
+            height: 3024,
+            quality: QualityScore {
+                sharpness: 812.5,
+                contrast: 48.0,
+                brightness: 120.0,
+                overall: 42.0,
+            },
+            metadata: PhotoMetadata {
+                camera_make: Some("Apple".to_string()),
+                orientation: Some(6),
+                ..Default::default()
+            },
+        };
+        let modified = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let entry = CacheEntry {
+            file_modified: modified,
+            details: Some(details.clone()),
+            ..create_entry("/new.jpg")
+        };
+        cache.set(entry).unwrap();
+        let new = cache
+            .get(
+                Path::new("/new.jpg"),
+                HashAlgorithmKind::Difference,
+                8,
+                1000,
+                modified,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(new.details, Some(details));
+    }
+
+    #[test]
+    fn sqlite_cache_stores_scan_and_match_state() {
+        use crate::core::comparator::{MatchResult, MatchType};
+        use crate::core::scanner::{ImageFormat, PhotoFile};
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("cache.db");
+
+        // Scan state as earlier versions created it, without listings
+        {
+            let conn = Connection::open(&db_path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE scan_state (
+                    directory TEXT PRIMARY KEY,
+                    last_scan_time INTEGER NOT NULL,
+                    file_count INTEGER NOT NULL
+                );
+                INSERT INTO scan_state VALUES ('/photos', 1700000000, 3);",
+            )
+            .unwrap();
+        }
+
+        let cache = SqliteCache::open(&db_path).unwrap();
+        assert!(cache
+            .get_scan_state(Path::new("/photos"))
+            .unwrap()
+            .is_none());
+
+        let modified = UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+        let photo = PhotoFile {
+            path: PathBuf::from("/photos/a.jpg"),
+            size: 1000,
+            modified,
+            format: ImageFormat::Jpeg,
+            file_id: None,
+        };
+        let state = ScanState {
+            directory: PathBuf::from("/photos"),
+            last_scan_time: modified,
+            file_count: 1,
+            modified,
+            filter: "default".to_string(),
+            photos: vec![photo],
+            subdirectories: vec![PathBuf::from("/photos/2024")],
+            ignore_file: true,
+        };
+        cache.set_scan_states(&[state]).unwrap();
+
+        let stored = cache.get_scan_state(Path::new("/photos")).unwrap().unwrap();
+        // Directory times keep their nanoseconds
+        assert_eq!(stored.modified, modified);
+        assert_eq!(stored.photos[0].path, PathBuf::from("/photos/a.jpg"));
+        assert_eq!(stored.subdirectories, vec![PathBuf::from("/photos/2024")]);
+        assert!(stored.ignore_file);
+
+        let matches = MatchState {
+            settings: "difference".to_string(),
+            photos: vec![(PathBuf::from("/a.jpg"), 1), (PathBuf::from("/b.jpg"), 2)],
+            matches: vec![MatchResult {
+                photo_a: PathBuf::from("/a.jpg"),
+                photo_b: PathBuf::from("/b.jpg"),
+                distance: 2,
+                similarity_percent: 96.875,
+                match_type: MatchType::NearExact,
+                confidence: None,
+                transform: None,
+                crop: None,
+            }],
+        };
+        cache.set_match_state(&matches).unwrap();
+        let stored = cache.get_match_state("difference").unwrap().unwrap();
+        assert_eq!(stored.photos, matches.photos);
+        assert_eq!(stored.matches[0].distance, 2);
+        assert!(cache.get_match_state("perceptual").unwrap().is_none());
+
+        // Clearing the cache forgets what earlier scans saw
+        cache.clear().unwrap();
+        assert!(cache
+            .get_scan_state(Path::new("/photos"))
+            .unwrap()
+            .is_none());
+        assert!(cache.get_match_state("difference").unwrap().is_none());
+    }
+
+    #[test]
+    fn sqlite_cache_stores_content_hashes() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = SqliteCache::open(&temp_dir.path().join("cache.db")).unwrap();
+        let modified = SystemTime::UNIX_EPOCH + std::time::Duration::new(1_700_000_000, 123);
+        let path = PathBuf::from("/photos/a.jpg");
+        cache
+            .set_content_hashes(&[ContentHash {
+                path: path.clone(),
+                file_size: 1000,
+                file_modified: modified,
+                hash: u128::MAX - 1,
+            }])
+            .unwrap();
+
+        assert_eq!(
+            cache.get_content_hash(&path, 1000, modified).unwrap(),
+            Some(u128::MAX - 1)
+        );
+        assert!(cache
+            .get_content_hash(&path, 1001, modified)
+            .unwrap()
+            .is_none());
+        let later = modified + std::time::Duration::from_nanos(1);
+        assert!(cache
+            .get_content_hash(&path, 1000, later)
+            .unwrap()
+            .is_none());
+
+        cache.remove(&path).unwrap();
+        assert!(cache
+            .get_content_hash(&path, 1000, modified)
+            .unwrap()
+            .is_none());
+    }
 }
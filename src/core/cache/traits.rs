@@ -1,6 +1,7 @@
 //! Cache backend trait definition.
 
-use super::{CacheEntry, CacheStats};
+use super::{CacheEntry, CacheStats, ContentHash, MatchState, ScanState};
+use crate::core::hasher::HashAlgorithmKind;
 use crate::error::CacheError;
 use std::path::Path;
 use std::time::SystemTime;
@@ -9,11 +10,14 @@ use std::time::SystemTime;
 pub trait CacheBackend: Send + Sync {
     /// Get a cached hash if it exists and is still valid
     ///
-    /// The entry is only returned if the file hasn't been modified
-    /// since it was cached.
+    /// The entry is only returned if it was computed with the requested
+    /// algorithm and hash size, and the file hasn't been modified since
+    /// it was cached.
     fn get(
         &self,
         path: &Path,
+        algorithm: HashAlgorithmKind,
+        hash_size: u32,
         current_size: u64,
         current_modified: SystemTime,
     ) -> Result<Option<CacheEntry>, CacheError>;
@@ -33,7 +37,7 @@ pub trait CacheBackend: Send + Sync {
         Ok(())
     }
 
-    /// Remove a specific entry
+    /// Remove all entries for a path (every algorithm and hash size)
     fn remove(&self, path: &Path) -> Result<(), CacheError>;
 
     /// Clear all cached entries
@@ -46,4 +50,54 @@ pub trait CacheBackend: Send + Sync {
     ///
     /// Returns the number of entries removed.
     fn prune_orphans(&self) -> Result<usize, CacheError>;
+
+    /// Get what a directory held when it was last read
+    ///
+    /// Backends that don't keep scan state return None, so incremental
+    /// scans read every directory.
+    fn get_scan_state(&self, _directory: &Path) -> Result<Option<ScanState>, CacheError> {
+        Ok(None)
+    }
+
+    /// Store what a directory held
+    fn set_scan_state(&self, _state: &ScanState) -> Result<(), CacheError> {
+        Ok(())
+    }
+
+    /// Store the state of multiple directories in a single transaction.
+    fn set_scan_states(&self, states: &[ScanState]) -> Result<(), CacheError> {
+        for state in states {
+            self.set_scan_state(state)?;
+        }
+        Ok(())
+    }
+
+    /// Get the matches the last scan with these settings found
+    fn get_match_state(&self, _settings: &str) -> Result<Option<MatchState>, CacheError> {
+        Ok(None)
+    }
+
+    /// Store the matches a scan found, replacing any with the same settings
+    fn set_match_state(&self, _state: &MatchState) -> Result<(), CacheError> {
+        Ok(())
+    }
+
+    /// Get the content hash of a file, if it hasn't changed since it was
+    /// stored
+    ///
+    /// Backends that don't keep content hashes return None, so files are
+    /// read again on every scan.
+    fn get_content_hash(
+        &self,
+        _path: &Path,
+        _current_size: u64,
+        _current_modified: SystemTime,
+    ) -> Result<Option<u128>, CacheError> {
+        Ok(None)
+    }
+
+    /// Store the content hashes of multiple files in a single transaction
+    fn set_content_hashes(&self, _hashes: &[ContentHash]) -> Result<(), CacheError> {
+        Ok(())
+    }
 }
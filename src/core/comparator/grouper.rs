@@ -1,32 +1,100 @@
-//! Groups duplicate photos into clusters using transitive relationships.
+//! Groups duplicate photos into clusters.
 //!
-//! # Algorithm
+//! # Algorithms
+//!
+//! ## Transitive Grouping (default)
 //!
 //! Uses the Union-Find (Disjoint Set Union) algorithm to efficiently
 //! group photos based on pairwise similarity matches.
 //!
-//! ## Transitive Grouping
-//!
 //! If A matches B and B matches C, then {A, B, C} forms a single group
 //! even if A doesn't directly match C. This is because similarity is
 //! transitive within a configurable threshold.
 //!
-//! ## Complexity
-//!
 //! - Time: O(n * α(n)) where α is the inverse Ackermann function (~constant)
 //! - Space: O(n) for the parent map
 //!
+//! ## Complete Linkage
+//!
+//! Every pair of photos in a group must match directly, optionally within a
+//! tighter maximum diameter. Closest pairs are merged first, so bursts split
+//! into tight groups instead of one long chain.
+//!
+//! ## Star Clustering
+//!
+//! The best photo (highest score, e.g. largest file) becomes a center and
+//! takes every unassigned photo that matches it. Repeats with the next best
+//! unassigned photo.
+//!
 //! # Example
 //!
 //! ```text
-//! Matches: (A,B), (B,C), (X,Y)
-//! Result:  Group1{A,B,C}, Group2{X,Y}
+//! Matches:          (A,B,2), (B,C,3), (X,Y,0)
+//! Transitive:       {A,B,C}, {X,Y}
+//! Complete linkage: {A,B}, {X,Y}      (A and C never matched)
 //! ```
 
-use super::{DuplicateGroup, MatchResult, MatchType};
+use super::{scale_threshold, CropMatch, DuplicateGroup, MatchResult, MatchType};
+use crate::core::hasher::{DihedralTransform, FusionConfidence};
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
+/// How matching pairs are turned into duplicate groups
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GroupingStrategy {
+    /// Union every matching pair (A~B and B~C gives {A, B, C})
+    #[default]
+    Transitive,
+    /// Every pair in a group must match directly
+    CompleteLinkage,
+    /// Each group is the best photo plus the photos that match it
+    Star,
+    /// Complete linkage where no two photos are further apart than this
+    MaxDiameter(u32),
+}
+
+impl GroupingStrategy {
+    /// Group match results using this strategy
+    ///
+    /// `scores` ranks photos for star clustering (higher is better, e.g.
+    /// file size). Other strategies ignore it.
+    pub fn group(
+        &self,
+        matches: &[MatchResult],
+        scores: &HashMap<PathBuf, u64>,
+    ) -> Vec<DuplicateGroup> {
+        match *self {
+            Self::Transitive => TransitiveGrouper::new().group(matches),
+            Self::CompleteLinkage => CompleteLinkageGrouper::new().group(matches),
+            Self::Star => StarGrouper::new(scores.clone()).group(matches),
+            Self::MaxDiameter(diameter) => CompleteLinkageGrouper::new()
+                .max_diameter(diameter)
+                .group(matches),
+        }
+    }
+
+    /// Rescale a max diameter given on the 64-bit scale to `bits`-bit
+    /// hashes (see [`scale_threshold`])
+    pub fn for_hash_bits(self, bits: u32) -> Self {
+        match self {
+            Self::MaxDiameter(diameter) => Self::MaxDiameter(scale_threshold(diameter, bits)),
+            other => other,
+        }
+    }
+}
+
+impl std::fmt::Display for GroupingStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Transitive => write!(f, "transitive"),
+            Self::CompleteLinkage => write!(f, "complete linkage"),
+            Self::Star => write!(f, "star"),
+            Self::MaxDiameter(diameter) => write!(f, "max diameter {}", diameter),
+        }
+    }
+}
+
 /// Union-Find data structure for grouping paths.
 ///
 /// Uses path compression for near-constant time operations.
@@ -77,12 +145,68 @@ impl UnionFind {
     }
 }
 
+/// Distances of directly matched pairs, plus each photo's neighbors
+struct PairDistances {
+    distances: HashMap<(PathBuf, PathBuf), u32>,
+    neighbors: HashMap<PathBuf, Vec<PathBuf>>,
+}
+
+impl PairDistances {
+    fn new<'a>(matches: impl IntoIterator<Item = &'a MatchResult>) -> Self {
+        let mut distances = HashMap::new();
+        let mut neighbors: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+        for m in matches {
+            distances.insert(Self::key(&m.photo_a, &m.photo_b), m.distance);
+            neighbors
+                .entry(m.photo_a.clone())
+                .or_default()
+                .push(m.photo_b.clone());
+            neighbors
+                .entry(m.photo_b.clone())
+                .or_default()
+                .push(m.photo_a.clone());
+        }
+        Self {
+            distances,
+            neighbors,
+        }
+    }
+
+    fn key(a: &PathBuf, b: &PathBuf) -> (PathBuf, PathBuf) {
+        if a <= b {
+            (a.clone(), b.clone())
+        } else {
+            (b.clone(), a.clone())
+        }
+    }
+
+    /// Distance between two photos, or None if they never matched
+    fn get(&self, a: &PathBuf, b: &PathBuf) -> Option<u32> {
+        self.distances.get(&Self::key(a, b)).copied()
+    }
+
+    /// Worst distance between any two photos, or None if some pair never
+    /// matched directly
+    fn diameter(&self, photos: &[PathBuf]) -> Option<u32> {
+        let mut worst = 0;
+        for (i, a) in photos.iter().enumerate() {
+            for b in &photos[i + 1..] {
+                worst = worst.max(self.get(a, b)?);
+            }
+        }
+        Some(worst)
+    }
+}
+
 /// Tracks aggregate match statistics for a group
 #[derive(Clone)]
 struct GroupStats {
     total_distance: f64,
     match_count: usize,
-    best_match_type: MatchType,
+    weakest_match: Option<MatchResult>,
+    weakest_confidence: Option<FusionConfidence>,
+    transform: Option<DihedralTransform>,
+    crop: Option<CropMatch>,
 }
 
 impl GroupStats {
@@ -90,17 +214,48 @@ impl GroupStats {
         Self {
             total_distance: 0.0,
             match_count: 0,
-            best_match_type: MatchType::MaybeSimilar,
+            weakest_match: None,
+            weakest_confidence: None,
+            transform: None,
+            crop: None,
         }
     }
 
-    fn add_match(&mut self, distance: u32, match_type: MatchType) {
-        self.total_distance += distance as f64;
+    fn add_match(&mut self, m: &MatchResult) {
+        self.total_distance += m.distance as f64;
         self.match_count += 1;
-        // Keep the best (lowest ordinal = more exact) match type
-        if (match_type as u8) < (self.best_match_type as u8) {
-            self.best_match_type = match_type;
+        // Keep the weakest (highest ordinal = less exact, then furthest)
+        // match, so a byte-identical pair doesn't vouch for the rest of its
+        // group
+        let weaker = self.weakest_match.as_ref().is_none_or(|current| {
+            (m.match_type as u8, m.distance) > (current.match_type as u8, current.distance)
+        });
+        if weaker {
+            self.weakest_match = Some(m.clone());
+        }
+        // Keep the weakest (highest ordinal = fewer votes) confidence
+        if let Some(confidence) = m.confidence {
+            let weaker = self
+                .weakest_confidence
+                .is_none_or(|current| (confidence as u8) > (current as u8));
+            if weaker {
+                self.weakest_confidence = Some(confidence);
+            }
         }
+        // Remember the first rotated or mirrored match
+        if self.transform.is_none() {
+            self.transform = m.transform;
+        }
+        // Remember the first crop match
+        if self.crop.is_none() {
+            self.crop = m.crop.clone();
+        }
+    }
+
+    fn match_type(&self) -> MatchType {
+        self.weakest_match
+            .as_ref()
+            .map_or(MatchType::MaybeSimilar, |m| m.match_type)
     }
 
     fn average_distance(&self) -> f64 {
@@ -112,6 +267,73 @@ impl GroupStats {
     }
 }
 
+/// Convert clusters of photos into DuplicateGroups.
+///
+/// The first photo of each cluster becomes the representative. Statistics
+/// only count matches between photos of the same cluster.
+fn build_groups(clusters: Vec<Vec<PathBuf>>, matches: &[MatchResult]) -> Vec<DuplicateGroup> {
+    let cluster_of: HashMap<&PathBuf, usize> = clusters
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, photos)| photos.iter().map(move |p| (p, idx)))
+        .collect();
+
+    let mut stats = vec![GroupStats::new(); clusters.len()];
+    let mut internal = vec![Vec::new(); clusters.len()];
+    for m in matches {
+        if let (Some(&a), Some(&b)) = (cluster_of.get(&m.photo_a), cluster_of.get(&m.photo_b)) {
+            if a == b {
+                stats[a].add_match(m);
+                internal[a].push(m);
+            }
+        }
+    }
+
+    clusters
+        .into_iter()
+        .zip(stats)
+        .zip(internal)
+        .map(|((mut photos, stats), internal)| {
+            let representative = photos[0].clone();
+            photos.sort(); // Deterministic ordering
+            let pairs = PairDistances::new(internal);
+            let max_distance = pairs.diameter(&photos);
+
+            let mut group = DuplicateGroup::new(photos, representative, stats.match_type());
+            group.average_distance = stats.average_distance();
+            group.max_distance = max_distance;
+            group.confidence = stats.weakest_confidence;
+            group.transform = stats.transform;
+            group.crop = stats.crop;
+            group.weakest_match = stats.weakest_match;
+            group
+        })
+        .collect()
+}
+
+/// Fill in the worst distance of groups where some pair never matched
+/// directly, such as the ends of a transitive chain or two members of a star.
+///
+/// Pairs that matched keep the distance they matched at, and the others are
+/// measured with `distance`. A group is left at None if a pair can't be.
+pub fn measure_diameters(
+    groups: &mut [DuplicateGroup],
+    matches: &[MatchResult],
+    distance: impl Fn(&PathBuf, &PathBuf) -> Option<u32>,
+) {
+    let pairs = PairDistances::new(matches);
+    for group in groups.iter_mut().filter(|g| g.max_distance.is_none()) {
+        let mut worst = Some(0);
+        for (i, a) in group.photos.iter().enumerate() {
+            for b in &group.photos[i + 1..] {
+                let d = pairs.get(a, b).or_else(|| distance(a, b));
+                worst = worst.zip(d).map(|(w, d)| w.max(d));
+            }
+        }
+        group.max_distance = worst;
+    }
+}
+
 /// Groups photos into duplicate clusters using transitive relationships
 pub struct TransitiveGrouper;
 
@@ -131,34 +353,6 @@ impl TransitiveGrouper {
         photos
     }
 
-    /// Build statistics for each group based on matches
-    fn build_group_stats(
-        matches: &[MatchResult],
-        uf: &mut UnionFind,
-    ) -> HashMap<PathBuf, GroupStats> {
-        let mut stats: HashMap<PathBuf, GroupStats> = HashMap::new();
-        for m in matches {
-            let root = uf.find(&m.photo_a);
-            stats
-                .entry(root)
-                .or_insert_with(GroupStats::new)
-                .add_match(m.distance, m.match_type);
-        }
-        stats
-    }
-
-    /// Convert a grouped set of photos into a DuplicateGroup
-    fn build_duplicate_group(
-        mut photos: Vec<PathBuf>,
-        stats: &GroupStats,
-    ) -> DuplicateGroup {
-        photos.sort(); // Deterministic ordering
-        let representative = photos[0].clone();
-        let mut group = DuplicateGroup::new(photos, representative, stats.best_match_type);
-        group.average_distance = stats.average_distance();
-        group
-    }
-
     /// Group match results into duplicate clusters
     ///
     /// Uses union-find to efficiently group photos transitively.
@@ -176,27 +370,173 @@ impl TransitiveGrouper {
             uf.union(&m.photo_a, &m.photo_b);
         }
 
-        // Get statistics for each group
-        let stats = Self::build_group_stats(matches, &mut uf);
+        // Sorted so the representative is the first path
+        let clusters = uf
+            .groups()
+            .into_values()
+            .filter(|photos| photos.len() >= 2)
+            .map(|mut photos| {
+                photos.sort();
+                photos
+            })
+            .collect();
+
+        build_groups(clusters, matches)
+    }
+}
+
+impl Default for TransitiveGrouper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Groups photos so that every pair within a group matches directly
+pub struct CompleteLinkageGrouper {
+    /// Largest distance allowed between any two photos in a group
+    max_diameter: Option<u32>,
+}
+
+impl CompleteLinkageGrouper {
+    /// Create a new complete-linkage grouper with no diameter cap
+    pub fn new() -> Self {
+        Self { max_diameter: None }
+    }
+
+    /// Cap the distance between any two photos in a group
+    ///
+    /// Pairs further apart than this are treated as non-matching.
+    pub fn max_diameter(mut self, diameter: u32) -> Self {
+        self.max_diameter = Some(diameter);
+        self
+    }
+
+    /// Group match results into duplicate clusters
+    ///
+    /// Merges clusters closest pair first, and only when every photo in one
+    /// cluster matches every photo in the other.
+    pub fn group(&self, matches: &[MatchResult]) -> Vec<DuplicateGroup> {
+        let mut allowed: Vec<&MatchResult> = matches
+            .iter()
+            .filter(|m| self.max_diameter.is_none_or(|cap| m.distance <= cap))
+            .collect();
+        allowed.sort_by(|a, b| {
+            a.distance
+                .cmp(&b.distance)
+                .then_with(|| a.photo_a.cmp(&b.photo_a))
+                .then_with(|| a.photo_b.cmp(&b.photo_b))
+        });
+        let pairs = PairDistances::new(allowed.iter().copied());
+
+        let mut cluster_of: HashMap<PathBuf, usize> = HashMap::new();
+        let mut clusters: Vec<Vec<PathBuf>> = Vec::new();
+        for m in &allowed {
+            let mut cluster_for = |photo: &PathBuf| {
+                *cluster_of.entry(photo.clone()).or_insert_with(|| {
+                    clusters.push(vec![photo.clone()]);
+                    clusters.len() - 1
+                })
+            };
+            let a = cluster_for(&m.photo_a);
+            let b = cluster_for(&m.photo_b);
+            if a == b {
+                continue;
+            }
+
+            let fully_linked = clusters[a]
+                .iter()
+                .all(|x| clusters[b].iter().all(|y| pairs.get(x, y).is_some()));
+            if !fully_linked {
+                continue;
+            }
+
+            // Merge the smaller cluster into the larger one
+            let (keep, absorb) = if clusters[a].len() >= clusters[b].len() {
+                (a, b)
+            } else {
+                (b, a)
+            };
+            let moved = std::mem::take(&mut clusters[absorb]);
+            for photo in &moved {
+                cluster_of.insert(photo.clone(), keep);
+            }
+            clusters[keep].extend(moved);
+        }
 
-        // Convert to DuplicateGroups
-        uf.groups()
+        let clusters = clusters
             .into_iter()
-            .filter(|(_, photos)| photos.len() >= 2)
-            .map(|(root, photos)| {
-                let group_stats = stats.get(&root).cloned().unwrap_or_else(GroupStats::new);
-                Self::build_duplicate_group(photos, &group_stats)
+            .filter(|photos| photos.len() >= 2)
+            .map(|mut photos| {
+                photos.sort();
+                photos
             })
-            .collect()
+            .collect();
+
+        build_groups(clusters, matches)
     }
 }
 
-impl Default for TransitiveGrouper {
+impl Default for CompleteLinkageGrouper {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Groups photos around the best photo of each cluster
+pub struct StarGrouper {
+    /// Photo scores, higher is better (missing photos score 0)
+    scores: HashMap<PathBuf, u64>,
+}
+
+impl StarGrouper {
+    /// Create a new star grouper ranking photos by `scores`
+    pub fn new(scores: HashMap<PathBuf, u64>) -> Self {
+        Self { scores }
+    }
+
+    fn score(&self, photo: &PathBuf) -> u64 {
+        self.scores.get(photo).copied().unwrap_or(0)
+    }
+
+    /// Group match results into duplicate clusters
+    ///
+    /// The best unassigned photo becomes a center and takes every
+    /// unassigned photo that matches it. The center is the representative.
+    pub fn group(&self, matches: &[MatchResult]) -> Vec<DuplicateGroup> {
+        let pairs = PairDistances::new(matches);
+
+        let mut candidates: Vec<&PathBuf> = pairs.neighbors.keys().collect();
+        candidates.sort_by(|a, b| self.score(b).cmp(&self.score(a)).then_with(|| a.cmp(b)));
+
+        let mut assigned: HashSet<&PathBuf> = HashSet::new();
+        let mut clusters = Vec::new();
+        for center in candidates {
+            if assigned.contains(center) {
+                continue;
+            }
+
+            let mut members: Vec<&PathBuf> = pairs.neighbors[center]
+                .iter()
+                .filter(|p| !assigned.contains(p))
+                .collect();
+            if members.is_empty() {
+                continue;
+            }
+            members.sort();
+            members.dedup();
+
+            assigned.insert(center);
+            assigned.extend(members.iter().copied());
+
+            let mut photos = vec![center.clone()];
+            photos.extend(members.into_iter().cloned());
+            clusters.push(photos);
+        }
+
+        build_groups(clusters, matches)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -208,6 +548,9 @@ mod tests {
             distance,
             similarity_percent: 100.0 - (distance as f64 * 1.5625),
             match_type: MatchType::from_distance(distance),
+            confidence: None,
+            transform: None,
+            crop: None,
         }
     }
 
@@ -333,8 +676,7 @@ mod tests {
     }
 
     #[test]
-    fn best_match_type_is_preserved() {
-        // When grouping photos with different match types, best should be preserved
+    fn weakest_match_type_labels_the_group() {
         let grouper = TransitiveGrouper::new();
         let matches = vec![
             create_match("/a.jpg", "/b.jpg", 0),  // Exact
@@ -343,8 +685,207 @@ mod tests {
 
         let groups = grouper.group(&matches);
 
-        // The group should have the "best" match type (Exact)
-        assert_eq!(groups[0].match_type, MatchType::Exact);
+        // Not every photo is an exact copy of the others
+        assert_eq!(groups[0].match_type, MatchType::Similar);
+    }
+
+    #[test]
+    fn byte_identical_pair_does_not_label_a_mixed_group() {
+        let grouper = TransitiveGrouper::new();
+        let matches = vec![
+            MatchResult {
+                match_type: MatchType::ByteIdentical,
+                ..create_match("/a.png", "/b.png", 0)
+            },
+            create_match("/a.png", "/c.png", 3),
+            create_match("/b.png", "/c.png", 3),
+        ];
+
+        let groups = grouper.group(&matches);
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].photos.len(), 3);
+        assert_eq!(groups[0].match_type, MatchType::NearExact);
+
+        // On its own the pair is still byte-identical
+        let groups = grouper.group(&matches[..1]);
+        assert_eq!(groups[0].match_type, MatchType::ByteIdentical);
+    }
+
+    #[test]
+    fn group_reports_weakest_fusion_confidence() {
+        let grouper = TransitiveGrouper::new();
+        let matches = vec![
+            MatchResult {
+                confidence: Some(FusionConfidence::High),
+                ..create_match("/a.jpg", "/b.jpg", 0)
+            },
+            MatchResult {
+                confidence: Some(FusionConfidence::Medium),
+                ..create_match("/b.jpg", "/c.jpg", 3)
+            },
+        ];
+
+        let groups = grouper.group(&matches);
+
+        assert_eq!(groups[0].confidence, Some(FusionConfidence::Medium));
+    }
+
+    #[test]
+    fn fully_matched_group_reports_max_distance() {
+        let grouper = TransitiveGrouper::new();
+        let matches = vec![
+            create_match("/a.jpg", "/b.jpg", 1),
+            create_match("/b.jpg", "/c.jpg", 2),
+            create_match("/a.jpg", "/c.jpg", 4),
+        ];
+
+        let groups = grouper.group(&matches);
+
+        assert_eq!(groups[0].max_distance, Some(4));
+    }
+
+    #[test]
+    fn transitive_chain_has_unknown_max_distance() {
+        let grouper = TransitiveGrouper::new();
+        let matches = vec![
+            create_match("/a.jpg", "/b.jpg", 2),
+            create_match("/b.jpg", "/c.jpg", 3),
+        ];
+
+        let groups = grouper.group(&matches);
+
+        // A and C never matched, so they are beyond the threshold
+        assert_eq!(groups[0].max_distance, None);
+    }
+
+    #[test]
+    fn measured_chain_reports_its_ends() {
+        let matches = vec![
+            create_match("/a.jpg", "/b.jpg", 2),
+            create_match("/b.jpg", "/c.jpg", 3),
+        ];
+        let mut groups = TransitiveGrouper::new().group(&matches);
+
+        // Only the pair that never matched is measured
+        measure_diameters(&mut groups, &matches, |a, b| {
+            assert_eq!((a.to_str(), b.to_str()), (Some("/a.jpg"), Some("/c.jpg")));
+            Some(14)
+        });
+        assert_eq!(groups[0].max_distance, Some(14));
+
+        let mut unmeasured = TransitiveGrouper::new().group(&matches);
+        measure_diameters(&mut unmeasured, &matches, |_, _| None);
+        assert_eq!(unmeasured[0].max_distance, None);
+    }
+
+    #[test]
+    fn complete_linkage_breaks_chains() {
+        let grouper = CompleteLinkageGrouper::new();
+        let matches = vec![
+            create_match("/a.jpg", "/b.jpg", 2),
+            create_match("/b.jpg", "/c.jpg", 3),
+        ];
+
+        let groups = grouper.group(&matches);
+
+        // The closest pair wins; C can't join because it never matched A
+        assert_eq!(groups.len(), 1);
+        assert_eq!(
+            groups[0].photos,
+            vec![PathBuf::from("/a.jpg"), PathBuf::from("/b.jpg")]
+        );
+        assert_eq!(groups[0].max_distance, Some(2));
+    }
+
+    #[test]
+    fn complete_linkage_keeps_cliques_together() {
+        let grouper = CompleteLinkageGrouper::new();
+        let matches = vec![
+            create_match("/a.jpg", "/b.jpg", 1),
+            create_match("/b.jpg", "/c.jpg", 1),
+            create_match("/a.jpg", "/c.jpg", 2),
+            create_match("/c.jpg", "/d.jpg", 0),
+        ];
+
+        let groups = grouper.group(&matches);
+
+        // C~D is closest, but D never matched A or B
+        let mut sizes: Vec<_> = groups.iter().map(|g| g.photos.len()).collect();
+        sizes.sort();
+        assert_eq!(sizes, vec![2, 2]);
+    }
+
+    #[test]
+    fn max_diameter_caps_groups() {
+        let grouper = CompleteLinkageGrouper::new().max_diameter(3);
+        let matches = vec![
+            create_match("/a.jpg", "/b.jpg", 1),
+            create_match("/b.jpg", "/c.jpg", 2),
+            create_match("/a.jpg", "/c.jpg", 6),
+        ];
+
+        let groups = grouper.group(&matches);
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].photos.len(), 2);
+        assert!(groups[0].max_distance.unwrap() <= 3);
+    }
+
+    #[test]
+    fn star_groups_around_best_photo() {
+        let scores = HashMap::from([
+            (PathBuf::from("/a.jpg"), 100),
+            (PathBuf::from("/b.jpg"), 500),
+            (PathBuf::from("/c.jpg"), 200),
+        ]);
+        let grouper = StarGrouper::new(scores);
+        let matches = vec![
+            create_match("/a.jpg", "/b.jpg", 2),
+            create_match("/b.jpg", "/c.jpg", 3),
+        ];
+
+        let groups = grouper.group(&matches);
+
+        // B is the best photo and matches both others
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].photos.len(), 3);
+        assert_eq!(groups[0].representative, PathBuf::from("/b.jpg"));
+    }
+
+    #[test]
+    fn star_does_not_chain_through_members() {
+        let scores = HashMap::from([(PathBuf::from("/a.jpg"), 500)]);
+        let grouper = StarGrouper::new(scores);
+        let matches = vec![
+            create_match("/a.jpg", "/b.jpg", 2),
+            create_match("/b.jpg", "/c.jpg", 3),
+        ];
+
+        let groups = grouper.group(&matches);
+
+        // C only matches B, which already belongs to A's star
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].representative, PathBuf::from("/a.jpg"));
+        assert!(!groups[0].photos.contains(&PathBuf::from("/c.jpg")));
+    }
+
+    #[test]
+    fn strategy_dispatches_to_grouper() {
+        let matches = vec![
+            create_match("/a.jpg", "/b.jpg", 2),
+            create_match("/b.jpg", "/c.jpg", 3),
+        ];
+        let scores = HashMap::new();
+
+        let transitive = GroupingStrategy::Transitive.group(&matches, &scores);
+        let complete = GroupingStrategy::CompleteLinkage.group(&matches, &scores);
+        let capped = GroupingStrategy::MaxDiameter(1).group(&matches, &scores);
+
+        assert_eq!(transitive[0].photos.len(), 3);
+        assert_eq!(complete[0].photos.len(), 2);
+        assert!(capped.is_empty());
+        assert_eq!(GroupingStrategy::default(), GroupingStrategy::Transitive);
     }
 
     #[test]
@@ -0,0 +1,334 @@
+//! # Multi-Index Hashing (MIH)
+//!
+//! Exact Hamming-radius search without comparing every pair.
+//!
+//! ## How It Works
+//! 1. Split each `b`-bit hash into `m` disjoint substrings
+//! 2. Index every substring in its own hash table
+//! 3. To find all hashes within distance `r`, probe each table with every
+//!    substring variant within distance `⌊r / m⌋` of the query's substring
+//! 4. Verify each candidate with a full Hamming distance
+//!
+//! ## Why It Is Exact
+//! If two hashes differ in at most `r` bits, the differences are spread over
+//! `m` substrings, so at least one substring differs in at most `⌊r / m⌋`
+//! bits (pigeonhole principle). That substring is always probed, so every
+//! true neighbor is a candidate. Unlike banded LSH, there is no threshold
+//! above which pairs are silently missed.
+//!
+//! ## Choosing `m`
+//! More substrings mean fewer probes per table but larger buckets. The index
+//! estimates the cost of each `m` from the hash length, the radius and the
+//! number of entries, and picks the cheapest. When no partitioning beats
+//! comparing against everything, it degrades to a linear scan.
+
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+/// Largest substring the index will use (it must fit in a `u64` key)
+const MAX_SUBSTRING_BITS: usize = 64;
+
+/// A contiguous range of bits within a hash
+#[derive(Debug, Clone, Copy)]
+struct Substring {
+    start: usize,
+    len: usize,
+}
+
+/// Multi-index hash table over fixed-length binary codes
+pub struct MultiIndexHash {
+    /// Indexed codes, all of the same length
+    codes: Vec<Vec<u8>>,
+    /// Search radius in bits
+    radius: u32,
+    /// Bit ranges, one per table (empty = linear scan)
+    substrings: Vec<Substring>,
+    /// Radius used when probing each table
+    sub_radius: u32,
+    /// substring value -> indices of codes with that value
+    tables: Vec<HashMap<u64, Vec<usize>>>,
+}
+
+impl MultiIndexHash {
+    /// Build an index over `codes` for searches within `radius` bits.
+    ///
+    /// Returns None if the codes don't all have the same length, since
+    /// partitioning is only meaningful over aligned bits.
+    pub fn build(codes: Vec<Vec<u8>>, radius: u32) -> Option<Self> {
+        let byte_len = codes.first().map(|c| c.len()).unwrap_or(0);
+        if codes.iter().any(|c| c.len() != byte_len) {
+            return None;
+        }
+
+        let bit_count = byte_len * 8;
+        let (substrings, sub_radius) = choose_partitioning(bit_count, radius, codes.len());
+
+        let mut tables: Vec<HashMap<u64, Vec<usize>>> = vec![HashMap::new(); substrings.len()];
+        for (idx, code) in codes.iter().enumerate() {
+            for (table, substring) in tables.iter_mut().zip(&substrings) {
+                table
+                    .entry(extract_bits(code, *substring))
+                    .or_default()
+                    .push(idx);
+            }
+        }
+
+        Some(Self {
+            codes,
+            radius,
+            substrings,
+            sub_radius,
+            tables,
+        })
+    }
+
+    /// Number of indexed codes
+    pub fn len(&self) -> usize {
+        self.codes.len()
+    }
+
+    /// Check if the index is empty
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+
+    /// Number of substrings (tables) the codes were split into.
+    ///
+    /// Zero means the index fell back to a linear scan.
+    pub fn partitions(&self) -> usize {
+        self.substrings.len()
+    }
+
+    /// Find the indices of all codes within the search radius of `code`.
+    ///
+    /// Results are verified and sorted by index. A code of a different
+    /// length than the indexed ones matches nothing.
+    pub fn query(&self, code: &[u8]) -> Vec<usize> {
+        if self.codes.first().is_some_and(|c| c.len() != code.len()) {
+            return Vec::new();
+        }
+
+        let mut found = if self.substrings.is_empty() {
+            (0..self.codes.len()).collect()
+        } else {
+            let mut candidates = Vec::new();
+            for (table, substring) in self.tables.iter().zip(&self.substrings) {
+                let key = extract_bits(code, *substring);
+                for_each_neighbor(key, substring.len, self.sub_radius, &mut |probe| {
+                    if let Some(bucket) = table.get(&probe) {
+                        candidates.extend_from_slice(bucket);
+                    }
+                });
+            }
+            candidates.sort_unstable();
+            candidates.dedup();
+            candidates
+        };
+
+        found.retain(|&idx| hamming(&self.codes[idx], code) <= self.radius);
+        found
+    }
+
+    /// Find all index pairs `(i, j)` with `i < j` whose codes are within the
+    /// search radius of each other, sorted lexicographically.
+    pub fn pairs(&self) -> Vec<(usize, usize)> {
+        (0..self.codes.len())
+            .into_par_iter()
+            .flat_map_iter(|i| {
+                self.query(&self.codes[i])
+                    .into_iter()
+                    .filter(move |&j| j > i)
+                    .map(move |j| (i, j))
+            })
+            .collect()
+    }
+}
+
+/// Pick the substring layout with the lowest estimated query cost.
+///
+/// Cost per query for `m` substrings of (at most) `len` bits probed at
+/// radius `r' = ⌊radius / m⌋` is `m * probes * (1 + n / 2^len)`, where
+/// `probes = Σ_{k≤r'} C(len, k)`: every probe is a table lookup, and each
+/// lookup returns `n / 2^len` entries on average for uniform hashes.
+fn choose_partitioning(bit_count: usize, radius: u32, n: usize) -> (Vec<Substring>, u32) {
+    let linear_cost = n as f64;
+    let mut best: Option<(f64, usize)> = None;
+
+    let max_m = bit_count.min(radius as usize + 1);
+    for m in 1..=max_m {
+        let len = bit_count.div_ceil(m);
+        if len > MAX_SUBSTRING_BITS {
+            continue;
+        }
+        let sub_radius = radius as usize / m;
+        if sub_radius >= len {
+            continue;
+        }
+        let probes = binomial_sum(len, sub_radius);
+        let bucket = n as f64 / 2f64.powi(len as i32);
+        let cost = m as f64 * probes * (1.0 + bucket);
+        if best.is_none_or(|(best_cost, _)| cost < best_cost) {
+            best = Some((cost, m));
+        }
+    }
+
+    match best {
+        Some((cost, m)) if cost < linear_cost => (split_bits(bit_count, m), radius / m as u32),
+        _ => (Vec::new(), radius),
+    }
+}
+
+/// Split `bit_count` bits into `m` contiguous substrings of near-equal length
+fn split_bits(bit_count: usize, m: usize) -> Vec<Substring> {
+    let base = bit_count / m;
+    let extra = bit_count % m;
+    let mut start = 0;
+    (0..m)
+        .map(|i| {
+            let len = base + usize::from(i < extra);
+            let substring = Substring { start, len };
+            start += len;
+            substring
+        })
+        .collect()
+}
+
+/// Σ_{k=0}^{r} C(n, k) as a float (only used for cost estimates)
+fn binomial_sum(n: usize, r: usize) -> f64 {
+    let mut total = 0.0;
+    let mut term = 1.0;
+    for k in 0..=r {
+        total += term;
+        term = term * (n - k) as f64 / (k + 1) as f64;
+    }
+    total
+}
+
+/// Read `substring.len` bits (MSB-first) starting at `substring.start`
+fn extract_bits(code: &[u8], substring: Substring) -> u64 {
+    let mut value = 0u64;
+    for bit in substring.start..substring.start + substring.len {
+        let set = (code[bit / 8] >> (7 - bit % 8)) & 1;
+        value = (value << 1) | set as u64;
+    }
+    value
+}
+
+/// Call `visit` for every value within `radius` bit flips of `key`
+fn for_each_neighbor(key: u64, len: usize, radius: u32, visit: &mut impl FnMut(u64)) {
+    fn flip_from(value: u64, from: usize, len: usize, left: u32, visit: &mut impl FnMut(u64)) {
+        visit(value);
+        if left == 0 {
+            return;
+        }
+        for bit in from..len {
+            flip_from(value ^ (1 << bit), bit + 1, len, left - 1, visit);
+        }
+    }
+    flip_from(key, 0, len, radius, visit);
+}
+
+/// Hamming distance between equal-length codes
+fn hamming(a: &[u8], b: &[u8]) -> u32 {
+    a.iter().zip(b).map(|(x, y)| (x ^ y).count_ones()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic xorshift generator so tests are reproducible
+    struct TestRng(u64);
+
+    impl TestRng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn code(&mut self, bytes: usize) -> Vec<u8> {
+            (0..bytes).map(|_| self.next() as u8).collect()
+        }
+    }
+
+    fn brute_force_pairs(codes: &[Vec<u8>], radius: u32) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        for i in 0..codes.len() {
+            for j in (i + 1)..codes.len() {
+                if hamming(&codes[i], &codes[j]) <= radius {
+                    pairs.push((i, j));
+                }
+            }
+        }
+        pairs
+    }
+
+    #[test]
+    fn extract_bits_reads_msb_first() {
+        let code = [0b1010_0000, 0b0000_0001];
+        assert_eq!(extract_bits(&code, Substring { start: 0, len: 4 }), 0b1010);
+        assert_eq!(extract_bits(&code, Substring { start: 12, len: 4 }), 0b0001);
+    }
+
+    #[test]
+    fn neighbors_are_enumerated_exactly_once() {
+        let mut seen = Vec::new();
+        for_each_neighbor(0, 8, 2, &mut |v| seen.push(v));
+
+        // C(8,0) + C(8,1) + C(8,2) = 1 + 8 + 28
+        assert_eq!(seen.len(), 37);
+        seen.sort_unstable();
+        seen.dedup();
+        assert_eq!(seen.len(), 37);
+        assert!(seen.iter().all(|v| v.count_ones() <= 2));
+    }
+
+    #[test]
+    fn partitions_cover_all_bits() {
+        let substrings = split_bits(64, 3);
+        assert_eq!(substrings.iter().map(|s| s.len).sum::<usize>(), 64);
+        assert_eq!(substrings[2].start + substrings[2].len, 64);
+    }
+
+    #[test]
+    fn mixed_lengths_are_rejected() {
+        let codes = vec![vec![0u8; 8], vec![0u8; 4]];
+        assert!(MultiIndexHash::build(codes, 4).is_none());
+    }
+
+    #[test]
+    fn large_collections_are_partitioned() {
+        let mut rng = TestRng(7);
+        let codes: Vec<Vec<u8>> = (0..5000).map(|_| rng.code(8)).collect();
+
+        let index = MultiIndexHash::build(codes, 8).unwrap();
+
+        assert!(index.partitions() > 1);
+    }
+
+    #[test]
+    fn matches_brute_force_on_random_data() {
+        let mut rng = TestRng(0x9E37_79B9_7F4A_7C15);
+
+        for &(bytes, radius) in &[(8, 0), (8, 3), (8, 8), (8, 12), (8, 20), (32, 16), (32, 40)] {
+            // Random codes plus near-duplicates so every radius has hits
+            let mut codes: Vec<Vec<u8>> = (0..300).map(|_| rng.code(bytes)).collect();
+            for i in 0..150 {
+                let mut copy = codes[i].clone();
+                let flips = rng.next() as usize % (radius as usize + 3);
+                for _ in 0..flips {
+                    let bit = rng.next() as usize % (bytes * 8);
+                    copy[bit / 8] ^= 1 << (bit % 8);
+                }
+                codes.push(copy);
+            }
+
+            let expected = brute_force_pairs(&codes, radius);
+            let index = MultiIndexHash::build(codes, radius).unwrap();
+
+            assert_eq!(index.pairs(), expected, "bytes={} radius={}", bytes, radius);
+        }
+    }
+}
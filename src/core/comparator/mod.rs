@@ -5,7 +5,23 @@
 //! ## How It Works
 //! 1. Compare all photo pairs using Hamming distance
 //! 2. Apply comparison strategy to determine duplicates
-//! 3. Group duplicates into clusters (transitive grouping)
+//! 3. Group duplicates into clusters (see [`GroupingStrategy`])
+//!
+//! Large collections use a [`MultiIndexHash`] to find the same pairs
+//! without comparing everything (see [`find_duplicate_pairs_indexed`]).
+//! Incremental scans only compare new and changed photos (see
+//! [`find_duplicate_pairs_involving`]).
+//!
+//! Rotated and mirrored copies are found by comparing hash variants (see
+//! [`find_transformed_pairs`]).
+//!
+//! Crop-resistant hashes also match a photo against the windows of another,
+//! so a cropped copy is reported along with the region it was cut from (see
+//! [`CropMatch`]).
+//!
+//! Fusion hashes are decoded into their aHash/dHash/pHash parts (plus any
+//! extra voters) and only count as a match when a majority agrees (see
+//! [`compare_hashes`]).
 //!
 //! ## Comparison Thresholds
 //! | Distance | Classification |
@@ -14,19 +30,55 @@
 //! | 1-4      | Near-exact    |
 //! | 5-10     | Similar       |
 //! | 11+      | Different     |
+//!
+//! Thresholds and classifications are on the scale of a 64-bit (8x8) hash.
+//! Larger hashes are compared at their own bit count and scaled to match
+//! (see [`scale_distance`]), so a threshold of 8 accepts 32 of 256 bits.
 
 mod grouper;
 pub mod lsh;
+pub mod mih;
 mod traits;
 
-pub use grouper::TransitiveGrouper;
+pub use grouper::{
+    measure_diameters, CompleteLinkageGrouper, GroupingStrategy, StarGrouper, TransitiveGrouper,
+};
 pub use lsh::{LshConfig, LshIndex, LshIndexStats};
+pub use mih::MultiIndexHash;
 pub use traits::{ComparisonStrategy, ThresholdStrategy};
 
-use crate::core::hasher::{ImageHashValue, PerceptualHash};
+/// Hash length, in bits, that thresholds and classifications are given for
+pub const REFERENCE_HASH_BITS: u32 = 64;
+
+/// Convert a distance between `bits`-bit hashes to the 64-bit scale.
+///
+/// Rounds up, so a scaled distance never looks closer than it is.
+pub fn scale_distance(distance: u32, bits: u32) -> u32 {
+    if bits == 0 || bits == REFERENCE_HASH_BITS {
+        return distance;
+    }
+    (distance as u64 * REFERENCE_HASH_BITS as u64).div_ceil(bits as u64) as u32
+}
+
+/// Convert a threshold on the 64-bit scale to `bits`-bit hashes.
+///
+/// Returns the largest distance whose [`scale_distance`] is within the
+/// threshold.
+pub fn scale_threshold(threshold: u32, bits: u32) -> u32 {
+    if bits == 0 || bits == REFERENCE_HASH_BITS {
+        return threshold;
+    }
+    (threshold as u64 * bits as u64 / REFERENCE_HASH_BITS as u64) as u32
+}
+
+use crate::core::hasher::{
+    CropHash, CropRegion, DihedralTransform, FusionConfidence, FusionHash, HashAlgorithmKind,
+    HashVariants, ImageHashValue, PerceptualHash,
+};
 use crate::events::{CompareEvent, CompareProgress, Event, EventSender};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use uuid::Uuid;
 
 /// Result of comparing two photos
@@ -42,11 +94,55 @@ pub struct MatchResult {
     pub similarity_percent: f64,
     /// Classification of the match
     pub match_type: MatchType,
+    /// Fusion voting confidence (None for single-algorithm hashes)
+    #[serde(default)]
+    pub confidence: Option<FusionConfidence>,
+    /// How photo A must be rotated or mirrored to match photo B
+    /// (None for upright matches)
+    #[serde(default)]
+    pub transform: Option<DihedralTransform>,
+    /// Set when one photo only matched as a crop of the other
+    #[serde(default)]
+    pub crop: Option<CropMatch>,
+}
+
+/// One photo found inside another by a crop-resistant hash
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CropMatch {
+    /// The uncropped photo
+    pub original: PathBuf,
+    /// The photo that is a crop of `original`
+    pub cropped: PathBuf,
+    /// Approximate part of `original` that `cropped` shows
+    pub region: CropRegion,
+}
+
+impl std::fmt::Display for CropMatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = |path: &PathBuf| {
+            path.file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string())
+        };
+        write!(
+            f,
+            "{} is a crop of {} ({})",
+            name(&self.cropped),
+            name(&self.original),
+            self.region
+        )
+    }
 }
 
 /// Classification of match types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MatchType {
+    /// Paths to one file on disk (hardlinks), so already deduplicated
+    Hardlinked,
+    /// Same file contents, byte for byte
+    ByteIdentical,
+    /// Same decoded pixels, but the files differ (usually in metadata)
+    SamePixelsDifferentMetadata,
     /// Distance = 0, identical perceptual content
     Exact,
     /// Distance 1-4, virtually identical
@@ -68,15 +164,30 @@ impl MatchType {
         }
     }
 
+    /// Classify a distance between `bits`-bit hashes on the 64-bit scale
+    pub fn from_scaled_distance(distance: u32, bits: u32) -> Self {
+        Self::from_distance(scale_distance(distance, bits))
+    }
+
     /// Check if this match type is considered a duplicate
     pub fn is_duplicate(&self) -> bool {
-        matches!(self, MatchType::Exact | MatchType::NearExact | MatchType::Similar)
+        matches!(
+            self,
+            MatchType::ByteIdentical
+                | MatchType::SamePixelsDifferentMetadata
+                | MatchType::Exact
+                | MatchType::NearExact
+                | MatchType::Similar
+        )
     }
 }
 
 impl std::fmt::Display for MatchType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            MatchType::Hardlinked => write!(f, "Already Deduplicated (Hardlinks)"),
+            MatchType::ByteIdentical => write!(f, "Byte-Identical"),
+            MatchType::SamePixelsDifferentMetadata => write!(f, "Same Pixels, Different Metadata"),
             MatchType::Exact => write!(f, "Exact Match"),
             MatchType::NearExact => write!(f, "Near-Exact Match"),
             MatchType::Similar => write!(f, "Similar"),
@@ -94,12 +205,33 @@ pub struct DuplicateGroup {
     pub photos: Vec<PathBuf>,
     /// The recommended photo to keep (usually highest quality)
     pub representative: PathBuf,
-    /// The type of match for this group
+    /// The weakest type of match between photos of the group, so a group is
+    /// only byte-identical when every pair in it is
     pub match_type: MatchType,
     /// Average distance within the group
     pub average_distance: f64,
+    /// Worst distance between any two photos in the group. Pairs that never
+    /// matched directly (e.g. the ends of a transitive chain) are measured
+    /// by the pipeline from their hashes (see [`measure_diameters`]); None
+    /// when one couldn't be.
+    #[serde(default)]
+    pub max_distance: Option<u32>,
     /// Total file size of duplicates (excluding representative)
     pub duplicate_size_bytes: u64,
+    /// Weakest fusion confidence among the group's matches
+    /// (None for single-algorithm hashes)
+    #[serde(default)]
+    pub confidence: Option<FusionConfidence>,
+    /// Set when some photos only matched as a rotated or mirrored copy
+    #[serde(default)]
+    pub transform: Option<DihedralTransform>,
+    /// Set when some photos only matched as a crop of another
+    #[serde(default)]
+    pub crop: Option<CropMatch>,
+    /// The least exact match between two photos of the group, which is
+    /// what it is explained by (None for hardlinks)
+    #[serde(default)]
+    pub weakest_match: Option<MatchResult>,
 }
 
 impl DuplicateGroup {
@@ -111,16 +243,165 @@ impl DuplicateGroup {
             representative,
             match_type,
             average_distance: 0.0,
+            max_distance: None,
             duplicate_size_bytes: 0,
+            confidence: None,
+            transform: None,
+            crop: None,
+            weakest_match: None,
         }
     }
 
     /// Get the number of duplicates (excluding the representative)
+    ///
+    /// Hardlinks aren't counted, as removing one frees no space.
     pub fn duplicate_count(&self) -> usize {
+        if self.match_type == MatchType::Hardlinked {
+            return 0;
+        }
         self.photos.len().saturating_sub(1)
     }
 }
 
+/// Compare two photos' hashes and build a match if they are duplicates.
+///
+/// Fusion hashes are decoded and compared component by component: the pair
+/// is a duplicate when a majority of the voters (aHash, dHash, pHash and any
+/// extra voters) are within the strategy's threshold. The reported distance is the smallest agreeing
+/// distance and the voting confidence is carried into the result.
+/// Crop-resistant hashes first compare whole images, then look for either
+/// photo among the other's windows (see [`compare_crop_hashes`]). All other
+/// algorithms use plain Hamming distance.
+pub fn compare_hashes(
+    path_a: &Path,
+    hash_a: &ImageHashValue,
+    path_b: &Path,
+    hash_b: &ImageHashValue,
+    strategy: &dyn ComparisonStrategy,
+) -> Option<MatchResult> {
+    if let (Some(crop_a), Some(crop_b)) = (decode_crop(hash_a), decode_crop(hash_b)) {
+        return compare_crop_hashes(path_a, &crop_a, path_b, &crop_b, strategy);
+    }
+
+    if let (Some(fusion_a), Some(fusion_b)) = (decode_fusion(hash_a), decode_fusion(hash_b)) {
+        let result = fusion_a.compare(&fusion_b, strategy.threshold());
+        if !result.is_duplicate {
+            return None;
+        }
+
+        let bits = fusion_a.dhash.bit_count().max(1);
+        let similarity = (1.0 - result.min_distance as f64 / bits as f64) * 100.0;
+
+        return Some(MatchResult {
+            photo_a: path_a.to_path_buf(),
+            photo_b: path_b.to_path_buf(),
+            distance: result.min_distance,
+            similarity_percent: similarity,
+            match_type: strategy.classify(result.min_distance),
+            confidence: Some(result.confidence),
+            transform: None,
+            crop: None,
+        });
+    }
+
+    let distance = hash_a.distance(hash_b);
+    if !strategy.is_duplicate(distance) {
+        return None;
+    }
+
+    Some(MatchResult {
+        photo_a: path_a.to_path_buf(),
+        photo_b: path_b.to_path_buf(),
+        distance,
+        similarity_percent: hash_a.similarity(hash_b),
+        match_type: strategy.classify(distance),
+        confidence: None,
+        transform: None,
+        crop: None,
+    })
+}
+
+/// Compare two crop-resistant hashes.
+///
+/// Whole images within the threshold are an ordinary match. Otherwise the
+/// closer of "B is a window of A" and "A is a window of B" is reported as a
+/// crop, with the region of the original it covers.
+pub fn compare_crop_hashes(
+    path_a: &Path,
+    crop_a: &CropHash,
+    path_b: &Path,
+    crop_b: &CropHash,
+    strategy: &dyn ComparisonStrategy,
+) -> Option<MatchResult> {
+    let (full_a, full_b) = (crop_a.full(), crop_b.full());
+    let mut result = MatchResult {
+        photo_a: path_a.to_path_buf(),
+        photo_b: path_b.to_path_buf(),
+        distance: full_a.distance(full_b),
+        similarity_percent: full_a.similarity(full_b),
+        match_type: strategy.classify(full_a.distance(full_b)),
+        confidence: None,
+        transform: None,
+        crop: None,
+    };
+    if strategy.is_duplicate(result.distance) {
+        return Some(result);
+    }
+
+    let b_in_a = crop_a
+        .locate(crop_b)
+        .map(|(region, d)| (d, region, path_a, path_b));
+    let a_in_b = crop_b
+        .locate(crop_a)
+        .map(|(region, d)| (d, region, path_b, path_a));
+    let (distance, region, original, cropped) =
+        b_in_a.into_iter().chain(a_in_b).min_by_key(|(d, ..)| *d)?;
+    if !strategy.is_duplicate(distance) {
+        return None;
+    }
+
+    let bits = full_a.bit_count().max(1);
+    result.distance = distance;
+    result.similarity_percent = (1.0 - distance as f64 / bits as f64) * 100.0;
+    result.match_type = strategy.classify(distance);
+    result.crop = Some(CropMatch {
+        original: original.to_path_buf(),
+        cropped: cropped.to_path_buf(),
+        region,
+    });
+    Some(result)
+}
+
+/// Distance between two photos' hashes, whether they match or not.
+///
+/// Measured as [`compare_hashes`] measures upright photos: crop-resistant
+/// hashes by their whole images, and fusion hashes by their closest voter.
+pub fn hash_distance(hash_a: &ImageHashValue, hash_b: &ImageHashValue) -> u32 {
+    if let (Some(crop_a), Some(crop_b)) = (decode_crop(hash_a), decode_crop(hash_b)) {
+        return crop_a.full().distance(crop_b.full());
+    }
+    if let (Some(fusion_a), Some(fusion_b)) = (decode_fusion(hash_a), decode_fusion(hash_b)) {
+        return fusion_a.compare(&fusion_b, u32::MAX).min_distance;
+    }
+    hash_a.distance(hash_b)
+}
+
+/// Decode a fusion hash, or None for any other algorithm
+fn decode_fusion(hash: &ImageHashValue) -> Option<FusionHash> {
+    if hash.algorithm() != HashAlgorithmKind::Fusion {
+        return None;
+    }
+    FusionHash::from_bytes(hash.as_bytes())
+}
+
+/// Decode a crop-resistant hash, or None for any other algorithm
+fn decode_crop(hash: &ImageHashValue) -> Option<CropHash> {
+    if hash.algorithm() != HashAlgorithmKind::CropResistant {
+        return None;
+    }
+    CropHash::from_bytes(hash.as_bytes())
+}
+
 /// Find all duplicate pairs from a collection of hashes
 pub fn find_duplicate_pairs(
     photos: &[(PathBuf, ImageHashValue)],
@@ -134,19 +415,8 @@ pub fn find_duplicate_pairs(
             let (path_a, hash_a) = &photos[i];
             let (path_b, hash_b) = &photos[j];
 
-            let distance = hash_a.distance(hash_b);
-
-            if strategy.is_duplicate(distance) {
-                let similarity = hash_a.similarity(hash_b);
-                let match_type = strategy.classify(distance);
-
-                matches.push(MatchResult {
-                    photo_a: path_a.clone(),
-                    photo_b: path_b.clone(),
-                    distance,
-                    similarity_percent: similarity,
-                    match_type,
-                });
+            if let Some(m) = compare_hashes(path_a, hash_a, path_b, hash_b, strategy) {
+                matches.push(m);
             }
         }
     }
@@ -181,19 +451,8 @@ pub fn find_duplicate_pairs_with_events(
             let (path_a, hash_a) = &photos[i];
             let (path_b, hash_b) = &photos[j];
 
-            let distance = hash_a.distance(hash_b);
-
-            if strategy.is_duplicate(distance) {
-                let similarity = hash_a.similarity(hash_b);
-                let match_type = strategy.classify(distance);
-
-                matches.push(MatchResult {
-                    photo_a: path_a.clone(),
-                    photo_b: path_b.clone(),
-                    distance,
-                    similarity_percent: similarity,
-                    match_type,
-                });
+            if let Some(m) = compare_hashes(path_a, hash_a, path_b, hash_b, strategy) {
+                matches.push(m);
             }
 
             comparisons_completed += 1;
@@ -219,41 +478,434 @@ pub fn find_duplicate_pairs_with_events(
     matches
 }
 
+/// Find all duplicate pairs using a multi-index hash.
+///
+/// Returns exactly the same matches, in the same order, as
+/// [`find_duplicate_pairs`], but only compares pairs the index proves could
+/// be within the strategy's threshold. The index is partitioned from the
+/// threshold and hash length, so recall doesn't depend on the threshold the
+/// way banded LSH does. Strategies must not accept distances above
+/// [`ComparisonStrategy::threshold`].
+///
+/// Collections that can't be indexed (mixed hash lengths, or fusion mixed
+/// with other algorithms) fall back to brute force.
+pub fn find_duplicate_pairs_indexed(
+    photos: &[(PathBuf, ImageHashValue)],
+    strategy: &dyn ComparisonStrategy,
+) -> Vec<MatchResult> {
+    let Some(candidates) = indexed_candidates(photos, strategy.threshold()) else {
+        return find_duplicate_pairs(photos, strategy);
+    };
+
+    candidates
+        .into_iter()
+        .filter_map(|(i, j)| {
+            let (path_a, hash_a) = &photos[i];
+            let (path_b, hash_b) = &photos[j];
+            compare_hashes(path_a, hash_a, path_b, hash_b, strategy)
+        })
+        .collect()
+}
+
+/// Find sorted candidate index pairs within `radius` using multi-index hashing.
+///
+/// Fusion hashes index their leading voters separately (see
+/// [`fusion_index_voters`]). Crop-resistant hashes index every
+/// window (see [`crop_candidates`]). Returns None if the hashes can't be
+/// indexed together.
+fn indexed_candidates(
+    photos: &[(PathBuf, ImageHashValue)],
+    radius: u32,
+) -> Option<Vec<(usize, usize)>> {
+    let crops: Option<Vec<CropHash>> = photos.iter().map(|(_, h)| decode_crop(h)).collect();
+    if let Some(crops) = crops.filter(|c| !c.is_empty()) {
+        return crop_candidates(&crops, radius);
+    }
+
+    let fusion: Option<Vec<FusionHash>> = photos.iter().map(|(_, h)| decode_fusion(h)).collect();
+
+    let Some(fusion) = fusion.filter(|f| !f.is_empty()) else {
+        let codes = photos.iter().map(|(_, h)| h.as_bytes().to_vec()).collect();
+        return MultiIndexHash::build(codes, radius).map(|index| index.pairs());
+    };
+
+    let mut candidates = Vec::new();
+    for voter in 0..fusion_index_voters(&fusion)? {
+        let codes = fusion
+            .iter()
+            .map(|f| f.voters()[voter].as_bytes().to_vec())
+            .collect();
+        candidates.extend(MultiIndexHash::build(codes, radius)?.pairs());
+    }
+    candidates.sort_unstable();
+    candidates.dedup();
+    Some(candidates)
+}
+
+/// Number of leading [`FusionHash::voters`] to index so that every fusion
+/// duplicate is a candidate in at least one of them.
+///
+/// A duplicate needs `k` of `n` voters within the threshold, so at most
+/// `n - k` disagree and any `n - k + 1` voters include one that agrees
+/// (2 of dHash and pHash by default). Returns None if the hashes don't all
+/// have the same voters.
+fn fusion_index_voters(fusion: &[FusionHash]) -> Option<usize> {
+    let extra = fusion.first()?.extra_kinds();
+    if fusion.iter().any(|f| f.extra_kinds() != extra) {
+        return None;
+    }
+    let voters = extra.len() + 3;
+    Some(voters - FusionHash::required_votes(voters) + 1)
+}
+
+/// Find sorted candidate pairs for crop-resistant hashes.
+///
+/// Every window of every photo goes into one index, and each photo's
+/// whole-image hash is queried against it. That covers both whole-image
+/// matches and either photo being a window of the other, which is every
+/// case [`compare_crop_hashes`] accepts.
+fn crop_candidates(crops: &[CropHash], radius: u32) -> Option<Vec<(usize, usize)>> {
+    let mut owners = Vec::new();
+    let mut codes = Vec::new();
+    for (i, crop) in crops.iter().enumerate() {
+        for segment in &crop.segments {
+            owners.push(i);
+            codes.push(segment.hash.as_bytes().to_vec());
+        }
+    }
+    let index = MultiIndexHash::build(codes, radius)?;
+
+    let mut candidates: Vec<(usize, usize)> = crops
+        .iter()
+        .enumerate()
+        .flat_map(|(i, crop)| {
+            index
+                .query(crop.full().as_bytes())
+                .into_iter()
+                .map(|k| owners[k])
+                .filter(move |&j| j != i)
+                .map(move |j| (i.min(j), i.max(j)))
+        })
+        .collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+    Some(candidates)
+}
+
+/// Find the duplicate pairs that involve at least one changed photo.
+///
+/// Gives the matches of [`find_duplicate_pairs`] that have a photo with
+/// `changed[i]` set, in the same order and orientation, so the matches
+/// among unchanged photos can be kept from an earlier scan and only new
+/// and modified photos are compared. The hashes are indexed like
+/// [`find_duplicate_pairs_indexed`] when they can be.
+pub fn find_duplicate_pairs_involving(
+    photos: &[(PathBuf, ImageHashValue)],
+    changed: &[bool],
+    strategy: &dyn ComparisonStrategy,
+) -> Vec<MatchResult> {
+    let candidates =
+        changed_candidates(photos, changed, strategy.threshold()).unwrap_or_else(|| {
+            (0..photos.len())
+                .flat_map(|i| ((i + 1)..photos.len()).map(move |j| (i, j)))
+                .filter(|&(i, j)| changed[i] || changed[j])
+                .collect()
+        });
+
+    candidates
+        .into_iter()
+        .filter_map(|(i, j)| {
+            let (path_a, hash_a) = &photos[i];
+            let (path_b, hash_b) = &photos[j];
+            compare_hashes(path_a, hash_a, path_b, hash_b, strategy)
+        })
+        .collect()
+}
+
+/// Find sorted candidate index pairs within `radius` that involve a changed
+/// photo, the way [`indexed_candidates`] finds all of them.
+fn changed_candidates(
+    photos: &[(PathBuf, ImageHashValue)],
+    changed: &[bool],
+    radius: u32,
+) -> Option<Vec<(usize, usize)>> {
+    let crops: Option<Vec<CropHash>> = photos.iter().map(|(_, h)| decode_crop(h)).collect();
+    if let Some(crops) = crops.filter(|c| !c.is_empty()) {
+        return crop_changed_candidates(&crops, changed, radius);
+    }
+
+    let fusion: Option<Vec<FusionHash>> = photos.iter().map(|(_, h)| decode_fusion(h)).collect();
+
+    let Some(fusion) = fusion.filter(|f| !f.is_empty()) else {
+        let codes = photos.iter().map(|(_, h)| h.as_bytes().to_vec()).collect();
+        return query_changed(codes, changed, radius);
+    };
+
+    let mut candidates = Vec::new();
+    for voter in 0..fusion_index_voters(&fusion)? {
+        let codes = fusion
+            .iter()
+            .map(|f| f.voters()[voter].as_bytes().to_vec())
+            .collect();
+        candidates.extend(query_changed(codes, changed, radius)?);
+    }
+    candidates.sort_unstable();
+    candidates.dedup();
+    Some(candidates)
+}
+
+/// Index `codes` and query it with the codes of changed photos
+fn query_changed(
+    codes: Vec<Vec<u8>>,
+    changed: &[bool],
+    radius: u32,
+) -> Option<Vec<(usize, usize)>> {
+    let queries: Vec<(usize, Vec<u8>)> = codes
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| changed[i])
+        .map(|(i, code)| (i, code.clone()))
+        .collect();
+    let index = MultiIndexHash::build(codes, radius)?;
+
+    let mut candidates: Vec<(usize, usize)> = queries
+        .iter()
+        .flat_map(|(i, code)| {
+            let i = *i;
+            index
+                .query(code)
+                .into_iter()
+                .filter(move |&j| j != i)
+                .map(move |j| (i.min(j), i.max(j)))
+        })
+        .collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+    Some(candidates)
+}
+
+/// Find sorted crop candidate pairs that involve a changed photo.
+///
+/// Like [`crop_candidates`], but only the whole-image hashes of changed
+/// photos are queried against every window, and every whole-image hash
+/// against the windows of changed photos.
+fn crop_changed_candidates(
+    crops: &[CropHash],
+    changed: &[bool],
+    radius: u32,
+) -> Option<Vec<(usize, usize)>> {
+    let windows = |only_changed: bool| {
+        let mut owners = Vec::new();
+        let mut codes = Vec::new();
+        for (i, crop) in crops.iter().enumerate() {
+            if only_changed && !changed[i] {
+                continue;
+            }
+            for segment in &crop.segments {
+                owners.push(i);
+                codes.push(segment.hash.as_bytes().to_vec());
+            }
+        }
+        (owners, codes)
+    };
+
+    let mut candidates = Vec::new();
+    for only_changed in [false, true] {
+        let (owners, codes) = windows(only_changed);
+        if codes.is_empty() {
+            continue;
+        }
+        let index = MultiIndexHash::build(codes, radius)?;
+        for (i, crop) in crops.iter().enumerate() {
+            if !only_changed && !changed[i] {
+                continue;
+            }
+            candidates.extend(
+                index
+                    .query(crop.full().as_bytes())
+                    .into_iter()
+                    .map(|k| owners[k])
+                    .filter(|&j| j != i)
+                    .map(|j| (i.min(j), i.max(j))),
+            );
+        }
+    }
+    candidates.sort_unstable();
+    candidates.dedup();
+    Some(candidates)
+}
+
+/// Find pairs that match once one photo is rotated or mirrored.
+///
+/// Each photo's variants (from [`DihedralHasher`](crate::core::hasher::DihedralHasher))
+/// are compared against every other photo's upright hash, in both
+/// directions, and the closest transform wins. Pairs that match upright are
+/// reported with no transform, so callers usually skip pairs they already
+/// found with [`find_duplicate_pairs`].
+pub fn find_transformed_pairs(
+    photos: &[(PathBuf, HashVariants)],
+    strategy: &dyn ComparisonStrategy,
+) -> Vec<MatchResult> {
+    let candidates = transformed_candidates(photos, strategy.threshold()).unwrap_or_else(|| {
+        (0..photos.len())
+            .flat_map(|i| ((i + 1)..photos.len()).map(move |j| (i, j)))
+            .collect()
+    });
+
+    candidates
+        .into_iter()
+        .filter_map(|(i, j)| best_transformed_match(&photos[i], &photos[j], strategy))
+        .collect()
+}
+
+/// Compare A's variants against B's upright hash and vice versa, keeping
+/// the closest match expressed as a transform of A.
+fn best_transformed_match(
+    (path_a, variants_a): &(PathBuf, HashVariants),
+    (path_b, variants_b): &(PathBuf, HashVariants),
+    strategy: &dyn ComparisonStrategy,
+) -> Option<MatchResult> {
+    let (_, upright_a) = variants_a.first()?;
+    let (_, upright_b) = variants_b.first()?;
+
+    let forward = variants_a.iter().filter_map(|(transform, hash)| {
+        compare_hashes(path_a, hash, path_b, upright_b, strategy).map(|m| (*transform, m))
+    });
+    let backward = variants_b.iter().filter_map(|(transform, hash)| {
+        compare_hashes(path_a, upright_a, path_b, hash, strategy).map(|m| (transform.inverse(), m))
+    });
+
+    let (transform, mut best) = forward.chain(backward).min_by_key(|(_, m)| m.distance)?;
+    best.transform = (transform != DihedralTransform::Identity).then_some(transform);
+    Some(best)
+}
+
+/// Find sorted candidate pairs whose variants could match, or None if the
+/// hashes can't be indexed together.
+fn transformed_candidates(
+    photos: &[(PathBuf, HashVariants)],
+    radius: u32,
+) -> Option<Vec<(usize, usize)>> {
+    let first = &photos.first()?.1.first()?.1;
+
+    // Rotated crops only show up window against window, so compare them all
+    if decode_crop(first).is_some() {
+        return None;
+    }
+
+    // Fusion needs a majority of voters to agree, so one of the leading
+    // voters always does (see `fusion_index_voters`)
+    type Component = Box<dyn Fn(&ImageHashValue) -> Option<Vec<u8>>>;
+    let components: Vec<Component> = if decode_fusion(first).is_some() {
+        let fusion = photos
+            .iter()
+            .flat_map(|(_, variants)| variants.iter().map(|(_, h)| decode_fusion(h)))
+            .collect::<Option<Vec<_>>>()?;
+        (0..fusion_index_voters(&fusion)?)
+            .map(|voter| -> Component {
+                Box::new(move |h| decode_fusion(h).map(|f| f.voters()[voter].as_bytes().to_vec()))
+            })
+            .collect()
+    } else {
+        vec![Box::new(|h| Some(h.as_bytes().to_vec()))]
+    };
+
+    let mut candidates = Vec::new();
+    for component in components {
+        let upright = photos
+            .iter()
+            .map(|(_, variants)| variants.first().and_then(|(_, h)| component(h)))
+            .collect::<Option<Vec<_>>>()?;
+        let index = MultiIndexHash::build(upright, radius)?;
+
+        for (i, (_, variants)) in photos.iter().enumerate() {
+            for (_, hash) in variants {
+                let code = component(hash)?;
+                candidates.extend(
+                    index
+                        .query(&code)
+                        .into_iter()
+                        .filter(|&j| j != i)
+                        .map(|j| (i.min(j), i.max(j))),
+                );
+            }
+        }
+    }
+
+    candidates.sort_unstable();
+    candidates.dedup();
+    Some(candidates)
+}
+
 /// Find duplicate pairs using LSH for O(n log n) candidate filtering
 ///
 /// This is much faster for large collections (1000+ photos) as it only
 /// compares candidate pairs identified by LSH rather than all pairs.
+///
+/// LSH only guarantees recall up to `bands - min_matching_bands` bits, so
+/// pairs further apart than that may be missed. Use
+/// [`find_duplicate_pairs_indexed`] when every pair must be found.
 pub fn find_duplicate_pairs_with_lsh(
     photos: Vec<(PathBuf, ImageHashValue)>,
     strategy: &dyn ComparisonStrategy,
     lsh_config: LshConfig,
 ) -> Vec<MatchResult> {
-    // Build LSH index
-    let index = LshIndex::build(lsh_config, photos);
+    // Get candidate pairs from the LSH index
+    let candidates = lsh_candidates(&photos, lsh_config, strategy.threshold());
 
-    // Get candidate pairs
-    let candidates = index.find_candidate_pairs();
+    candidates
+        .into_iter()
+        .filter_map(|(i, j)| {
+            let (path_a, hash_a) = &photos[i];
+            let (path_b, hash_b) = &photos[j];
+            compare_hashes(path_a, hash_a, path_b, hash_b, strategy)
+        })
+        .collect()
+}
 
-    let mut matches = Vec::new();
+/// Find candidate index pairs for a collection of hashes using LSH.
+///
+/// Fusion blobs include length prefixes and several unrelated hashes, so they
+/// are not banded directly. Instead the leading voters are each indexed and
+/// their candidates merged: a fusion duplicate needs a majority of voters to
+/// agree, so at least one of these always does (see [`fusion_index_voters`]).
+/// Hashes with mismatched voters are compared pairwise. Crop-resistant hashes use the exact window index at `radius` instead.
+fn lsh_candidates(
+    photos: &[(PathBuf, ImageHashValue)],
+    lsh_config: LshConfig,
+    radius: u32,
+) -> Vec<(usize, usize)> {
+    let crops: Option<Vec<CropHash>> = photos.iter().map(|(_, h)| decode_crop(h)).collect();
+    if let Some(candidates) = crops
+        .filter(|c| !c.is_empty())
+        .and_then(|crops| crop_candidates(&crops, radius))
+    {
+        return candidates;
+    }
 
-    for (path_a, hash_a, path_b, hash_b) in candidates {
-        let distance = hash_a.distance(hash_b);
+    let fusion: Option<Vec<FusionHash>> = photos.iter().map(|(_, h)| decode_fusion(h)).collect();
 
-        if strategy.is_duplicate(distance) {
-            let similarity = hash_a.similarity(hash_b);
-            let match_type = strategy.classify(distance);
+    let Some(fusion) = fusion.filter(|f| !f.is_empty()) else {
+        return LshIndex::build(lsh_config, photos.to_vec()).find_candidates();
+    };
 
-            matches.push(MatchResult {
-                photo_a: path_a.clone(),
-                photo_b: path_b.clone(),
-                distance,
-                similarity_percent: similarity,
-                match_type,
-            });
-        }
+    let Some(indexed) = fusion_index_voters(&fusion) else {
+        let n = photos.len();
+        return (0..n)
+            .flat_map(|i| ((i + 1)..n).map(move |j| (i, j)))
+            .collect();
+    };
+
+    let mut candidates: HashSet<(usize, usize)> = HashSet::new();
+    for voter in 0..indexed {
+        let hashes = photos
+            .iter()
+            .zip(&fusion)
+            .map(|((path, _), f)| (path.clone(), f.voters()[voter].clone()))
+            .collect();
+        candidates.extend(LshIndex::build(lsh_config.clone(), hashes).find_candidates());
     }
-
-    matches
+    candidates.into_iter().collect()
 }
 
 /// Find duplicate pairs using LSH with progress events
@@ -270,12 +922,8 @@ pub fn find_duplicate_pairs_with_lsh_events(
     // Emit started event
     events.send(Event::Compare(CompareEvent::Started { total_photos: n }));
 
-    // Build LSH index
-    let index = LshIndex::build(lsh_config, photos);
-    let _stats = index.stats(); // Available for debugging if needed
-
-    // Get candidate pairs
-    let candidates = index.find_candidate_pairs();
+    // Get candidate pairs from the LSH index
+    let candidates = lsh_candidates(&photos, lsh_config, strategy.threshold());
     let total_comparisons = candidates.len();
 
     let mut matches = Vec::new();
@@ -284,20 +932,12 @@ pub fn find_duplicate_pairs_with_lsh_events(
 
     let update_interval = std::cmp::min(1000, std::cmp::max(1, total_comparisons / 50));
 
-    for (path_a, hash_a, path_b, hash_b) in candidates {
-        let distance = hash_a.distance(hash_b);
-
-        if strategy.is_duplicate(distance) {
-            let similarity = hash_a.similarity(hash_b);
-            let match_type = strategy.classify(distance);
+    for (i, j) in candidates {
+        let (path_a, hash_a) = &photos[i];
+        let (path_b, hash_b) = &photos[j];
 
-            matches.push(MatchResult {
-                photo_a: path_a.clone(),
-                photo_b: path_b.clone(),
-                distance,
-                similarity_percent: similarity,
-                match_type,
-            });
+        if let Some(m) = compare_hashes(path_a, hash_a, path_b, hash_b, strategy) {
+            matches.push(m);
         }
 
         comparisons_completed += 1;
@@ -326,6 +966,34 @@ mod tests {
     use super::*;
     use crate::core::hasher::HashAlgorithmKind;
 
+    #[test]
+    fn distances_scale_to_64_bits() {
+        assert_eq!(scale_distance(7, 64), 7);
+        assert_eq!(scale_distance(32, 256), 8);
+        // Rounds up so 33 of 256 bits is over a threshold of 8
+        assert_eq!(scale_distance(33, 256), 9);
+        assert_eq!(scale_threshold(8, 256), 32);
+        assert_eq!(scale_threshold(8, 1024), 128);
+
+        for d in 0..=256 {
+            assert_eq!(scale_distance(d, 256) <= 8, d <= scale_threshold(8, 256));
+        }
+    }
+
+    #[test]
+    fn match_type_from_scaled_distance() {
+        assert_eq!(MatchType::from_scaled_distance(0, 256), MatchType::Exact);
+        assert_eq!(
+            MatchType::from_scaled_distance(16, 256),
+            MatchType::NearExact
+        );
+        assert_eq!(MatchType::from_scaled_distance(40, 256), MatchType::Similar);
+        assert_eq!(
+            MatchType::from_scaled_distance(44, 256),
+            MatchType::MaybeSimilar
+        );
+    }
+
     #[test]
     fn match_type_from_distance() {
         assert_eq!(MatchType::from_distance(0), MatchType::Exact);
@@ -386,6 +1054,391 @@ mod tests {
         assert_eq!(pairs[0].match_type, MatchType::Exact);
     }
 
+    fn fusion_value(a: u8, d: u8, p: u8) -> ImageHashValue {
+        let fusion = FusionHash::new(
+            ImageHashValue::new(vec![a], HashAlgorithmKind::Average),
+            ImageHashValue::new(vec![d], HashAlgorithmKind::Difference),
+            ImageHashValue::new(vec![p], HashAlgorithmKind::Perceptual),
+        );
+        ImageHashValue::new(fusion.to_bytes(), HashAlgorithmKind::Fusion)
+    }
+
+    #[test]
+    fn fusion_hashes_use_two_of_three_voting() {
+        let strategy = ThresholdStrategy::new(2);
+        let photos = vec![
+            (PathBuf::from("/a.jpg"), fusion_value(0xFF, 0xFF, 0xFF)),
+            // dHash and pHash agree, aHash is 8 bits away
+            (PathBuf::from("/b.jpg"), fusion_value(0x00, 0xFF, 0xFE)),
+            // Only aHash agrees
+            (PathBuf::from("/c.jpg"), fusion_value(0xFF, 0x00, 0x00)),
+        ];
+
+        let pairs = find_duplicate_pairs(&photos, &strategy);
+
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].photo_b, PathBuf::from("/b.jpg"));
+        assert_eq!(pairs[0].distance, 0);
+        assert_eq!(pairs[0].confidence, Some(FusionConfidence::Medium));
+    }
+
+    #[test]
+    fn fusion_length_prefixes_do_not_affect_distance() {
+        let strategy = ThresholdStrategy::new(0);
+        let a = fusion_value(0x0F, 0x0F, 0x0F);
+        let b = fusion_value(0x0F, 0x0F, 0x0F);
+
+        let m =
+            compare_hashes(Path::new("/a.jpg"), &a, Path::new("/b.jpg"), &b, &strategy).unwrap();
+
+        assert_eq!(m.distance, 0);
+        assert_eq!(m.match_type, MatchType::Exact);
+        assert_eq!(m.confidence, Some(FusionConfidence::High));
+    }
+
+    #[test]
+    fn lsh_path_applies_fusion_voting() {
+        let strategy = ThresholdStrategy::new(2);
+        let photos = vec![
+            (PathBuf::from("/a.jpg"), fusion_value(0xFF, 0xFF, 0xFF)),
+            (PathBuf::from("/b.jpg"), fusion_value(0x00, 0xFF, 0xFF)),
+            (PathBuf::from("/c.jpg"), fusion_value(0xFF, 0x00, 0x00)),
+        ];
+
+        let pairs = find_duplicate_pairs_with_lsh(photos, &strategy, LshConfig::new(1));
+
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].confidence, Some(FusionConfidence::Medium));
+    }
+
+    /// Random 64-bit hashes where every other one is a noisy copy of the
+    /// one before it, so each threshold has some matches
+    fn random_hashes(count: usize, seed: u64) -> Vec<[u8; 8]> {
+        let mut state = seed;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        let mut hashes = Vec::with_capacity(count);
+        while hashes.len() < count {
+            let base = next().to_be_bytes();
+            let mut copy = u64::from_be_bytes(base);
+            for _ in 0..next() % 14 {
+                copy ^= 1 << (next() % 64);
+            }
+            hashes.push(base);
+            hashes.push(copy.to_be_bytes());
+        }
+        hashes.truncate(count);
+        hashes
+    }
+
+    fn summarize(matches: &[MatchResult]) -> Vec<(PathBuf, PathBuf, u32)> {
+        matches
+            .iter()
+            .map(|m| (m.photo_a.clone(), m.photo_b.clone(), m.distance))
+            .collect()
+    }
+
+    #[test]
+    fn indexed_search_matches_brute_force() {
+        let photos: Vec<_> = random_hashes(800, 0x2545_F491_4F6C_DD1D)
+            .into_iter()
+            .enumerate()
+            .map(|(i, h)| {
+                let hash = ImageHashValue::new(h.to_vec(), HashAlgorithmKind::Difference);
+                (PathBuf::from(format!("/{}.jpg", i)), hash)
+            })
+            .collect();
+
+        for threshold in [0, 3, 5, 8, 10, 12] {
+            let strategy = ThresholdStrategy::new(threshold);
+
+            let expected = find_duplicate_pairs(&photos, &strategy);
+            let indexed = find_duplicate_pairs_indexed(&photos, &strategy);
+
+            assert!(!expected.is_empty());
+            assert_eq!(
+                summarize(&indexed),
+                summarize(&expected),
+                "threshold {}",
+                threshold
+            );
+        }
+    }
+
+    #[test]
+    fn indexed_search_matches_brute_force_for_fusion() {
+        let ahashes = random_hashes(400, 11);
+        let dhashes = random_hashes(400, 22);
+        let phashes = random_hashes(400, 33);
+
+        let photos: Vec<_> = (0..400)
+            .map(|i| {
+                let fusion = FusionHash::new(
+                    ImageHashValue::new(ahashes[i].to_vec(), HashAlgorithmKind::Average),
+                    ImageHashValue::new(dhashes[i].to_vec(), HashAlgorithmKind::Difference),
+                    ImageHashValue::new(phashes[i].to_vec(), HashAlgorithmKind::Perceptual),
+                );
+                let hash = ImageHashValue::new(fusion.to_bytes(), HashAlgorithmKind::Fusion);
+                (PathBuf::from(format!("/{}.jpg", i)), hash)
+            })
+            .collect();
+
+        for threshold in [4, 8, 12] {
+            let strategy = ThresholdStrategy::new(threshold);
+
+            let expected = find_duplicate_pairs(&photos, &strategy);
+            let indexed = find_duplicate_pairs_indexed(&photos, &strategy);
+
+            assert_eq!(
+                summarize(&indexed),
+                summarize(&expected),
+                "threshold {}",
+                threshold
+            );
+        }
+    }
+
+    #[test]
+    fn indexed_search_matches_brute_force_for_fusion_with_extra_voters() {
+        let voters: Vec<_> = (1..=5).map(|seed| random_hashes(400, seed * 7)).collect();
+
+        let photos: Vec<_> = (0..400)
+            .map(|i| {
+                let hash =
+                    |voter: usize, kind| ImageHashValue::new(voters[voter][i].to_vec(), kind);
+                let fusion = FusionHash::new(
+                    hash(0, HashAlgorithmKind::Average),
+                    hash(1, HashAlgorithmKind::Difference),
+                    hash(2, HashAlgorithmKind::Perceptual),
+                )
+                .with_extra(vec![
+                    hash(3, HashAlgorithmKind::Wavelet),
+                    hash(4, HashAlgorithmKind::BlockMean),
+                ]);
+                let hash = ImageHashValue::new(fusion.to_bytes(), HashAlgorithmKind::Fusion);
+                (PathBuf::from(format!("/{}.jpg", i)), hash)
+            })
+            .collect();
+
+        for threshold in [4, 8, 12] {
+            let strategy = ThresholdStrategy::new(threshold);
+
+            let expected = find_duplicate_pairs(&photos, &strategy);
+            let indexed = find_duplicate_pairs_indexed(&photos, &strategy);
+
+            assert_eq!(
+                summarize(&indexed),
+                summarize(&expected),
+                "threshold {}",
+                threshold
+            );
+        }
+    }
+
+    #[test]
+    fn indexed_search_falls_back_for_mixed_lengths() {
+        let strategy = ThresholdStrategy::new(1);
+        let photos = vec![
+            (
+                PathBuf::from("/a.jpg"),
+                ImageHashValue::new(vec![0xFF, 0x00], HashAlgorithmKind::Difference),
+            ),
+            (
+                PathBuf::from("/b.jpg"),
+                ImageHashValue::new(vec![0xFF], HashAlgorithmKind::Difference),
+            ),
+        ];
+
+        let matches = find_duplicate_pairs_indexed(&photos, &strategy);
+
+        assert_eq!(matches.len(), 1);
+    }
+
+    #[test]
+    fn rotated_copies_report_their_transform() {
+        use crate::core::hasher::{DifferenceHasher, DihedralHasher};
+        use image::{DynamicImage, GrayImage, Luma};
+
+        let original = DynamicImage::ImageLuma8(GrayImage::from_fn(64, 48, |x, y| {
+            Luma([if x < 20 && y < 12 {
+                255
+            } else {
+                (x * 3 + y) as u8
+            }])
+        }));
+        let unrelated = DynamicImage::ImageLuma8(GrayImage::from_fn(64, 48, |x, y| {
+            Luma([if (x / 8 + y / 8) % 2 == 0 { 0 } else { 255 }])
+        }));
+        let inner = DifferenceHasher::new(8);
+        let hasher = DihedralHasher::new(&inner);
+        let photos = vec![
+            (
+                PathBuf::from("/a.jpg"),
+                hasher.hash_image(&original).unwrap(),
+            ),
+            (
+                PathBuf::from("/b.jpg"),
+                hasher.hash_image(&original.rotate90()).unwrap(),
+            ),
+            (
+                PathBuf::from("/c.jpg"),
+                hasher.hash_image(&unrelated).unwrap(),
+            ),
+        ];
+
+        let matches = find_transformed_pairs(&photos, &ThresholdStrategy::new(2));
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].photo_b, PathBuf::from("/b.jpg"));
+        assert_eq!(matches[0].transform, Some(DihedralTransform::Rotate90));
+    }
+
+    #[test]
+    fn upright_variant_matches_have_no_transform() {
+        let variants: HashVariants = DihedralTransform::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                let hash = ImageHashValue::new(vec![1 << i], HashAlgorithmKind::Difference);
+                (*t, hash)
+            })
+            .collect();
+        let photos = vec![
+            (PathBuf::from("/a.jpg"), variants.clone()),
+            (PathBuf::from("/b.jpg"), variants),
+        ];
+
+        let matches = find_transformed_pairs(&photos, &ThresholdStrategy::new(0));
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].transform, None);
+    }
+
+    fn crop_photos() -> Vec<(PathBuf, ImageHashValue)> {
+        use crate::core::hasher::CropResistantHasher;
+        use crate::core::hasher::HashAlgorithm;
+        use image::{DynamicImage, GrayImage, Luma};
+
+        let original = DynamicImage::ImageLuma8(GrayImage::from_fn(320, 240, |x, y| {
+            let wave = ((x as f32 / 9.0).sin() * (y as f32 / 13.0).cos() * 100.0) as i32;
+            let blocks = ((x / 40 + y / 30) % 3) as i32 * 40;
+            Luma([(wave + blocks + 100).clamp(0, 255) as u8])
+        }));
+        let unrelated = DynamicImage::ImageLuma8(GrayImage::from_fn(320, 240, |x, y| {
+            Luma([if (x / 16 + y / 16) % 2 == 0 { 0 } else { 255 }])
+        }));
+        let hasher = CropResistantHasher::new();
+
+        vec![
+            (
+                PathBuf::from("/cropped.jpg"),
+                hasher
+                    .hash_image(&original.crop_imm(0, 60, 160, 180))
+                    .unwrap(),
+            ),
+            (
+                PathBuf::from("/original.jpg"),
+                hasher.hash_image(&original).unwrap(),
+            ),
+            (
+                PathBuf::from("/unrelated.jpg"),
+                hasher.hash_image(&unrelated).unwrap(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn crops_report_original_and_region() {
+        let matches = find_duplicate_pairs(&crop_photos(), &ThresholdStrategy::new(4));
+
+        assert_eq!(matches.len(), 1);
+        let crop = matches[0].crop.as_ref().unwrap();
+        assert_eq!(crop.original, PathBuf::from("/original.jpg"));
+        assert_eq!(crop.cropped, PathBuf::from("/cropped.jpg"));
+        assert_eq!(
+            crop.region,
+            CropRegion {
+                x: 0.0,
+                y: 0.25,
+                width: 0.5,
+                height: 0.75
+            }
+        );
+    }
+
+    #[test]
+    fn indexed_search_finds_crops() {
+        let photos = crop_photos();
+        let strategy = ThresholdStrategy::new(4);
+
+        assert_eq!(
+            summarize(&find_duplicate_pairs_indexed(&photos, &strategy)),
+            summarize(&find_duplicate_pairs(&photos, &strategy))
+        );
+    }
+
+    #[test]
+    fn changed_search_matches_brute_force() {
+        let photos: Vec<_> = random_hashes(600, 0x9E37_79B9_7F4A_7C15)
+            .into_iter()
+            .enumerate()
+            .map(|(i, h)| {
+                let hash = ImageHashValue::new(h.to_vec(), HashAlgorithmKind::Difference);
+                (PathBuf::from(format!("/{}.jpg", i)), hash)
+            })
+            .collect();
+        let changed: Vec<bool> = (0..photos.len()).map(|i| i % 7 == 3).collect();
+        let position = |path: &Path| photos.iter().position(|(p, _)| p == path).unwrap();
+
+        for threshold in [0, 5, 10] {
+            let strategy = ThresholdStrategy::new(threshold);
+
+            let expected: Vec<_> = find_duplicate_pairs(&photos, &strategy)
+                .into_iter()
+                .filter(|m| changed[position(&m.photo_a)] || changed[position(&m.photo_b)])
+                .collect();
+            let found = find_duplicate_pairs_involving(&photos, &changed, &strategy);
+
+            assert!(!expected.is_empty());
+            assert_eq!(
+                summarize(&found),
+                summarize(&expected),
+                "threshold {}",
+                threshold
+            );
+        }
+    }
+
+    #[test]
+    fn changed_search_finds_crops_either_way() {
+        let photos = crop_photos();
+        let strategy = ThresholdStrategy::new(4);
+        let all = summarize(&find_duplicate_pairs(&photos, &strategy));
+
+        for changed in [[true, false, false], [false, true, false]] {
+            let found = find_duplicate_pairs_involving(&photos, &changed, &strategy);
+            assert_eq!(summarize(&found), all);
+        }
+        let unrelated = find_duplicate_pairs_involving(&photos, &[false, false, true], &strategy);
+        assert!(unrelated.is_empty());
+    }
+
+    #[test]
+    fn plain_hashes_have_no_confidence() {
+        let strategy = ThresholdStrategy::new(5);
+        let a = ImageHashValue::new(vec![0xFF], HashAlgorithmKind::Difference);
+
+        let m =
+            compare_hashes(Path::new("/a.jpg"), &a, Path::new("/b.jpg"), &a, &strategy).unwrap();
+
+        assert_eq!(m.confidence, None);
+    }
+
     #[test]
     fn find_duplicate_pairs_with_events_emits_progress() {
         use crate::events::EventChannel;
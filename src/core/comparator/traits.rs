@@ -1,6 +1,6 @@
 //! Trait definitions for comparison strategies.
 
-use super::MatchType;
+use super::{scale_threshold, MatchType, REFERENCE_HASH_BITS};
 
 /// Strategy trait for determining if photos are duplicates
 pub trait ComparisonStrategy: Send + Sync {
@@ -10,7 +10,7 @@ pub trait ComparisonStrategy: Send + Sync {
     /// Classify the match type based on distance
     fn classify(&self, distance: u32) -> MatchType;
 
-    /// Get the threshold used
+    /// Get the threshold used, in bits of the hashes being compared
     fn threshold(&self) -> u32;
 
     /// Human-readable description of the strategy
@@ -20,8 +20,10 @@ pub trait ComparisonStrategy: Send + Sync {
 /// Simple threshold-based comparison strategy
 #[derive(Debug, Clone)]
 pub struct ThresholdStrategy {
-    /// Maximum distance to consider as duplicate
+    /// Maximum distance to consider as duplicate, on the 64-bit scale
     threshold: u32,
+    /// Length of the hashes being compared
+    hash_bits: u32,
 }
 
 impl ThresholdStrategy {
@@ -32,7 +34,19 @@ impl ThresholdStrategy {
     /// - 8: Balanced (default)
     /// - 10: Permissive, catches more near-duplicates
     pub fn new(threshold: u32) -> Self {
-        Self { threshold }
+        Self {
+            threshold,
+            hash_bits: REFERENCE_HASH_BITS,
+        }
+    }
+
+    /// Compare hashes of this many bits (default 64).
+    ///
+    /// The threshold stays on the 64-bit scale and is scaled to the hash
+    /// length, so 8 accepts 32 differing bits of a 256-bit hash.
+    pub fn with_hash_bits(mut self, bits: u32) -> Self {
+        self.hash_bits = bits;
+        self
     }
 
     /// Create a conservative strategy (threshold = 5)
@@ -59,21 +73,26 @@ impl Default for ThresholdStrategy {
 
 impl ComparisonStrategy for ThresholdStrategy {
     fn is_duplicate(&self, distance: u32) -> bool {
-        distance <= self.threshold
+        distance <= self.threshold()
     }
 
     fn classify(&self, distance: u32) -> MatchType {
-        MatchType::from_distance(distance)
+        MatchType::from_scaled_distance(distance, self.hash_bits)
     }
 
     fn threshold(&self) -> u32 {
-        self.threshold
+        scale_threshold(self.threshold, self.hash_bits)
     }
 
     fn description(&self) -> String {
+        let limit = if self.hash_bits == REFERENCE_HASH_BITS {
+            self.threshold.to_string()
+        } else {
+            format!("{} of {} bits", self.threshold(), self.hash_bits)
+        };
         format!(
             "Threshold strategy: photos with distance ≤ {} are considered duplicates",
-            self.threshold
+            limit
         )
     }
 }
@@ -101,6 +120,17 @@ mod tests {
         assert_eq!(strategy.classify(12), MatchType::MaybeSimilar);
     }
 
+    #[test]
+    fn threshold_scales_with_hash_bits() {
+        let strategy = ThresholdStrategy::new(8).with_hash_bits(256);
+
+        assert_eq!(strategy.threshold(), 32);
+        assert!(strategy.is_duplicate(32));
+        assert!(!strategy.is_duplicate(33));
+        assert_eq!(strategy.classify(12), MatchType::NearExact);
+        assert_eq!(strategy.classify(30), MatchType::Similar);
+    }
+
     #[test]
     fn preset_strategies() {
         assert_eq!(ThresholdStrategy::conservative().threshold(), 5);
@@ -0,0 +1,181 @@
+//! Block Mean Hash implementation.
+//!
+//! Block mean hash works by:
+//! 1. Resizing the image to ((hash_size + 1) * 4) x ((hash_size + 1) * 4)
+//! 2. Converting to grayscale
+//! 3. Computing the mean of hash_size x hash_size blocks of 8x8 pixels,
+//!    each overlapping its neighbors by half
+//! 4. If a block's mean is above the median block mean, set bit to 1, else 0
+//!
+//! Overlapping the blocks (Yang et al., method 2) makes the hash less
+//! sensitive to small shifts than comparing single pixels, and the median
+//! threshold keeps the bits balanced.
+//!
+//! Uses SIMD-accelerated resizing via fast_image_resize for 5-14x speedup.
+
+use super::super::fast_resize::resize_to_grayscale;
+use super::super::traits::{HashAlgorithm, HashAlgorithmKind, ImageHashValue};
+use crate::error::HashError;
+use image::DynamicImage;
+
+/// Distance in pixels between neighboring blocks (half a block)
+const BLOCK_STEP: u32 = 4;
+
+/// Block Mean Hash implementation
+pub struct BlockMeanHasher {
+    /// Size of the hash (blocks per side)
+    hash_size: u32,
+}
+
+impl BlockMeanHasher {
+    /// Create a new block mean hasher
+    pub fn new(hash_size: u32) -> Self {
+        Self { hash_size }
+    }
+}
+
+impl HashAlgorithm for BlockMeanHasher {
+    fn hash_image(&self, image: &DynamicImage) -> Result<ImageHashValue, HashError> {
+        // hash_size blocks of 2 steps, each starting one step after the last
+        let side = (self.hash_size + 1) * BLOCK_STEP;
+        let gray = resize_to_grayscale(image, side, side)?;
+
+        let block = 2 * BLOCK_STEP;
+        let mut means = Vec::with_capacity((self.hash_size * self.hash_size) as usize);
+        for by in 0..self.hash_size {
+            for bx in 0..self.hash_size {
+                let mut total: u32 = 0;
+                for y in (by * BLOCK_STEP)..(by * BLOCK_STEP + block) {
+                    for x in (bx * BLOCK_STEP)..(bx * BLOCK_STEP + block) {
+                        total += gray.get_pixel(x, y)[0] as u32;
+                    }
+                }
+                means.push(total);
+            }
+        }
+
+        // Every block has the same pixel count, so sums compare like means
+        let mut sorted = means.clone();
+        sorted.sort_unstable();
+        let median = sorted[sorted.len() / 2];
+
+        // Build hash: 1 if block mean > median, 0 otherwise
+        let mut hash_bytes = Vec::with_capacity(means.len() / 8 + 1);
+        let mut current_byte: u8 = 0;
+        let mut bit_position = 0;
+
+        for mean in &means {
+            if *mean > median {
+                current_byte |= 1 << (7 - bit_position);
+            }
+
+            bit_position += 1;
+
+            if bit_position == 8 {
+                hash_bytes.push(current_byte);
+                current_byte = 0;
+                bit_position = 0;
+            }
+        }
+
+        if bit_position > 0 {
+            hash_bytes.push(current_byte);
+        }
+
+        Ok(ImageHashValue::new(
+            hash_bytes,
+            HashAlgorithmKind::BlockMean,
+        ))
+    }
+
+    fn kind(&self) -> HashAlgorithmKind {
+        HashAlgorithmKind::BlockMean
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::traits::PerceptualHash;
+    use super::*;
+    use image::{ImageBuffer, Rgb};
+
+    fn create_solid_image(r: u8, g: u8, b: u8) -> DynamicImage {
+        let img = ImageBuffer::from_fn(100, 100, |_, _| Rgb([r, g, b]));
+        DynamicImage::ImageRgb8(img)
+    }
+
+    fn create_top_to_bottom_gradient(bright_top: bool) -> DynamicImage {
+        let img = ImageBuffer::from_fn(100, 100, |_, y| {
+            let row = if bright_top { 99 - y } else { y };
+            let brightness = (row * 255 / 99) as u8;
+            Rgb([brightness, brightness, brightness])
+        });
+        DynamicImage::ImageRgb8(img)
+    }
+
+    #[test]
+    fn identical_images_produce_identical_hash() {
+        let hasher = BlockMeanHasher::new(8);
+        let image = create_top_to_bottom_gradient(true);
+
+        let hash1 = hasher.hash_image(&image).unwrap();
+        let hash2 = hasher.hash_image(&image).unwrap();
+
+        assert_eq!(hash1.distance(&hash2), 0);
+    }
+
+    #[test]
+    fn different_images_produce_different_hash() {
+        let hasher = BlockMeanHasher::new(8);
+
+        // Opposite gradients put the bright blocks at opposite ends
+        let hash1 = hasher
+            .hash_image(&create_top_to_bottom_gradient(true))
+            .unwrap();
+        let hash2 = hasher
+            .hash_image(&create_top_to_bottom_gradient(false))
+            .unwrap();
+
+        let distance = hash1.distance(&hash2);
+        assert!(
+            distance > 32,
+            "Expected opposite gradients to produce very different hashes, got distance {}",
+            distance
+        );
+    }
+
+    #[test]
+    fn hash_size_affects_output_length() {
+        let hasher_8 = BlockMeanHasher::new(8);
+        let hasher_16 = BlockMeanHasher::new(16);
+
+        let image = create_solid_image(128, 128, 128);
+
+        let hash_8 = hasher_8.hash_image(&image).unwrap();
+        let hash_16 = hasher_16.hash_image(&image).unwrap();
+
+        // 8x8 = 64 bits = 8 bytes
+        assert_eq!(hash_8.as_bytes().len(), 8);
+
+        // 16x16 = 256 bits = 32 bytes
+        assert_eq!(hash_16.as_bytes().len(), 32);
+    }
+
+    #[test]
+    fn gradient_sets_half_the_bits() {
+        let hasher = BlockMeanHasher::new(8);
+        let hash = hasher
+            .hash_image(&create_top_to_bottom_gradient(true))
+            .unwrap();
+
+        // Rows above the median are set; the median row itself is not
+        let ones: u32 = hash.as_bytes().iter().map(|b| b.count_ones()).sum();
+        assert!((24..=32).contains(&ones), "{} bits set", ones);
+    }
+
+    #[test]
+    fn kind_returns_block_mean() {
+        let hasher = BlockMeanHasher::new(8);
+        assert_eq!(hasher.kind(), HashAlgorithmKind::BlockMean);
+    }
+}
@@ -1,9 +1,13 @@
 //! Hash algorithm implementations.
 
 mod average;
+mod block_mean;
 mod difference;
 mod perceptual;
+mod wavelet;
 
 pub use average::AverageHasher;
+pub use block_mean::BlockMeanHasher;
 pub use difference::DifferenceHasher;
 pub use perceptual::PerceptualHasher;
+pub use wavelet::WaveletHasher;
@@ -0,0 +1,194 @@
+//! Wavelet Hash (wHash) implementation.
+//!
+//! wHash works by:
+//! 1. Resizing the image to (hash_size * 8) x (hash_size * 8)
+//! 2. Converting to grayscale
+//! 3. Applying three levels of the 2D Haar wavelet transform, keeping only
+//!    the low-frequency (LL) band, which ends up hash_size x hash_size
+//! 4. If a coefficient is above the median coefficient, set bit to 1, else 0
+//!
+//! The detail bands dropped at each level hold fine texture, noise and
+//! compression artifacts, so the hash follows the coarse structure of the
+//! image. Thresholding at the median keeps the bits balanced.
+//!
+//! Uses SIMD-accelerated resizing via fast_image_resize for 5-14x speedup.
+
+use super::super::fast_resize::resize_to_grayscale;
+use super::super::traits::{HashAlgorithm, HashAlgorithmKind, ImageHashValue};
+use crate::error::HashError;
+use image::DynamicImage;
+
+/// Number of Haar levels between the resized image and the hash grid
+const HAAR_LEVELS: u32 = 3;
+
+/// Wavelet Hash (wHash) implementation
+pub struct WaveletHasher {
+    /// Size of the hash (width and height of the LL band)
+    hash_size: u32,
+}
+
+impl WaveletHasher {
+    /// Create a new wHash hasher
+    pub fn new(hash_size: u32) -> Self {
+        Self { hash_size }
+    }
+}
+
+/// One level of the 2D Haar transform, returning only the LL band.
+///
+/// `size` is the side of the square input; the output is `size / 2` square.
+fn haar_ll(band: &[f32], size: usize) -> Vec<f32> {
+    let half = size / 2;
+    let mut ll = Vec::with_capacity(half * half);
+    for y in 0..half {
+        for x in 0..half {
+            let top = (2 * y) * size + 2 * x;
+            let bottom = top + size;
+            // Orthonormal Haar: (a + b + c + d) / 2
+            ll.push((band[top] + band[top + 1] + band[bottom] + band[bottom + 1]) / 2.0);
+        }
+    }
+    ll
+}
+
+/// Median of a set of coefficients
+fn median(values: &[f32]) -> f32 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let mid = sorted.len() / 2;
+    if sorted.len().is_multiple_of(2) {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+impl HashAlgorithm for WaveletHasher {
+    fn hash_image(&self, image: &DynamicImage) -> Result<ImageHashValue, HashError> {
+        // Resize so that HAAR_LEVELS halvings land exactly on hash_size
+        let scale = self.hash_size << HAAR_LEVELS;
+        let gray = resize_to_grayscale(image, scale, scale)?;
+
+        // Run the transform on normalized brightness
+        let mut band: Vec<f32> = gray.pixels().map(|p| p[0] as f32 / 255.0).collect();
+        let mut size = scale as usize;
+        for _ in 0..HAAR_LEVELS {
+            band = haar_ll(&band, size);
+            size /= 2;
+        }
+
+        // Build hash: 1 if coefficient > median, 0 otherwise
+        let threshold = median(&band);
+        let mut hash_bytes = Vec::with_capacity(band.len() / 8 + 1);
+        let mut current_byte: u8 = 0;
+        let mut bit_position = 0;
+
+        for coefficient in &band {
+            if *coefficient > threshold {
+                current_byte |= 1 << (7 - bit_position);
+            }
+
+            bit_position += 1;
+
+            if bit_position == 8 {
+                hash_bytes.push(current_byte);
+                current_byte = 0;
+                bit_position = 0;
+            }
+        }
+
+        if bit_position > 0 {
+            hash_bytes.push(current_byte);
+        }
+
+        Ok(ImageHashValue::new(hash_bytes, HashAlgorithmKind::Wavelet))
+    }
+
+    fn kind(&self) -> HashAlgorithmKind {
+        HashAlgorithmKind::Wavelet
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::traits::PerceptualHash;
+    use super::*;
+    use image::{ImageBuffer, Rgb};
+
+    fn create_solid_image(r: u8, g: u8, b: u8) -> DynamicImage {
+        let img = ImageBuffer::from_fn(100, 100, |_, _| Rgb([r, g, b]));
+        DynamicImage::ImageRgb8(img)
+    }
+
+    fn create_quadrant_image(bright_left: bool) -> DynamicImage {
+        let img = ImageBuffer::from_fn(100, 100, |x, y| {
+            let bright = (x < 50) == bright_left;
+            let value = if bright { 220 } else { 30 } + (y / 10) as u8;
+            Rgb([value, value, value])
+        });
+        DynamicImage::ImageRgb8(img)
+    }
+
+    #[test]
+    fn identical_images_produce_identical_hash() {
+        let hasher = WaveletHasher::new(8);
+        let image = create_quadrant_image(true);
+
+        let hash1 = hasher.hash_image(&image).unwrap();
+        let hash2 = hasher.hash_image(&image).unwrap();
+
+        assert_eq!(hash1.distance(&hash2), 0);
+    }
+
+    #[test]
+    fn different_images_produce_different_hash() {
+        let hasher = WaveletHasher::new(8);
+
+        // Mirror images: bright half on opposite sides
+        let hash1 = hasher.hash_image(&create_quadrant_image(true)).unwrap();
+        let hash2 = hasher.hash_image(&create_quadrant_image(false)).unwrap();
+
+        let distance = hash1.distance(&hash2);
+        assert!(
+            distance > 32,
+            "Expected mirrored halves to produce very different hashes, got distance {}",
+            distance
+        );
+    }
+
+    #[test]
+    fn hash_size_affects_output_length() {
+        let hasher_8 = WaveletHasher::new(8);
+        let hasher_16 = WaveletHasher::new(16);
+
+        let image = create_solid_image(128, 128, 128);
+
+        let hash_8 = hasher_8.hash_image(&image).unwrap();
+        let hash_16 = hasher_16.hash_image(&image).unwrap();
+
+        // 8x8 = 64 bits = 8 bytes
+        assert_eq!(hash_8.as_bytes().len(), 8);
+
+        // 16x16 = 256 bits = 32 bytes
+        assert_eq!(hash_16.as_bytes().len(), 32);
+    }
+
+    #[test]
+    fn haar_ll_averages_blocks() {
+        // 4x4 input with a distinct value per 2x2 block
+        let band = [
+            1.0, 1.0, 2.0, 2.0, //
+            1.0, 1.0, 2.0, 2.0, //
+            3.0, 3.0, 4.0, 4.0, //
+            3.0, 3.0, 4.0, 4.0,
+        ];
+
+        assert_eq!(haar_ll(&band, 4), vec![2.0, 4.0, 6.0, 8.0]);
+    }
+
+    #[test]
+    fn kind_returns_wavelet() {
+        let hasher = WaveletHasher::new(8);
+        assert_eq!(hasher.kind(), HashAlgorithmKind::Wavelet);
+    }
+}
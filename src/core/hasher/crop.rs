@@ -0,0 +1,325 @@
+//! # Crop-Resistant Hasher
+//!
+//! Finds cropped copies of a photo, which fall outside the threshold of
+//! every global hash because the framing changed.
+//!
+//! ## How It Works
+//! 1. Shrink the upright image to a grayscale thumbnail
+//! 2. Lay a grid over it and cut out every overlapping window of at least
+//!    half the width and half the height
+//! 3. dHash the whole image and every window, remembering where each
+//!    window is
+//!
+//! A photo is a crop of another when its whole-image hash is within the
+//! threshold of one of the other photo's window hashes. The matching
+//! window gives the approximate crop region; how close it is depends on
+//! how well the crop lines up with the grid.
+
+use super::algorithms::DifferenceHasher;
+use super::traits::{HashAlgorithm, HashAlgorithmKind, ImageHashValue, PerceptualHash};
+use crate::error::HashError;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+/// Number of grid cells per side; windows start and end on cell edges
+pub const CROP_GRID: u8 = 8;
+
+/// Smallest window side, in grid cells (half the image)
+pub const MIN_SEGMENT_CELLS: u8 = CROP_GRID / 2;
+
+/// Longest side of the thumbnail the windows are cut from
+pub const SEGMENT_THUMBNAIL_SIZE: u32 = 256;
+
+/// A rectangle within an image, as fractions (0.0-1.0) of its size
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CropRegion {
+    /// Left edge
+    pub x: f32,
+    /// Top edge
+    pub y: f32,
+    /// Width
+    pub width: f32,
+    /// Height
+    pub height: f32,
+}
+
+impl CropRegion {
+    /// The whole image
+    pub fn full() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            width: 1.0,
+            height: 1.0,
+        }
+    }
+
+    /// Check if this region covers the whole image
+    pub fn is_full(&self) -> bool {
+        self.width >= 1.0 && self.height >= 1.0
+    }
+
+    /// Fraction of the image's area this region covers
+    pub fn area(&self) -> f32 {
+        self.width * self.height
+    }
+
+    /// Build a region from grid cell coordinates
+    fn from_cells(cells: [u8; 4]) -> Self {
+        let grid = CROP_GRID as f32;
+        Self {
+            x: cells[0] as f32 / grid,
+            y: cells[1] as f32 / grid,
+            width: cells[2] as f32 / grid,
+            height: cells[3] as f32 / grid,
+        }
+    }
+
+    /// Grid cell coordinates of this region
+    fn to_cells(self) -> [u8; 4] {
+        let grid = CROP_GRID as f32;
+        [self.x, self.y, self.width, self.height].map(|v| (v * grid).round() as u8)
+    }
+}
+
+impl std::fmt::Display for CropRegion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:.0}% × {:.0}% starting {:.0}% from the left and {:.0}% from the top",
+            self.width * 100.0,
+            self.height * 100.0,
+            self.x * 100.0,
+            self.y * 100.0
+        )
+    }
+}
+
+/// One window of an image and its hash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Segment {
+    /// Where the window is
+    pub region: CropRegion,
+    /// dHash of the window
+    pub hash: ImageHashValue,
+}
+
+/// Hashes of an image and all of its windows, whole image first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CropHash {
+    /// Whole image first, then every window
+    pub segments: Vec<Segment>,
+}
+
+impl CropHash {
+    /// Create a crop hash; the first segment must be the whole image
+    pub fn new(segments: Vec<Segment>) -> Self {
+        Self { segments }
+    }
+
+    /// Hash of the whole image
+    pub fn full(&self) -> &ImageHashValue {
+        &self.segments[0].hash
+    }
+
+    /// Find where `cropped` best fits inside this image.
+    ///
+    /// Compares the other image's whole-image hash against every window of
+    /// this one and returns the closest window and its distance. The whole
+    /// frame is skipped, since that is an ordinary match rather than a crop.
+    pub fn locate(&self, cropped: &CropHash) -> Option<(CropRegion, u32)> {
+        let target = cropped.full();
+        self.segments
+            .iter()
+            .filter(|s| !s.region.is_full())
+            .map(|s| (s.region, s.hash.distance(target)))
+            .min_by_key(|(_, distance)| *distance)
+    }
+
+    /// Get the combined bytes for caching
+    /// Format: [hash_len:2] then per segment [x][y][w][h][hash], in grid cells
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let hash_len = self.full().as_bytes().len();
+        let mut bytes = Vec::with_capacity(2 + self.segments.len() * (4 + hash_len));
+        bytes.extend_from_slice(&(hash_len as u16).to_le_bytes());
+
+        for segment in &self.segments {
+            bytes.extend_from_slice(&segment.region.to_cells());
+            bytes.extend_from_slice(segment.hash.as_bytes());
+        }
+
+        bytes
+    }
+
+    /// Restore from cached bytes
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 2 {
+            return None;
+        }
+        let hash_len = u16::from_le_bytes([bytes[0], bytes[1]]) as usize;
+        let body = &bytes[2..];
+        let stride = 4 + hash_len;
+        if hash_len == 0 || body.is_empty() || !body.len().is_multiple_of(stride) {
+            return None;
+        }
+
+        let segments: Vec<Segment> = body
+            .chunks_exact(stride)
+            .map(|chunk| Segment {
+                region: CropRegion::from_cells([chunk[0], chunk[1], chunk[2], chunk[3]]),
+                hash: ImageHashValue::from_bytes(&chunk[4..], HashAlgorithmKind::Difference),
+            })
+            .collect();
+
+        segments[0].region.is_full().then_some(Self { segments })
+    }
+}
+
+/// Computes crop-resistant hashes from overlapping image windows
+pub struct CropResistantHasher {
+    segment_hasher: DifferenceHasher,
+}
+
+impl CropResistantHasher {
+    /// Create a new crop-resistant hasher with default hash size (8)
+    pub fn new() -> Self {
+        Self::with_size(8)
+    }
+
+    /// Create a crop-resistant hasher with a specific per-window hash size
+    pub fn with_size(size: u32) -> Self {
+        Self {
+            segment_hasher: DifferenceHasher::new(size),
+        }
+    }
+
+    /// Compute the crop hash of a loaded image
+    pub fn hash_segments(&self, image: &DynamicImage) -> Result<CropHash, HashError> {
+        let thumbnail = image
+            .thumbnail(SEGMENT_THUMBNAIL_SIZE, SEGMENT_THUMBNAIL_SIZE)
+            .to_luma8();
+        let (width, height) = thumbnail.dimensions();
+        let grid = CROP_GRID as u32;
+        let to_pixels = |cells: u8, size: u32| cells as u32 * size / grid;
+
+        let mut segments = Vec::new();
+        for cells_h in (MIN_SEGMENT_CELLS..=CROP_GRID).rev() {
+            for cells_w in (MIN_SEGMENT_CELLS..=CROP_GRID).rev() {
+                for cell_y in 0..=(CROP_GRID - cells_h) {
+                    for cell_x in 0..=(CROP_GRID - cells_w) {
+                        let x = to_pixels(cell_x, width);
+                        let y = to_pixels(cell_y, height);
+                        let w = to_pixels(cells_w, width).max(1);
+                        let h = to_pixels(cells_h, height).max(1);
+
+                        let window = image::imageops::crop_imm(&thumbnail, x, y, w, h).to_image();
+                        let hash = self
+                            .segment_hasher
+                            .hash_image(&DynamicImage::ImageLuma8(window))?;
+
+                        segments.push(Segment {
+                            region: CropRegion::from_cells([cell_x, cell_y, cells_w, cells_h]),
+                            hash,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(CropHash::new(segments))
+    }
+}
+
+impl Default for CropResistantHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HashAlgorithm for CropResistantHasher {
+    fn hash_image(&self, image: &DynamicImage) -> Result<ImageHashValue, HashError> {
+        let crop = self.hash_segments(image)?;
+        // Store all windows as combined bytes - can be decoded back to CropHash
+        Ok(ImageHashValue::new(
+            crop.to_bytes(),
+            HashAlgorithmKind::CropResistant,
+        ))
+    }
+
+    fn kind(&self) -> HashAlgorithmKind {
+        HashAlgorithmKind::CropResistant
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+
+    /// A 320x240 image with distinct detail in every region
+    fn detailed_image() -> DynamicImage {
+        let img = GrayImage::from_fn(320, 240, |x, y| {
+            let wave = ((x as f32 / 9.0).sin() * (y as f32 / 13.0).cos() * 100.0) as i32;
+            let blocks = ((x / 40 + y / 30) % 3) as i32 * 40;
+            Luma([(wave + blocks + 100).clamp(0, 255) as u8])
+        });
+        DynamicImage::ImageLuma8(img)
+    }
+
+    #[test]
+    fn first_segment_is_whole_image() {
+        let hasher = CropResistantHasher::new();
+        let crop = hasher.hash_segments(&detailed_image()).unwrap();
+
+        assert!(crop.segments[0].region.is_full());
+        // 5 widths x 5 heights of windows, each placed on every grid offset
+        assert_eq!(crop.segments.len(), 15 * 15);
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let hasher = CropResistantHasher::new();
+        let crop = hasher.hash_segments(&detailed_image()).unwrap();
+
+        let restored = CropHash::from_bytes(&crop.to_bytes()).unwrap();
+
+        assert_eq!(restored.segments.len(), crop.segments.len());
+        assert_eq!(restored.segments[30].region, crop.segments[30].region);
+        assert_eq!(
+            restored.segments[30].hash.as_bytes(),
+            crop.segments[30].hash.as_bytes()
+        );
+    }
+
+    #[test]
+    fn from_bytes_rejects_truncated_data() {
+        let hasher = CropResistantHasher::new();
+        let bytes = hasher.hash_segments(&detailed_image()).unwrap().to_bytes();
+
+        assert!(CropHash::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+        assert!(CropHash::from_bytes(&[]).is_none());
+    }
+
+    #[test]
+    fn grid_aligned_crop_is_located() {
+        let hasher = CropResistantHasher::new();
+        let original = detailed_image();
+        // Right three quarters, top half
+        let cropped = original.crop_imm(80, 0, 240, 120);
+
+        let original_hash = hasher.hash_segments(&original).unwrap();
+        let cropped_hash = hasher.hash_segments(&cropped).unwrap();
+
+        let (region, distance) = original_hash.locate(&cropped_hash).unwrap();
+        assert!(distance <= 4, "distance {}", distance);
+        assert_eq!(region.to_cells(), [2, 0, 6, 4]);
+    }
+
+    #[test]
+    fn kind_returns_crop_resistant() {
+        assert_eq!(
+            CropResistantHasher::new().kind(),
+            HashAlgorithmKind::CropResistant
+        );
+    }
+}
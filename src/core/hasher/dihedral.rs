@@ -0,0 +1,244 @@
+//! # Dihedral Transforms
+//!
+//! The eight ways an image can be rotated or mirrored without distortion
+//! (the dihedral group D4). Used to match rotated and flipped copies, and to
+//! undo EXIF orientation before hashing.
+//!
+//! ## How It Works
+//! 1. Decode the image and apply its EXIF orientation, so it is upright
+//! 2. Shrink it to a small grayscale thumbnail (transforms are cheap there)
+//! 3. Hash all eight transforms of the thumbnail
+//!
+//! Two photos are a rotated or mirrored copy when one photo's transformed
+//! hash is within the threshold of the other's upright hash. Variant hashes
+//! are cached alongside the upright hash, so a cache hit needs no decode.
+
+use super::fast_decode::{FastDecoder, HASH_DECODE_SIZE};
+use super::traits::{HashAlgorithm, ImageHashValue};
+use crate::error::HashError;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Longest side of the thumbnail the variants are hashed from
+pub const VARIANT_THUMBNAIL_SIZE: u32 = 256;
+
+/// Hashes of every rotation and mirror of one photo, identity first
+pub type HashVariants = Vec<(DihedralTransform, ImageHashValue)>;
+
+/// A rotation and/or mirror of an image
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DihedralTransform {
+    /// Unchanged
+    Identity,
+    /// Rotated 90° clockwise
+    Rotate90,
+    /// Rotated 180°
+    Rotate180,
+    /// Rotated 270° clockwise (90° counter-clockwise)
+    Rotate270,
+    /// Mirrored left to right
+    FlipHorizontal,
+    /// Mirrored top to bottom
+    FlipVertical,
+    /// Mirrored across the top-left to bottom-right diagonal
+    Transpose,
+    /// Mirrored across the top-right to bottom-left diagonal
+    Transverse,
+}
+
+impl DihedralTransform {
+    /// All eight transforms, identity first
+    pub const ALL: [DihedralTransform; 8] = [
+        DihedralTransform::Identity,
+        DihedralTransform::Rotate90,
+        DihedralTransform::Rotate180,
+        DihedralTransform::Rotate270,
+        DihedralTransform::FlipHorizontal,
+        DihedralTransform::FlipVertical,
+        DihedralTransform::Transpose,
+        DihedralTransform::Transverse,
+    ];
+
+    /// The transform that displays an image with the given EXIF orientation
+    /// upright.
+    ///
+    /// Unknown values are treated as 1 (already upright).
+    pub fn from_exif_orientation(orientation: u16) -> Self {
+        match orientation {
+            2 => Self::FlipHorizontal,
+            3 => Self::Rotate180,
+            4 => Self::FlipVertical,
+            5 => Self::Transpose,
+            6 => Self::Rotate90,
+            7 => Self::Transverse,
+            8 => Self::Rotate270,
+            _ => Self::Identity,
+        }
+    }
+
+    /// The transform that undoes this one
+    pub fn inverse(&self) -> Self {
+        match self {
+            Self::Rotate90 => Self::Rotate270,
+            Self::Rotate270 => Self::Rotate90,
+            other => *other,
+        }
+    }
+
+    /// Check if this transform mirrors the image
+    pub fn is_mirrored(&self) -> bool {
+        matches!(
+            self,
+            Self::FlipHorizontal | Self::FlipVertical | Self::Transpose | Self::Transverse
+        )
+    }
+
+    /// Apply this transform to an image
+    pub fn apply(&self, image: &DynamicImage) -> DynamicImage {
+        match self {
+            Self::Identity => image.clone(),
+            Self::Rotate90 => image.rotate90(),
+            Self::Rotate180 => image.rotate180(),
+            Self::Rotate270 => image.rotate270(),
+            Self::FlipHorizontal => image.fliph(),
+            Self::FlipVertical => image.flipv(),
+            Self::Transpose => image.rotate90().fliph(),
+            Self::Transverse => image.rotate270().fliph(),
+        }
+    }
+}
+
+impl std::fmt::Display for DihedralTransform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Identity => write!(f, "not rotated"),
+            Self::Rotate90 => write!(f, "rotated 90°"),
+            Self::Rotate180 => write!(f, "rotated 180°"),
+            Self::Rotate270 => write!(f, "rotated 270°"),
+            Self::FlipHorizontal => write!(f, "mirrored horizontally"),
+            Self::FlipVertical => write!(f, "mirrored vertically"),
+            Self::Transpose => write!(f, "rotated 90° and mirrored"),
+            Self::Transverse => write!(f, "rotated 270° and mirrored"),
+        }
+    }
+}
+
+/// Hashes all rotations and mirrors of an image with another algorithm
+pub struct DihedralHasher<'a> {
+    inner: &'a dyn HashAlgorithm,
+}
+
+impl<'a> DihedralHasher<'a> {
+    /// Wrap a hasher so it produces one hash per transform
+    pub fn new(inner: &'a dyn HashAlgorithm) -> Self {
+        Self { inner }
+    }
+
+    /// Decode a file, make it upright using its EXIF orientation, and hash
+    /// every transform of it.
+    pub fn hash_file(&self, path: &Path) -> Result<HashVariants, HashError> {
+        let image = FastDecoder::decode_for_hash(path, HASH_DECODE_SIZE)?;
+        self.hash_upright(&image)
+    }
+
+    /// Like [`hash_file`](Self::hash_file), for an image that was already
+    /// decoded upright. Shrinks it to a thumbnail before transforming.
+    pub fn hash_upright(&self, image: &DynamicImage) -> Result<HashVariants, HashError> {
+        let thumbnail = image.thumbnail(VARIANT_THUMBNAIL_SIZE, VARIANT_THUMBNAIL_SIZE);
+        self.hash_image(&DynamicImage::ImageLuma8(thumbnail.to_luma8()))
+    }
+
+    /// Hash every transform of an (upright) image, identity first
+    pub fn hash_image(&self, image: &DynamicImage) -> Result<HashVariants, HashError> {
+        DihedralTransform::ALL
+            .iter()
+            .map(|t| Ok((*t, self.inner.hash_image(&t.apply(image))?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hasher::{DifferenceHasher, PerceptualHash};
+    use image::{GrayImage, Luma};
+
+    /// An image with no rotational or mirror symmetry
+    fn asymmetric_image() -> DynamicImage {
+        let img = GrayImage::from_fn(64, 48, |x, y| {
+            let value = if x < 20 && y < 12 {
+                255
+            } else {
+                (x * 3 + y) as u8
+            };
+            Luma([value])
+        });
+        DynamicImage::ImageLuma8(img)
+    }
+
+    #[test]
+    fn exif_orientations_map_to_transforms() {
+        assert_eq!(
+            DihedralTransform::from_exif_orientation(1),
+            DihedralTransform::Identity
+        );
+        assert_eq!(
+            DihedralTransform::from_exif_orientation(6),
+            DihedralTransform::Rotate90
+        );
+        assert_eq!(
+            DihedralTransform::from_exif_orientation(8),
+            DihedralTransform::Rotate270
+        );
+        assert_eq!(
+            DihedralTransform::from_exif_orientation(0),
+            DihedralTransform::Identity
+        );
+    }
+
+    #[test]
+    fn inverse_undoes_transform() {
+        let image = asymmetric_image();
+        for t in DihedralTransform::ALL {
+            let round_trip = t.inverse().apply(&t.apply(&image));
+            assert_eq!(round_trip.as_bytes(), image.as_bytes(), "{:?}", t);
+        }
+    }
+
+    #[test]
+    fn transforms_are_distinct() {
+        let image = asymmetric_image();
+        let outputs: Vec<_> = DihedralTransform::ALL
+            .iter()
+            .map(|t| {
+                let out = t.apply(&image);
+                (out.width(), out.height(), out.as_bytes().to_vec())
+            })
+            .collect();
+
+        for i in 0..outputs.len() {
+            for j in (i + 1)..outputs.len() {
+                assert_ne!(outputs[i], outputs[j]);
+            }
+        }
+    }
+
+    #[test]
+    fn rotated_copy_matches_a_variant() {
+        let inner = DifferenceHasher::new(8);
+        let hasher = DihedralHasher::new(&inner);
+        let original = asymmetric_image();
+        let rotated = original.rotate90();
+
+        let upright = inner.hash_image(&original).unwrap();
+        let variants = hasher.hash_image(&rotated).unwrap();
+
+        let (transform, best) = variants
+            .iter()
+            .min_by_key(|(_, h)| h.distance(&upright))
+            .unwrap();
+        assert_eq!(best.distance(&upright), 0);
+        assert_eq!(*transform, DihedralTransform::Rotate270);
+    }
+}
@@ -5,16 +5,42 @@
 //!
 //! For large files (>1MB), uses memory-mapped I/O to reduce
 //! kernel copy overhead by 20-40%.
+//!
+//! Camera RAW files are decoded from their embedded full-size JPEG preview,
+//! or demosaiced at half size when they don't have one. AVIF and JPEG XL
+//! need the `avif` and `jxl` cargo features.
+//!
+//! Decoders return pixels in stored order, except JPEG XL whose codestream
+//! orientation is always applied by the decoder. Pass [`DecodeOptions`] with
+//! `apply_orientation` to get the image the way a viewer shows it, rotated
+//! and mirrored according to its EXIF orientation tag.
 
+use super::dihedral::DihedralTransform;
 use super::mmap_decode::{read_file_bytes, validate_image_header};
+use super::thumbnail::exif_thumbnail;
+use crate::core::metadata::read_orientation;
+use crate::core::raw::{self, MIN_PREVIEW_SIZE};
+use crate::core::scanner;
 use crate::error::HashError;
 use image::{DynamicImage, ImageBuffer, Luma, Rgb, Rgba};
+use jpeg_decoder::PixelFormat;
 use std::path::Path;
+#[cfg(all(target_os = "macos", not(feature = "heif")))]
 use std::process::Command;
 use zune_core::colorspace::ColorSpace;
 use zune_core::options::DecoderOptions;
 use zune_jpeg::JpegDecoder;
 
+/// Longer side that images are decoded to for hashing.
+///
+/// Covers every hash grid, the 256px thumbnails that rotation variants and
+/// crop-resistant windows are cut from, and the 512px quality analysis, so
+/// one decode serves all of them.
+pub const HASH_DECODE_SIZE: u32 = 512;
+
+/// EXIF thumbnails with a shorter long edge than this are too coarse to hash
+pub const MIN_THUMBNAIL_SIZE: u32 = 128;
+
 /// Supported image formats for fast decoding
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ImageFormat {
@@ -22,6 +48,10 @@ pub enum ImageFormat {
     Png,
     WebP,
     Heic,
+    Tiff,
+    Avif,
+    Jxl,
+    Raw,
     Other,
 }
 
@@ -38,55 +68,255 @@ impl ImageFormat {
             Some("png") => Self::Png,
             Some("webp") => Self::WebP,
             Some("heic" | "heif") => Self::Heic,
+            Some("tif" | "tiff") => Self::Tiff,
+            Some("avif") => Self::Avif,
+            Some("jxl") => Self::Jxl,
+            Some(ext) if raw::is_raw_extension(ext) => Self::Raw,
             _ => Self::Other,
         }
     }
+
+    /// Detect format from the magic bytes at the start of a file, using the
+    /// extension when they agree with it or aren't recognized
+    pub fn from_header(path: &Path, header: &[u8]) -> Self {
+        Self::from(scanner::ImageFormat::from_content(path, header))
+    }
+
+    /// Read the start of a file and detect its format; see
+    /// [`ImageFormat::from_header`]
+    pub fn detect(path: &Path) -> Self {
+        Self::from(scanner::ImageFormat::sniff(path))
+    }
+
+    /// Detect format from the header with `sniff_content`, and from the
+    /// extension alone otherwise
+    pub fn from_header_if(path: &Path, header: &[u8], sniff_content: bool) -> Self {
+        if sniff_content {
+            Self::from_header(path, header)
+        } else {
+            Self::from_path(path)
+        }
+    }
+}
+
+impl From<scanner::ImageFormat> for ImageFormat {
+    fn from(format: scanner::ImageFormat) -> Self {
+        match format {
+            scanner::ImageFormat::Jpeg => Self::Jpeg,
+            scanner::ImageFormat::Png => Self::Png,
+            scanner::ImageFormat::WebP => Self::WebP,
+            scanner::ImageFormat::Heic => Self::Heic,
+            scanner::ImageFormat::Tiff => Self::Tiff,
+            scanner::ImageFormat::Avif => Self::Avif,
+            scanner::ImageFormat::Jxl => Self::Jxl,
+            scanner::ImageFormat::Raw => Self::Raw,
+            scanner::ImageFormat::Gif
+            | scanner::ImageFormat::Bmp
+            | scanner::ImageFormat::Unknown => Self::Other,
+        }
+    }
+}
+
+/// Options controlling how [`FastDecoder`] decodes an image
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// Rotate and mirror the image according to its EXIF orientation tag
+    pub apply_orientation: bool,
+    /// Pick the decoder by the file's magic bytes rather than its extension,
+    /// so mislabeled files decode as what they are. Costs reading the start
+    /// of the file first.
+    pub sniff_content: bool,
+}
+
+impl DecodeOptions {
+    /// Create options that decode pixels in stored order
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Options used for hashing and quality analysis (orientation applied)
+    pub fn upright() -> Self {
+        Self::new().apply_orientation(true)
+    }
+
+    /// Set whether to apply the EXIF orientation
+    pub fn apply_orientation(mut self, apply: bool) -> Self {
+        self.apply_orientation = apply;
+        self
+    }
+
+    /// Set whether to pick the decoder by the file's magic bytes
+    pub fn sniff_content(mut self, sniff: bool) -> Self {
+        self.sniff_content = sniff;
+        self
+    }
+
+    /// Format whose decoder these options pick for a file
+    fn format(&self, path: &Path) -> ImageFormat {
+        if self.sniff_content {
+            ImageFormat::detect(path)
+        } else {
+            ImageFormat::from_path(path)
+        }
+    }
 }
 
 /// Fast image decoder that uses optimized decoders per format
 pub struct FastDecoder;
 
 impl FastDecoder {
+    /// Decode an image with the given options.
+    ///
+    /// With `apply_orientation`, JPEG, TIFF, HEIC, AVIF, WebP and RAW files
+    /// carrying an EXIF orientation tag are returned upright. Formats without
+    /// EXIF, or files without the tag, are returned as stored. JPEG XL is
+    /// always upright.
+    pub fn decode_with(path: &Path, options: &DecodeOptions) -> Result<DynamicImage, HashError> {
+        let format = options.format(path);
+        let image = Self::decode_as(path, format)?;
+        if !options.apply_orientation {
+            return Ok(image);
+        }
+
+        Ok(Self::make_upright(path, format, image))
+    }
+
+    /// Apply a decoded file's EXIF orientation
+    fn make_upright(path: &Path, format: ImageFormat, image: DynamicImage) -> DynamicImage {
+        let orientation = if Self::has_exif_orientation(format) {
+            read_orientation(path)
+        } else {
+            None
+        };
+        Self::apply_orientation(format, image, orientation)
+    }
+
+    /// Apply an EXIF orientation already read from the file
+    fn apply_orientation(
+        format: ImageFormat,
+        image: DynamicImage,
+        orientation: Option<u16>,
+    ) -> DynamicImage {
+        let transform = orientation
+            .filter(|_| Self::has_exif_orientation(format))
+            .map(DihedralTransform::from_exif_orientation)
+            .unwrap_or(DihedralTransform::Identity);
+        match transform {
+            DihedralTransform::Identity => image,
+            transform => transform.apply(&image),
+        }
+    }
+
+    /// Whether the decoders leave a file's EXIF orientation to the caller
+    fn has_exif_orientation(format: ImageFormat) -> bool {
+        matches!(
+            format,
+            ImageFormat::Jpeg
+                | ImageFormat::Tiff
+                | ImageFormat::Heic
+                | ImageFormat::Avif
+                | ImageFormat::WebP
+                | ImageFormat::Raw
+        )
+    }
+
     /// Decode an image from a file path using the fastest available decoder.
     ///
     /// - JPEG: Uses zune-jpeg (1.5-2x faster)
-    /// - HEIC/HEIF: Uses libheif-rs (native support for iPhone photos)
+    /// - HEIC/HEIF: Uses libheif with the `heif` feature, `sips` on macOS
+    /// - AVIF: Uses dav1d through the image crate with the `avif` feature
+    /// - JPEG XL: Uses jxl-oxide with the `jxl` feature
+    /// - Camera RAW: Uses the embedded preview, demosaics without one
     /// - Other formats: Falls back to image crate
+    ///
+    /// The decoder is picked by the extension. Mislabeled files the image
+    /// crate reads still decode through the fallback; see
+    /// [`DecodeOptions::sniff_content`] to pick by the magic bytes instead.
     pub fn decode(path: &Path) -> Result<DynamicImage, HashError> {
-        let format = ImageFormat::from_path(path);
+        Self::decode_as(path, ImageFormat::from_path(path))
+    }
 
+    /// Decode with the decoder for `format`
+    fn decode_as(path: &Path, format: ImageFormat) -> Result<DynamicImage, HashError> {
         match format {
             ImageFormat::Jpeg => Self::decode_jpeg(path).or_else(|_| Self::decode_fallback(path)),
             ImageFormat::Heic => Self::decode_heic(path).or_else(|_| Self::decode_fallback(path)),
+            ImageFormat::Avif => Self::decode_avif(path),
+            ImageFormat::Jxl => Self::decode_jxl(path),
+            ImageFormat::Raw => Self::decode_raw(path, None),
             _ => Self::decode_fallback(path),
         }
     }
 
-    /// Decode an image optimized for hashing (smaller memory footprint).
+    /// Decode an image upright for hashing, at reduced size when that is cheap.
     ///
-    /// For JPEGs, uses DCT scaling to decode at reduced resolution when the
-    /// target hash size is much smaller than the source image. This can reduce
-    /// memory usage by 60-90% for large images.
+    /// JPEGs and RAW previews are decoded with IDCT scaling at 1/2, 1/4 or
+    /// 1/8 size, the smallest that keeps the longer side at least
+    /// `target_size`. That skips most of the decoding work and memory for
+    /// large photos. Other formats are decoded at full size; either way the
+    /// result still needs resizing to the hash grid.
     ///
     /// # Arguments
     /// * `path` - Path to the image file
-    /// * `target_size` - Target dimension for hashing (e.g., 8 for 8x8 hash)
+    /// * `target_size` - Smallest useful length of the longer side
     pub fn decode_for_hash(path: &Path, target_size: u32) -> Result<DynamicImage, HashError> {
         let format = ImageFormat::from_path(path);
+        let image = match format {
+            ImageFormat::Jpeg => Self::decode_jpeg_scaled(path, target_size)
+                .or_else(|_| Self::decode_as(path, format))?,
+            ImageFormat::Raw => Self::decode_raw(path, Some(target_size))?,
+            _ => Self::decode_as(path, format)?,
+        };
+        Ok(Self::make_upright(path, format, image))
+    }
 
-        match format {
-            ImageFormat::Jpeg => {
-                Self::decode_jpeg_scaled(path, target_size).or_else(|_| Self::decode_fallback(path))
+    /// Like [`FastDecoder::decode_for_hash`], from the file's bytes and EXIF
+    /// orientation when the caller has already read them, with the decoder
+    /// for `format` (see [`ImageFormat::from_header_if`]).
+    ///
+    /// HEIC, AVIF and JPEG XL decoders read the file themselves, so those
+    /// files are opened again.
+    pub fn decode_bytes_for_hash(
+        path: &Path,
+        bytes: &[u8],
+        format: ImageFormat,
+        target_size: u32,
+        orientation: Option<u16>,
+    ) -> Result<DynamicImage, HashError> {
+        let image = match format {
+            ImageFormat::Jpeg => Self::decode_jpeg_bytes_scaled(path, bytes, target_size)
+                .or_else(|_| Self::decode_jpeg_bytes(path, bytes))
+                .or_else(|_| Self::decode_fallback(path))?,
+            ImageFormat::Raw => Self::decode_raw_bytes(path, bytes, Some(target_size))?,
+            ImageFormat::Heic | ImageFormat::Avif | ImageFormat::Jxl => {
+                Self::decode_as(path, format)?
             }
-            _ => Self::decode(path),
+            _ => image::load_from_memory(bytes).or_else(|_| Self::decode_fallback(path))?,
+        };
+        Ok(Self::apply_orientation(format, image, orientation))
+    }
+
+    /// Decode a file's EXIF thumbnail upright, instead of the photo itself.
+    ///
+    /// Returns `None` when the file has no thumbnail, it can't be decoded, or
+    /// its longer side is under [`MIN_THUMBNAIL_SIZE`]; callers then decode
+    /// the photo.
+    pub fn decode_thumbnail(path: &Path) -> Option<DynamicImage> {
+        let file_bytes = read_file_bytes(path).ok()?;
+        let jpeg = exif_thumbnail(&file_bytes)?;
+        let image = Self::decode_jpeg_bytes(path, jpeg).ok()?;
+        if image.width().max(image.height()) < MIN_THUMBNAIL_SIZE {
+            return None;
         }
+        let format = ImageFormat::from_path(path);
+        Some(Self::make_upright(path, format, image))
     }
 
-    /// Decode JPEG at reduced resolution using DCT scaling.
+    /// Decode JPEG at reduced resolution using IDCT scaling.
     ///
-    /// zune-jpeg doesn't support DCT scaling directly, but we can use
-    /// a fast grayscale decode which is more memory efficient for hashing.
-    fn decode_jpeg_scaled(path: &Path, _target_size: u32) -> Result<DynamicImage, HashError> {
+    /// zune-jpeg can't scale, so this uses jpeg-decoder, which only runs the
+    /// inverse DCT for the coefficients the output size needs.
+    fn decode_jpeg_scaled(path: &Path, target_size: u32) -> Result<DynamicImage, HashError> {
         let file_bytes = read_file_bytes(path)?;
 
         // Early rejection of corrupt files
@@ -97,32 +327,41 @@ impl FastDecoder {
             });
         };
 
-        // For hashing, we only need grayscale - this halves memory usage
-        // compared to RGB decode (1 byte vs 3 bytes per pixel)
-        let options = DecoderOptions::new_fast().jpeg_set_out_colorspace(ColorSpace::Luma);
-        let mut decoder = JpegDecoder::new_with_options(&*file_bytes, options);
-
-        let pixels = decoder.decode().map_err(|e| HashError::DecodeError {
-            path: path.to_path_buf(),
-            reason: format!("zune-jpeg scaled decode failed: {:?}", e),
-        })?;
-
-        let info = decoder.info().ok_or_else(|| HashError::DecodeError {
-            path: path.to_path_buf(),
-            reason: "Failed to get image info".to_string(),
-        })?;
-
-        let width = info.width as u32;
-        let height = info.height as u32;
+        Self::decode_jpeg_bytes_scaled(path, &file_bytes, target_size)
+    }
 
-        // Create grayscale image (more memory efficient for hashing)
-        let buffer: ImageBuffer<Luma<u8>, Vec<u8>> = ImageBuffer::from_raw(width, height, pixels)
-            .ok_or_else(|| HashError::DecodeError {
+    /// Decode JPEG bytes with IDCT scaling; `path` is only used in errors
+    fn decode_jpeg_bytes_scaled(
+        path: &Path,
+        bytes: &[u8],
+        target_size: u32,
+    ) -> Result<DynamicImage, HashError> {
+        let error = |reason: String| HashError::DecodeError {
             path: path.to_path_buf(),
-            reason: "Failed to create Luma buffer".to_string(),
-        })?;
+            reason,
+        };
 
-        Ok(DynamicImage::ImageLuma8(buffer))
+        let requested = target_size.min(u16::MAX as u32) as u16;
+        let mut decoder = jpeg_decoder::Decoder::new(bytes);
+        let (width, height) = decoder
+            .scale(requested, requested)
+            .map_err(|e| error(format!("jpeg-decoder scaled decode failed: {}", e)))?;
+        let pixels = decoder
+            .decode()
+            .map_err(|e| error(format!("jpeg-decoder scaled decode failed: {}", e)))?;
+
+        let (width, height) = (width as u32, height as u32);
+        let image = match decoder.info().map(|info| info.pixel_format) {
+            Some(PixelFormat::RGB24) => {
+                ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageRgb8)
+            }
+            Some(PixelFormat::L8) => {
+                ImageBuffer::from_raw(width, height, pixels).map(DynamicImage::ImageLuma8)
+            }
+            // CMYK and 16-bit lossless JPEGs go through the full-size decoders
+            _ => None,
+        };
+        image.ok_or_else(|| error("Unsupported scaled JPEG output".to_string()))
     }
 
     /// Fast JPEG decoding using zune-jpeg
@@ -137,9 +376,14 @@ impl FastDecoder {
             });
         };
 
+        Self::decode_jpeg_bytes(path, &file_bytes)
+    }
+
+    /// Decode JPEG bytes with zune-jpeg; `path` is only used in errors
+    fn decode_jpeg_bytes(path: &Path, bytes: &[u8]) -> Result<DynamicImage, HashError> {
         // Configure decoder to output RGB
         let options = DecoderOptions::new_fast().jpeg_set_out_colorspace(ColorSpace::RGB);
-        let mut decoder = JpegDecoder::new_with_options(&*file_bytes, options);
+        let mut decoder = JpegDecoder::new_with_options(bytes, options);
 
         // Decode the image
         let pixels = decoder.decode().map_err(|e| HashError::DecodeError {
@@ -190,18 +434,70 @@ impl FastDecoder {
                     })?;
                 DynamicImage::ImageLuma8(buffer)
             }
-            _ => {
-                // Unsupported colorspace, fall back to image crate
-                return Self::decode_fallback(path);
+            other => {
+                // Unsupported colorspace; callers fall back to the image crate
+                return Err(HashError::DecodeError {
+                    path: path.to_path_buf(),
+                    reason: format!("Unsupported JPEG colorspace: {:?}", other),
+                });
             }
         };
 
         Ok(image)
     }
 
+    /// Camera RAW decoding from the embedded preview or the sensor data.
+    ///
+    /// A full-size preview is preferred: it is what the camera rendered, so it
+    /// matches the JPEG saved alongside the RAW. Without one, the sensor data
+    /// is demosaiced, and a small preview is only used if that fails.
+    ///
+    /// With a `target_size`, the preview is decoded with IDCT scaling.
+    fn decode_raw(path: &Path, target_size: Option<u32>) -> Result<DynamicImage, HashError> {
+        let file_bytes = read_file_bytes(path)?;
+        Self::decode_raw_bytes(path, &file_bytes, target_size)
+    }
+
+    /// Camera RAW decoding from bytes already read; see [`FastDecoder::decode_raw`]
+    fn decode_raw_bytes(
+        path: &Path,
+        bytes: &[u8],
+        target_size: Option<u32>,
+    ) -> Result<DynamicImage, HashError> {
+        let preview = raw::embedded_jpeg(bytes);
+
+        let decode_preview = |jpeg: raw::EmbeddedJpeg| {
+            if let Some(size) = target_size {
+                if let Ok(image) = Self::decode_jpeg_bytes_scaled(path, jpeg.data, size) {
+                    return Ok(image);
+                }
+            }
+            Self::decode_jpeg_bytes(path, jpeg.data).or_else(|_| {
+                image::load_from_memory_with_format(jpeg.data, image::ImageFormat::Jpeg).map_err(
+                    |e| HashError::DecodeError {
+                        path: path.to_path_buf(),
+                        reason: format!("Embedded preview decode failed: {}", e),
+                    },
+                )
+            })
+        };
+
+        match preview {
+            Some(jpeg) if jpeg.width.max(jpeg.height) >= MIN_PREVIEW_SIZE => decode_preview(jpeg),
+            Some(jpeg) => raw::demosaic(path).or_else(|_| decode_preview(jpeg)),
+            None => raw::demosaic(path),
+        }
+    }
+
+    /// Native HEIC/HEIF decoding through libheif
+    #[cfg(feature = "heif")]
+    fn decode_heic(path: &Path) -> Result<DynamicImage, HashError> {
+        super::heif_decode::decode_heif(path)
+    }
+
     /// Native HEIC/HEIF decoding using macOS sips command
     /// This uses the built-in macOS image conversion tool which natively supports HEIC
-    #[cfg(target_os = "macos")]
+    #[cfg(all(target_os = "macos", not(feature = "heif")))]
     fn decode_heic(path: &Path) -> Result<DynamicImage, HashError> {
         use tempfile::Builder;
 
@@ -253,27 +549,206 @@ impl FastDecoder {
         result
     }
 
-    /// Fallback for non-macOS platforms - HEIC not supported
-    #[cfg(not(target_os = "macos"))]
+    /// Fallback for other platforms without the `heif` feature
+    #[cfg(not(any(target_os = "macos", feature = "heif")))]
     fn decode_heic(path: &Path) -> Result<DynamicImage, HashError> {
         Err(HashError::DecodeError {
             path: path.to_path_buf(),
-            reason: "HEIC decoding is only supported on macOS".to_string(),
+            reason: "HEIC decoding needs the `heif` feature on this platform".to_string(),
         })
     }
 
-    /// Fallback to image crate for non-JPEG formats
-    fn decode_fallback(path: &Path) -> Result<DynamicImage, HashError> {
-        image::open(path).map_err(|e| HashError::DecodeError {
+    /// AVIF decoding through dav1d, which the `avif` feature adds to the image crate
+    #[cfg(feature = "avif")]
+    fn decode_avif(path: &Path) -> Result<DynamicImage, HashError> {
+        Self::decode_fallback(path)
+    }
+
+    /// AVIF needs dav1d, which isn't built without the `avif` feature
+    #[cfg(not(feature = "avif"))]
+    fn decode_avif(path: &Path) -> Result<DynamicImage, HashError> {
+        Err(HashError::DecodeError {
             path: path.to_path_buf(),
-            reason: e.to_string(),
+            reason: "AVIF decoding needs the `avif` feature".to_string(),
         })
     }
+
+    /// JPEG XL decoding using jxl-oxide
+    ///
+    /// The orientation in the codestream header is applied while rendering.
+    #[cfg(feature = "jxl")]
+    fn decode_jxl(path: &Path) -> Result<DynamicImage, HashError> {
+        use jxl_oxide::integration::JxlDecoder;
+
+        let error = |reason: String| HashError::DecodeError {
+            path: path.to_path_buf(),
+            reason,
+        };
+
+        let file_bytes = read_file_bytes(path)?;
+        let decoder = JxlDecoder::new(&*file_bytes).map_err(|e| error(e.to_string()))?;
+        DynamicImage::from_decoder(decoder).map_err(|e| error(e.to_string()))
+    }
+
+    /// JPEG XL decoding is only built with the `jxl` feature
+    #[cfg(not(feature = "jxl"))]
+    fn decode_jxl(path: &Path) -> Result<DynamicImage, HashError> {
+        Err(HashError::DecodeError {
+            path: path.to_path_buf(),
+            reason: "JPEG XL decoding needs the `jxl` feature".to_string(),
+        })
+    }
+
+    /// Fallback to image crate for non-JPEG formats, which it picks by the
+    /// magic bytes before the extension
+    fn decode_fallback(path: &Path) -> Result<DynamicImage, HashError> {
+        let error = |reason: String| HashError::DecodeError {
+            path: path.to_path_buf(),
+            reason,
+        };
+        image::ImageReader::open(path)
+            .and_then(|reader| reader.with_guessed_format())
+            .map_err(|e| error(e.to_string()))?
+            .decode()
+            .map_err(|e| error(e.to_string()))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use image::codecs::jpeg::JpegEncoder;
+    use image::RgbImage;
+    use tempfile::TempDir;
+
+    /// Write a 64x32 JPEG tagged with the given EXIF orientation
+    fn write_oriented_jpeg(dir: &TempDir, orientation: u16) -> std::path::PathBuf {
+        let image = RgbImage::from_fn(64, 32, |x, _| image::Rgb([(x * 4) as u8, 0, 0]));
+        let mut jpeg = Vec::new();
+        JpegEncoder::new(&mut jpeg)
+            .encode_image(&DynamicImage::ImageRgb8(image))
+            .unwrap();
+
+        // APP1 "Exif" segment: big-endian TIFF header with a one-entry IFD
+        let mut tiff = b"MM\0\x2a\0\0\0\x08\0\x01".to_vec();
+        tiff.extend_from_slice(&[0x01, 0x12, 0x00, 0x03, 0, 0, 0, 1]);
+        tiff.extend_from_slice(&orientation.to_be_bytes());
+        tiff.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+        let mut app1 = b"Exif\0\0".to_vec();
+        app1.extend_from_slice(&tiff);
+
+        let mut bytes = jpeg[..2].to_vec();
+        bytes.extend_from_slice(&[0xFF, 0xE1]);
+        bytes.extend_from_slice(&(app1.len() as u16 + 2).to_be_bytes());
+        bytes.extend_from_slice(&app1);
+        bytes.extend_from_slice(&jpeg[2..]);
+
+        let path = dir.path().join(format!("orientation_{}.jpg", orientation));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn decode_keeps_stored_orientation() {
+        let dir = TempDir::new().unwrap();
+        let path = write_oriented_jpeg(&dir, 6);
+
+        let image = FastDecoder::decode(&path).unwrap();
+
+        assert_eq!((image.width(), image.height()), (64, 32));
+    }
+
+    #[test]
+    fn mislabeled_jpeg_decodes_upright() {
+        let dir = TempDir::new().unwrap();
+        let path = write_oriented_jpeg(&dir, 6);
+        let mislabeled = dir.path().join("mislabeled.png");
+        std::fs::rename(&path, &mislabeled).unwrap();
+
+        assert_eq!(ImageFormat::detect(&mislabeled), ImageFormat::Jpeg);
+        let sniffing = DecodeOptions::upright().sniff_content(true);
+        let image = FastDecoder::decode_with(&mislabeled, &sniffing).unwrap();
+        assert_eq!((image.width(), image.height()), (32, 64));
+
+        // Without sniffing it still decodes, but as a PNG has no orientation
+        let image = FastDecoder::decode_with(&mislabeled, &DecodeOptions::upright()).unwrap();
+        assert_eq!((image.width(), image.height()), (64, 32));
+    }
+
+    #[test]
+    fn decode_with_orientation_rotates_image() {
+        let dir = TempDir::new().unwrap();
+        let path = write_oriented_jpeg(&dir, 6);
+
+        let image = FastDecoder::decode_with(&path, &DecodeOptions::upright()).unwrap();
+
+        assert_eq!((image.width(), image.height()), (32, 64));
+    }
+
+    #[test]
+    fn decode_with_normal_orientation_is_unchanged() {
+        let dir = TempDir::new().unwrap();
+        let path = write_oriented_jpeg(&dir, 1);
+
+        let raw = FastDecoder::decode(&path).unwrap();
+        let upright = FastDecoder::decode_with(&path, &DecodeOptions::upright()).unwrap();
+
+        assert_eq!(raw.as_bytes(), upright.as_bytes());
+    }
+
+    fn write_image(dir: &TempDir, name: &str, width: u32, height: u32) -> std::path::PathBuf {
+        let image = RgbImage::from_fn(width, height, |x, y| {
+            image::Rgb([(x % 256) as u8, (y % 256) as u8, 128])
+        });
+        let path = dir.path().join(name);
+        image.save(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn decode_for_hash_scales_jpeg_to_target() {
+        let dir = TempDir::new().unwrap();
+        let path = write_image(&dir, "large.jpg", 2048, 1536);
+
+        let eighth = FastDecoder::decode_for_hash(&path, 256).unwrap();
+        let half = FastDecoder::decode_for_hash(&path, 1000).unwrap();
+
+        assert_eq!((eighth.width(), eighth.height()), (256, 192));
+        // The longer side never drops below the target
+        assert_eq!((half.width(), half.height()), (1024, 768));
+    }
+
+    #[test]
+    fn decode_for_hash_applies_orientation() {
+        let dir = TempDir::new().unwrap();
+        let path = write_oriented_jpeg(&dir, 6);
+
+        let image = FastDecoder::decode_for_hash(&path, 256).unwrap();
+
+        assert_eq!((image.width(), image.height()), (32, 64));
+    }
+
+    #[test]
+    fn decode_for_hash_keeps_other_formats_full_size() {
+        let dir = TempDir::new().unwrap();
+        let path = write_image(&dir, "large.png", 2048, 1536);
+
+        let image = FastDecoder::decode_for_hash(&path, 256).unwrap();
+
+        assert_eq!((image.width(), image.height()), (2048, 1536));
+    }
+
+    #[test]
+    fn format_detection_tiff() {
+        assert_eq!(
+            ImageFormat::from_path(Path::new("scan.tif")),
+            ImageFormat::Tiff
+        );
+        assert_eq!(
+            ImageFormat::from_path(Path::new("scan.TIFF")),
+            ImageFormat::Tiff
+        );
+    }
 
     #[test]
     fn format_detection_jpeg() {
@@ -315,6 +790,97 @@ mod tests {
         );
     }
 
+    #[test]
+    fn format_detection_raw() {
+        assert_eq!(
+            ImageFormat::from_path(Path::new("DSC_0001.NEF")),
+            ImageFormat::Raw
+        );
+        assert_eq!(
+            ImageFormat::from_path(Path::new("IMG_0001.cr3")),
+            ImageFormat::Raw
+        );
+    }
+
+    /// Write a RAW-like file: a TIFF with an orientation tag, then a preview
+    fn write_raw(dir: &TempDir, name: &str, width: u32, height: u32) -> std::path::PathBuf {
+        use crate::core::raw::test_tiff::{short, tiff, SHORT};
+
+        let image = RgbImage::from_fn(width, height, |x, _| image::Rgb([(x % 256) as u8, 0, 0]));
+        let mut bytes = tiff(&[(0x0112, SHORT, short(6))]);
+        JpegEncoder::new(&mut bytes)
+            .encode_image(&DynamicImage::ImageRgb8(image))
+            .unwrap();
+
+        let path = dir.path().join(name);
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn raw_decodes_full_size_preview_upright() {
+        let dir = TempDir::new().unwrap();
+        let path = write_raw(&dir, "DSC_0001.NEF", 1024, 32);
+
+        let stored = FastDecoder::decode(&path).unwrap();
+        let upright = FastDecoder::decode_with(&path, &DecodeOptions::upright()).unwrap();
+
+        assert_eq!((stored.width(), stored.height()), (1024, 32));
+        assert_eq!((upright.width(), upright.height()), (32, 1024));
+    }
+
+    #[test]
+    fn raw_falls_back_to_small_preview() {
+        let dir = TempDir::new().unwrap();
+        // Not real sensor data, so demosaicing fails
+        let path = write_raw(&dir, "IMG_0001.CR2", 64, 32);
+
+        let image = FastDecoder::decode(&path).unwrap();
+
+        assert_eq!((image.width(), image.height()), (64, 32));
+    }
+
+    #[test]
+    fn format_detection_avif_and_jxl() {
+        assert_eq!(
+            ImageFormat::from_path(Path::new("export.AVIF")),
+            ImageFormat::Avif
+        );
+        assert_eq!(
+            ImageFormat::from_path(Path::new("export.jxl")),
+            ImageFormat::Jxl
+        );
+    }
+
+    #[cfg(not(feature = "jxl"))]
+    #[test]
+    fn jxl_without_feature_is_a_decode_error() {
+        let error = FastDecoder::decode(Path::new("export.jxl")).unwrap_err();
+
+        assert!(error.to_string().contains("`jxl` feature"), "{}", error);
+    }
+
+    #[cfg(feature = "jxl")]
+    #[test]
+    fn jxl_decodes_lossless_pixels() {
+        use zune_core::bit_depth::BitDepth;
+        use zune_core::options::EncoderOptions;
+        use zune_jpegxl::JxlSimpleEncoder;
+
+        let image = RgbImage::from_fn(64, 32, |x, y| image::Rgb([(x * 4) as u8, (y * 8) as u8, 0]));
+        let options = EncoderOptions::new(64, 32, ColorSpace::RGB, BitDepth::Eight);
+        let jxl = JxlSimpleEncoder::new(image.as_raw(), options)
+            .encode()
+            .unwrap();
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("export.jxl");
+        std::fs::write(&path, jxl).unwrap();
+
+        let decoded = FastDecoder::decode(&path).unwrap();
+
+        assert_eq!(decoded.to_rgb8(), image);
+    }
+
     #[test]
     fn format_detection_other() {
         assert_eq!(
@@ -4,22 +4,35 @@
 //!
 //! ## How It Works
 //! 1. Compute all three hashes from a single image decode
-//! 2. Compare using voting: a match requires 2+ algorithms to agree
+//! 2. Compare using voting: a match requires a majority of algorithms to
+//!    agree (2 of 3 by default)
 //! 3. Confidence level based on how many algorithms agree
 //!
+//! wHash and block-mean hashes can be added as extra voters (see
+//! [`FusionHasher::with_voters`]). They are appended to the cached bytes, so
+//! hashes without them still decode.
+//!
 //! ## Benefits
 //! - Higher accuracy through consensus
 //! - Reduces false positives (single algorithm quirks)
 //! - Handles different types of edits better
 
-use super::fast_decode::FastDecoder;
+use super::fast_decode::{FastDecoder, HASH_DECODE_SIZE};
 use super::traits::PerceptualHash;
-use super::{AverageHasher, DifferenceHasher, HashAlgorithm, ImageHashValue, PerceptualHasher};
+use super::{
+    AverageHasher, BlockMeanHasher, DifferenceHasher, HashAlgorithm, HashAlgorithmKind,
+    ImageHashValue, PerceptualHasher, WaveletHasher,
+};
 use crate::error::HashError;
 use image::DynamicImage;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
+/// Algorithms that can be added to fusion as extra voters
+pub const EXTRA_VOTERS: [HashAlgorithmKind; 2] =
+    [HashAlgorithmKind::Wavelet, HashAlgorithmKind::BlockMean];
+
 /// A fusion hash containing results from multiple algorithms
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FusionHash {
@@ -29,6 +42,9 @@ pub struct FusionHash {
     pub dhash: ImageHashValue,
     /// Perceptual hash (pHash)
     pub phash: ImageHashValue,
+    /// Extra voters (wHash, block-mean), in the order they were configured
+    #[serde(default)]
+    pub extra: Vec<ImageHashValue>,
 }
 
 impl FusionHash {
@@ -38,58 +54,102 @@ impl FusionHash {
             ahash,
             dhash,
             phash,
+            extra: Vec::new(),
         }
     }
 
-    /// Compare two fusion hashes and return a detailed result
+    /// Add extra voters to this hash
+    pub fn with_extra(mut self, extra: Vec<ImageHashValue>) -> Self {
+        self.extra = extra;
+        self
+    }
+
+    /// Algorithms of the extra voters
+    pub fn extra_kinds(&self) -> Vec<HashAlgorithmKind> {
+        self.extra.iter().map(|h| h.algorithm()).collect()
+    }
+
+    /// Every voter, most discriminating first: dHash, pHash, the extra
+    /// voters, then aHash
+    pub fn voters(&self) -> Vec<&ImageHashValue> {
+        let mut voters = vec![&self.dhash, &self.phash];
+        voters.extend(&self.extra);
+        voters.push(&self.ahash);
+        voters
+    }
+
+    /// Votes needed for a duplicate out of `voters` (a strict majority)
+    pub fn required_votes(voters: usize) -> usize {
+        voters / 2 + 1
+    }
+
+    /// Compare two fusion hashes and return a detailed result.
+    ///
+    /// Extra voters only count when both hashes have the same algorithm.
     pub fn compare(&self, other: &FusionHash, threshold: u32) -> FusionCompareResult {
         let ahash_dist = self.ahash.distance(&other.ahash);
         let dhash_dist = self.dhash.distance(&other.dhash);
         let phash_dist = self.phash.distance(&other.phash);
 
-        let ahash_match = ahash_dist <= threshold;
-        let dhash_match = dhash_dist <= threshold;
-        let phash_match = phash_dist <= threshold;
-
-        let votes = ahash_match as u8 + dhash_match as u8 + phash_match as u8;
-
-        let confidence = match votes {
-            3 => FusionConfidence::High,
-            2 => FusionConfidence::Medium,
-            1 => FusionConfidence::Low,
-            0 => FusionConfidence::None,
-            _ => unreachable!(),
+        let extra_distances: Vec<(HashAlgorithmKind, u32)> = self
+            .extra
+            .iter()
+            .filter_map(|hash| {
+                let theirs = other
+                    .extra
+                    .iter()
+                    .find(|h| h.algorithm() == hash.algorithm())?;
+                Some((hash.algorithm(), hash.distance(theirs)))
+            })
+            .collect();
+
+        let distances: Vec<u32> = [ahash_dist, dhash_dist, phash_dist]
+            .into_iter()
+            .chain(extra_distances.iter().map(|(_, d)| *d))
+            .collect();
+        let voters = distances.len() as u8;
+        let votes = distances.iter().filter(|d| **d <= threshold).count() as u8;
+
+        let confidence = if votes == 0 {
+            FusionConfidence::None
+        } else if votes == voters {
+            FusionConfidence::High
+        } else if votes as usize >= Self::required_votes(voters as usize) {
+            FusionConfidence::Medium
+        } else {
+            FusionConfidence::Low
         };
 
         // Use the minimum distance among matching algorithms for classification
-        let min_distance = [
-            if ahash_match { Some(ahash_dist) } else { None },
-            if dhash_match { Some(dhash_dist) } else { None },
-            if phash_match { Some(phash_dist) } else { None },
-        ]
-        .into_iter()
-        .flatten()
-        .min()
-        .unwrap_or(u32::MAX);
+        let min_distance = distances
+            .iter()
+            .copied()
+            .filter(|d| *d <= threshold)
+            .min()
+            .unwrap_or(u32::MAX);
 
         FusionCompareResult {
             ahash_distance: ahash_dist,
             dhash_distance: dhash_dist,
             phash_distance: phash_dist,
+            extra_distances,
             votes,
+            voters,
             confidence,
             min_distance,
-            is_duplicate: votes >= 2, // Require at least 2 algorithms to agree
+            // Require a majority of algorithms to agree
+            is_duplicate: votes as usize >= Self::required_votes(voters as usize),
         }
     }
 
-    /// Quick check if this is a duplicate (requires 2+ algorithms to agree)
+    /// Quick check if this is a duplicate (requires a majority to agree)
     pub fn is_duplicate_of(&self, other: &FusionHash, threshold: u32) -> bool {
         self.compare(other, threshold).is_duplicate
     }
 
     /// Get the combined hash bytes for caching
     /// Format: [ahash_len:2][ahash][dhash_len:2][dhash][phash_len:2][phash]
+    /// followed by [tag:1][len:2][hash] for each extra voter
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes = Vec::new();
 
@@ -108,13 +168,21 @@ impl FusionHash {
         bytes.extend_from_slice(&(phash_bytes.len() as u16).to_le_bytes());
         bytes.extend_from_slice(phash_bytes);
 
+        // Extra voters
+        for hash in &self.extra {
+            let Some(tag) = voter_tag(hash.algorithm()) else {
+                continue;
+            };
+            bytes.push(tag);
+            bytes.extend_from_slice(&(hash.as_bytes().len() as u16).to_le_bytes());
+            bytes.extend_from_slice(hash.as_bytes());
+        }
+
         bytes
     }
 
     /// Restore from cached bytes
     pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
-        use super::HashAlgorithmKind;
-
         let mut offset = 0;
 
         // aHash
@@ -160,15 +228,54 @@ impl FusionHash {
             &bytes[offset..offset + phash_len],
             HashAlgorithmKind::Perceptual,
         );
+        offset += phash_len;
+
+        // Extra voters (absent in hashes from before they existed)
+        let mut extra = Vec::new();
+        while offset < bytes.len() {
+            if bytes.len() < offset + 3 {
+                return None;
+            }
+            let algorithm = voter_from_tag(bytes[offset])?;
+            let len = u16::from_le_bytes([bytes[offset + 1], bytes[offset + 2]]) as usize;
+            offset += 3;
+            if bytes.len() < offset + len {
+                return None;
+            }
+            extra.push(ImageHashValue::from_bytes(
+                &bytes[offset..offset + len],
+                algorithm,
+            ));
+            offset += len;
+        }
 
         Some(Self {
             ahash,
             dhash,
             phash,
+            extra,
         })
     }
 }
 
+/// Byte tag identifying an extra voter in cached fusion hashes
+fn voter_tag(algorithm: HashAlgorithmKind) -> Option<u8> {
+    match algorithm {
+        HashAlgorithmKind::Wavelet => Some(1),
+        HashAlgorithmKind::BlockMean => Some(2),
+        _ => None,
+    }
+}
+
+/// Inverse of [`voter_tag`]
+fn voter_from_tag(tag: u8) -> Option<HashAlgorithmKind> {
+    match tag {
+        1 => Some(HashAlgorithmKind::Wavelet),
+        2 => Some(HashAlgorithmKind::BlockMean),
+        _ => None,
+    }
+}
+
 /// Result of comparing two fusion hashes
 #[derive(Debug, Clone)]
 pub struct FusionCompareResult {
@@ -178,24 +285,28 @@ pub struct FusionCompareResult {
     pub dhash_distance: u32,
     /// Distance from pHash comparison
     pub phash_distance: u32,
-    /// Number of algorithms that agree (0-3)
+    /// Distances from extra voters both hashes share
+    pub extra_distances: Vec<(HashAlgorithmKind, u32)>,
+    /// Number of algorithms that agree
     pub votes: u8,
+    /// Number of algorithms that voted (3 plus shared extra voters)
+    pub voters: u8,
     /// Confidence level based on agreement
     pub confidence: FusionConfidence,
     /// Minimum distance among agreeing algorithms
     pub min_distance: u32,
-    /// Whether this is considered a duplicate (2+ votes)
+    /// Whether this is considered a duplicate (majority of votes)
     pub is_duplicate: bool,
 }
 
 /// Confidence level based on algorithm agreement
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FusionConfidence {
-    /// All three algorithms agree
+    /// All algorithms agree
     High,
-    /// Two algorithms agree
+    /// A majority agrees (2 of 3 by default)
     Medium,
-    /// Only one algorithm matches (not considered a duplicate)
+    /// Some algorithms match, but not a majority (not considered a duplicate)
     Low,
     /// No algorithms match
     None,
@@ -204,19 +315,20 @@ pub enum FusionConfidence {
 impl std::fmt::Display for FusionConfidence {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            FusionConfidence::High => write!(f, "High (3/3)"),
-            FusionConfidence::Medium => write!(f, "Medium (2/3)"),
-            FusionConfidence::Low => write!(f, "Low (1/3)"),
+            FusionConfidence::High => write!(f, "High (all agree)"),
+            FusionConfidence::Medium => write!(f, "Medium (majority)"),
+            FusionConfidence::Low => write!(f, "Low (minority)"),
             FusionConfidence::None => write!(f, "No match"),
         }
     }
 }
 
-/// Computes fusion hashes using all three algorithms
+/// Computes fusion hashes using all three algorithms, plus any extra voters
 pub struct FusionHasher {
     ahash: AverageHasher,
     dhash: DifferenceHasher,
     phash: PerceptualHasher,
+    extra: Vec<Box<dyn HashAlgorithm>>,
 }
 
 impl FusionHasher {
@@ -231,31 +343,74 @@ impl FusionHasher {
             ahash: AverageHasher::new(size),
             dhash: DifferenceHasher::new(size),
             phash: PerceptualHasher::new(size),
+            extra: Vec::new(),
+        }
+    }
+
+    /// Create a fusion hasher that also votes with the given algorithms.
+    ///
+    /// Only [`EXTRA_VOTERS`] are accepted; duplicates are ignored.
+    pub fn with_voters(size: u32, voters: &[HashAlgorithmKind]) -> Result<Self, HashError> {
+        let mut hasher = Self::with_size(size);
+        let mut added: Vec<HashAlgorithmKind> = Vec::new();
+
+        for &kind in voters {
+            if added.contains(&kind) {
+                continue;
+            }
+            let voter: Box<dyn HashAlgorithm> = match kind {
+                HashAlgorithmKind::Wavelet => Box::new(WaveletHasher::new(size)),
+                HashAlgorithmKind::BlockMean => Box::new(BlockMeanHasher::new(size)),
+                other => {
+                    return Err(HashError::InvalidFusionVoter {
+                        algorithm: other.to_string(),
+                    })
+                }
+            };
+            hasher.extra.push(voter);
+            added.push(kind);
         }
+
+        Ok(hasher)
+    }
+
+    /// Algorithms of the extra voters
+    pub fn extra_kinds(&self) -> Vec<HashAlgorithmKind> {
+        self.extra.iter().map(|h| h.kind()).collect()
     }
 
-    /// Compute fusion hash from a file
+    /// Compute fusion hash from a file, after applying its EXIF orientation
     pub fn hash_file(&self, path: &Path) -> Result<FusionHash, HashError> {
-        let image = FastDecoder::decode(path)?;
+        let image = FastDecoder::decode_for_hash(path, HASH_DECODE_SIZE)?;
         self.hash_image(&image)
     }
 
     /// Compute fusion hash from a loaded image
-    /// Uses parallel execution for all three algorithms
+    /// Uses parallel execution for all algorithms
     pub fn hash_image(&self, image: &DynamicImage) -> Result<FusionHash, HashError> {
-        // Run all three hash algorithms in parallel using rayon
+        // Run all hash algorithms in parallel using rayon
         // This provides ~3x speedup for CPU-bound hashing
-        let (ahash_result, (dhash_result, phash_result)) = rayon::join(
-            || self.ahash.hash_image(image),
+        let ((ahash_result, (dhash_result, phash_result)), extra_result) = rayon::join(
             || {
                 rayon::join(
-                    || self.dhash.hash_image(image),
-                    || self.phash.hash_image(image),
+                    || self.ahash.hash_image(image),
+                    || {
+                        rayon::join(
+                            || self.dhash.hash_image(image),
+                            || self.phash.hash_image(image),
+                        )
+                    },
                 )
             },
+            || {
+                self.extra
+                    .par_iter()
+                    .map(|voter| voter.hash_image(image))
+                    .collect::<Result<Vec<_>, _>>()
+            },
         );
 
-        Ok(FusionHash::new(ahash_result?, dhash_result?, phash_result?))
+        Ok(FusionHash::new(ahash_result?, dhash_result?, phash_result?).with_extra(extra_result?))
     }
 }
 
@@ -271,12 +426,12 @@ impl HashAlgorithm for FusionHasher {
         // Store fusion hash as combined bytes - can be decoded back to FusionHash
         Ok(ImageHashValue::new(
             fusion.to_bytes(),
-            super::HashAlgorithmKind::Fusion,
+            HashAlgorithmKind::Fusion,
         ))
     }
 
-    fn kind(&self) -> super::HashAlgorithmKind {
-        super::HashAlgorithmKind::Fusion
+    fn kind(&self) -> HashAlgorithmKind {
+        HashAlgorithmKind::Fusion
     }
 }
 
@@ -353,6 +508,59 @@ mod tests {
         assert_eq!(hash.phash.as_bytes(), restored.phash.as_bytes());
     }
 
+    fn with_extras(hash: FusionHash, wavelet: u8, block_mean: u8) -> FusionHash {
+        hash.with_extra(vec![
+            ImageHashValue::new(vec![wavelet], HashAlgorithmKind::Wavelet),
+            ImageHashValue::new(vec![block_mean], HashAlgorithmKind::BlockMean),
+        ])
+    }
+
+    #[test]
+    fn extra_voters_need_a_majority_of_five() {
+        let hash1 = with_extras(create_fusion_hash(0xFF, 0xFF, 0xFF), 0xFF, 0xFF);
+        // aHash, dHash and wHash agree; pHash and block mean differ
+        let hash2 = with_extras(create_fusion_hash(0xFF, 0xFF, 0x00), 0xFF, 0x00);
+        // Only aHash and dHash agree
+        let hash3 = with_extras(create_fusion_hash(0xFF, 0xFF, 0x00), 0x00, 0x00);
+
+        let majority = hash1.compare(&hash2, 5);
+        assert_eq!((majority.votes, majority.voters), (3, 5));
+        assert_eq!(majority.confidence, FusionConfidence::Medium);
+        assert!(majority.is_duplicate);
+
+        let minority = hash1.compare(&hash3, 5);
+        assert_eq!(minority.votes, 2);
+        assert_eq!(minority.confidence, FusionConfidence::Low);
+        assert!(!minority.is_duplicate);
+    }
+
+    #[test]
+    fn bytes_roundtrip_with_extra_voters() {
+        let hash = with_extras(create_fusion_hash(0xDE, 0xAD, 0xBE), 0xEF, 0x42);
+        let restored = FusionHash::from_bytes(&hash.to_bytes()).unwrap();
+
+        assert_eq!(
+            restored.extra_kinds(),
+            vec![HashAlgorithmKind::Wavelet, HashAlgorithmKind::BlockMean]
+        );
+        assert_eq!(restored.extra[1].as_bytes(), &[0x42]);
+    }
+
+    #[test]
+    fn bytes_without_extra_voters_still_decode() {
+        let hash = create_fusion_hash(0xDE, 0xAD, 0xBE);
+        let restored = FusionHash::from_bytes(&hash.to_bytes()).unwrap();
+
+        assert!(restored.extra.is_empty());
+        assert_eq!(restored.voters().len(), 3);
+    }
+
+    #[test]
+    fn with_voters_rejects_non_voting_algorithms() {
+        assert!(FusionHasher::with_voters(8, &[HashAlgorithmKind::Wavelet]).is_ok());
+        assert!(FusionHasher::with_voters(8, &[HashAlgorithmKind::Average]).is_err());
+    }
+
     #[test]
     fn fusion_hasher_creates_all_hashes() {
         use image::{DynamicImage, ImageBuffer, Luma};
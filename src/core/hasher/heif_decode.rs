@@ -0,0 +1,125 @@
+//! # HEIF Decoding
+//!
+//! Native HEIC/HEIF decoding through libheif, enabled by the `heif` cargo
+//! feature. Needs libheif >= 1.17 with an HEVC decoder plugin (libde265)
+//! installed.
+//!
+//! Transforms stored in the container (`irot`, `imir`, `clap`) are ignored,
+//! so pixels come back in stored order like every other decoder; the EXIF
+//! orientation is applied afterwards by [`FastDecoder::decode_with`]. This
+//! keeps hashes the same as the macOS `sips` path.
+//!
+//! [`FastDecoder::decode_with`]: super::fast_decode::FastDecoder::decode_with
+
+use crate::error::HashError;
+use image::{DynamicImage, RgbImage, RgbaImage};
+use libheif_rs::{ColorSpace, DecodingOptions, HeifContext, ImageHandle, LibHeif, RgbChroma};
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Shared libheif instance; initializing loads the decoder plugins
+fn lib_heif() -> &'static LibHeif {
+    static LIB_HEIF: OnceLock<LibHeif> = OnceLock::new();
+    LIB_HEIF.get_or_init(LibHeif::new)
+}
+
+/// Open a file and get its primary image
+fn primary_image(path: &Path) -> Result<(HeifContext<'static>, ImageHandle), String> {
+    let name = path.to_str().ok_or("Path is not valid UTF-8")?;
+    let context = HeifContext::read_from_file(name).map_err(|e| e.to_string())?;
+    let handle = context.primary_image_handle().map_err(|e| e.to_string())?;
+    Ok((context, handle))
+}
+
+/// Decode the primary image of a HEIC/HEIF file to 8-bit RGB(A)
+pub fn decode_heif(path: &Path) -> Result<DynamicImage, HashError> {
+    let error = |reason: String| HashError::DecodeError {
+        path: path.to_path_buf(),
+        reason,
+    };
+
+    let (_context, handle) = primary_image(path).map_err(error)?;
+    let has_alpha = handle.has_alpha_channel();
+    let chroma = if has_alpha {
+        RgbChroma::Rgba
+    } else {
+        RgbChroma::Rgb
+    };
+
+    let mut options =
+        DecodingOptions::new().ok_or_else(|| error("Failed to allocate options".to_string()))?;
+    options.set_ignore_transformations(true);
+    options.set_convert_hdr_to_8bit(true);
+
+    let decoded = lib_heif()
+        .decode(&handle, ColorSpace::Rgb(chroma), Some(options))
+        .map_err(|e| error(e.to_string()))?;
+    let plane = decoded
+        .planes()
+        .interleaved
+        .ok_or_else(|| error("Decoded image has no interleaved plane".to_string()))?;
+
+    // Rows are padded to `stride`; copy them out tightly packed
+    let channels = if has_alpha { 4 } else { 3 };
+    let row_len = plane.width as usize * channels;
+    let mut pixels = Vec::with_capacity(row_len * plane.height as usize);
+    for row in plane.data.chunks(plane.stride).take(plane.height as usize) {
+        pixels.extend_from_slice(&row[..row_len]);
+    }
+
+    let image = if has_alpha {
+        RgbaImage::from_raw(plane.width, plane.height, pixels).map(DynamicImage::ImageRgba8)
+    } else {
+        RgbImage::from_raw(plane.width, plane.height, pixels).map(DynamicImage::ImageRgb8)
+    };
+    image.ok_or_else(|| error("Decoded buffer size mismatch".to_string()))
+}
+
+/// Stored dimensions of the primary image, without decoding it
+///
+/// Taken from the `ispe` property, so they match the coded pixels even when
+/// the EXIF dimension tags are stale (e.g. after an edit).
+pub fn heif_dimensions(path: &Path) -> Option<(u32, u32)> {
+    let (_context, handle) = primary_image(path).ok()?;
+    let width = u32::try_from(handle.ispe_width()).ok()?;
+    let height = u32::try_from(handle.ispe_height()).ok()?;
+    (width > 0 && height > 0).then_some((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> &'static Path {
+        Path::new(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/heif/sample.heic"
+        ))
+    }
+
+    #[test]
+    fn decodes_heic_fixture() {
+        let image = decode_heif(fixture()).unwrap();
+
+        assert_eq!((image.width(), image.height()), (1652, 1791));
+        assert!(image.as_rgb8().is_some());
+    }
+
+    #[test]
+    fn reads_dimensions_without_decoding() {
+        assert_eq!(heif_dimensions(fixture()), Some((1652, 1791)));
+    }
+
+    #[test]
+    fn non_heif_file_is_a_decode_error() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("fake.heic");
+        std::fs::write(&path, b"not a heif file").unwrap();
+
+        assert!(matches!(
+            decode_heif(&path),
+            Err(HashError::DecodeError { .. })
+        ));
+        assert_eq!(heif_dimensions(&path), None);
+    }
+}
@@ -4,9 +4,11 @@
 //! when reading large image files. This provides 20-40% speedup
 //! for files > 1MB.
 
+use crate::core::scanner::ImageFormat;
 use crate::error::HashError;
 use memmap2::Mmap;
 use std::fs::File;
+use std::io::Read;
 use std::path::Path;
 
 /// Minimum file size to use memory-mapped I/O (1MB)
@@ -92,62 +94,105 @@ pub fn should_use_mmap(path: &Path) -> bool {
         .unwrap_or(false)
 }
 
+/// Number of bytes [`sniff_image_format`] needs to tell every format apart,
+/// including the compatible brands of a HEIF `ftyp` box
+pub const HEADER_LEN: usize = 64;
+
+/// Read the first [`HEADER_LEN`] bytes of a file, or all of a shorter one
+pub fn read_file_header(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    File::open(path)?
+        .take(HEADER_LEN as u64)
+        .read_to_end(&mut header)?;
+    Ok(header)
+}
+
 /// Validate image header bytes to quickly reject non-images.
 ///
 /// Checks the magic bytes at the start of the file to determine
 /// if it's a valid image format. This is much faster than attempting
 /// a full decode.
 pub fn validate_image_header(bytes: &[u8]) -> bool {
+    sniff_image_format(bytes).is_some()
+}
+
+/// Identify an image format from the magic bytes at the start of a file.
+///
+/// TIFF-based camera RAW files (NEF, ARW, CR2, DNG) are reported as TIFF,
+/// and RAW formats with their own signature aren't recognized.
+pub fn sniff_image_format(bytes: &[u8]) -> Option<ImageFormat> {
     if bytes.len() < 8 {
-        return false;
+        return None;
     }
 
     // JPEG: FF D8 FF
     if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
-        return true;
+        return Some(ImageFormat::Jpeg);
     }
 
     // PNG: 89 50 4E 47 0D 0A 1A 0A
     if bytes.starts_with(&[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A]) {
-        return true;
+        return Some(ImageFormat::Png);
     }
 
     // GIF: 47 49 46 38
     if bytes.starts_with(&[0x47, 0x49, 0x46, 0x38]) {
-        return true;
+        return Some(ImageFormat::Gif);
     }
 
     // WebP: RIFF....WEBP
     if bytes.len() >= 12 && bytes.starts_with(&[0x52, 0x49, 0x46, 0x46]) {
         if &bytes[8..12] == b"WEBP" {
-            return true;
+            return Some(ImageFormat::WebP);
         }
     }
 
-    // HEIC/HEIF: ftyp container
-    if bytes.len() >= 12 {
-        // Check for ftyp box
-        if &bytes[4..8] == b"ftyp" {
-            let brand = &bytes[8..12];
-            // Common HEIC brands
-            if brand == b"heic" || brand == b"heix" || brand == b"mif1" || brand == b"hevc" {
-                return true;
-            }
+    // HEIC/HEIF and AVIF: ftyp container
+    if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" {
+        if let Some(format) = heif_brand_format(&bytes[8..12]) {
+            return Some(format);
         }
+        // The generic HEIF brands are used by both, so the compatible brands
+        // (after the minor version) tell them apart
+        let brand = &bytes[8..12];
+        if brand == b"mif1" || brand == b"msf1" {
+            let box_len = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+            return bytes
+                .get(16..box_len.min(bytes.len()))
+                .unwrap_or_default()
+                .chunks_exact(4)
+                .find_map(heif_brand_format)
+                .or(Some(ImageFormat::Heic));
+        }
+    }
+
+    // JPEG XL: bare codestream (FF 0A) or ISO BMFF container
+    if bytes.starts_with(&[0xFF, 0x0A]) || bytes.starts_with(b"\0\0\0\x0cJXL \r\n\x87\n") {
+        return Some(ImageFormat::Jxl);
     }
 
     // BMP: 42 4D
     if bytes.starts_with(&[0x42, 0x4D]) {
-        return true;
+        return Some(ImageFormat::Bmp);
     }
 
     // TIFF: 49 49 2A 00 (little endian) or 4D 4D 00 2A (big endian)
     if bytes.starts_with(&[0x49, 0x49, 0x2A, 0x00]) || bytes.starts_with(&[0x4D, 0x4D, 0x00, 0x2A])
     {
-        return true;
+        return Some(ImageFormat::Tiff);
     }
 
-    false
+    None
+}
+
+/// Format of an `ftyp` brand specific to HEIC or AVIF
+fn heif_brand_format(brand: &[u8]) -> Option<ImageFormat> {
+    match brand {
+        b"heic" | b"heix" | b"heim" | b"heis" | b"hevc" | b"hevx" => Some(ImageFormat::Heic),
+        // AVIF still images and sequences
+        b"avif" | b"avis" => Some(ImageFormat::Avif),
+        _ => None,
+    }
 }
 
 #[cfg(test)]
@@ -180,6 +225,20 @@ mod tests {
         assert!(validate_image_header(&webp_header));
     }
 
+    #[test]
+    fn validate_avif_header() {
+        let avif_header = b"\0\0\0\x1cftypavif\0\0\0\0";
+        assert!(validate_image_header(avif_header));
+    }
+
+    #[test]
+    fn validate_jxl_headers() {
+        let codestream = [0xFF, 0x0A, 0xFA, 0x1F, 0x00, 0x00, 0x00, 0x00];
+        let container = b"\0\0\0\x0cJXL \r\n\x87\n\0\0\0\x14ftypjxl ";
+        assert!(validate_image_header(&codestream));
+        assert!(validate_image_header(container));
+    }
+
     #[test]
     fn reject_invalid_header() {
         let invalid = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07];
@@ -192,6 +251,27 @@ mod tests {
         assert!(!validate_image_header(&short));
     }
 
+    #[test]
+    fn sniff_tells_formats_apart() {
+        let jpeg = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, 0x4A, 0x46];
+        let heic = b"\0\0\0\x18ftypheic\0\0\0\0";
+        let tiff = [0x49, 0x49, 0x2A, 0x00, 0x08, 0x00, 0x00, 0x00];
+        assert_eq!(sniff_image_format(&jpeg), Some(ImageFormat::Jpeg));
+        assert_eq!(sniff_image_format(heic), Some(ImageFormat::Heic));
+        assert_eq!(sniff_image_format(&tiff), Some(ImageFormat::Tiff));
+        assert_eq!(sniff_image_format(b"%PDF-1.7\n"), None);
+    }
+
+    #[test]
+    fn sniff_reads_compatible_brands_of_generic_heif() {
+        let avif = b"\0\0\0\x1cftypmif1\0\0\0\0mif1avifmiaf";
+        let heic = b"\0\0\0\x18ftypmif1\0\0\0\0mif1heic";
+        let unknown = b"\0\0\0\x14ftypmif1\0\0\0\0mif1";
+        assert_eq!(sniff_image_format(avif), Some(ImageFormat::Avif));
+        assert_eq!(sniff_image_format(heic), Some(ImageFormat::Heic));
+        assert_eq!(sniff_image_format(unknown), Some(ImageFormat::Heic));
+    }
+
     #[test]
     fn file_bytes_deref() {
         let bytes = FileBytes::Vec(vec![1, 2, 3, 4]);
@@ -6,6 +6,9 @@
 //! - **dHash (Difference Hash)** - Best balance of speed and accuracy
 //! - **aHash (Average Hash)** - Fastest, good for exact duplicates
 //! - **pHash (Perceptual Hash)** - Most robust, handles edits well
+//! - **wHash (Wavelet Hash)** - Haar low-frequency band, robust to noise
+//! - **Block mean** - Overlapping block averages, robust to small shifts
+//! - **Crop-resistant** - dHashes of overlapping windows, finds cropped copies
 //!
 //! ## How It Works
 //! 1. Resize image to small size (8x8 or 16x16)
@@ -19,6 +22,28 @@
 //! - Uses memory-mapped I/O for large files (20-40% faster)
 //! - Validates image headers before full decode
 //!
+//! ## HEIC/HEIF
+//! With the `heif` feature, HEIC/HEIF files are decoded natively through
+//! libheif on every platform. Without it, they are converted with `sips`,
+//! which only exists on macOS.
+//!
+//! ## AVIF and JPEG XL
+//! The `avif` feature decodes AVIF through dav1d (libdav1d must be
+//! installed), and the `jxl` feature decodes JPEG XL with the pure-Rust
+//! jxl-oxide. Both formats are scanned either way.
+//!
+//! ## Rotations and Mirrors
+//! [`DihedralHasher`] hashes all eight rotations and mirrors of an upright
+//! thumbnail so rotated or flipped copies can be matched (opt-in).
+//!
+//! ## Pixel-Identical Copies
+//! [`PixelHash`] fingerprints the full decoded pixels, so copies that only
+//! differ in metadata can be told apart from perceptual near-matches.
+//!
+//! ## EXIF Thumbnails
+//! [`FastDecoder::decode_thumbnail`] decodes the small JPEG stored in a
+//! photo's EXIF data, for preview scans that trade accuracy for speed.
+//!
 //! ## Example
 //! ```rust,ignore
 //! use duplicate_photo_cleaner::core::hasher::{HasherConfig, HashAlgorithmKind};
@@ -32,15 +57,31 @@
 //! ```
 
 mod algorithms;
+pub mod crop;
+pub mod dihedral;
 pub mod fast_decode;
 pub mod fast_resize;
 pub mod fusion;
+#[cfg(feature = "heif")]
+pub mod heif_decode;
 pub mod mmap_decode;
+pub mod pixel;
+pub mod thumbnail;
 mod traits;
 
-pub use algorithms::{AverageHasher, DifferenceHasher, PerceptualHasher};
-pub use fusion::{FusionCompareResult, FusionConfidence, FusionHash, FusionHasher};
-pub use mmap_decode::{read_file_bytes, validate_image_header, FileBytes};
+pub use algorithms::{
+    AverageHasher, BlockMeanHasher, DifferenceHasher, PerceptualHasher, WaveletHasher,
+};
+pub use crop::{CropHash, CropRegion, CropResistantHasher, Segment};
+pub use dihedral::{DihedralHasher, DihedralTransform, HashVariants};
+pub use fast_decode::{DecodeOptions, FastDecoder, HASH_DECODE_SIZE, MIN_THUMBNAIL_SIZE};
+pub use fusion::{FusionCompareResult, FusionConfidence, FusionHash, FusionHasher, EXTRA_VOTERS};
+pub use mmap_decode::{
+    read_file_bytes, read_file_header, sniff_image_format, validate_image_header, FileBytes,
+    HEADER_LEN,
+};
+pub use pixel::PixelHash;
+pub use thumbnail::exif_thumbnail;
 pub use traits::{HashAlgorithm, HashAlgorithmKind, ImageHashValue, PerceptualHash};
 
 // Re-export PerceptualHash for external use
@@ -48,6 +89,12 @@ pub use traits::PerceptualHash as _;
 
 use crate::error::HashError;
 
+/// Default hash size (8x8 = 64 bits)
+pub const DEFAULT_HASH_SIZE: u32 = 8;
+
+/// Hash sizes [`HasherConfig::hash_size`] accepts
+pub const SUPPORTED_HASH_SIZES: [u32; 3] = [8, 16, 32];
+
 /// Configuration builder for hashers
 #[derive(Debug, Clone)]
 pub struct HasherConfig {
@@ -55,14 +102,17 @@ pub struct HasherConfig {
     hash_size: u32,
     /// Algorithm to use
     algorithm: HashAlgorithmKind,
+    /// Extra fusion voters (wHash, block-mean)
+    fusion_voters: Vec<HashAlgorithmKind>,
 }
 
 impl HasherConfig {
     /// Create a new hasher configuration with defaults
     pub fn new() -> Self {
         Self {
-            hash_size: 8,
+            hash_size: DEFAULT_HASH_SIZE,
             algorithm: HashAlgorithmKind::Difference,
+            fusion_voters: Vec::new(),
         }
     }
 
@@ -83,13 +133,36 @@ impl HasherConfig {
         self
     }
 
+    /// Add extra voters to fusion (ignored by other algorithms)
+    ///
+    /// Only [`EXTRA_VOTERS`] are accepted; [`build`](Self::build) fails for
+    /// anything else.
+    pub fn fusion_voters(mut self, voters: Vec<HashAlgorithmKind>) -> Self {
+        self.fusion_voters = voters;
+        self
+    }
+
     /// Build the hasher
     pub fn build(self) -> Result<Box<dyn HashAlgorithm>, HashError> {
+        if !SUPPORTED_HASH_SIZES.contains(&self.hash_size) {
+            return Err(HashError::UnsupportedHashSize {
+                size: self.hash_size,
+            });
+        }
+
         match self.algorithm {
             HashAlgorithmKind::Average => Ok(Box::new(AverageHasher::new(self.hash_size))),
             HashAlgorithmKind::Difference => Ok(Box::new(DifferenceHasher::new(self.hash_size))),
             HashAlgorithmKind::Perceptual => Ok(Box::new(PerceptualHasher::new(self.hash_size))),
-            HashAlgorithmKind::Fusion => Ok(Box::new(FusionHasher::with_size(self.hash_size))),
+            HashAlgorithmKind::Fusion => Ok(Box::new(FusionHasher::with_voters(
+                self.hash_size,
+                &self.fusion_voters,
+            )?)),
+            HashAlgorithmKind::Wavelet => Ok(Box::new(WaveletHasher::new(self.hash_size))),
+            HashAlgorithmKind::BlockMean => Ok(Box::new(BlockMeanHasher::new(self.hash_size))),
+            HashAlgorithmKind::CropResistant => {
+                Ok(Box::new(CropResistantHasher::with_size(self.hash_size)))
+            }
         }
     }
 }
@@ -125,4 +198,32 @@ mod tests {
         let hasher = HasherConfig::new().build();
         assert!(hasher.is_ok());
     }
+
+    #[test]
+    fn build_rejects_invalid_fusion_voters() {
+        let valid = HasherConfig::new()
+            .algorithm(HashAlgorithmKind::Fusion)
+            .fusion_voters(EXTRA_VOTERS.to_vec())
+            .build();
+        let invalid = HasherConfig::new()
+            .algorithm(HashAlgorithmKind::Fusion)
+            .fusion_voters(vec![HashAlgorithmKind::Average])
+            .build();
+
+        assert!(valid.is_ok());
+        assert!(matches!(invalid, Err(HashError::InvalidFusionVoter { .. })));
+    }
+
+    #[test]
+    fn build_rejects_unsupported_hash_size() {
+        for size in SUPPORTED_HASH_SIZES {
+            assert!(HasherConfig::new().hash_size(size).build().is_ok());
+        }
+
+        let invalid = HasherConfig::new().hash_size(12).build();
+        assert!(matches!(
+            invalid,
+            Err(HashError::UnsupportedHashSize { size: 12 })
+        ));
+    }
 }
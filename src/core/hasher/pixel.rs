@@ -0,0 +1,100 @@
+//! # Pixel Hash
+//!
+//! Fingerprints the decoded pixels of a photo, ignoring its metadata.
+//!
+//! Copies that only differ in EXIF (stripped GPS, edited tags, re-saved
+//! without recompression) have different file contents but decode to the
+//! same pixels. Hashing the upright pixel buffer with xxh3-128 finds them
+//! without any perceptual tolerance: one changed pixel gives a different
+//! hash.
+
+use super::fast_decode::{DecodeOptions, FastDecoder};
+use crate::error::HashError;
+use image::DynamicImage;
+use std::path::Path;
+use xxhash_rust::xxh3::Xxh3;
+
+/// Hash of a decoded pixel buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PixelHash(pub u128);
+
+impl PixelHash {
+    /// Hash the pixels of a decoded image.
+    ///
+    /// Covers the dimensions and color type as well as the raw samples, so
+    /// the same bytes laid out differently don't collide.
+    pub fn of_image(image: &DynamicImage) -> Self {
+        let mut hasher = Xxh3::new();
+        hasher.update(&image.width().to_le_bytes());
+        hasher.update(&image.height().to_le_bytes());
+        hasher.update(format!("{:?}", image.color()).as_bytes());
+        hasher.update(image.as_bytes());
+        Self(hasher.digest128())
+    }
+
+    /// Hash the pixels of a file, after applying its EXIF orientation
+    pub fn of_file(path: &Path) -> Result<Self, HashError> {
+        let image = FastDecoder::decode_with(path, &DecodeOptions::upright())?;
+        Ok(Self::of_image(&image))
+    }
+
+    /// Get the bytes for caching
+    pub fn to_bytes(self) -> [u8; 16] {
+        self.0.to_le_bytes()
+    }
+
+    /// Restore from cached bytes
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let bytes: [u8; 16] = bytes.try_into().ok()?;
+        Some(Self(u128::from_le_bytes(bytes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb};
+
+    fn gradient(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(ImageBuffer::from_fn(width, height, |x, y| {
+            Rgb([x as u8, y as u8, (x + y) as u8])
+        }))
+    }
+
+    #[test]
+    fn same_pixels_same_hash() {
+        assert_eq!(
+            PixelHash::of_image(&gradient(40, 30)),
+            PixelHash::of_image(&gradient(40, 30))
+        );
+    }
+
+    #[test]
+    fn one_changed_pixel_changes_hash() {
+        let original = gradient(40, 30);
+        let mut edited = original.to_rgb8();
+        edited.put_pixel(10, 10, Rgb([0, 0, 0]));
+
+        assert_ne!(
+            PixelHash::of_image(&original),
+            PixelHash::of_image(&DynamicImage::ImageRgb8(edited))
+        );
+    }
+
+    #[test]
+    fn layout_is_part_of_hash() {
+        // Same samples, transposed dimensions
+        let wide = DynamicImage::ImageLuma8(ImageBuffer::from_raw(4, 2, vec![7; 8]).unwrap());
+        let tall = DynamicImage::ImageLuma8(ImageBuffer::from_raw(2, 4, vec![7; 8]).unwrap());
+
+        assert_ne!(PixelHash::of_image(&wide), PixelHash::of_image(&tall));
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let hash = PixelHash::of_image(&gradient(8, 8));
+
+        assert_eq!(PixelHash::from_bytes(&hash.to_bytes()), Some(hash));
+        assert_eq!(PixelHash::from_bytes(&[1, 2, 3]), None);
+    }
+}
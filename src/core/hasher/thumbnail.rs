@@ -0,0 +1,180 @@
+//! EXIF thumbnail extraction, for preview scans.
+//!
+//! Cameras and phones store a small JPEG, typically 160x120, in the second
+//! IFD (IFD1) of a photo's EXIF data. Decoding it is a fraction of the cost
+//! of decoding the photo, so hashing it gives a quick approximation of the
+//! photo's hash. Thumbnails can be letterboxed or stale after an edit, so
+//! matches found from them need confirming against the full image.
+
+use crate::core::raw::jpeg_exif;
+
+/// IFD1 tag holding the offset of the thumbnail JPEG
+const JPEG_OFFSET: u16 = 0x0201;
+/// IFD1 tag holding the length of the thumbnail JPEG
+const JPEG_LENGTH: u16 = 0x0202;
+/// TIFF field type SHORT; offsets and lengths are otherwise LONG
+const SHORT: u16 = 3;
+
+/// Find the EXIF thumbnail of a JPEG or TIFF-based file
+pub fn exif_thumbnail(bytes: &[u8]) -> Option<&[u8]> {
+    let tiff = if bytes.starts_with(&[0xFF, 0xD8]) {
+        jpeg_exif(bytes)?
+    } else {
+        bytes
+    };
+    ifd1_jpeg(tiff)
+}
+
+/// Get the JPEG that IFD1 of a TIFF block points at
+fn ifd1_jpeg(tiff: &[u8]) -> Option<&[u8]> {
+    let little_endian = match tiff.get(..4)? {
+        [b'I', b'I', 42, 0] => true,
+        [b'M', b'M', 0, 42] => false,
+        _ => return None,
+    };
+    let u16_at = |pos: usize| {
+        let bytes: [u8; 2] = tiff.get(pos..pos.checked_add(2)?)?.try_into().ok()?;
+        Some(if little_endian {
+            u16::from_le_bytes(bytes)
+        } else {
+            u16::from_be_bytes(bytes)
+        })
+    };
+    let u32_at = |pos: usize| {
+        let bytes: [u8; 4] = tiff.get(pos..pos.checked_add(4)?)?.try_into().ok()?;
+        Some(if little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    };
+
+    // IFD0 ends with the offset of IFD1, or 0 when there isn't one
+    let ifd0 = u32_at(4)? as usize;
+    let ifd0_entries = u16_at(ifd0)? as usize;
+    let ifd1 = u32_at(ifd0 + 2 + ifd0_entries * 12)? as usize;
+    if ifd1 == 0 {
+        return None;
+    }
+
+    let (mut offset, mut length) = (None, None);
+    for entry in (0..u16_at(ifd1)? as usize).map(|i| ifd1 + 2 + i * 12) {
+        let value = if u16_at(entry + 2)? == SHORT {
+            u16_at(entry + 8)? as u32
+        } else {
+            u32_at(entry + 8)?
+        };
+        match u16_at(entry)? {
+            JPEG_OFFSET => offset = Some(value as usize),
+            JPEG_LENGTH => length = Some(value as usize),
+            _ => {}
+        }
+    }
+
+    let start = offset?;
+    let jpeg = tiff.get(start..start.checked_add(length?)?)?;
+    jpeg.starts_with(&[0xFF, 0xD8]).then_some(jpeg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::hasher::FastDecoder;
+    use crate::core::raw::test_tiff::{short, tiff, SHORT as SHORT_TYPE};
+    use image::codecs::jpeg::JpegEncoder;
+    use image::RgbImage;
+    use tempfile::TempDir;
+
+    /// Add an IFD1 pointing at `thumbnail` to a one-entry TIFF
+    fn with_thumbnail(mut tiff: Vec<u8>, thumbnail: &[u8]) -> Vec<u8> {
+        let ifd1 = tiff.len() as u32;
+        // Next-IFD offset of the one-entry IFD0
+        tiff[22..26].copy_from_slice(&ifd1.to_le_bytes());
+        tiff.extend_from_slice(&2u16.to_le_bytes());
+        for (tag, value) in [
+            (JPEG_OFFSET, ifd1 + 2 + 24 + 4),
+            (JPEG_LENGTH, thumbnail.len() as u32),
+        ] {
+            tiff.extend_from_slice(&tag.to_le_bytes());
+            tiff.extend_from_slice(&4u16.to_le_bytes());
+            tiff.extend_from_slice(&1u32.to_le_bytes());
+            tiff.extend_from_slice(&value.to_le_bytes());
+        }
+        tiff.extend_from_slice(&0u32.to_le_bytes());
+        tiff.extend_from_slice(thumbnail);
+        tiff
+    }
+
+    /// Insert a TIFF block as the APP1 segment of `jpeg`
+    fn jpeg_with_exif(jpeg: &[u8], exif: &[u8]) -> Vec<u8> {
+        let mut out = jpeg[..2].to_vec();
+        out.extend_from_slice(&[0xFF, 0xE1]);
+        out.extend_from_slice(&((exif.len() + 8) as u16).to_be_bytes());
+        out.extend_from_slice(b"Exif\0\0");
+        out.extend_from_slice(exif);
+        out.extend_from_slice(&jpeg[2..]);
+        out
+    }
+
+    fn encode_jpeg(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbImage::from_fn(width, height, |x, y| {
+            image::Rgb([(x * 2) as u8, (y * 2) as u8, 90])
+        });
+        let mut out = Vec::new();
+        JpegEncoder::new(&mut out).encode_image(&image).unwrap();
+        out
+    }
+
+    #[test]
+    fn finds_thumbnail_in_jpeg_exif() {
+        let thumbnail = [0xFF, 0xD8, 0xFF, 0xDB, 1, 2, 3, 0xFF, 0xD9];
+        let exif = with_thumbnail(tiff(&[(0x0112, SHORT_TYPE, short(1))]), &thumbnail);
+
+        assert_eq!(
+            exif_thumbnail(&jpeg_with_exif(&[0xFF, 0xD8, 0xFF, 0xD9], &exif)),
+            Some(&thumbnail[..])
+        );
+        // TIFF-based files carry the same structure at the start of the file
+        assert_eq!(exif_thumbnail(&exif), Some(&thumbnail[..]));
+    }
+
+    #[test]
+    fn no_thumbnail_without_ifd1() {
+        let exif = tiff(&[(0x0112, SHORT_TYPE, short(1))]);
+
+        assert_eq!(
+            exif_thumbnail(&jpeg_with_exif(&[0xFF, 0xD8, 0xFF, 0xD9], &exif)),
+            None
+        );
+        assert_eq!(exif_thumbnail(b"not an image"), None);
+    }
+
+    #[test]
+    fn rejects_thumbnail_that_is_not_jpeg() {
+        let exif = with_thumbnail(tiff(&[(0x0112, SHORT_TYPE, short(1))]), b"garbage");
+
+        assert_eq!(exif_thumbnail(&exif), None);
+    }
+
+    #[test]
+    fn decodes_thumbnail_instead_of_photo() {
+        let dir = TempDir::new().unwrap();
+        let exif = with_thumbnail(
+            tiff(&[(0x0112, SHORT_TYPE, short(1))]),
+            &encode_jpeg(160, 120),
+        );
+        let with_thumbnail = dir.path().join("photo.jpg");
+        std::fs::write(
+            &with_thumbnail,
+            jpeg_with_exif(&encode_jpeg(640, 480), &exif),
+        )
+        .unwrap();
+        let without = dir.path().join("plain.jpg");
+        std::fs::write(&without, encode_jpeg(640, 480)).unwrap();
+
+        let thumbnail = FastDecoder::decode_thumbnail(&with_thumbnail).unwrap();
+
+        assert_eq!((thumbnail.width(), thumbnail.height()), (160, 120));
+        assert!(FastDecoder::decode_thumbnail(&without).is_none());
+    }
+}
@@ -1,6 +1,6 @@
 //! Trait definitions for perceptual hashing.
 
-use super::fast_decode::FastDecoder;
+use super::fast_decode::{FastDecoder, HASH_DECODE_SIZE};
 use crate::error::HashError;
 use image::DynamicImage;
 use serde::{Deserialize, Serialize};
@@ -52,6 +52,12 @@ pub enum HashAlgorithmKind {
     Perceptual,
     /// Multi-Algorithm Fusion - Combines aHash+dHash+pHash with voting
     Fusion,
+    /// Wavelet Hash (wHash) - Haar low-frequency band, robust to noise
+    Wavelet,
+    /// Block Mean Hash - Overlapping block averages, robust to small shifts
+    BlockMean,
+    /// Crop-Resistant - dHashes overlapping windows to find cropped copies
+    CropResistant,
 }
 
 impl HashAlgorithmKind {
@@ -70,6 +76,15 @@ impl HashAlgorithmKind {
             HashAlgorithmKind::Fusion => {
                 "Multi-Algorithm Fusion - Combines aHash+dHash+pHash with voting for higher accuracy"
             }
+            HashAlgorithmKind::Wavelet => {
+                "Wavelet Hash (wHash) - Haar wavelet low frequencies, robust to noise and compression"
+            }
+            HashAlgorithmKind::BlockMean => {
+                "Block Mean Hash - Compares overlapping block averages, robust to small shifts"
+            }
+            HashAlgorithmKind::CropResistant => {
+                "Crop-Resistant Hash - Hashes overlapping windows to find cropped copies"
+            }
         }
     }
 }
@@ -81,6 +96,9 @@ impl std::fmt::Display for HashAlgorithmKind {
             HashAlgorithmKind::Difference => write!(f, "dHash"),
             HashAlgorithmKind::Perceptual => write!(f, "pHash"),
             HashAlgorithmKind::Fusion => write!(f, "fusion"),
+            HashAlgorithmKind::Wavelet => write!(f, "wHash"),
+            HashAlgorithmKind::BlockMean => write!(f, "block-mean"),
+            HashAlgorithmKind::CropResistant => write!(f, "crop-resistant"),
         }
     }
 }
@@ -95,8 +113,11 @@ pub trait HashAlgorithm: Send + Sync {
     /// Uses fast decoders for optimal performance:
     /// - JPEG: zune-jpeg (1.5-2x faster)
     /// - Other formats: image crate fallback
+    ///
+    /// The EXIF orientation is applied first, so a photo hashes the same
+    /// whether its pixels or its orientation tag were rotated.
     fn hash_file(&self, path: &Path) -> Result<ImageHashValue, HashError> {
-        let image = FastDecoder::decode(path)?;
+        let image = FastDecoder::decode_for_hash(path, HASH_DECODE_SIZE)?;
         self.hash_image(&image)
     }
 
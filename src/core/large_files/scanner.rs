@@ -3,6 +3,7 @@
 //! Finds files above a size threshold for disk space cleanup.
 //! Uses efficient filesystem metadata queries (O(1)) rather than reading file contents.
 
+use crate::core::scanner::ScanLimits;
 use serde::{Deserialize, Serialize};
 use std::cmp::Reverse;
 use std::collections::BinaryHeap;
@@ -59,6 +60,8 @@ pub struct LargeFileScanner {
     min_size_bytes: u64,
     /// Maximum number of results to return (default 50)
     max_results: usize,
+    /// Size, date and dimension limits a file must be within
+    limits: ScanLimits,
 }
 
 impl Default for LargeFileScanner {
@@ -66,6 +69,7 @@ impl Default for LargeFileScanner {
         Self {
             min_size_bytes: 10 * 1024 * 1024, // 10MB
             max_results: 50,
+            limits: ScanLimits::default(),
         }
     }
 }
@@ -80,9 +84,17 @@ impl LargeFileScanner {
         Self {
             min_size_bytes: min_size_mb * 1024 * 1024,
             max_results,
+            limits: ScanLimits::default(),
         }
     }
 
+    /// Only report files within these limits. Nothing is decoded, so
+    /// dimensions are read from the file header.
+    pub fn with_limits(mut self, limits: ScanLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
     /// Scan directories for large files
     ///
     /// # Arguments
@@ -161,7 +173,10 @@ impl LargeFileScanner {
                     let size = metadata.len();
 
                     // Check if file meets size threshold and is a media file
-                    if size >= self.min_size_bytes && is_media_file(entry_path) {
+                    if size >= self.min_size_bytes
+                        && is_media_file(entry_path)
+                        && self.within_limits(entry_path, &metadata)
+                    {
                         let file_info = LargeFileInfo {
                             path: entry_path.display().to_string(),
                             filename: entry_path
@@ -207,13 +222,21 @@ impl LargeFileScanner {
             scan_duration_ms: duration.as_millis(),
         })
     }
+
+    /// Check a large media file against the limits
+    fn within_limits(&self, path: &Path, metadata: &fs::Metadata) -> bool {
+        let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+        self.limits.admits_file(metadata.len(), modified)
+            && self.limits.admits_header_dimensions(path)
+    }
 }
 
 /// Check if a file is a media file by extension
 fn is_media_file(path: &Path) -> bool {
     const MEDIA_EXTENSIONS: &[&str] = &[
         "jpg", "jpeg", "png", "gif", "webp", "bmp", "tiff", "tif", "mp4", "mov", "avi", "mkv",
-        "flv", "wmv", "webm", "m4v", "heic", "heif", "raw", "cr2", "nef", "dng", "arw", "raf",
+        "flv", "wmv", "webm", "m4v", "heic", "heif", "avif", "jxl", "raw", "cr2", "cr3", "nef",
+        "dng", "arw", "raf", "orf",
     ];
 
     path.extension()
@@ -400,4 +423,26 @@ mod tests {
         assert_eq!(result.files.len(), 2);
         assert_eq!(result.total_size_bytes, 35 * 1024 * 1024);
     }
+
+    #[test]
+    fn test_scan_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_file(temp_dir.path(), "small.jpg", 2 * 1024 * 1024).unwrap();
+        create_test_file(temp_dir.path(), "medium.jpg", 5 * 1024 * 1024).unwrap();
+        create_test_file(temp_dir.path(), "huge.mp4", 50 * 1024 * 1024).unwrap();
+
+        let limits = ScanLimits {
+            max_size: Some(10 * 1024 * 1024),
+            min_dimension: Some(200),
+            ..Default::default()
+        };
+        let scanner = LargeFileScanner::new(1, 50).with_limits(limits);
+        let result = scanner
+            .scan(&[temp_dir.path().display().to_string()])
+            .unwrap();
+
+        // Dimensions of files without a readable header aren't known
+        assert_eq!(result.files.len(), 2);
+        assert!(result.files.iter().all(|f| f.file_type == "jpg"));
+    }
 }
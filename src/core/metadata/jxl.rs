@@ -0,0 +1,87 @@
+//! EXIF from JPEG XL containers.
+//!
+//! A JPEG XL file is either a bare codestream, which has no EXIF, or an
+//! ISO BMFF-style container whose `Exif` box holds a TIFF block. Boxes
+//! compressed with Brotli (`brob`) are not read.
+
+/// Signature box that starts every JPEG XL container
+const CONTAINER_SIGNATURE: &[u8] = b"\0\0\0\x0cJXL \r\n\x87\n";
+
+/// Get the TIFF-formatted EXIF block from a JPEG XL container
+pub fn jxl_exif(bytes: &[u8]) -> Option<&[u8]> {
+    if !bytes.starts_with(CONTAINER_SIGNATURE) {
+        return None;
+    }
+
+    let mut pos = 0;
+    while let Some((kind, payload, next)) = box_at(bytes, pos) {
+        if kind == b"Exif" {
+            // The payload starts with the offset of the TIFF header
+            let offset = u32::from_be_bytes(payload.get(..4)?.try_into().ok()?) as usize;
+            return payload.get(4usize.checked_add(offset)?..);
+        }
+        pos = next;
+    }
+    None
+}
+
+/// Read the box at `pos`: (type, payload, next position)
+fn box_at(bytes: &[u8], pos: usize) -> Option<(&[u8], &[u8], usize)> {
+    let size = u32::from_be_bytes(bytes.get(pos..pos + 4)?.try_into().ok()?) as u64;
+    let kind = bytes.get(pos + 4..pos + 8)?;
+
+    let (header, size) = match size {
+        // Extends to the end of the file
+        0 => (8, (bytes.len() - pos) as u64),
+        // 64-bit size follows the type
+        1 => (
+            16,
+            u64::from_be_bytes(bytes.get(pos + 8..pos + 16)?.try_into().ok()?),
+        ),
+        size => (8, size),
+    };
+
+    let end = pos.checked_add(usize::try_from(size).ok()?)?;
+    let payload = bytes.get(pos + header..end)?;
+    Some((kind, payload, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn jxl_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    #[test]
+    fn reads_exif_box_after_offset() {
+        let tiff = b"MM\0\x2a\0\0\0\x08\0\0";
+        let mut exif = 2u32.to_be_bytes().to_vec();
+        exif.extend_from_slice(b"xx");
+        exif.extend_from_slice(tiff);
+
+        let mut file = CONTAINER_SIGNATURE.to_vec();
+        file.extend_from_slice(&jxl_box(b"ftyp", b"jxl \0\0\0\0jxl "));
+        file.extend_from_slice(&jxl_box(b"Exif", &exif));
+        file.extend_from_slice(&jxl_box(b"jxlc", &[0xFF, 0x0A]));
+
+        assert_eq!(jxl_exif(&file), Some(&tiff[..]));
+    }
+
+    #[test]
+    fn bare_codestream_has_no_exif() {
+        assert_eq!(jxl_exif(&[0xFF, 0x0A, 0xFA, 0x1F, 0, 0, 0, 0]), None);
+    }
+
+    #[test]
+    fn truncated_box_is_ignored() {
+        let mut file = CONTAINER_SIGNATURE.to_vec();
+        file.extend_from_slice(b"\0\0\x10\0Exif\0\0");
+
+        assert_eq!(jxl_exif(&file), None);
+    }
+}
@@ -8,20 +8,34 @@
 //! - Camera make and model
 //! - Orientation
 //! - Software (e.g., "screencaptureui")
+//! - GPS position
+//!
+//! Two copies of a photo can be compared field by field with
+//! [`PhotoMetadata::differences`].
 //!
 //! ## Supported Formats
-//! EXIF metadata is typically found in JPEG and TIFF files.
-//! HEIC files may use different metadata formats.
+//! EXIF metadata is typically found in JPEG, TIFF, HEIC and AVIF files. With
+//! the `heif` feature, HEIC/HEIF dimensions are read from the container.
+//!
+//! Camera RAW files that aren't TIFF-based (CR3, ORF, RAF) have their EXIF
+//! read through [`crate::core::raw::exif_blocks`], and JPEG XL files from
+//! their container's `Exif` box.
+
+mod jxl;
 
+use crate::core::hasher::fast_decode::ImageFormat;
+#[cfg(feature = "heif")]
+use crate::core::hasher::heif_decode::heif_dimensions;
+use crate::core::raw::exif_blocks;
 use chrono::{DateTime, NaiveDateTime, Utc};
-use exif::{In, Reader, Tag, Value};
+use exif::{Exif, Field, In, Reader, Tag, Value};
 use serde::{Deserialize, Serialize};
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Cursor};
 use std::path::Path;
 
 /// Extracted photo metadata
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct PhotoMetadata {
     /// Original capture date/time
     pub date_taken: Option<DateTime<Utc>>,
@@ -37,6 +51,36 @@ pub struct PhotoMetadata {
     pub orientation: Option<u16>,
     /// Software used to create the image (e.g., "screencaptureui")
     pub software: Option<String>,
+    /// GPS position as (latitude, longitude) in degrees
+    #[serde(default)]
+    pub gps: Option<(f64, f64)>,
+}
+
+/// Reads one field of a photo's metadata as display text
+type FieldReader = fn(&PhotoMetadata) -> Option<String>;
+
+/// One metadata field that differs between two photos
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetadataDifference {
+    /// Name of the field (e.g., "GPS position")
+    pub field: String,
+    /// Value in the first photo, if set
+    pub a: Option<String>,
+    /// Value in the second photo, if set
+    pub b: Option<String>,
+}
+
+impl std::fmt::Display for MetadataDifference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = |v: &Option<String>| v.clone().unwrap_or_else(|| "none".to_string());
+        write!(
+            f,
+            "{}: {} vs {}",
+            self.field,
+            value(&self.a),
+            value(&self.b)
+        )
+    }
 }
 
 impl PhotoMetadata {
@@ -49,6 +93,36 @@ impl PhotoMetadata {
             || self.camera_model.is_some()
             || self.orientation.is_some()
             || self.software.is_some()
+            || self.gps.is_some()
+    }
+
+    /// List the fields whose values differ from `other`'s
+    pub fn differences(&self, other: &PhotoMetadata) -> Vec<MetadataDifference> {
+        let fields: [(&str, FieldReader); 6] = [
+            ("Date taken", |m| {
+                m.date_taken
+                    .map(|d| d.format("%Y-%m-%d %H:%M:%S").to_string())
+            }),
+            ("Dimensions", |m| m.dimensions_display()),
+            ("Camera", |m| m.camera_display()),
+            ("Orientation", |m| m.orientation.map(|o| o.to_string())),
+            ("Software", |m| m.software.clone()),
+            ("GPS position", |m| {
+                m.gps.map(|(lat, lon)| format!("{:.5}, {:.5}", lat, lon))
+            }),
+        ];
+
+        fields
+            .into_iter()
+            .filter_map(|(field, value)| {
+                let (a, b) = (value(self), value(other));
+                (a != b).then(|| MetadataDifference {
+                    field: field.to_string(),
+                    a,
+                    b,
+                })
+            })
+            .collect()
     }
 
     /// Get a display string for the camera
@@ -86,23 +160,49 @@ impl PhotoMetadata {
 }
 
 /// Extract EXIF metadata from a photo file
+///
+/// With the `heif` feature, HEIC/HEIF dimensions come from the container
+/// rather than the EXIF tags, which edits often leave stale.
 pub fn extract_metadata(path: &Path) -> PhotoMetadata {
-    let mut metadata = PhotoMetadata::default();
+    let metadata = read_exif(&load_exif(path));
+    #[cfg(feature = "heif")]
+    let metadata = with_heif_dimensions(path, metadata);
+    metadata
+}
 
-    // Try to open and read the file
-    let file = match File::open(path) {
-        Ok(f) => f,
-        Err(_) => return metadata,
-    };
+/// Extract EXIF metadata from a photo file's contents, already read.
+///
+/// Gives the same result as [`extract_metadata`] without opening the file
+/// again; `path` is only used to tell the format.
+pub fn extract_metadata_from_bytes(path: &Path, bytes: &[u8]) -> PhotoMetadata {
+    let metadata = read_exif(&load_exif_from_bytes(path, bytes));
+    #[cfg(feature = "heif")]
+    let metadata = with_heif_dimensions(path, metadata);
+    metadata
+}
 
-    let mut bufreader = BufReader::new(&file);
-    let exif_reader = match Reader::new().read_from_container(&mut bufreader) {
-        Ok(r) => r,
-        Err(_) => return metadata,
-    };
+/// Replace the EXIF dimensions of a HEIC/HEIF file with its container's
+#[cfg(feature = "heif")]
+fn with_heif_dimensions(path: &Path, mut metadata: PhotoMetadata) -> PhotoMetadata {
+    if ImageFormat::from_path(path) == ImageFormat::Heic {
+        if let Some((width, height)) = heif_dimensions(path) {
+            metadata.width = Some(width);
+            metadata.height = Some(height);
+        }
+    }
+    metadata
+}
+
+/// Read the fields we use out of a photo's EXIF blocks
+fn read_exif(exif: &[Exif]) -> PhotoMetadata {
+    let mut metadata = PhotoMetadata::default();
+
+    if exif.is_empty() {
+        return metadata;
+    }
 
     // Extract date taken
-    if let Some(field) = exif_reader.get_field(Tag::DateTimeOriginal, In::PRIMARY) {
+    if let Some(field) = get_field(exif, Tag::DateTimeOriginal) {
         if let Value::Ascii(ref vec) = field.value {
             if let Some(bytes) = vec.first() {
                 if let Ok(s) = std::str::from_utf8(bytes) {
@@ -116,50 +216,136 @@ pub fn extract_metadata(path: &Path) -> PhotoMetadata {
     }
 
     // Extract dimensions - prefer actual pixel dimensions
-    if let Some(field) = exif_reader.get_field(Tag::PixelXDimension, In::PRIMARY) {
+    if let Some(field) = get_field(exif, Tag::PixelXDimension) {
         metadata.width = get_u32_value(&field.value);
     }
-    if let Some(field) = exif_reader.get_field(Tag::PixelYDimension, In::PRIMARY) {
+    if let Some(field) = get_field(exif, Tag::PixelYDimension) {
         metadata.height = get_u32_value(&field.value);
     }
 
     // Fallback to image width/height tags
     if metadata.width.is_none() {
-        if let Some(field) = exif_reader.get_field(Tag::ImageWidth, In::PRIMARY) {
+        if let Some(field) = get_field(exif, Tag::ImageWidth) {
             metadata.width = get_u32_value(&field.value);
         }
     }
     if metadata.height.is_none() {
-        if let Some(field) = exif_reader.get_field(Tag::ImageLength, In::PRIMARY) {
+        if let Some(field) = get_field(exif, Tag::ImageLength) {
             metadata.height = get_u32_value(&field.value);
         }
     }
 
     // Extract camera make
-    if let Some(field) = exif_reader.get_field(Tag::Make, In::PRIMARY) {
+    if let Some(field) = get_field(exif, Tag::Make) {
         metadata.camera_make = get_string_value(&field.value);
     }
 
     // Extract camera model
-    if let Some(field) = exif_reader.get_field(Tag::Model, In::PRIMARY) {
+    if let Some(field) = get_field(exif, Tag::Model) {
         metadata.camera_model = get_string_value(&field.value);
     }
 
     // Extract orientation
-    if let Some(field) = exif_reader.get_field(Tag::Orientation, In::PRIMARY) {
+    if let Some(field) = get_field(exif, Tag::Orientation) {
         if let Value::Short(ref vec) = field.value {
             metadata.orientation = vec.first().copied();
         }
     }
 
     // Extract software
-    if let Some(field) = exif_reader.get_field(Tag::Software, In::PRIMARY) {
+    if let Some(field) = get_field(exif, Tag::Software) {
         metadata.software = get_string_value(&field.value);
     }
 
+    // Extract GPS position
+    let coordinate = |value_tag, ref_tag, negative: &str| {
+        let field = get_field(exif, value_tag)?;
+        let degrees = get_degrees(&field.value)?;
+        let reference = get_field(exif, ref_tag).and_then(|f| get_string_value(&f.value));
+        Some(if reference.as_deref() == Some(negative) {
+            -degrees
+        } else {
+            degrees
+        })
+    };
+    if let (Some(lat), Some(lon)) = (
+        coordinate(Tag::GPSLatitude, Tag::GPSLatitudeRef, "S"),
+        coordinate(Tag::GPSLongitude, Tag::GPSLongitudeRef, "W"),
+    ) {
+        metadata.gps = Some((lat, lon));
+    }
+
     metadata
 }
 
+/// Read only the EXIF orientation tag (1-8) from a photo file.
+///
+/// Cheaper to call than [`extract_metadata`] when decoding, since it skips
+/// parsing the other fields.
+pub fn read_orientation(path: &Path) -> Option<u16> {
+    let exif = load_exif(path);
+
+    let field = get_field(&exif, Tag::Orientation)?;
+    match field.value {
+        Value::Short(ref vec) => vec.first().copied(),
+        _ => None,
+    }
+}
+
+/// Read the EXIF data of a photo file
+///
+/// Usually one block, straight from the container. JPEG XL and RAW files
+/// the EXIF reader doesn't understand are searched for EXIF blocks, and RAW
+/// files can yield several, in priority order.
+fn load_exif(path: &Path) -> Vec<Exif> {
+    let from_container = File::open(path).ok().and_then(|file| {
+        Reader::new()
+            .read_from_container(&mut BufReader::new(&file))
+            .ok()
+    });
+    if let Some(exif) = from_container {
+        return vec![exif];
+    }
+
+    if !matches!(
+        ImageFormat::from_path(path),
+        ImageFormat::Raw | ImageFormat::Jxl
+    ) {
+        return Vec::new();
+    }
+    let Ok(bytes) = std::fs::read(path) else {
+        return Vec::new();
+    };
+    search_exif_blocks(path, &bytes)
+}
+
+/// Read the EXIF data of a photo from its contents; see [`load_exif`]
+fn load_exif_from_bytes(path: &Path, bytes: &[u8]) -> Vec<Exif> {
+    if let Ok(exif) = Reader::new().read_from_container(&mut Cursor::new(bytes)) {
+        return vec![exif];
+    }
+    search_exif_blocks(path, bytes)
+}
+
+/// Find EXIF blocks in JPEG XL and RAW files the EXIF reader doesn't parse
+fn search_exif_blocks(path: &Path, bytes: &[u8]) -> Vec<Exif> {
+    let blocks = match ImageFormat::from_path(path) {
+        ImageFormat::Jxl => jxl::jxl_exif(bytes).map(|b| vec![b.to_vec()]),
+        ImageFormat::Raw => Some(exif_blocks(bytes)),
+        _ => None,
+    };
+    blocks
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|block| Reader::new().read_raw(block).ok())
+        .collect()
+}
+
+/// Look up a primary-image field in the first EXIF block that has it
+fn get_field(exif: &[Exif], tag: Tag) -> Option<&Field> {
+    exif.iter().find_map(|e| e.get_field(tag, In::PRIMARY))
+}
+
 /// Helper to extract u32 from various EXIF value types
 fn get_u32_value(value: &Value) -> Option<u32> {
     match value {
@@ -169,6 +355,16 @@ fn get_u32_value(value: &Value) -> Option<u32> {
     }
 }
 
+/// Helper to convert EXIF degrees/minutes/seconds rationals to degrees
+fn get_degrees(value: &Value) -> Option<f64> {
+    match value {
+        Value::Rational(vec) if vec.len() >= 3 => {
+            Some(vec[0].to_f64() + vec[1].to_f64() / 60.0 + vec[2].to_f64() / 3600.0)
+        }
+        _ => None,
+    }
+}
+
 /// Helper to extract string from EXIF ASCII value
 fn get_string_value(value: &Value) -> Option<String> {
     if let Value::Ascii(ref vec) = value {
@@ -243,6 +439,81 @@ mod tests {
         assert!(!meta.has_data());
     }
 
+    #[test]
+    fn read_orientation_from_nonexistent_is_none() {
+        assert_eq!(read_orientation(Path::new("/nonexistent/file.jpg")), None);
+    }
+
+    #[test]
+    fn raw_exif_is_read_from_non_tiff_containers() {
+        use crate::core::raw::test_tiff::{ascii, short, tiff, ASCII, SHORT};
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("P1010001.ORF");
+        let mut orf = tiff(&[(0x010F, ASCII, ascii("OLYMPUS")), (0x0112, SHORT, short(8))]);
+        orf[2..4].copy_from_slice(b"RO");
+        std::fs::write(&path, orf).unwrap();
+
+        assert_eq!(
+            extract_metadata(&path).camera_make.as_deref(),
+            Some("OLYMPUS")
+        );
+        assert_eq!(read_orientation(&path), Some(8));
+    }
+
+    #[test]
+    fn jxl_exif_is_read_from_its_box() {
+        use crate::core::raw::test_tiff::{ascii, tiff, ASCII};
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("export.jxl");
+        let mut exif_box = 0u32.to_be_bytes().to_vec();
+        exif_box.extend_from_slice(&tiff(&[(0x0131, ASCII, ascii("Lightroom"))]));
+        let mut jxl = b"\0\0\0\x0cJXL \r\n\x87\n".to_vec();
+        jxl.extend_from_slice(&((exif_box.len() + 8) as u32).to_be_bytes());
+        jxl.extend_from_slice(b"Exif");
+        jxl.extend_from_slice(&exif_box);
+        std::fs::write(&path, jxl).unwrap();
+
+        assert_eq!(
+            extract_metadata(&path).software.as_deref(),
+            Some("Lightroom")
+        );
+    }
+
+    #[test]
+    fn differences_list_changed_fields() {
+        let original = PhotoMetadata {
+            camera_model: Some("EOS R5".to_string()),
+            gps: Some((51.5, -0.125)),
+            ..Default::default()
+        };
+        let mut stripped = original.clone();
+        stripped.gps = None;
+        stripped.software = Some("Photos".to_string());
+
+        let differences = original.differences(&stripped);
+
+        let fields: Vec<&str> = differences.iter().map(|d| d.field.as_str()).collect();
+        assert_eq!(fields, vec!["Software", "GPS position"]);
+        assert_eq!(
+            differences[1].to_string(),
+            "GPS position: 51.50000, -0.12500 vs none"
+        );
+        assert!(original.differences(&original).is_empty());
+    }
+
+    #[test]
+    fn degrees_from_rationals() {
+        let value = Value::Rational(vec![
+            exif::Rational { num: 51, denom: 1 },
+            exif::Rational { num: 30, denom: 1 },
+            exif::Rational { num: 36, denom: 1 },
+        ]);
+        let degrees = get_degrees(&value).unwrap();
+        assert!((degrees - 51.51).abs() < 1e-9);
+    }
+
     #[test]
     fn metadata_with_software_has_data() {
         let mut meta = PhotoMetadata::default();
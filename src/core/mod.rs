@@ -3,6 +3,7 @@
 //! The GUI-agnostic duplicate detection engine.
 //!
 //! ## Modules
+//! - `analysis` - Decodes each photo once for hash, quality and metadata
 //! - `scanner` - Discovers photos in directories
 //! - `hasher` - Computes perceptual hashes
 //! - `comparator` - Finds duplicates by comparing hashes
@@ -11,6 +12,7 @@
 //! - `pipeline` - Orchestrates the full workflow
 //! - `metadata` - Extracts EXIF metadata from photos
 //! - `quality` - Analyzes image quality (sharpness, contrast)
+//! - `raw` - Camera RAW previews, EXIF and demosaicing
 //! - `watcher` - Monitors folders for file changes
 //! - `screenshot` - Detects screenshots using multiple methods
 //! - `large_files` - Finds large files for disk space cleanup
@@ -19,6 +21,7 @@
 //! - `similar` - Finds perceptually similar (not exact duplicate) photos
 //! - `history` - Stores and retrieves scan history
 
+pub mod analysis;
 pub mod cache;
 pub mod comparator;
 pub mod hasher;
@@ -28,6 +31,7 @@ pub mod metadata;
 pub mod organize;
 pub mod pipeline;
 pub mod quality;
+pub mod raw;
 pub mod reporter;
 pub mod scanner;
 pub mod screenshot;
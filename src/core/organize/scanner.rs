@@ -79,8 +79,9 @@ impl OrganizeScanner {
     /// Check if file is a supported media type
     pub fn is_media_file(path: &Path) -> bool {
         const MEDIA_EXTENSIONS: &[&str] = &[
-            "jpg", "jpeg", "png", "gif", "webp", "bmp", "tiff", "tif", "heic", "heif", "raw",
-            "cr2", "nef", "dng", "arw", "raf", "mp4", "mov", "avi", "mkv", "wmv", "webm", "m4v",
+            "jpg", "jpeg", "png", "gif", "webp", "bmp", "tiff", "tif", "heic", "heif", "avif",
+            "jxl", "raw", "cr2", "cr3", "nef", "dng", "arw", "raf", "orf", "mp4", "mov", "avi",
+            "mkv", "wmv", "webm", "m4v",
         ];
 
         path.extension()
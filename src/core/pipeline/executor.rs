@@ -1,26 +1,44 @@
 //! Pipeline execution implementation.
 
-use super::optimization::{prefilter_candidates, OptimizationConfig};
-use crate::core::cache::{CacheBackend, CacheEntry, InMemoryCache};
+use super::hardlinks::{collapse_hardlinks, hardlink_groups};
+use super::identical::{
+    find_identical_files, find_pixel_identical, identical_matches, in_same_set, same_pixel_matches,
+    set_index,
+};
+use super::optimization::{
+    prefilter_candidates, DetectionMode, OptimizationConfig, PreviewStats, ScanMode,
+    TwoPhaseHasher, TwoPhaseStats,
+};
+use crate::core::analysis::{PhotoAnalyzer, PhotoDetails};
+use crate::core::cache::{CacheBackend, CacheEntry, InMemoryCache, MatchState};
 use crate::core::comparator::{
-    find_duplicate_pairs, find_duplicate_pairs_with_lsh, DuplicateGroup, LshConfig,
-    ThresholdStrategy, TransitiveGrouper,
+    compare_hashes, find_duplicate_pairs, find_duplicate_pairs_indexed,
+    find_duplicate_pairs_involving, find_transformed_pairs, hash_distance, measure_diameters,
+    DuplicateGroup, GroupingStrategy, MatchResult, ThresholdStrategy,
 };
+use crate::core::hasher::fast_decode::FastDecoder;
 use crate::core::hasher::{
-    HashAlgorithm, HashAlgorithmKind, HasherConfig, ImageHashValue, PerceptualHash,
+    DihedralHasher, FusionHash, HashAlgorithm, HashAlgorithmKind, HashVariants, HasherConfig,
+    ImageHashValue, PerceptualHash, PixelHash, DEFAULT_HASH_SIZE,
+};
+use crate::core::raw::is_raw_path;
+use crate::core::scanner::{
+    find_format_mismatches, FormatMismatch, IncrementalScanner, ParallelScanner, PhotoFile,
+    PhotoScanner, ScanConfig, ScanLimits, WalkDirScanner,
 };
-use crate::core::scanner::{PhotoFile, PhotoScanner, ScanConfig, WalkDirScanner};
-use crate::error::DuplicateFinderError;
+use crate::error::{DuplicateFinderError, HashError};
 use crate::events::{
     null_sender, CompareEvent, Event, EventSender, HashEvent, HashProgress, PipelineEvent,
     PipelinePhase, PipelineSummary,
 };
+use image::DynamicImage;
 use rayon::prelude::*;
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Instant, SystemTime};
+use xxhash_rust::xxh3::xxh3_64;
 
 /// Cancellation token for stopping pipeline execution
 pub type CancellationToken = Arc<AtomicBool>;
@@ -29,9 +47,10 @@ pub type CancellationToken = Arc<AtomicBool>;
 // Constants
 // =============================================================================
 
-/// Threshold for using LSH acceleration instead of brute-force comparison.
-/// Collections larger than this use O(n log n) LSH; smaller use O(n²) pairwise.
-const LSH_THRESHOLD: usize = 500;
+/// Threshold for using the multi-index hash instead of brute-force comparison.
+/// Collections larger than this are indexed; smaller use O(n²) pairwise.
+/// Both paths find exactly the same pairs.
+const INDEX_THRESHOLD: usize = 500;
 
 /// Dynamic chunk sizes for batch cache operations.
 /// Larger scans benefit from larger chunks to reduce SQLite transaction overhead.
@@ -43,6 +62,16 @@ const CHUNK_XLARGE: usize = 1000; // 10000+ photos
 /// Result of the hashing phase
 struct HashingResult {
     hashes: Vec<(PathBuf, ImageHashValue)>,
+    /// Rotation and mirror variants (empty unless rotations are matched)
+    variants: Vec<(PathBuf, HashVariants)>,
+    /// Pixel hashes found in the cache
+    pixels: HashMap<PathBuf, PixelHash>,
+    /// Quality, dimensions and metadata of every fully decoded photo
+    details: HashMap<PathBuf, PhotoDetails>,
+    /// Photos hashed from their EXIF thumbnail (preview scans only)
+    thumbnails: HashSet<PathBuf>,
+    /// Photos decoded in full
+    decoded: usize,
     cache_hits: usize,
 }
 
@@ -50,26 +79,72 @@ struct HashingResult {
 struct SingleHashResult {
     path: PathBuf,
     hash: ImageHashValue,
+    variants: Option<HashVariants>,
+    /// Cached pixel hash, if one was stored
+    pixels: Option<PixelHash>,
+    /// Quality, dimensions and metadata (None if hashed from the thumbnail)
+    details: Option<PhotoDetails>,
+    /// Whether the hash came from the EXIF thumbnail
+    from_thumbnail: bool,
     /// Cache entry to save (None if it was a cache hit)
     cache_entry: Option<CacheEntry>,
 }
 
+/// Key a pair of paths the same way whichever order they come in
+fn pair_key(a: &Path, b: &Path) -> (PathBuf, PathBuf) {
+    if a <= b {
+        (a.to_path_buf(), b.to_path_buf())
+    } else {
+        (b.to_path_buf(), a.to_path_buf())
+    }
+}
+
 /// Build a lookup map of photo paths to their file sizes.
 fn build_photo_size_map(photos: &[PhotoFile]) -> HashMap<PathBuf, u64> {
     photos.iter().map(|p| (p.path.clone(), p.size)).collect()
 }
 
+/// Give byte-identical copies the hashes, variants and details of their
+/// original.
+///
+/// `originals` maps each copy to the file that was decoded in its place.
+fn share_with_copies(result: &mut HashingResult, originals: &HashMap<PathBuf, PathBuf>) {
+    if originals.is_empty() {
+        return;
+    }
+
+    let hashes: HashMap<PathBuf, ImageHashValue> = result.hashes.iter().cloned().collect();
+    let variants: HashMap<PathBuf, HashVariants> = result.variants.iter().cloned().collect();
+    let mut copies: Vec<_> = originals.iter().collect();
+    copies.sort();
+    for (copy, original) in copies {
+        if let Some(hash) = hashes.get(original) {
+            result.hashes.push((copy.clone(), hash.clone()));
+        }
+        if let Some(variants) = variants.get(original) {
+            result.variants.push((copy.clone(), variants.clone()));
+        }
+        if let Some(details) = result.details.get(original).cloned() {
+            result.details.insert(copy.clone(), details);
+        }
+    }
+}
+
 /// Calculate duplicate size savings for each group
+///
+/// Photos in `linked` have other hardlinks, so deleting them frees nothing
+/// and they don't count.
 fn calculate_group_savings(
     groups: &mut [DuplicateGroup],
     photo_sizes: &HashMap<PathBuf, u64>,
+    linked: &HashSet<&PathBuf>,
 ) -> u64 {
     let mut total_savings = 0u64;
     for group in groups.iter_mut() {
         let duplicate_size: u64 = group
             .photos
             .iter()
-            .filter(|p| *p != &group.representative)
+            .filter(|p| *p != &group.representative && !linked.contains(p))
             .filter_map(|p| photo_sizes.get(p))
             .sum();
         group.duplicate_size_bytes = duplicate_size;
@@ -80,16 +155,34 @@ fn calculate_group_savings(
 
 /// Select the best representative for each group based on quality heuristics.
 ///
-/// Strategy: Pick the largest file as it typically has the best quality/resolution.
-/// This is a fast heuristic that doesn't require expensive quality analysis.
-fn select_best_representatives(groups: &mut [DuplicateGroup], photo_sizes: &HashMap<PathBuf, u64>) {
+/// Strategy: Pick the highest resolution, then the best quality score, then
+/// the largest file. Resolution and quality come from the hashing decode, so
+/// this needs no extra I/O. Camera RAW originals win over rendered copies,
+/// whatever their size.
+fn select_best_representatives(
+    groups: &mut [DuplicateGroup],
+    photo_sizes: &HashMap<PathBuf, u64>,
+    details: &HashMap<PathBuf, PhotoDetails>,
+) {
+    let rank = |path: &PathBuf| {
+        let details = details.get(path);
+        (
+            details.map_or(0, PhotoDetails::pixels),
+            details.map_or(0.0, |d| d.quality.overall),
+            photo_sizes.get(path).copied().unwrap_or(0),
+        )
+    };
+
     for group in groups.iter_mut() {
-        // Find the photo with the largest file size
+        let has_raw = group.photos.iter().any(|p| is_raw_path(p));
+
         let best = group
             .photos
             .iter()
-            .max_by_key(|p| photo_sizes.get(*p).copied().unwrap_or(0))
-            .cloned();
+            .filter(|p| !has_raw || is_raw_path(p))
+            .map(|p| (p, rank(p)))
+            .max_by(|(_, a), (_, b)| a.0.cmp(&b.0).then(a.1.total_cmp(&b.1)).then(a.2.cmp(&b.2)))
+            .map(|(p, _)| p.clone());
 
         if let Some(best_photo) = best {
             group.representative = best_photo;
@@ -110,6 +203,50 @@ pub struct PipelineResult {
     pub errors: Vec<String>,
     /// Duration in milliseconds
     pub duration_ms: u64,
+    /// How much a preview scan relied on thumbnails (None for full scans)
+    pub preview: Option<PreviewStats>,
+    /// Quality, dimensions and metadata of every photo in a group
+    pub details: HashMap<PathBuf, PhotoDetails>,
+    /// Photos whose extension doesn't match their contents (only found
+    /// when the scan sniffs content)
+    pub format_mismatches: Vec<FormatMismatch>,
+    /// Hash algorithm the groups were found with
+    pub algorithm: HashAlgorithmKind,
+    /// Hash size the groups were found with; their distances are counted
+    /// in its size² bits
+    pub hash_size: u32,
+}
+
+impl PipelineResult {
+    /// Follow a photo that was renamed after the scan, such as by
+    /// [`rename_to_content_extension`](crate::core::scanner::rename_to_content_extension),
+    /// so its groups and details point at the new path
+    pub fn rename_photo(&mut self, from: &Path, to: &Path) {
+        let follow = |path: &mut PathBuf| {
+            if path == from {
+                *path = to.to_path_buf();
+            }
+        };
+        for group in &mut self.groups {
+            group.photos.iter_mut().for_each(follow);
+            follow(&mut group.representative);
+            if let Some(crop) = &mut group.crop {
+                follow(&mut crop.original);
+                follow(&mut crop.cropped);
+            }
+            if let Some(m) = &mut group.weakest_match {
+                follow(&mut m.photo_a);
+                follow(&mut m.photo_b);
+                if let Some(crop) = &mut m.crop {
+                    follow(&mut crop.original);
+                    follow(&mut crop.cropped);
+                }
+            }
+        }
+        if let Some(details) = self.details.remove(from) {
+            self.details.insert(to.to_path_buf(), details);
+        }
+    }
 }
 
 /// Configuration for the pipeline
@@ -119,8 +256,25 @@ pub struct PipelineConfig {
     pub paths: Vec<PathBuf>,
     /// Hash algorithm to use
     pub algorithm: HashAlgorithmKind,
-    /// Comparison threshold (lower = stricter)
+    /// Hash size (8, 16 or 32; the hash has size² bits)
+    pub hash_size: u32,
+    /// Comparison threshold on the 64-bit scale (lower = stricter)
     pub threshold: u32,
+    /// Exact-copy or perceptual detection
+    pub detection_mode: DetectionMode,
+    /// How matching pairs are clustered into groups
+    pub grouping: GroupingStrategy,
+    /// Also match rotated and mirrored copies
+    pub match_rotations: bool,
+    /// Find byte-identical copies from file contents before hashing, and
+    /// pixel-identical copies from their decoded pixels
+    pub byte_identical: bool,
+    /// Extra algorithms that vote alongside aHash, dHash and pHash in fusion
+    pub fusion_voters: Vec<HashAlgorithmKind>,
+    /// Decode every photo, or hash EXIF thumbnails where possible
+    pub scan_mode: ScanMode,
+    /// Pre-filters and two-phase hashing
+    pub optimization: OptimizationConfig,
     /// Scanner configuration
     pub scan_config: ScanConfig,
 }
@@ -130,7 +284,15 @@ impl Default for PipelineConfig {
         Self {
             paths: Vec::new(),
             algorithm: HashAlgorithmKind::Difference,
+            hash_size: DEFAULT_HASH_SIZE,
             threshold: 8,
+            detection_mode: DetectionMode::default(),
+            grouping: GroupingStrategy::default(),
+            match_rotations: false,
+            byte_identical: true,
+            fusion_voters: Vec::new(),
+            scan_mode: ScanMode::default(),
+            optimization: OptimizationConfig::default(),
             scan_config: ScanConfig::default(),
         }
     }
@@ -163,12 +325,103 @@ impl PipelineBuilder {
         self
     }
 
-    /// Set the comparison threshold
+    /// Set the hash size (8, 16 or 32).
+    ///
+    /// Larger hashes tell similar photos apart more reliably but take longer
+    /// to compare. The threshold is scaled to match, so it means the same at
+    /// every size.
+    pub fn hash_size(mut self, size: u32) -> Self {
+        self.config.hash_size = size;
+        self
+    }
+
+    /// Set the comparison threshold.
+    ///
+    /// Given for a 64-bit hash; larger hashes scale it by their bit count.
     pub fn threshold(mut self, threshold: u32) -> Self {
         self.config.threshold = threshold;
         self
     }
 
+    /// Set the detection mode.
+    ///
+    /// `Exact` only reports identical copies and skips files that cannot be
+    /// one (unique size or prefix); `Perceptual` hashes every photo.
+    pub fn detection_mode(mut self, mode: DetectionMode) -> Self {
+        self.config.detection_mode = mode;
+        self
+    }
+
+    /// Set how matching pairs are clustered into groups.
+    ///
+    /// `Transitive` (the default) chains matches, so A~B~C forms one group
+    /// even if A and C are far apart. The other strategies keep groups tight.
+    pub fn grouping(mut self, grouping: GroupingStrategy) -> Self {
+        self.config.grouping = grouping;
+        self
+    }
+
+    /// Also match rotated and mirrored copies.
+    ///
+    /// Photos are made upright using their EXIF orientation, then all eight
+    /// rotations and mirrors are compared. Matches found this way report
+    /// their transform. Off by default since it hashes every photo again.
+    pub fn match_rotations(mut self, enabled: bool) -> Self {
+        self.config.match_rotations = enabled;
+        self
+    }
+
+    /// Find byte-identical copies from file contents (on by default).
+    ///
+    /// Files with the same size are hashed in full; matching contents are
+    /// reported as [`MatchType::ByteIdentical`] and only one of each set is
+    /// decoded for perceptual hashing. Files whose bytes differ but whose
+    /// pixels are the same are reported as
+    /// [`MatchType::SamePixelsDifferentMetadata`]; without the byte pass the
+    /// two can't be told apart, so both are off together.
+    ///
+    /// [`MatchType::ByteIdentical`]: crate::core::comparator::MatchType::ByteIdentical
+    /// [`MatchType::SamePixelsDifferentMetadata`]: crate::core::comparator::MatchType::SamePixelsDifferentMetadata
+    pub fn byte_identical(mut self, enabled: bool) -> Self {
+        self.config.byte_identical = enabled;
+        self
+    }
+
+    /// Add extra voters to the fusion algorithm.
+    ///
+    /// Fusion then needs a majority of all its voters to agree, instead of
+    /// two of three. Only `Wavelet` and `BlockMean` can vote; building the
+    /// hasher fails for anything else.
+    pub fn fusion_voters(mut self, voters: Vec<HashAlgorithmKind>) -> Self {
+        self.config.fusion_voters = voters;
+        self
+    }
+
+    /// Set the scan mode.
+    ///
+    /// A `Preview` scan hashes each photo's EXIF thumbnail instead of
+    /// decoding it, which is much faster on large libraries. Photos without
+    /// a thumbnail are decoded as usual, and any photo that matches another
+    /// is decoded and rehashed before it is reported, so thumbnails only
+    /// decide which photos need a closer look. Copies whose thumbnails
+    /// differ more than the photos do can be missed.
+    pub fn scan_mode(mut self, mode: ScanMode) -> Self {
+        self.config.scan_mode = mode;
+        self
+    }
+
+    /// Set the optimization strategies.
+    ///
+    /// With `two_phase_hash`, scans of at least `min_photos_threshold`
+    /// photos give every photo a cheap aHash first and hash only the photos
+    /// with a candidate match using the configured algorithm. Rotation
+    /// matching, crop-resistant hashing and preview scans always hash every
+    /// photo, since a global aHash can't screen for those.
+    pub fn optimization(mut self, config: OptimizationConfig) -> Self {
+        self.config.optimization = config;
+        self
+    }
+
     /// Set the cache backend
     pub fn cache(mut self, cache: Box<dyn CacheBackend>) -> Self {
         self.cache = Some(cache);
@@ -187,6 +440,44 @@ impl PipelineBuilder {
         self
     }
 
+    /// Skip files and directories matching these gitignore-style patterns
+    pub fn exclude(mut self, patterns: Vec<String>) -> Self {
+        self.config.scan_config.exclude = patterns;
+        self
+    }
+
+    /// Only scan photos matching one of these gitignore-style patterns
+    pub fn include(mut self, patterns: Vec<String>) -> Self {
+        self.config.scan_config.include = patterns;
+        self
+    }
+
+    /// Classify and decode photos by their magic bytes instead of their
+    /// extension, and report the mislabeled ones
+    pub fn sniff_content(mut self, enabled: bool) -> Self {
+        self.config.scan_config.sniff_content = enabled;
+        self
+    }
+
+    /// Read directories on all cores while scanning
+    pub fn parallel_scan(mut self, enabled: bool) -> Self {
+        self.config.scan_config.parallel = enabled;
+        self
+    }
+
+    /// Only scan photos within these size, date and dimension limits
+    pub fn limits(mut self, limits: ScanLimits) -> Self {
+        self.config.scan_config.limits = limits;
+        self
+    }
+
+    /// Skip directories and reuse matches that haven't changed since the
+    /// last incremental scan with this cache
+    pub fn incremental(mut self, enabled: bool) -> Self {
+        self.config.scan_config.incremental = enabled;
+        self
+    }
+
     /// Build the pipeline
     pub fn build(self) -> Pipeline {
         Pipeline {
@@ -266,15 +557,18 @@ impl Pipeline {
         );
 
         let total_photos = photos.len();
-        let hasher = HasherConfig::new()
-            .algorithm(self.config.algorithm)
-            .build()?;
+        let hasher = self.build_hasher()?;
 
         let cache_hits = AtomicUsize::new(0);
         let completed = AtomicUsize::new(0);
         let events_arc = Arc::new(events.clone());
 
         let mut all_hashes: Vec<(PathBuf, ImageHashValue)> = Vec::with_capacity(total_photos);
+        let mut all_variants: Vec<(PathBuf, HashVariants)> = Vec::new();
+        let mut all_pixels: HashMap<PathBuf, PixelHash> = HashMap::new();
+        let mut all_details: HashMap<PathBuf, PhotoDetails> = HashMap::new();
+        let mut thumbnails: HashSet<PathBuf> = HashSet::new();
+        let mut decoded = 0;
 
         // Process photos in chunks for incremental cache durability
         for chunk in photos.chunks(chunk_size) {
@@ -323,15 +617,181 @@ impl Pipeline {
             }
 
             // Collect hashes from this chunk
-            all_hashes.extend(results.into_iter().map(|r| (r.path, r.hash)));
+            for result in results {
+                if result.from_thumbnail {
+                    thumbnails.insert(result.path.clone());
+                } else if result.cache_entry.is_some() {
+                    decoded += 1;
+                }
+                if let Some(variants) = result.variants {
+                    all_variants.push((result.path.clone(), variants));
+                }
+                if let Some(pixels) = result.pixels {
+                    all_pixels.insert(result.path.clone(), pixels);
+                }
+                if let Some(details) = result.details {
+                    all_details.insert(result.path.clone(), details);
+                }
+                all_hashes.push((result.path, result.hash));
+            }
         }
 
         Ok(HashingResult {
             hashes: all_hashes,
+            variants: all_variants,
+            pixels: all_pixels,
+            details: all_details,
+            thumbnails,
+            decoded,
             cache_hits: cache_hits.load(Ordering::SeqCst),
         })
     }
 
+    /// Build the configured hasher
+    fn build_hasher(&self) -> Result<Box<dyn HashAlgorithm>, HashError> {
+        HasherConfig::new()
+            .algorithm(self.config.algorithm)
+            .hash_size(self.config.hash_size)
+            .fusion_voters(self.config.fusion_voters.clone())
+            .build()
+    }
+
+    /// Whether two-phase hashing applies to a run hashing this many photos.
+    ///
+    /// Rotated copies and crops don't match by a global aHash, preview scans
+    /// already screen with thumbnails, and screening with aHash saves nothing
+    /// when aHash is the configured algorithm.
+    fn uses_two_phase(&self, photos: usize) -> bool {
+        let optimization = &self.config.optimization;
+        optimization.two_phase_hash
+            && photos >= optimization.min_photos_threshold
+            && self.config.scan_mode == ScanMode::Full
+            && !self.config.match_rotations
+            && !matches!(
+                self.config.algorithm,
+                HashAlgorithmKind::Average | HashAlgorithmKind::CropResistant
+            )
+    }
+
+    /// Phase 1 of two-phase hashing: keep only the photos whose cheap aHash
+    /// is within a loose threshold of another photo's, plus those in `keep`.
+    ///
+    /// Screening hashes are cached as 8x8 aHash entries without details, so
+    /// later scans screen without decoding. Photos that can't be screened
+    /// are kept, for phase 2 to report.
+    fn screen_photos(
+        &self,
+        photos: Vec<PhotoFile>,
+        keep: &HashSet<&PathBuf>,
+        events: &EventSender,
+        cancel_token: Option<&CancellationToken>,
+    ) -> Result<(Vec<PhotoFile>, TwoPhaseStats), DuplicateFinderError> {
+        let screener = TwoPhaseHasher::new(self.config.threshold);
+        let cache_hits = AtomicUsize::new(0);
+        let mut hashes: Vec<(PathBuf, ImageHashValue)> = Vec::with_capacity(photos.len());
+        let mut unscreened: HashSet<PathBuf> = HashSet::new();
+
+        for chunk in photos.chunks(Self::calculate_chunk_size(photos.len())) {
+            if let Some(token) = cancel_token {
+                if token.load(Ordering::SeqCst) {
+                    events.send(Event::Pipeline(PipelineEvent::Cancelled));
+                    return Err(DuplicateFinderError::Cancelled);
+                }
+            }
+
+            let results: Vec<_> = chunk
+                .par_iter()
+                .map(|photo| {
+                    let cached = self
+                        .cache
+                        .get(
+                            &photo.path,
+                            HashAlgorithmKind::Average,
+                            DEFAULT_HASH_SIZE,
+                            photo.size,
+                            photo.modified,
+                        )
+                        .ok()
+                        .flatten();
+                    match cached {
+                        Some(entry) => {
+                            cache_hits.fetch_add(1, Ordering::SeqCst);
+                            let hash = ImageHashValue::from_bytes(&entry.hash, entry.algorithm);
+                            (photo, Some(hash), false)
+                        }
+                        None => (photo, screener.screen_hash(&photo.path).ok(), true),
+                    }
+                })
+                .collect();
+
+            let cache_entries: Vec<CacheEntry> = results
+                .iter()
+                .filter(|(_, _, computed)| *computed)
+                .filter_map(|(photo, hash, _)| {
+                    let hash = hash.as_ref()?;
+                    Some(CacheEntry {
+                        path: photo.path.clone(),
+                        hash: hash.as_bytes().to_vec(),
+                        algorithm: HashAlgorithmKind::Average,
+                        hash_size: DEFAULT_HASH_SIZE,
+                        file_size: photo.size,
+                        file_modified: photo.modified,
+                        cached_at: SystemTime::now(),
+                        pixel_hash: None,
+                        details: None,
+                        variants: None,
+                    })
+                })
+                .collect();
+            if !cache_entries.is_empty() {
+                if let Err(e) = self.cache.set_batch(&cache_entries) {
+                    events.send(Event::Hash(HashEvent::Error {
+                        path: PathBuf::from("cache"),
+                        message: format!(
+                            "Failed to write {} screening hashes to cache: {}",
+                            cache_entries.len(),
+                            e
+                        ),
+                    }));
+                }
+            }
+
+            for (photo, hash, _) in results {
+                match hash {
+                    Some(hash) => hashes.push((photo.path.clone(), hash)),
+                    None => {
+                        unscreened.insert(photo.path.clone());
+                    }
+                }
+            }
+        }
+
+        let matched = screener.find_candidates(&hashes);
+        let total = photos.len();
+        let candidates: Vec<PhotoFile> = photos
+            .into_iter()
+            .filter(|photo| {
+                matched.contains(&photo.path)
+                    || keep.contains(&photo.path)
+                    || unscreened.contains(&photo.path)
+            })
+            .collect();
+        tracing::debug!(
+            "Two-phase screening kept {} of {} photos",
+            candidates.len(),
+            total
+        );
+
+        let stats = TwoPhaseStats {
+            screened: hashes.len(),
+            screen_cache_hits: cache_hits.load(Ordering::SeqCst),
+            candidates: candidates.len(),
+            skipped: total - candidates.len(),
+            threshold: screener.threshold(),
+        };
+        Ok((candidates, stats))
+    }
+
     /// Hash a single photo, checking cache first.
     ///
     /// Returns the hash and optionally a cache entry to be batch-written later.
@@ -346,7 +806,17 @@ impl Pipeline {
         events: &Arc<EventSender>,
     ) -> Option<SingleHashResult> {
         // Check cache first
-        if let Ok(Some(entry)) = self.cache.get(&photo.path, photo.size, photo.modified) {
+        if let Ok(Some(entry)) = self
+            .cache
+            .get(
+                &photo.path,
+                self.config.algorithm,
+                self.config.hash_size,
+                photo.size,
+                photo.modified,
+            )
+            .map(|entry| entry.filter(|e| self.is_complete(e)))
+        {
             let hits = cache_hits.fetch_add(1, Ordering::SeqCst) + 1;
             // Increment completed AFTER work is done (for accurate progress)
             let current_completed = completed.fetch_add(1, Ordering::SeqCst) + 1;
@@ -365,22 +835,23 @@ impl Pipeline {
             return Some(SingleHashResult {
                 path: photo.path.clone(),
                 hash: ImageHashValue::from_bytes(&entry.hash, entry.algorithm),
+                variants: entry.variants.filter(|_| self.config.match_rotations),
+                pixels: entry.pixel_hash.as_deref().and_then(PixelHash::from_bytes),
+                details: entry.details,
+                from_thumbnail: false,
                 cache_entry: None, // Already in cache
             });
         }
 
         // Compute hash
-        match hasher.hash_file(&photo.path) {
-            Ok(hash) => {
-                // Create cache entry (will be batch-written later)
-                let cache_entry = CacheEntry {
-                    path: photo.path.clone(),
-                    hash: hash.as_bytes().to_vec(),
-                    algorithm: self.config.algorithm,
-                    file_size: photo.size,
-                    file_modified: photo.modified,
-                    cached_at: SystemTime::now(),
-                };
+        match self.compute_scan_hash(&photo.path, hasher) {
+            Ok((hash, variants, details)) => {
+                // Create cache entry (will be batch-written later); thumbnail
+                // hashes are only approximations, so they aren't cached
+                let from_thumbnail = details.is_none();
+                let cache_entry = details
+                    .as_ref()
+                    .map(|details| self.cache_entry(photo, &hash, variants.as_ref(), details));
 
                 // Increment completed AFTER work is done (for accurate progress)
                 let current_completed = completed.fetch_add(1, Ordering::SeqCst) + 1;
@@ -395,7 +866,11 @@ impl Pipeline {
                 Some(SingleHashResult {
                     path: photo.path.clone(),
                     hash,
-                    cache_entry: Some(cache_entry),
+                    variants,
+                    pixels: None,
+                    details,
+                    from_thumbnail,
+                    cache_entry,
                 })
             }
             Err(e) => {
@@ -416,6 +891,481 @@ impl Pipeline {
         }
     }
 
+    /// Check that a cache entry has everything this scan needs: the details,
+    /// the variants when rotations are matched, and the current voters
+    fn is_complete(&self, entry: &CacheEntry) -> bool {
+        entry.details.is_some()
+            && (!self.config.match_rotations || entry.variants.is_some())
+            && self.has_current_voters(entry)
+    }
+
+    /// Check that a cached fusion hash was computed with the configured
+    /// voters; other algorithms always match.
+    fn has_current_voters(&self, entry: &CacheEntry) -> bool {
+        entry.algorithm != HashAlgorithmKind::Fusion
+            || FusionHash::from_bytes(&entry.hash)
+                .is_some_and(|fusion| fusion.extra_kinds() == self.config.fusion_voters)
+    }
+
+    /// Create a cache entry for a freshly computed hash
+    fn cache_entry(
+        &self,
+        photo: &PhotoFile,
+        hash: &ImageHashValue,
+        variants: Option<&HashVariants>,
+        details: &PhotoDetails,
+    ) -> CacheEntry {
+        CacheEntry {
+            path: photo.path.clone(),
+            hash: hash.as_bytes().to_vec(),
+            algorithm: self.config.algorithm,
+            hash_size: self.config.hash_size,
+            file_size: photo.size,
+            file_modified: photo.modified,
+            cached_at: SystemTime::now(),
+            pixel_hash: None,
+            details: Some(details.clone()),
+            variants: variants.cloned(),
+        }
+    }
+
+    /// Hash a photo for the scan: from its EXIF thumbnail in a preview scan
+    /// when it has one, otherwise from the photo.
+    ///
+    /// Details are only returned for full decodes, so their absence means
+    /// the thumbnail was used.
+    fn compute_scan_hash(
+        &self,
+        path: &Path,
+        hasher: &dyn HashAlgorithm,
+    ) -> Result<(ImageHashValue, Option<HashVariants>, Option<PhotoDetails>), HashError> {
+        if self.config.scan_mode == ScanMode::Preview {
+            if let Some(thumbnail) = FastDecoder::decode_thumbnail(path) {
+                let (hash, variants) = self.hash_image(&thumbnail, hasher)?;
+                return Ok((hash, variants, None));
+            }
+        }
+        let (hash, variants, details) = self.compute_hash(path, hasher)?;
+        Ok((hash, variants, Some(details)))
+    }
+
+    /// Hash a photo, plus its rotation and mirror variants when enabled,
+    /// and analyze its quality, dimensions and metadata.
+    ///
+    /// The file is read and decoded once, at hashing size, for all of them.
+    fn compute_hash(
+        &self,
+        path: &Path,
+        hasher: &dyn HashAlgorithm,
+    ) -> Result<(ImageHashValue, Option<HashVariants>, PhotoDetails), HashError> {
+        let analysis = PhotoAnalyzer::new(hasher)
+            .match_rotations(self.config.match_rotations)
+            .sniff_content(self.config.scan_config.sniff_content)
+            .analyze_file(path)?;
+        Ok((analysis.hash, analysis.variants, analysis.details))
+    }
+
+    /// Hash a decoded image, plus its rotation and mirror variants when enabled
+    fn hash_image(
+        &self,
+        image: &DynamicImage,
+        hasher: &dyn HashAlgorithm,
+    ) -> Result<(ImageHashValue, Option<HashVariants>), HashError> {
+        let hash = hasher.hash_image(image)?;
+        let variants = if self.config.match_rotations {
+            Some(DihedralHasher::new(hasher).hash_upright(image)?)
+        } else {
+            None
+        };
+        Ok((hash, variants))
+    }
+
+    /// Find every matching pair, including rotated and mirrored copies when
+    /// enabled.
+    fn find_matches(
+        &self,
+        hashes: &[(PathBuf, ImageHashValue)],
+        variants: &[(PathBuf, HashVariants)],
+        strategy: &ThresholdStrategy,
+    ) -> Vec<MatchResult> {
+        // Use a multi-index hash for large collections (>500 photos)
+        // Unlike LSH, this never misses a pair within the threshold
+        let mut matches = if hashes.len() > INDEX_THRESHOLD {
+            find_duplicate_pairs_indexed(hashes, strategy)
+        } else {
+            find_duplicate_pairs(hashes, strategy)
+        };
+
+        // Add rotated and mirrored copies that didn't already match upright
+        if self.config.match_rotations {
+            let matched: HashSet<_> = matches
+                .iter()
+                .map(|m| pair_key(&m.photo_a, &m.photo_b))
+                .collect();
+            let transformed = find_transformed_pairs(variants, strategy);
+            matches.extend(
+                transformed
+                    .into_iter()
+                    .filter(|m| !matched.contains(&pair_key(&m.photo_a, &m.photo_b))),
+            );
+        }
+
+        matches
+    }
+
+    /// Whether matches are kept between incremental scans.
+    ///
+    /// Preview scans rehash their matches, and rotated copies are found from
+    /// hash variants that aren't kept, so both compare everything.
+    fn reuses_matches(&self) -> bool {
+        self.config.scan_config.incremental
+            && self.config.scan_mode == ScanMode::Full
+            && !self.config.match_rotations
+    }
+
+    /// Key of the settings that decide which photos match
+    fn match_settings(&self) -> String {
+        format!(
+            "{:?};size={};threshold={};{:?};voters={:?}",
+            self.config.algorithm,
+            self.config.hash_size,
+            self.config.threshold,
+            self.config.detection_mode,
+            self.config.fusion_voters
+        )
+    }
+
+    /// Find matches like [`Self::find_matches`], reusing those of the last
+    /// scan with the same settings.
+    ///
+    /// Matches between photos whose hashes haven't changed are kept, and only
+    /// new and changed photos are compared, against every photo. The matches
+    /// are then stored for the next scan.
+    fn find_matches_incremental(
+        &self,
+        hashes: &[(PathBuf, ImageHashValue)],
+        strategy: &ThresholdStrategy,
+    ) -> Vec<MatchResult> {
+        let settings = self.match_settings();
+        let previous = self.cache.get_match_state(&settings).unwrap_or_else(|e| {
+            tracing::warn!("Failed to read previous matches: {}", e);
+            None
+        });
+        let (previous_photos, previous_matches): (HashMap<PathBuf, u64>, Vec<MatchResult>) =
+            previous
+                .map(|state| (state.photos.into_iter().collect(), state.matches))
+                .unwrap_or_default();
+
+        let fingerprints: Vec<u64> = hashes
+            .iter()
+            .map(|(_, hash)| xxh3_64(hash.as_bytes()))
+            .collect();
+        let changed: Vec<bool> = hashes
+            .iter()
+            .zip(&fingerprints)
+            .map(|((path, _), fingerprint)| previous_photos.get(path) != Some(fingerprint))
+            .collect();
+        let positions: HashMap<&Path, usize> = hashes
+            .iter()
+            .enumerate()
+            .map(|(i, (path, _))| (path.as_path(), i))
+            .collect();
+
+        // Matches are ordered by photo position, as a full comparison gives
+        // them; pairs stored the other way round are compared again, since
+        // crop matches depend on which photo comes first
+        let mut matches: Vec<((usize, usize), MatchResult)> = Vec::new();
+        for m in previous_matches {
+            let (Some(&a), Some(&b)) = (
+                positions.get(m.photo_a.as_path()),
+                positions.get(m.photo_b.as_path()),
+            ) else {
+                continue;
+            };
+            if changed[a] || changed[b] {
+                continue;
+            }
+            if a < b {
+                matches.push(((a, b), m));
+            } else if let Some(m) = compare_hashes(
+                &hashes[b].0,
+                &hashes[b].1,
+                &hashes[a].0,
+                &hashes[a].1,
+                strategy,
+            ) {
+                matches.push(((b, a), m));
+            }
+        }
+        let reused = matches.len();
+
+        matches.extend(
+            find_duplicate_pairs_involving(hashes, &changed, strategy)
+                .into_iter()
+                .map(|m| {
+                    let key = (
+                        positions[m.photo_a.as_path()],
+                        positions[m.photo_b.as_path()],
+                    );
+                    (key, m)
+                }),
+        );
+        matches.sort_by_key(|(key, _)| *key);
+        let matches: Vec<MatchResult> = matches.into_iter().map(|(_, m)| m).collect();
+
+        tracing::info!(
+            "Reused {} matches, compared {} new or changed of {} photos",
+            reused,
+            changed.iter().filter(|&&c| c).count(),
+            hashes.len()
+        );
+
+        let state = MatchState {
+            settings,
+            photos: hashes
+                .iter()
+                .map(|(path, _)| path.clone())
+                .zip(fingerprints)
+                .collect(),
+            matches: matches.clone(),
+        };
+        if let Err(e) = self.cache.set_match_state(&state) {
+            tracing::warn!("Failed to store matches: {}", e);
+        }
+
+        matches
+    }
+
+    /// Confirm the matches of a preview scan by decoding the photos that
+    /// were only hashed from their thumbnail.
+    ///
+    /// Every thumbnail-hashed photo in a match is decoded and rehashed, and
+    /// the matches are found again. Its new hash can match other
+    /// thumbnail-hashed photos, so this repeats until every match is between
+    /// fully hashed photos. Copies share their original's new hash.
+    #[allow(clippy::too_many_arguments)]
+    fn confirm_preview_matches(
+        &self,
+        mut matches: Vec<MatchResult>,
+        hash_result: &mut HashingResult,
+        hashes: &mut Vec<(PathBuf, ImageHashValue)>,
+        photos: &[PhotoFile],
+        originals: &HashMap<PathBuf, PathBuf>,
+        strategy: &ThresholdStrategy,
+        events: &EventSender,
+        cancel_token: Option<&CancellationToken>,
+    ) -> Result<(Vec<MatchResult>, PreviewStats), DuplicateFinderError> {
+        let source = |path: &PathBuf| originals.get(path).unwrap_or(path).clone();
+        let pair_keys = |matches: &[MatchResult]| -> HashSet<(PathBuf, PathBuf)> {
+            matches
+                .iter()
+                .map(|m| pair_key(&m.photo_a, &m.photo_b))
+                .collect()
+        };
+
+        let hasher = self.build_hasher()?;
+        let photos: HashMap<&Path, &PhotoFile> =
+            photos.iter().map(|p| (p.path.as_path(), p)).collect();
+        let initial = pair_keys(&matches);
+        let mut stats = PreviewStats {
+            no_thumbnail: hash_result.decoded,
+            ..PreviewStats::default()
+        };
+
+        loop {
+            let mut pending: Vec<PathBuf> = matches
+                .iter()
+                .flat_map(|m| [source(&m.photo_a), source(&m.photo_b)])
+                .filter(|path| hash_result.thumbnails.contains(path))
+                .collect();
+            pending.sort();
+            pending.dedup();
+            if pending.is_empty() {
+                break;
+            }
+
+            if let Some(token) = cancel_token {
+                if token.load(Ordering::SeqCst) {
+                    events.send(Event::Pipeline(PipelineEvent::Cancelled));
+                    return Err(DuplicateFinderError::Cancelled);
+                }
+            }
+
+            let rehashed: Vec<_> = pending
+                .into_par_iter()
+                .map(|path| {
+                    let result = self.compute_hash(&path, hasher.as_ref());
+                    (path, result)
+                })
+                .collect();
+            stats.confirmed += rehashed.len();
+
+            let mut full: HashMap<PathBuf, (ImageHashValue, Option<HashVariants>)> = HashMap::new();
+            let mut details: HashMap<PathBuf, PhotoDetails> = HashMap::new();
+            let mut failed: HashSet<PathBuf> = HashSet::new();
+            for (path, result) in rehashed {
+                hash_result.thumbnails.remove(&path);
+                match result {
+                    Ok((hash, variants, analyzed)) => {
+                        details.insert(path.clone(), analyzed);
+                        full.insert(path, (hash, variants));
+                    }
+                    Err(e) => {
+                        events.send(Event::Hash(HashEvent::Error {
+                            path: path.clone(),
+                            message: e.to_string(),
+                        }));
+                        failed.insert(path);
+                    }
+                }
+            }
+
+            let cache_entries: Vec<CacheEntry> = full
+                .iter()
+                .filter_map(|(path, (hash, variants))| {
+                    let photo = photos.get(path.as_path())?;
+                    Some(self.cache_entry(photo, hash, variants.as_ref(), details.get(path)?))
+                })
+                .collect();
+            if !cache_entries.is_empty() {
+                if let Err(e) = self.cache.set_batch(&cache_entries) {
+                    events.send(Event::Hash(HashEvent::Error {
+                        path: PathBuf::from("cache"),
+                        message: format!(
+                            "Failed to write {} entries to cache: {}",
+                            cache_entries.len(),
+                            e
+                        ),
+                    }));
+                }
+            }
+
+            // Photos that can't be decoded in full are dropped, like any
+            // other photo that fails to hash
+            hashes.retain(|(path, _)| !failed.contains(&source(path)));
+            hash_result
+                .variants
+                .retain(|(path, _)| !failed.contains(&source(path)));
+            for (path, hash) in hashes.iter_mut() {
+                if let Some((full_hash, _)) = full.get(&source(path)) {
+                    *hash = full_hash.clone();
+                }
+            }
+            for (path, variants) in hash_result.variants.iter_mut() {
+                if let Some((_, Some(full_variants))) = full.get(&source(path)) {
+                    *variants = full_variants.clone();
+                }
+            }
+
+            hash_result.details.extend(details);
+
+            matches = self.find_matches(hashes, &hash_result.variants, strategy);
+        }
+
+        let confirmed = pair_keys(&matches);
+        stats.rejected_pairs = initial.difference(&confirmed).count();
+        stats.thumbnails_only = hash_result.thumbnails.len();
+        Ok((matches, stats))
+    }
+
+    /// Hash the full-size pixels of every photo that matched another.
+    ///
+    /// Pixel-identical photos always match perceptually, so only matched
+    /// photos need a full decode. Copies use their original's pixels, and
+    /// newly computed pixel hashes are written back to the cache along with
+    /// the details already analyzed.
+    fn hash_matched_pixels(
+        &self,
+        matches: &[MatchResult],
+        photos: &[PhotoFile],
+        hashes: &[(PathBuf, ImageHashValue)],
+        originals: &HashMap<PathBuf, PathBuf>,
+        hash_result: &HashingResult,
+        events: &EventSender,
+    ) -> Vec<(PathBuf, PixelHash)> {
+        let source = |path: &PathBuf| originals.get(path).unwrap_or(path).clone();
+        let cached = &hash_result.pixels;
+
+        let mut matched: Vec<PathBuf> = matches
+            .iter()
+            .flat_map(|m| [m.photo_a.clone(), m.photo_b.clone()])
+            .collect();
+        matched.sort();
+        matched.dedup();
+
+        let mut to_decode: Vec<PathBuf> = matched
+            .iter()
+            .map(source)
+            .filter(|path| !cached.contains_key(path))
+            .collect();
+        to_decode.sort();
+        to_decode.dedup();
+
+        let computed: HashMap<PathBuf, PixelHash> = to_decode
+            .into_par_iter()
+            .filter_map(|path| match PixelHash::of_file(&path) {
+                Ok(pixels) => Some((path, pixels)),
+                Err(e) => {
+                    events.send(Event::Hash(HashEvent::Error {
+                        path,
+                        message: e.to_string(),
+                    }));
+                    None
+                }
+            })
+            .collect();
+
+        let photos: HashMap<&Path, &PhotoFile> =
+            photos.iter().map(|p| (p.path.as_path(), p)).collect();
+        let hashes: HashMap<&Path, &ImageHashValue> =
+            hashes.iter().map(|(p, h)| (p.as_path(), h)).collect();
+        let variants: HashMap<&Path, &HashVariants> = hash_result
+            .variants
+            .iter()
+            .map(|(p, v)| (p.as_path(), v))
+            .collect();
+        let cache_entries: Vec<CacheEntry> = computed
+            .iter()
+            .filter_map(|(path, pixels)| {
+                let photo = photos.get(path.as_path())?;
+                let hash = hashes.get(path.as_path())?;
+                Some(CacheEntry {
+                    path: path.clone(),
+                    hash: hash.as_bytes().to_vec(),
+                    algorithm: self.config.algorithm,
+                    hash_size: self.config.hash_size,
+                    file_size: photo.size,
+                    file_modified: photo.modified,
+                    cached_at: SystemTime::now(),
+                    pixel_hash: Some(pixels.to_bytes().to_vec()),
+                    details: hash_result.details.get(path).cloned(),
+                    variants: variants.get(path.as_path()).map(|&v| v.clone()),
+                })
+            })
+            .collect();
+        if !cache_entries.is_empty() {
+            if let Err(e) = self.cache.set_batch(&cache_entries) {
+                events.send(Event::Hash(HashEvent::Error {
+                    path: PathBuf::from("cache"),
+                    message: format!(
+                        "Failed to write {} pixel hashes to cache: {}",
+                        cache_entries.len(),
+                        e
+                    ),
+                }));
+            }
+        }
+
+        matched
+            .into_iter()
+            .filter_map(|path| {
+                let original = source(&path);
+                let pixels = cached.get(&original).or_else(|| computed.get(&original))?;
+                Some((path, *pixels))
+            })
+            .collect()
+    }
+
     /// Run the pipeline with event reporting
     pub fn run_with_events(
         &self,
@@ -442,15 +1392,25 @@ impl Pipeline {
             phase: PipelinePhase::Scanning,
         }));
 
-        let scanner = WalkDirScanner::new(self.config.scan_config.clone());
+        let scanner: Box<dyn PhotoScanner + '_> = if self.config.scan_config.incremental {
+            Box::new(IncrementalScanner::new(
+                self.config.scan_config.clone(),
+                self.cache.as_ref(),
+            ))
+        } else if self.config.scan_config.parallel {
+            Box::new(ParallelScanner::new(self.config.scan_config.clone()))
+        } else {
+            Box::new(WalkDirScanner::new(self.config.scan_config.clone()))
+        };
         let scan_result = scanner.scan_with_events(&self.config.paths, events)?;
 
         for error in scan_result.errors {
             errors.push(error.to_string());
         }
 
-        let photos = scan_result.photos;
-        let total_photos = photos.len();
+        let total_photos = scan_result.photos.len();
+        let format_mismatches = find_format_mismatches(&scan_result.photos);
+        let (photos, mut hardlinks) = collapse_hardlinks(scan_result.photos);
 
         if photos.is_empty() {
             return Ok(self.empty_result(events, start_time, errors));
@@ -465,26 +1425,62 @@ impl Pipeline {
         }
 
         // Phase 2: Optimization pre-filtering
-        let opt_config = OptimizationConfig::default();
-        let opt_result = prefilter_candidates(&photos, &opt_config);
+        // Byte-level filters only run for the exact pass; the perceptual pass
+        // must hash everything or resized copies would never be compared.
+        let opt_result = prefilter_candidates(
+            &photos,
+            &self.config.optimization,
+            self.config.detection_mode,
+        );
         let photos_to_hash = opt_result.candidates;
+        let candidates = photos_to_hash.len();
 
-        // Emit optimization stats event
-        events.send(Event::Pipeline(PipelineEvent::OptimizationStats {
-            skipped_unique_size: opt_result.skipped_unique_size,
-            skipped_unique_prefix: opt_result.skipped_unique_prefix,
-            candidates: photos_to_hash.len(),
-        }));
+        // Byte-identical copies are found from their contents, so only the
+        // first file of each set needs decoding
+        let identical_sets = if self.config.byte_identical {
+            find_identical_files(&photos_to_hash, self.cache.as_ref())
+        } else {
+            Vec::new()
+        };
+        let originals: HashMap<PathBuf, PathBuf> = identical_sets
+            .iter()
+            .flat_map(|set| set[1..].iter().map(|copy| (copy.clone(), set[0].clone())))
+            .collect();
+        let photos_to_decode: Vec<PhotoFile> = photos_to_hash
+            .into_iter()
+            .filter(|photo| !originals.contains_key(&photo.path))
+            .collect();
 
         // Phase 3: Hashing (after optimization pre-filtering)
         events.send(Event::Pipeline(PipelineEvent::PhaseChanged {
             phase: PipelinePhase::Hashing,
         }));
+
+        // Two-phase hashing screens out photos with no candidate match;
+        // originals of byte-identical copies always have one
+        let (photos_to_decode, two_phase) = if self.uses_two_phase(photos_to_decode.len()) {
+            let keep: HashSet<&PathBuf> = identical_sets.iter().map(|set| &set[0]).collect();
+            let (screened, stats) =
+                self.screen_photos(photos_to_decode, &keep, events, cancel_token.as_ref())?;
+            (screened, Some(stats))
+        } else {
+            (photos_to_decode, None)
+        };
+
+        // Emit optimization stats event
+        events.send(Event::Pipeline(PipelineEvent::OptimizationStats {
+            mode: opt_result.mode,
+            skipped_unique_size: opt_result.skipped_unique_size,
+            skipped_unique_prefix: opt_result.skipped_unique_prefix,
+            candidates,
+            two_phase,
+        }));
         events.send(Event::Hash(HashEvent::Started {
-            total_photos: photos_to_hash.len(),
+            total_photos: photos_to_decode.len(),
         }));
 
-        let mut hash_result = self.hash_photos(&photos_to_hash, events, cancel_token.as_ref())?;
+        let mut hash_result = self.hash_photos(&photos_to_decode, events, cancel_token.as_ref())?;
+        share_with_copies(&mut hash_result, &originals);
 
         events.send(Event::Hash(HashEvent::Completed {
             total_hashed: hash_result.hashes.len(),
@@ -507,31 +1503,117 @@ impl Pipeline {
             total_photos: hash_result.hashes.len(),
         }));
 
-        let strategy = ThresholdStrategy::new(self.config.threshold);
+        // An exact pass only reports identical fingerprints
+        let hash_bits = self.config.hash_size * self.config.hash_size;
+        let strategy = match self.config.detection_mode {
+            DetectionMode::Exact => ThresholdStrategy::new(0),
+            DetectionMode::Perceptual => ThresholdStrategy::new(self.config.threshold),
+        }
+        .with_hash_bits(hash_bits);
 
-        // Use LSH acceleration for large collections (>500 photos)
-        // This reduces O(n²) to O(n log n) - roughly 250x speedup for 10,000 photos
-        let hashes = std::mem::take(&mut hash_result.hashes);
-        let matches = if hashes.len() > LSH_THRESHOLD {
-            find_duplicate_pairs_with_lsh(hashes, &strategy, LshConfig::default())
+        let mut hashes = std::mem::take(&mut hash_result.hashes);
+        let mut matches = if self.reuses_matches() {
+            self.find_matches_incremental(&hashes, &strategy)
         } else {
-            find_duplicate_pairs(&hashes, &strategy)
+            self.find_matches(&hashes, &hash_result.variants, &strategy)
         };
 
-        let grouper = TransitiveGrouper::new();
-        let mut groups = grouper.group(&matches);
+        // Thumbnail matches only pick out the photos worth decoding
+        let preview = if self.config.scan_mode == ScanMode::Preview {
+            let (confirmed, stats) = self.confirm_preview_matches(
+                matches,
+                &mut hash_result,
+                &mut hashes,
+                &photos,
+                &originals,
+                &strategy,
+                events,
+                cancel_token.as_ref(),
+            )?;
+            matches = confirmed;
+            Some(stats)
+        } else {
+            None
+        };
+
+        // Byte-identical and pixel-identical pairs are reported as such,
+        // whatever their perceptual hashes say
+        let pixel_sets = if self.config.byte_identical {
+            let pixels = self.hash_matched_pixels(
+                &matches,
+                &photos,
+                &hashes,
+                &originals,
+                &hash_result,
+                events,
+            );
+            find_pixel_identical(&pixels)
+        } else {
+            Vec::new()
+        };
+        if !identical_sets.is_empty() || !pixel_sets.is_empty() {
+            let byte_index = set_index(&identical_sets);
+            let pixel_index = set_index(&pixel_sets);
+            matches.retain(|m| {
+                !in_same_set(&byte_index, &m.photo_a, &m.photo_b)
+                    && !in_same_set(&pixel_index, &m.photo_a, &m.photo_b)
+            });
+            matches.extend(identical_matches(&identical_sets));
+            matches.extend(same_pixel_matches(&pixel_sets, &identical_sets));
+        }
+
+        // Dimensions are only known once decoded, so photos under the limit
+        // are dropped from the matches rather than before hashing
+        let limits = &self.config.scan_config.limits;
+        if limits.min_dimension.is_some() {
+            let admitted = |path: &PathBuf| {
+                let original = originals.get(path).unwrap_or(path);
+                hash_result
+                    .details
+                    .get(original)
+                    .is_none_or(|d| limits.admits_dimensions(d.width, d.height))
+            };
+            matches.retain(|m| admitted(&m.photo_a) && admitted(&m.photo_b));
+            hardlinks.retain(|paths| admitted(&paths[0]));
+        }
+
+        // Star clustering centers groups on the largest file
+        let photo_sizes = build_photo_size_map(&photos);
+        let mut groups = self
+            .config
+            .grouping
+            .for_hash_bits(hash_bits)
+            .group(&matches, &photo_sizes);
+
+        // Photos that never matched directly are measured by their hashes
+        let hash_of: HashMap<&PathBuf, &ImageHashValue> =
+            hashes.iter().map(|(path, hash)| (path, hash)).collect();
+        measure_diameters(&mut groups, &matches, |a, b| {
+            Some(hash_distance(hash_of.get(a)?, hash_of.get(b)?))
+        });
 
         events.send(Event::Compare(CompareEvent::Completed {
             total_groups: groups.len(),
             total_duplicates: groups.iter().map(|g| g.duplicate_count()).sum(),
         }));
 
-        // Select best representative for each group (largest file = best quality)
-        let photo_sizes = build_photo_size_map(&photos);
-        select_best_representatives(&mut groups, &photo_sizes);
+        // Copies confirmed from a preview share their original's details
+        let details: HashMap<PathBuf, PhotoDetails> = groups
+            .iter()
+            .flat_map(|group| &group.photos)
+            .filter_map(|path| {
+                let original = originals.get(path).unwrap_or(path);
+                Some((path.clone(), hash_result.details.get(original)?.clone()))
+            })
+            .collect();
+
+        // Select best representative for each group (resolution, then quality)
+        select_best_representatives(&mut groups, &photo_sizes, &details);
 
         // Calculate savings and emit summary
-        let potential_savings = calculate_group_savings(&mut groups, &photo_sizes);
+        let linked: HashSet<&PathBuf> = hardlinks.iter().map(|paths| &paths[0]).collect();
+        let potential_savings = calculate_group_savings(&mut groups, &photo_sizes, &linked);
+        groups.extend(hardlink_groups(hardlinks));
         let duration_ms = start_time.elapsed().as_millis() as u64;
 
         events.send(Event::Pipeline(PipelineEvent::Completed {
@@ -550,6 +1632,11 @@ impl Pipeline {
             cache_hits: hash_result.cache_hits,
             errors,
             duration_ms,
+            preview,
+            details,
+            format_mismatches,
+            algorithm: self.config.algorithm,
+            hash_size: self.config.hash_size,
         })
     }
 
@@ -577,6 +1664,11 @@ impl Pipeline {
             cache_hits: 0,
             errors,
             duration_ms,
+            preview: None,
+            details: HashMap::new(),
+            format_mismatches: Vec::new(),
+            algorithm: self.config.algorithm,
+            hash_size: self.config.hash_size,
         }
     }
 }
@@ -617,6 +1709,53 @@ mod tests {
             .build();
 
         assert_eq!(pipeline.config.threshold, 8);
+        assert_eq!(pipeline.config.detection_mode, DetectionMode::Perceptual);
+    }
+
+    #[test]
+    fn pipeline_reports_optimization_stats_for_mode() {
+        let temp_dir = TempDir::new().unwrap();
+        let photo =
+            image::RgbImage::from_fn(64, 48, |x, y| image::Rgb([x as u8 * 4, y as u8 * 5, 90]));
+        photo.save(temp_dir.path().join("a.png")).unwrap();
+        photo.save(temp_dir.path().join("b.png")).unwrap();
+        image::RgbImage::from_pixel(32, 32, image::Rgb([200, 10, 10]))
+            .save(temp_dir.path().join("c.png"))
+            .unwrap();
+
+        let stats_for = |mode| {
+            let pipeline = Pipeline::builder()
+                .paths(vec![temp_dir.path().to_path_buf()])
+                .detection_mode(mode)
+                .optimization(OptimizationConfig {
+                    min_photos_threshold: 0,
+                    ..Default::default()
+                })
+                .build();
+            let (sender, receiver) = crossbeam_channel::unbounded();
+            pipeline
+                .run_with_events(&crate::events::EventSender::new(sender))
+                .unwrap();
+            receiver.try_iter().find_map(|event| match event {
+                Event::Pipeline(PipelineEvent::OptimizationStats {
+                    mode,
+                    skipped_unique_size,
+                    candidates,
+                    ..
+                }) => Some((mode, skipped_unique_size, candidates)),
+                _ => None,
+            })
+        };
+
+        // The exact pass skips the photo with a unique size
+        assert_eq!(
+            stats_for(DetectionMode::Exact),
+            Some((DetectionMode::Exact, 1, 2))
+        );
+        assert_eq!(
+            stats_for(DetectionMode::Perceptual),
+            Some((DetectionMode::Perceptual, 0, 3))
+        );
     }
 
     #[test]
@@ -727,6 +1866,65 @@ mod tests {
         assert!(found_started, "Expected Pipeline::Started event");
     }
 
+    #[test]
+    fn renamed_photos_are_followed_in_groups_and_details() {
+        use crate::core::comparator::{DuplicateGroup, MatchResult, MatchType};
+        use crate::core::quality::QualityScore;
+
+        let (old, new, other) = (
+            PathBuf::from("/a.png"),
+            PathBuf::from("/a.jpg"),
+            PathBuf::from("/b.jpg"),
+        );
+        let details = PhotoDetails {
+            width: 640,
+            height: 480,
+            quality: QualityScore {
+                sharpness: 0.0,
+                contrast: 0.0,
+                brightness: 0.0,
+                overall: 0.0,
+            },
+            metadata: Default::default(),
+        };
+        let mut group = DuplicateGroup::new(
+            vec![old.clone(), other.clone()],
+            old.clone(),
+            MatchType::ByteIdentical,
+        );
+        group.weakest_match = Some(MatchResult {
+            photo_a: old.clone(),
+            photo_b: other.clone(),
+            distance: 0,
+            similarity_percent: 100.0,
+            match_type: MatchType::ByteIdentical,
+            confidence: None,
+            transform: None,
+            crop: None,
+        });
+        let mut result = PipelineResult {
+            groups: vec![group],
+            total_photos: 2,
+            cache_hits: 0,
+            errors: Vec::new(),
+            duration_ms: 0,
+            preview: None,
+            details: HashMap::from([(old.clone(), details)]),
+            format_mismatches: Vec::new(),
+            algorithm: HashAlgorithmKind::Difference,
+            hash_size: 8,
+        };
+
+        result.rename_photo(&old, &new);
+
+        assert_eq!(result.groups[0].photos, vec![new.clone(), other]);
+        assert_eq!(result.groups[0].representative, new);
+        let weakest = result.groups[0].weakest_match.as_ref().unwrap();
+        assert_eq!(weakest.photo_a, new);
+        assert!(result.details.contains_key(&new));
+        assert!(!result.details.contains_key(&old));
+    }
+
     #[test]
     fn calculate_group_savings_computes_correctly() {
         use crate::core::comparator::{DuplicateGroup, MatchType};
@@ -738,12 +1936,14 @@ mod tests {
                 size: 1000,
                 modified: std::time::SystemTime::now(),
                 format: ImageFormat::Jpeg,
+                file_id: None,
             },
             PhotoFile {
                 path: PathBuf::from("/b.jpg"),
                 size: 1000,
                 modified: std::time::SystemTime::now(),
                 format: ImageFormat::Jpeg,
+                file_id: None,
             },
         ];
 
@@ -753,15 +1953,26 @@ mod tests {
             representative: PathBuf::from("/a.jpg"),
             match_type: MatchType::Exact,
             average_distance: 0.0,
+            max_distance: None,
             duplicate_size_bytes: 0, // Will be calculated
+            confidence: None,
+            transform: None,
+            crop: None,
+            weakest_match: None,
         }];
 
         let photo_sizes = build_photo_size_map(&photos);
-        let savings = calculate_group_savings(&mut groups, &photo_sizes);
+        let savings = calculate_group_savings(&mut groups, &photo_sizes, &HashSet::new());
 
         // Should calculate savings as size of duplicates (not representative)
         // In a group of 2 identical files of 1000 bytes, savings = 1000 bytes (one duplicate)
         assert_eq!(savings, 1000);
         assert_eq!(groups[0].duplicate_size_bytes, 1000);
+
+        // A duplicate with other hardlinks frees nothing when deleted
+        let b = PathBuf::from("/b.jpg");
+        let savings = calculate_group_savings(&mut groups, &photo_sizes, &HashSet::from([&b]));
+        assert_eq!(savings, 0);
+        assert_eq!(groups[0].duplicate_size_bytes, 0);
     }
 }
@@ -0,0 +1,101 @@
+//! Hardlinked photos.
+//!
+//! Hardlinks are several paths to one file on disk. They hold the same bytes
+//! but the file is stored once, so removing one of the paths frees nothing.
+//! The pipeline hashes and compares each file once, through the first path
+//! the scan found to it, and reports the others as already deduplicated.
+//! When that file also has copies, deleting its path from their group frees
+//! nothing either, so it doesn't count toward the group's savings.
+
+use crate::core::comparator::{DuplicateGroup, MatchType};
+use crate::core::scanner::PhotoFile;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Keep one photo per file on disk.
+///
+/// Returns the photos with the first path to each file, and the paths of
+/// every file that was found through more than one, the kept path first.
+/// A path found twice (through overlapping scan roots) is only kept once.
+pub fn collapse_hardlinks(photos: Vec<PhotoFile>) -> (Vec<PhotoFile>, Vec<Vec<PathBuf>>) {
+    let mut kept = Vec::with_capacity(photos.len());
+    let mut links: Vec<Vec<PathBuf>> = Vec::new();
+    let mut by_id = HashMap::new();
+
+    for photo in photos {
+        let Some(file_id) = photo.file_id else {
+            kept.push(photo);
+            continue;
+        };
+        match by_id.get(&file_id) {
+            Some(&index) => {
+                let paths: &mut Vec<PathBuf> = &mut links[index];
+                if !paths.contains(&photo.path) {
+                    paths.push(photo.path);
+                }
+            }
+            None => {
+                by_id.insert(file_id, links.len());
+                links.push(vec![photo.path.clone()]);
+                kept.push(photo);
+            }
+        }
+    }
+
+    links.retain(|paths| paths.len() > 1);
+    (kept, links)
+}
+
+/// Report each file with several paths as a group with nothing to save
+pub fn hardlink_groups(links: Vec<Vec<PathBuf>>) -> Vec<DuplicateGroup> {
+    links
+        .into_iter()
+        .map(|paths| {
+            let representative = paths[0].clone();
+            DuplicateGroup::new(paths, representative, MatchType::Hardlinked)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::scanner::{FileId, ImageFormat};
+    use std::time::SystemTime;
+
+    fn photo(path: &str, inode: Option<u64>) -> PhotoFile {
+        PhotoFile {
+            path: PathBuf::from(path),
+            size: 1000,
+            modified: SystemTime::UNIX_EPOCH,
+            format: ImageFormat::Jpeg,
+            file_id: inode.map(|inode| FileId { device: 1, inode }),
+        }
+    }
+
+    #[test]
+    fn keeps_the_first_path_to_each_file() {
+        let photos = vec![
+            photo("/a/1.jpg", Some(10)),
+            photo("/a/2.jpg", Some(20)),
+            photo("/b/1.jpg", Some(10)),
+            photo("/a/1.jpg", Some(10)),
+            photo("/c/3.jpg", None),
+        ];
+
+        let (kept, links) = collapse_hardlinks(photos);
+
+        let kept: Vec<_> = kept.iter().map(|p| p.path.to_str().unwrap()).collect();
+        assert_eq!(kept, vec!["/a/1.jpg", "/a/2.jpg", "/c/3.jpg"]);
+        assert_eq!(
+            links,
+            vec![vec![PathBuf::from("/a/1.jpg"), PathBuf::from("/b/1.jpg")]]
+        );
+
+        let groups = hardlink_groups(links);
+        assert_eq!(groups[0].match_type, MatchType::Hardlinked);
+        assert_eq!(groups[0].representative, PathBuf::from("/a/1.jpg"));
+        assert_eq!(groups[0].duplicate_count(), 0);
+        assert_eq!(groups[0].duplicate_size_bytes, 0);
+    }
+}
@@ -0,0 +1,328 @@
+//! Byte-identical and pixel-identical file detection.
+//!
+//! Finds exact copies by hashing full file contents, without decoding a
+//! single image. Perceptual distance 0 only says two photos look the same;
+//! a re-encode or a metadata edit can get there too. Matching content
+//! hashes say the files are the same bytes.
+//!
+//! Only files that share their size with another file are read, and only
+//! when the cache has no digest for them at their current size and modified
+//! time. Files under 1MB are streamed through xxh3-128; larger files are
+//! memory-mapped.
+//!
+//! Files whose bytes differ but whose decoded pixels hash the same (see
+//! [`PixelHash`]) only differ in metadata, and are reported separately.
+
+use super::optimization::should_use_mmap;
+use crate::core::cache::{CacheBackend, ContentHash};
+use crate::core::comparator::{MatchResult, MatchType};
+use crate::core::hasher::{read_file_bytes, PixelHash};
+use crate::core::scanner::PhotoFile;
+use crate::error::HashError;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use xxhash_rust::xxh3::{xxh3_128, Xxh3};
+
+/// Read buffer for streamed hashing (64KB)
+const STREAM_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Hash the full contents of a file with xxh3-128.
+pub fn content_hash(path: &Path, size: u64) -> Result<u128, HashError> {
+    if should_use_mmap(size) {
+        return Ok(xxh3_128(&read_file_bytes(path)?));
+    }
+
+    let io_error = |source| HashError::IoError {
+        path: path.to_path_buf(),
+        source,
+    };
+    let mut file = File::open(path).map_err(io_error)?;
+    let mut hasher = Xxh3::new();
+    let mut buffer = vec![0u8; STREAM_BUFFER_SIZE];
+    loop {
+        let read = file.read(&mut buffer).map_err(io_error)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher.digest128())
+}
+
+/// Find sets of byte-identical files.
+///
+/// Each set is sorted by path and has at least two files. Files that can't
+/// be read are left out. Digests are reused from `cache` for unchanged
+/// files, and the new ones stored in it.
+#[tracing::instrument(skip(photos, cache), fields(total_photos = photos.len()))]
+pub fn find_identical_files(photos: &[PhotoFile], cache: &dyn CacheBackend) -> Vec<Vec<PathBuf>> {
+    // Only files that share a size can be identical
+    let mut by_size: HashMap<u64, Vec<&PhotoFile>> = HashMap::new();
+    for photo in photos {
+        by_size.entry(photo.size).or_default().push(photo);
+    }
+    let candidates: Vec<&PhotoFile> = by_size
+        .into_values()
+        .filter(|group| group.len() >= 2)
+        .flatten()
+        .collect();
+
+    // The flag marks digests that weren't cached
+    let hashes: Vec<(ContentHash, bool)> = candidates
+        .par_iter()
+        .filter_map(|photo| {
+            let cached = cache
+                .get_content_hash(&photo.path, photo.size, photo.modified)
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Failed to read content hash cache: {}", e);
+                    None
+                });
+            let hash = match cached {
+                Some(hash) => hash,
+                None => match content_hash(&photo.path, photo.size) {
+                    Ok(hash) => hash,
+                    Err(e) => {
+                        tracing::warn!("Content hash failed for {:?}: {}", photo.path, e);
+                        return None;
+                    }
+                },
+            };
+            let entry = ContentHash {
+                path: photo.path.clone(),
+                file_size: photo.size,
+                file_modified: photo.modified,
+                hash,
+            };
+            Some((entry, cached.is_none()))
+        })
+        .collect();
+
+    let computed: Vec<ContentHash> = hashes
+        .iter()
+        .filter(|(_, computed)| *computed)
+        .map(|(entry, _)| entry.clone())
+        .collect();
+    if let Err(e) = cache.set_content_hashes(&computed) {
+        tracing::warn!("Failed to cache content hashes: {}", e);
+    }
+
+    let mut by_content: HashMap<(u64, u128), Vec<PathBuf>> = HashMap::new();
+    for (entry, _) in hashes {
+        by_content
+            .entry((entry.file_size, entry.hash))
+            .or_default()
+            .push(entry.path);
+    }
+
+    let mut sets: Vec<Vec<PathBuf>> = by_content
+        .into_values()
+        .filter(|set| set.len() >= 2)
+        .map(|mut set| {
+            set.sort();
+            set
+        })
+        .collect();
+    sets.sort();
+    tracing::debug!("Found {} sets of byte-identical files", sets.len());
+    sets
+}
+
+/// Find sets of files with identical decoded pixels.
+///
+/// Each set is sorted by path and has at least two files.
+pub fn find_pixel_identical(pixels: &[(PathBuf, PixelHash)]) -> Vec<Vec<PathBuf>> {
+    let mut by_pixels: HashMap<PixelHash, Vec<PathBuf>> = HashMap::new();
+    for (path, hash) in pixels {
+        by_pixels.entry(*hash).or_default().push(path.clone());
+    }
+
+    let mut sets: Vec<Vec<PathBuf>> = by_pixels
+        .into_values()
+        .filter(|set| set.len() >= 2)
+        .map(|mut set| {
+            set.sort();
+            set
+        })
+        .collect();
+    sets.sort();
+    sets
+}
+
+/// Map each path to the index of the set it belongs to
+pub fn set_index(sets: &[Vec<PathBuf>]) -> HashMap<&PathBuf, usize> {
+    sets.iter()
+        .enumerate()
+        .flat_map(|(i, set)| set.iter().map(move |path| (path, i)))
+        .collect()
+}
+
+/// Whether two paths belong to the same set of an index
+pub fn in_same_set(index: &HashMap<&PathBuf, usize>, a: &PathBuf, b: &PathBuf) -> bool {
+    match index.get(a) {
+        Some(set) => index.get(b) == Some(set),
+        None => false,
+    }
+}
+
+/// Build a [`MatchType::ByteIdentical`] match for every pair in each set
+pub fn identical_matches(sets: &[Vec<PathBuf>]) -> Vec<MatchResult> {
+    set_matches(sets, MatchType::ByteIdentical, &HashMap::new())
+}
+
+/// Build a [`MatchType::SamePixelsDifferentMetadata`] match for every pair
+/// in each pixel set, except pairs that are byte-identical
+pub fn same_pixel_matches(
+    pixel_sets: &[Vec<PathBuf>],
+    byte_sets: &[Vec<PathBuf>],
+) -> Vec<MatchResult> {
+    set_matches(
+        pixel_sets,
+        MatchType::SamePixelsDifferentMetadata,
+        &set_index(byte_sets),
+    )
+}
+
+/// Pairwise matches within each set, skipping pairs in the same `skip` set
+fn set_matches(
+    sets: &[Vec<PathBuf>],
+    match_type: MatchType,
+    skip: &HashMap<&PathBuf, usize>,
+) -> Vec<MatchResult> {
+    let mut matches = Vec::new();
+    for set in sets {
+        for (i, photo_a) in set.iter().enumerate() {
+            for photo_b in &set[i + 1..] {
+                if in_same_set(skip, photo_a, photo_b) {
+                    continue;
+                }
+                matches.push(MatchResult {
+                    photo_a: photo_a.clone(),
+                    photo_b: photo_b.clone(),
+                    distance: 0,
+                    similarity_percent: 100.0,
+                    match_type,
+                    confidence: None,
+                    transform: None,
+                    crop: None,
+                });
+            }
+        }
+    }
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::cache::InMemoryCache;
+    use std::time::SystemTime;
+    use tempfile::TempDir;
+
+    fn write_photo(dir: &TempDir, name: &str, content: &[u8]) -> PhotoFile {
+        let path = dir.path().join(name);
+        std::fs::write(&path, content).unwrap();
+        PhotoFile {
+            path,
+            size: content.len() as u64,
+            modified: SystemTime::now(),
+            format: crate::core::scanner::ImageFormat::Jpeg,
+            file_id: None,
+        }
+    }
+
+    #[test]
+    fn identical_files_form_a_set() {
+        let dir = TempDir::new().unwrap();
+        let photos = vec![
+            write_photo(&dir, "a.jpg", b"same bytes"),
+            write_photo(&dir, "b.jpg", b"same bytes"),
+            // Same size, different content
+            write_photo(&dir, "c.jpg", b"other byte"),
+            write_photo(&dir, "d.jpg", b"unique size"),
+        ];
+
+        let sets = find_identical_files(&photos, &InMemoryCache::new());
+
+        assert_eq!(
+            sets,
+            vec![vec![photos[0].path.clone(), photos[1].path.clone()]]
+        );
+    }
+
+    #[test]
+    fn unchanged_files_are_not_read_again() {
+        let dir = TempDir::new().unwrap();
+        let photos = vec![
+            write_photo(&dir, "a.jpg", b"same bytes"),
+            write_photo(&dir, "b.jpg", b"same bytes"),
+        ];
+        let cache = InMemoryCache::new();
+        assert_eq!(find_identical_files(&photos, &cache).len(), 1);
+
+        // Same size and modified time: the cached digests are used, so the
+        // new contents go unnoticed
+        std::fs::write(&photos[1].path, b"new  bytes").unwrap();
+        assert_eq!(find_identical_files(&photos, &cache).len(), 1);
+
+        // A changed modified time gets the file read again
+        let mut changed = photos.clone();
+        changed[1].modified += std::time::Duration::from_secs(1);
+        assert!(find_identical_files(&changed, &cache).is_empty());
+    }
+
+    #[test]
+    fn streamed_and_mapped_hashes_agree() {
+        let dir = TempDir::new().unwrap();
+        let content: Vec<u8> = (0..3 * STREAM_BUFFER_SIZE)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let photo = write_photo(&dir, "big.jpg", &content);
+
+        let streamed = content_hash(&photo.path, photo.size).unwrap();
+        let mapped = content_hash(&photo.path, u64::MAX).unwrap();
+
+        assert_eq!(streamed, mapped);
+        assert_eq!(streamed, xxh3_128(&content));
+    }
+
+    #[test]
+    fn sets_become_pairwise_matches() {
+        let set = vec![
+            PathBuf::from("/a.jpg"),
+            PathBuf::from("/b.jpg"),
+            PathBuf::from("/c.jpg"),
+        ];
+
+        let matches = identical_matches(&[set]);
+
+        assert_eq!(matches.len(), 3);
+        assert!(matches
+            .iter()
+            .all(|m| m.match_type == MatchType::ByteIdentical && m.distance == 0));
+    }
+
+    #[test]
+    fn same_pixels_skip_byte_identical_pairs() {
+        let a = PathBuf::from("/a.jpg");
+        let b = PathBuf::from("/b.jpg");
+        let c = PathBuf::from("/c.jpg");
+        let pixel_sets = find_pixel_identical(&[
+            (a.clone(), PixelHash(1)),
+            (b.clone(), PixelHash(1)),
+            (c.clone(), PixelHash(1)),
+            (PathBuf::from("/d.jpg"), PixelHash(2)),
+        ]);
+        let byte_sets = vec![vec![a.clone(), b.clone()]];
+
+        let matches = same_pixel_matches(&pixel_sets, &byte_sets);
+
+        let pairs: Vec<_> = matches.iter().map(|m| (&m.photo_a, &m.photo_b)).collect();
+        assert_eq!(pairs, vec![(&a, &c), (&b, &c)]);
+        assert!(matches
+            .iter()
+            .all(|m| m.match_type == MatchType::SamePixelsDifferentMetadata));
+    }
+}
@@ -3,10 +3,12 @@
 //! Orchestrates the full duplicate detection workflow.
 //!
 //! ## Pipeline Stages
-//! 1. **Scan** - Discover all photos in specified directories
-//! 2. **Hash** - Compute perceptual hashes (with caching)
-//! 3. **Compare** - Find duplicates using hash comparison
-//! 4. **Report** - Generate human-readable explanations
+//! 1. **Scan** - Discover all photos in specified directories, keeping one
+//!    path to each hardlinked file
+//! 2. **Identical** - Find byte-identical copies by file contents
+//! 3. **Hash** - Compute perceptual hashes (with caching)
+//! 4. **Compare** - Find duplicates using hash comparison
+//! 5. **Report** - Generate human-readable explanations
 //!
 //! ## Parallelism
 //! Uses rayon for parallel hashing across multiple CPU cores.
@@ -16,7 +18,12 @@
 //! duplicate detection by 2-5x on large photo libraries.
 
 mod executor;
+pub mod hardlinks;
+pub mod identical;
 pub mod optimization;
 
 pub use executor::{CancellationToken, Pipeline, PipelineBuilder, PipelineResult};
-pub use optimization::{OptimizationConfig, OptimizationResult, TwoPhaseHasher};
+pub use optimization::{
+    DetectionMode, OptimizationConfig, OptimizationResult, PreviewStats, ScanMode, TwoPhaseHasher,
+    TwoPhaseStats,
+};
@@ -4,22 +4,27 @@
 //! speed up duplicate detection on large photo libraries:
 //!
 //! 1. **Size Pre-filtering**: Skip hashing files with unique sizes
-//! 2. **Two-phase Hashing**: Fast aHash first, full fusion only on matches
+//! 2. **Two-phase Hashing**: Fast aHash first, the configured algorithm only
+//!    on photos with a candidate match
 //! 3. **Prefix Byte Hashing**: Ultra-fast first-4KB comparison
+//! 4. **Preview Scans**: Hash EXIF thumbnails, confirm matches in full
 //!
-//! These optimizations maintain full accuracy while reducing work by 50-90%.
-
-use crate::core::hasher::{
-    AverageHasher, FusionHash, FusionHasher, HashAlgorithm, HashAlgorithmKind, ImageHashValue,
-    PerceptualHash,
-};
+//! The byte-level filters (size and prefix) are only sound when looking for
+//! exact copies: a resized or re-encoded photo has a different size and a
+//! different prefix from its original. They therefore only run for
+//! [`DetectionMode::Exact`]; a [`DetectionMode::Perceptual`] pass hashes
+//! every photo.
+
+use crate::core::comparator::{find_duplicate_pairs_indexed, ThresholdStrategy};
+use crate::core::hasher::{AverageHasher, FastDecoder, HashAlgorithm, ImageHashValue};
 use crate::core::scanner::PhotoFile;
 use crate::error::HashError;
 use rayon::prelude::*;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use xxhash_rust::xxh3::xxh3_64;
 
 /// Size of prefix to hash for preliminary filtering (4KB)
@@ -28,9 +33,91 @@ const PREFIX_SIZE: usize = 4096;
 /// Minimum file size to use memory-mapped I/O
 const MMAP_THRESHOLD: u64 = 1024 * 1024; // 1MB
 
+/// Two-phase screening accepts aHash distances up to this multiple of the
+/// comparison threshold
+const SCREEN_THRESHOLD_FACTOR: u32 = 2;
+
+/// Two-phase screening threshold floor, so exact passes (threshold 0) still
+/// tolerate the aHash noise of re-encoding
+const MIN_SCREEN_THRESHOLD: u32 = 10;
+
+/// Decode size for two-phase screening
+const SCREEN_DECODE_SIZE: u32 = 64;
+
+/// Which kind of duplicates a pass is looking for.
+///
+/// Determines which pre-filters are safe to apply before hashing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DetectionMode {
+    /// Byte-level copies only; size and prefix filters apply
+    Exact,
+    /// Visually similar photos (resized, re-encoded); every photo is hashed
+    #[default]
+    Perceptual,
+}
+
+impl std::fmt::Display for DetectionMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DetectionMode::Exact => write!(f, "exact"),
+            DetectionMode::Perceptual => write!(f, "perceptual"),
+        }
+    }
+}
+
+/// What the pipeline decodes to hash each photo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ScanMode {
+    /// Decode every photo
+    #[default]
+    Full,
+    /// Hash the embedded EXIF thumbnail where there is one, and decode the
+    /// photo only to confirm a match or when it has no thumbnail
+    Preview,
+}
+
+impl std::fmt::Display for ScanMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScanMode::Full => write!(f, "full"),
+            ScanMode::Preview => write!(f, "preview"),
+        }
+    }
+}
+
+/// How a preview scan traded accuracy for speed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreviewStats {
+    /// Photos hashed from their EXIF thumbnail and never decoded
+    pub thumbnails_only: usize,
+    /// Photos without a usable thumbnail, decoded up front
+    pub no_thumbnail: usize,
+    /// Thumbnail-hashed photos decoded to confirm a match
+    pub confirmed: usize,
+    /// Pairs matched by their thumbnails that didn't hold up in full
+    pub rejected_pairs: usize,
+}
+
+/// How two-phase hashing split the work
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TwoPhaseStats {
+    /// Photos given a cheap aHash in phase 1
+    pub screened: usize,
+    /// Phase-1 hashes found in the cache
+    pub screen_cache_hits: usize,
+    /// Photos with a candidate under the loose threshold, hashed in phase 2
+    pub candidates: usize,
+    /// Photos without a candidate, never hashed with the configured algorithm
+    pub skipped: usize,
+    /// Loose aHash threshold phase 1 screened with
+    pub threshold: u32,
+}
+
 /// Result of the optimization pre-processing phase
 #[derive(Debug)]
 pub struct OptimizationResult {
+    /// Detection mode the filters were applied for
+    pub mode: DetectionMode,
     /// Photos that need full hashing (have potential duplicates)
     pub candidates: Vec<PhotoFile>,
     /// Photos that can be skipped (unique file sizes)
@@ -65,16 +152,32 @@ impl Default for OptimizationConfig {
 
 /// Pre-process photos to filter out those that definitely can't be duplicates.
 ///
-/// This function applies multiple fast filters before expensive perceptual hashing:
-/// 1. Size pre-filter: Files with unique sizes can't be duplicates
-/// 2. Prefix hash: Files with different first 4KB can't be duplicates
+/// In [`DetectionMode::Exact`] this applies fast byte-level filters:
+/// 1. Size pre-filter: Files with unique sizes can't be exact copies
+/// 2. Prefix hash: Files with different first 4KB can't be exact copies
+///
+/// In [`DetectionMode::Perceptual`] nothing is skipped, since a resized or
+/// re-encoded copy differs from its original in both size and prefix.
 ///
 /// Returns only the photos that need full hashing.
 #[tracing::instrument(skip(photos), fields(total_photos = photos.len()))]
 pub fn prefilter_candidates(
     photos: &[PhotoFile],
     config: &OptimizationConfig,
+    mode: DetectionMode,
 ) -> OptimizationResult {
+    let unfiltered = || OptimizationResult {
+        mode,
+        candidates: photos.to_vec(),
+        skipped_unique_size: 0,
+        skipped_unique_prefix: 0,
+    };
+
+    if mode == DetectionMode::Perceptual {
+        tracing::debug!("Perceptual pass: hashing all {} photos", photos.len());
+        return unfiltered();
+    }
+
     // Skip optimization for small scans
     if photos.len() < config.min_photos_threshold {
         tracing::debug!(
@@ -82,11 +185,7 @@ pub fn prefilter_candidates(
             photos.len(),
             config.min_photos_threshold
         );
-        return OptimizationResult {
-            candidates: photos.to_vec(),
-            skipped_unique_size: 0,
-            skipped_unique_prefix: 0,
-        };
+        return unfiltered();
     }
 
     tracing::info!(
@@ -115,6 +214,7 @@ pub fn prefilter_candidates(
     }
 
     OptimizationResult {
+        mode,
         candidates,
         skipped_unique_size: skipped_size,
         skipped_unique_prefix: skipped_prefix,
@@ -190,7 +290,6 @@ fn filter_by_prefix(photos: &[PhotoFile]) -> (Vec<PhotoFile>, usize) {
     (candidates, skipped)
 }
 
-/// Compute a fast hash of the first 4KB of a file.
 /// Compute a fast hash of the first 4KB of a file.
 fn compute_prefix_hash(path: &std::path::Path) -> std::io::Result<u64> {
     let mut file = File::open(path)?;
@@ -199,115 +298,53 @@ fn compute_prefix_hash(path: &std::path::Path) -> std::io::Result<u64> {
     Ok(xxh3_64(&buffer[..bytes_read]))
 }
 
-/// Two-phase progressive hasher for fusion mode.
+/// Two-phase progressive hasher.
 ///
-/// Phase 1: Compute only aHash (fastest) for all photos
-/// Phase 2: Compute full fusion hash only for photos with aHash matches
+/// Phase 1: Compute only aHash (fastest), from a small decode, for all photos
+/// Phase 2: Hash with the configured algorithm only the photos whose aHash is
+/// within a loose threshold of another photo's
 ///
-/// This can skip 80-90% of expensive pHash computations.
+/// A photo with no phase-1 candidate is taken to have no duplicate, which
+/// can skip 80-90% of expensive pHash or fusion computations. The loose
+/// threshold keeps the photos whose aHash drifts further than their pHash.
 pub struct TwoPhaseHasher {
     ahash: AverageHasher,
-    fusion: FusionHasher,
     threshold: u32,
 }
 
 impl TwoPhaseHasher {
-    /// Create a new two-phase hasher with the given similarity threshold.
+    /// Create a two-phase hasher for a comparison threshold on the 64-bit
+    /// scale; phase 1 screens with a looser one.
     pub fn new(threshold: u32) -> Self {
         Self {
             ahash: AverageHasher::new(8),
-            fusion: FusionHasher::new(),
-            threshold,
+            threshold: (threshold * SCREEN_THRESHOLD_FACTOR).max(MIN_SCREEN_THRESHOLD),
         }
     }
 
-    /// Hash photos using two-phase strategy.
-    ///
-    /// Returns full fusion hashes, but only computes them for photos
-    /// that have potential matches based on fast aHash comparison.
-    pub fn hash_two_phase(
-        &self,
-        photos: &[PhotoFile],
-    ) -> Vec<(PathBuf, Result<FusionHash, HashError>)> {
-        // Phase 1: Compute aHash for all photos in parallel
-        let ahash_results: Vec<(PathBuf, Option<ImageHashValue>)> = photos
-            .par_iter()
-            .map(|photo| {
-                let hash = self.ahash.hash_file(&photo.path).ok();
-                (photo.path.clone(), hash)
-            })
-            .collect();
-
-        // Find photos with potential matches (aHash within threshold)
-        let needs_full_hash = self.find_ahash_matches(&ahash_results);
-
-        // Phase 2: Compute full fusion hash only for matched photos
-        photos
-            .par_iter()
-            .map(|photo| {
-                if needs_full_hash.contains(&photo.path) {
-                    let hash = self.fusion.hash_file(&photo.path);
-                    (photo.path.clone(), hash)
-                } else {
-                    // For non-matched photos, create a "dummy" fusion hash from aHash
-                    // This ensures they won't match anything in comparison
-                    let ahash = ahash_results
-                        .iter()
-                        .find(|(p, _)| p == &photo.path)
-                        .and_then(|(_, h)| h.clone());
-
-                    match ahash {
-                        Some(ah) => {
-                            // Create fusion hash with just aHash - will never match 2/3 threshold
-                            let dummy_hash = ImageHashValue::new(
-                                vec![0xFF; 8], // Max distance from any real hash
-                                HashAlgorithmKind::Difference,
-                            );
-                            (
-                                photo.path.clone(),
-                                Ok(FusionHash::new(ah, dummy_hash.clone(), dummy_hash)),
-                            )
-                        }
-                        None => (
-                            photo.path.clone(),
-                            Err(HashError::DecodeError {
-                                path: photo.path.clone(),
-                                reason: "Failed to compute aHash".to_string(),
-                            }),
-                        ),
-                    }
-                }
-            })
-            .collect()
+    /// The loose aHash threshold phase 1 screens with
+    pub fn threshold(&self) -> u32 {
+        self.threshold
     }
 
-    /// Find all photos that have at least one potential match based on aHash.
-    fn find_ahash_matches(
-        &self,
-        hashes: &[(PathBuf, Option<ImageHashValue>)],
-    ) -> std::collections::HashSet<PathBuf> {
-        let mut matches = std::collections::HashSet::new();
-
-        // Filter to only successfully hashed photos
-        let valid_hashes: Vec<(&PathBuf, &ImageHashValue)> = hashes
-            .iter()
-            .filter_map(|(p, h)| h.as_ref().map(|hash| (p, hash)))
-            .collect();
-
-        // O(n²) comparison, but aHash comparison is very fast
-        for i in 0..valid_hashes.len() {
-            for j in (i + 1)..valid_hashes.len() {
-                let (path_a, hash_a) = valid_hashes[i];
-                let (path_b, hash_b) = valid_hashes[j];
-
-                if hash_a.distance(hash_b) <= self.threshold {
-                    matches.insert(path_a.clone());
-                    matches.insert(path_b.clone());
-                }
-            }
-        }
+    /// Compute the phase-1 aHash of a photo, after applying its EXIF
+    /// orientation.
+    ///
+    /// JPEGs are decoded at 1/8 scale where they are large enough, since
+    /// aHash only looks at 8x8 pixels.
+    pub fn screen_hash(&self, path: &Path) -> Result<ImageHashValue, HashError> {
+        let image = FastDecoder::decode_for_hash(path, SCREEN_DECODE_SIZE)?;
+        self.ahash.hash_image(&image)
+    }
 
-        matches
+    /// Find every photo with at least one other photo within the loose
+    /// threshold; only these need phase 2.
+    pub fn find_candidates(&self, hashes: &[(PathBuf, ImageHashValue)]) -> HashSet<PathBuf> {
+        let strategy = ThresholdStrategy::new(self.threshold);
+        find_duplicate_pairs_indexed(hashes, &strategy)
+            .into_iter()
+            .flat_map(|m| [m.photo_a, m.photo_b])
+            .collect()
     }
 }
 
@@ -319,6 +356,7 @@ pub fn should_use_mmap(size: u64) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::hasher::HashAlgorithmKind;
     use crate::core::scanner::ImageFormat;
     use std::time::SystemTime;
 
@@ -328,6 +366,7 @@ mod tests {
             size,
             modified: SystemTime::now(),
             format: ImageFormat::Jpeg,
+            file_id: None,
         }
     }
 
@@ -377,13 +416,75 @@ mod tests {
             .collect();
 
         let config = OptimizationConfig::default();
-        let result = prefilter_candidates(&photos, &config);
+        let result = prefilter_candidates(&photos, &config, DetectionMode::Exact);
 
         // All photos should remain (below threshold)
         assert_eq!(result.candidates.len(), 50);
         assert_eq!(result.skipped_unique_size, 0);
     }
 
+    #[test]
+    fn exact_mode_skips_unique_sizes() {
+        let photos: Vec<PhotoFile> = (0..120)
+            .map(|i| create_test_photo(&format!("/{}.jpg", i), i as u64 * 1000))
+            .collect();
+
+        let config = OptimizationConfig::default();
+        let result = prefilter_candidates(&photos, &config, DetectionMode::Exact);
+
+        // Every size is unique, so nothing can be an exact copy
+        assert_eq!(result.mode, DetectionMode::Exact);
+        assert_eq!(result.candidates.len(), 0);
+        assert_eq!(result.skipped_unique_size, 120);
+    }
+
+    #[test]
+    fn perceptual_mode_hashes_everything() {
+        // A resized copy has a different byte size from its original
+        let photos: Vec<PhotoFile> = (0..120)
+            .map(|i| create_test_photo(&format!("/{}.jpg", i), i as u64 * 1000))
+            .collect();
+
+        let config = OptimizationConfig::default();
+        let result = prefilter_candidates(&photos, &config, DetectionMode::Perceptual);
+
+        assert_eq!(result.mode, DetectionMode::Perceptual);
+        assert_eq!(result.candidates.len(), 120);
+        assert_eq!(result.skipped_unique_size, 0);
+        assert_eq!(result.skipped_unique_prefix, 0);
+    }
+
+    #[test]
+    fn detection_mode_defaults_to_perceptual() {
+        assert_eq!(DetectionMode::default(), DetectionMode::Perceptual);
+    }
+
+    #[test]
+    fn two_phase_screens_with_loose_threshold() {
+        assert_eq!(TwoPhaseHasher::new(8).threshold(), 16);
+        // Exact passes still allow for re-encoding noise
+        assert_eq!(TwoPhaseHasher::new(0).threshold(), MIN_SCREEN_THRESHOLD);
+    }
+
+    #[test]
+    fn two_phase_keeps_only_photos_with_candidates() {
+        let hash = |bits: u64| {
+            ImageHashValue::new(bits.to_be_bytes().to_vec(), HashAlgorithmKind::Average)
+        };
+        let hashes = vec![
+            (PathBuf::from("/a.jpg"), hash(0)),
+            // 12 bits from a: past the comparison threshold, within the loose one
+            (PathBuf::from("/b.jpg"), hash(0xFFF)),
+            (PathBuf::from("/c.jpg"), hash(u64::MAX)),
+        ];
+
+        let candidates = TwoPhaseHasher::new(8).find_candidates(&hashes);
+
+        assert_eq!(candidates.len(), 2);
+        assert!(candidates.contains(Path::new("/a.jpg")));
+        assert!(candidates.contains(Path::new("/b.jpg")));
+    }
+
     #[test]
     fn mmap_threshold_check() {
         assert!(!should_use_mmap(100));
@@ -4,13 +4,14 @@
 //! Uses Laplacian variance for blur/sharpness detection.
 
 use image::{DynamicImage, GrayImage};
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
 use crate::core::hasher::fast_decode::FastDecoder;
 use crate::error::HashError;
 
 /// Quality metrics for an image
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct QualityScore {
     /// Sharpness score from Laplacian variance (higher = sharper)
     /// Typical range: 0-10000+ (very blurry to very sharp)
@@ -76,9 +77,9 @@ impl QualityAnalyzer {
         Self { analysis_size }
     }
 
-    /// Analyze quality of an image file
+    /// Analyze quality of an image file, after applying its EXIF orientation
     pub fn analyze_file(&self, path: &Path) -> Result<QualityScore, HashError> {
-        let image = FastDecoder::decode(path)?;
+        let image = FastDecoder::decode_for_hash(path, self.analysis_size)?;
         Ok(self.analyze_image(&image))
     }
 
@@ -0,0 +1,168 @@
+//! Fallback rendering of RAW sensor data.
+//!
+//! Used only when a RAW file has no usable embedded preview. Each 2x2 block
+//! of the colour filter array becomes one RGB pixel, which gives a half-size
+//! image without interpolation. That is plenty for perceptual hashing.
+
+use crate::error::HashError;
+use image::{DynamicImage, RgbImage};
+use rawloader::{RawImage, RawImageData};
+use std::path::Path;
+
+/// Display gamma applied to the linear sensor values
+const GAMMA: f32 = 1.0 / 2.2;
+
+/// Decode a RAW file's sensor data into a half-size RGB image
+///
+/// Pixels are returned in stored order; orientation is applied by the caller.
+pub fn demosaic(path: &Path) -> Result<DynamicImage, HashError> {
+    let error = |reason: String| HashError::DecodeError {
+        path: path.to_path_buf(),
+        reason,
+    };
+
+    let raw = rawloader::decode_file(path).map_err(|e| error(e.to_string()))?;
+    render(&raw)
+        .map(DynamicImage::ImageRgb8)
+        .ok_or_else(|| error("RAW image has no usable pixels".to_string()))
+}
+
+/// Render decoded sensor data, cropped to the usable area
+fn render(raw: &RawImage) -> Option<RgbImage> {
+    let [top, right, bottom, left] = raw.crops;
+    let crop_width = raw.width.checked_sub(left + right)?;
+    let crop_height = raw.height.checked_sub(top + bottom)?;
+    let step = if raw.cpp == 1 { 2 } else { 1 };
+    let (width, height) = (crop_width / step, crop_height / step);
+    if width == 0 || height == 0 || raw.cpp == 0 {
+        return None;
+    }
+
+    // Linear value of one sample, 0.0 (black) to 1.0 (white)
+    let sample = |row: usize, col: usize, channel: usize| -> f32 {
+        let index = (row * raw.width + col) * raw.cpp + channel;
+        let color = if raw.cpp == 1 {
+            raw.cfa.color_at(row, col)
+        } else {
+            channel
+        };
+        match &raw.data {
+            RawImageData::Integer(data) => {
+                let black = raw.blacklevels[color] as f32;
+                let white = raw.whitelevels[color] as f32;
+                let value = data.get(index).copied().unwrap_or(0) as f32;
+                (value - black) / (white - black).max(1.0)
+            }
+            RawImageData::Float(data) => data.get(index).copied().unwrap_or(0.0),
+        }
+    };
+
+    // White balance relative to green; missing coefficients are neutral
+    let coefficient = |c: usize| {
+        let value = raw.wb_coeffs[c];
+        if value.is_finite() && value > 0.0 {
+            value
+        } else {
+            1.0
+        }
+    };
+    let green = coefficient(1);
+    let balance = [coefficient(0) / green, 1.0, coefficient(2) / green];
+
+    let to_u8 = |linear: f32| (linear.clamp(0.0, 1.0).powf(GAMMA) * 255.0).round() as u8;
+
+    Some(RgbImage::from_fn(width as u32, height as u32, |x, y| {
+        let row = top + y as usize * step;
+        let col = left + x as usize * step;
+
+        let mut sums = [0.0f32; 3];
+        let mut counts = [0u32; 3];
+        if raw.cpp == 1 {
+            for (dy, dx) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+                // The fourth CFA colour (emerald or a second green) counts as green
+                let color = raw.cfa.color_at(row + dy, col + dx).min(3);
+                let channel = if color == 3 { 1 } else { color };
+                sums[channel] += sample(row + dy, col + dx, 0);
+                counts[channel] += 1;
+            }
+        } else {
+            for channel in 0..3.min(raw.cpp) {
+                sums[channel] = sample(row, col, channel);
+                counts[channel] = 1;
+            }
+        }
+
+        let value = |c: usize| {
+            let mean = if counts[c] > 0 {
+                sums[c] / counts[c] as f32
+            } else {
+                0.0
+            };
+            to_u8(mean * balance[c])
+        };
+        image::Rgb([value(0), value(1), value(2)])
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rawloader::{Orientation, CFA};
+
+    /// An RGGB sensor where every 2x2 block reads (red, green, green, blue)
+    fn bayer(width: usize, height: usize, rggb: [u16; 4]) -> RawImage {
+        let data = (0..height)
+            .flat_map(|row| (0..width).map(move |col| rggb[(row % 2) * 2 + col % 2]))
+            .collect();
+        RawImage {
+            make: String::new(),
+            model: String::new(),
+            clean_make: String::new(),
+            clean_model: String::new(),
+            width,
+            height,
+            cpp: 1,
+            wb_coeffs: [1.0, 1.0, 1.0, f32::NAN],
+            whitelevels: [4095; 4],
+            blacklevels: [0; 4],
+            xyz_to_cam: [[0.0; 3]; 4],
+            cfa: CFA::new("RGGB"),
+            crops: [0; 4],
+            blackareas: Vec::new(),
+            orientation: Orientation::Normal,
+            data: RawImageData::Integer(data),
+        }
+    }
+
+    #[test]
+    fn renders_half_size_superpixels() {
+        let image = render(&bayer(8, 6, [4095, 0, 0, 0])).unwrap();
+
+        assert_eq!(image.dimensions(), (4, 3));
+        assert_eq!(image.get_pixel(1, 1).0, [255, 0, 0]);
+    }
+
+    #[test]
+    fn applies_levels_crop_and_white_balance() {
+        let mut raw = bayer(10, 6, [1000, 1000, 1000, 1000]);
+        raw.blacklevels = [1000; 4];
+        raw.whitelevels = [2000; 4];
+        raw.crops = [0, 2, 2, 0];
+        raw.data = RawImageData::Integer(vec![1250; 60]);
+        raw.wb_coeffs = [4.0, 2.0, 1.0, 0.0];
+
+        let image = render(&raw).unwrap();
+
+        assert_eq!(image.dimensions(), (4, 2));
+        let [r, g, b] = image.get_pixel(0, 0).0;
+        assert!(r > g && g > b, "{:?}", [r, g, b]);
+    }
+
+    #[test]
+    fn empty_crop_has_no_image() {
+        let mut raw = bayer(4, 4, [0; 4]);
+        raw.crops = [2, 0, 2, 0];
+
+        assert!(render(&raw).is_none());
+    }
+}
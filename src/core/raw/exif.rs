@@ -0,0 +1,192 @@
+//! EXIF extraction from RAW containers that aren't plain TIFF.
+//!
+//! CR2, NEF, ARW and DNG are TIFF files, which the EXIF reader handles
+//! directly. The others need some help:
+//! - ORF is TIFF with a different magic number
+//! - CR3 keeps its IFDs as separate TIFF blocks (`CMT1`, `CMT2`, `CMT4`)
+//! - RAF only has EXIF inside its embedded JPEG
+
+use super::preview::{embedded_jpeg, jpeg_exif};
+
+/// TIFF tag pointing at the EXIF sub-IFD
+const EXIF_IFD_POINTER: u16 = 0x8769;
+/// TIFF tag pointing at the GPS sub-IFD
+const GPS_IFD_POINTER: u16 = 0x8825;
+
+/// Collect TIFF-formatted EXIF blocks from a RAW file, most complete first.
+///
+/// Each block can be parsed with `exif::Reader::read_raw`.
+pub fn exif_blocks(bytes: &[u8]) -> Vec<Vec<u8>> {
+    let mut blocks = Vec::new();
+
+    if let Some(tiff) = orf_as_tiff(bytes) {
+        blocks.push(tiff);
+    }
+
+    if is_cr3(bytes) {
+        if let Some(ifd0) = cr3_box(bytes, b"CMT1") {
+            blocks.push(ifd0.to_vec());
+        }
+        if let Some(exif) = cr3_box(bytes, b"CMT2").and_then(|b| wrap_ifd(b, EXIF_IFD_POINTER)) {
+            blocks.push(exif);
+        }
+        if let Some(gps) = cr3_box(bytes, b"CMT4").and_then(|b| wrap_ifd(b, GPS_IFD_POINTER)) {
+            blocks.push(gps);
+        }
+    }
+
+    if let Some(exif) = embedded_jpeg(bytes).and_then(|jpeg| jpeg_exif(jpeg.data)) {
+        blocks.push(exif.to_vec());
+    }
+
+    blocks
+}
+
+/// Byte order of a TIFF header, if `bytes` starts with one
+fn tiff_little_endian(bytes: &[u8]) -> Option<bool> {
+    match bytes.get(..4)? {
+        [b'I', b'I', 42, 0] => Some(true),
+        [b'M', b'M', 0, 42] => Some(false),
+        _ => None,
+    }
+}
+
+/// Olympus ORF: a TIFF whose magic number is "RO" or "RS" instead of 42
+fn orf_as_tiff(bytes: &[u8]) -> Option<Vec<u8>> {
+    let little_endian = match bytes.get(..4)? {
+        b"IIRO" | b"IIRS" => true,
+        b"MMOR" => false,
+        _ => return None,
+    };
+
+    let mut tiff = bytes.to_vec();
+    tiff[2..4].copy_from_slice(&if little_endian { [42, 0] } else { [0, 42] });
+    Some(tiff)
+}
+
+/// Canon CR3: an ISO base media file with the `crx ` brand
+fn is_cr3(bytes: &[u8]) -> bool {
+    bytes.get(4..12) == Some(b"ftypcrx ")
+}
+
+/// Find a CR3 metadata box by type; its payload is a complete TIFF
+fn cr3_box<'a>(bytes: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+    let mut from = 4;
+    while let Some(offset) = bytes[from..].windows(4).position(|w| w == kind) {
+        let at = from + offset;
+        let size = u32::from_be_bytes(bytes[at - 4..at].try_into().ok()?) as usize;
+        if let Some(payload) = bytes.get(at + 4..(at - 4).saturating_add(size)) {
+            if tiff_little_endian(payload).is_some() {
+                return Some(payload);
+            }
+        }
+        from = at + 4;
+    }
+    None
+}
+
+/// Turn a TIFF whose first IFD is really a sub-IFD (EXIF or GPS) into one
+/// whose IFD0 points at it, so its tags are read in the right context.
+///
+/// Appends a one-entry IFD0 and repoints the header at it; the original
+/// offsets stay valid because nothing before them moves.
+fn wrap_ifd(block: &[u8], pointer_tag: u16) -> Option<Vec<u8>> {
+    let little_endian = tiff_little_endian(block)?;
+    let u16_bytes = |v: u16| {
+        if little_endian {
+            v.to_le_bytes()
+        } else {
+            v.to_be_bytes()
+        }
+    };
+    let u32_bytes = |v: u32| {
+        if little_endian {
+            v.to_le_bytes()
+        } else {
+            v.to_be_bytes()
+        }
+    };
+
+    let sub_ifd = block.get(4..8)?;
+    let mut tiff = block.to_vec();
+    // IFDs start on a word boundary
+    if tiff.len() % 2 == 1 {
+        tiff.push(0);
+    }
+    let ifd0 = u32::try_from(tiff.len()).ok()?;
+
+    tiff.extend_from_slice(&u16_bytes(1));
+    tiff.extend_from_slice(&u16_bytes(pointer_tag));
+    tiff.extend_from_slice(&u16_bytes(4)); // LONG
+    tiff.extend_from_slice(&u32_bytes(1));
+    tiff.extend_from_slice(sub_ifd);
+    tiff.extend_from_slice(&u32_bytes(0));
+    tiff[4..8].copy_from_slice(&u32_bytes(ifd0));
+    Some(tiff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_tiff::{ascii, short, tiff, ASCII, SHORT};
+    use super::*;
+    use exif::{In, Reader, Tag, Value};
+
+    fn read(block: Vec<u8>) -> exif::Exif {
+        Reader::new().read_raw(block).unwrap()
+    }
+
+    fn ascii_field(exif: &exif::Exif, tag: Tag) -> Option<String> {
+        match &exif.get_field(tag, In::PRIMARY)?.value {
+            Value::Ascii(values) => Some(String::from_utf8_lossy(values.first()?).into_owned()),
+            _ => None,
+        }
+    }
+
+    fn cr3_box_bytes(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    #[test]
+    fn orf_magic_is_patched() {
+        let mut orf = tiff(&[(0x0112, SHORT, short(6))]);
+        orf[2..4].copy_from_slice(b"RO");
+
+        let blocks = exif_blocks(&orf);
+
+        assert_eq!(blocks.len(), 1);
+        let exif = read(blocks[0].clone());
+        let orientation = exif.get_field(Tag::Orientation, In::PRIMARY).unwrap();
+        assert_eq!(orientation.value.get_uint(0), Some(6));
+    }
+
+    #[test]
+    fn cr3_boxes_are_read_in_context() {
+        let ifd0 = tiff(&[(0x010F, ASCII, ascii("Canon"))]);
+        // DateTimeOriginal, stored as the first IFD of its own TIFF
+        let exif_ifd = tiff(&[(0x9003, ASCII, ascii("2024:05:01 10:20:30"))]);
+        let mut cr3 = vec![0, 0, 0, 24];
+        cr3.extend_from_slice(b"ftypcrx \0\0\0\x01crx isom");
+        cr3.extend_from_slice(&cr3_box_bytes(b"CMT1", &ifd0));
+        cr3.extend_from_slice(&cr3_box_bytes(b"CMT2", &exif_ifd));
+
+        let blocks = exif_blocks(&cr3);
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(
+            ascii_field(&read(blocks[0].clone()), Tag::Make).as_deref(),
+            Some("Canon")
+        );
+        assert_eq!(
+            ascii_field(&read(blocks[1].clone()), Tag::DateTimeOriginal).as_deref(),
+            Some("2024:05:01 10:20:30")
+        );
+    }
+
+    #[test]
+    fn plain_bytes_have_no_blocks() {
+        assert!(exif_blocks(b"not a raw file at all").is_empty());
+    }
+}
@@ -0,0 +1,118 @@
+//! # RAW Module
+//!
+//! Camera RAW support: finding the embedded JPEG preview, reading EXIF out
+//! of RAW containers, and a fallback demosaic for files without a preview.
+//!
+//! ## Supported Formats
+//! - Canon (.cr2, .cr3)
+//! - Nikon (.nef)
+//! - Sony (.arw)
+//! - Fujifilm (.raf)
+//! - Olympus (.orf)
+//! - Adobe DNG (.dng)
+//!
+//! ## How It Works
+//! Almost every RAW file carries a camera-rendered JPEG, usually at full
+//! resolution. [`embedded_jpeg`] finds the largest one without knowing the
+//! container layout, by walking every JPEG stream in the file. Hashing that
+//! preview is much faster than demosaicing the sensor data, and matches the
+//! JPEG the camera saved alongside it. [`demosaic`] renders the sensor data
+//! at half resolution when there is no usable preview.
+
+mod demosaic;
+mod exif;
+mod preview;
+
+pub use demosaic::demosaic;
+pub use exif::exif_blocks;
+pub use preview::{embedded_jpeg, jpeg_exif, EmbeddedJpeg};
+
+use std::path::Path;
+
+/// File extensions of supported camera RAW formats
+pub const RAW_EXTENSIONS: &[&str] = &["cr2", "cr3", "nef", "arw", "raf", "orf", "dng"];
+
+/// Previews with a shorter long edge than this are thumbnails, not previews
+pub const MIN_PREVIEW_SIZE: u32 = 1024;
+
+/// Check if a file extension is a camera RAW format
+pub fn is_raw_extension(ext: &str) -> bool {
+    let ext = ext.to_lowercase();
+    RAW_EXTENSIONS.contains(&ext.as_str())
+}
+
+/// Check if a path has a camera RAW extension
+pub fn is_raw_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(is_raw_extension)
+}
+
+/// Minimal little-endian TIFF writer for building RAW-like test files
+#[cfg(test)]
+pub(crate) mod test_tiff {
+    /// TIFF field types used by the tests
+    pub const ASCII: u16 = 2;
+    pub const SHORT: u16 = 3;
+
+    /// Build a TIFF with one IFD holding `entries` of (tag, type, value)
+    ///
+    /// Values longer than four bytes are stored after the IFD.
+    pub fn tiff(entries: &[(u16, u16, Vec<u8>)]) -> Vec<u8> {
+        let mut entries = entries.to_vec();
+        entries.sort_by_key(|(tag, _, _)| *tag);
+
+        let ifd_len = 2 + entries.len() * 12 + 4;
+        let mut out = b"II*\0".to_vec();
+        out.extend_from_slice(&8u32.to_le_bytes());
+        out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+
+        let mut extra = Vec::new();
+        for (tag, kind, value) in &entries {
+            let unit = if *kind == SHORT { 2 } else { 1 };
+            out.extend_from_slice(&tag.to_le_bytes());
+            out.extend_from_slice(&kind.to_le_bytes());
+            out.extend_from_slice(&((value.len() / unit) as u32).to_le_bytes());
+            if value.len() <= 4 {
+                let mut inline = value.clone();
+                inline.resize(4, 0);
+                out.extend_from_slice(&inline);
+            } else {
+                let offset = 8 + ifd_len + extra.len();
+                out.extend_from_slice(&(offset as u32).to_le_bytes());
+                extra.extend_from_slice(value);
+            }
+        }
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&extra);
+        out
+    }
+
+    /// A NUL-terminated ASCII value
+    pub fn ascii(text: &str) -> Vec<u8> {
+        let mut value = text.as_bytes().to_vec();
+        value.push(0);
+        value
+    }
+
+    /// A single SHORT value
+    pub fn short(value: u16) -> Vec<u8> {
+        value.to_le_bytes().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_raw_extensions() {
+        for ext in ["cr2", "CR3", "nef", "arw", "RAF", "orf", "dng"] {
+            assert!(is_raw_extension(ext), "{}", ext);
+        }
+        assert!(!is_raw_extension("jpg"));
+        assert!(is_raw_path(Path::new("/photos/IMG_0001.CR2")));
+        assert!(!is_raw_path(Path::new("/photos/IMG_0001.JPG")));
+        assert!(!is_raw_path(Path::new("/photos/raw")));
+    }
+}
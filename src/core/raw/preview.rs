@@ -0,0 +1,198 @@
+//! Embedded JPEG preview extraction.
+//!
+//! Walks every JPEG stream in a file instead of parsing each maker's
+//! container. Lossless JPEG (used for CR2 and DNG sensor data) is skipped,
+//! since it holds raw sensor values rather than a viewable image.
+
+/// A JPEG stream found inside another file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmbeddedJpeg<'a> {
+    /// The JPEG bytes, from SOI to EOI
+    pub data: &'a [u8],
+    /// Width from the frame header
+    pub width: u32,
+    /// Height from the frame header
+    pub height: u32,
+}
+
+impl EmbeddedJpeg<'_> {
+    /// Number of pixels in the image
+    pub fn pixels(&self) -> u64 {
+        self.width as u64 * self.height as u64
+    }
+}
+
+/// Find the largest baseline or progressive JPEG embedded in a file
+pub fn embedded_jpeg(bytes: &[u8]) -> Option<EmbeddedJpeg<'_>> {
+    let mut best: Option<EmbeddedJpeg> = None;
+    let mut pos = 0;
+
+    while let Some(offset) = find(&bytes[pos..], &[0xFF, 0xD8, 0xFF]) {
+        let start = pos + offset;
+        match parse_jpeg(bytes, start) {
+            Some(jpeg) => {
+                if best.is_none_or(|b| jpeg.pixels() > b.pixels()) {
+                    best = Some(jpeg);
+                }
+                // Thumbnails nested in the preview's EXIF are smaller anyway
+                pos = start + jpeg.data.len();
+            }
+            None => pos = start + 1,
+        }
+    }
+
+    best
+}
+
+/// Get the TIFF-formatted EXIF block from a JPEG's APP1 segment
+pub fn jpeg_exif(jpeg: &[u8]) -> Option<&[u8]> {
+    let mut pos = 2;
+    loop {
+        let (marker, segment, next) = segment_at(jpeg, pos)?;
+        if marker == 0xE1 && segment.starts_with(b"Exif\0\0") {
+            return Some(&segment[6..]);
+        }
+        if marker == 0xDA {
+            return None;
+        }
+        pos = next;
+    }
+}
+
+/// Parse the JPEG stream starting at `start`, if it is a valid one
+fn parse_jpeg(bytes: &[u8], start: usize) -> Option<EmbeddedJpeg<'_>> {
+    let mut pos = start + 2;
+    let mut size = None;
+
+    // Header segments, up to the first scan
+    loop {
+        let (marker, segment, next) = segment_at(bytes, pos)?;
+        match marker {
+            // Baseline, extended sequential and progressive frames
+            0xC0..=0xC2 => {
+                let height = u16::from_be_bytes([*segment.get(1)?, *segment.get(2)?]) as u32;
+                let width = u16::from_be_bytes([*segment.get(3)?, *segment.get(4)?]) as u32;
+                size = Some((width, height));
+            }
+            // Lossless, hierarchical and arithmetic-coded frames
+            0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => return None,
+            _ => {}
+        }
+        pos = next;
+        if marker == 0xDA {
+            break;
+        }
+    }
+
+    let (width, height) = size.filter(|&(w, h)| w > 0 && h > 0)?;
+
+    // Byte stuffing keeps FF D9 out of entropy-coded data
+    let end = pos + find(&bytes[pos..], &[0xFF, 0xD9])? + 2;
+    Some(EmbeddedJpeg {
+        data: &bytes[start..end],
+        width,
+        height,
+    })
+}
+
+/// Read the marker segment at `pos`: (marker, payload, next position)
+///
+/// Returns None at SOI, EOI or anything that isn't a segment.
+fn segment_at(bytes: &[u8], mut pos: usize) -> Option<(u8, &[u8], usize)> {
+    if *bytes.get(pos)? != 0xFF {
+        return None;
+    }
+    // Markers may be preceded by fill bytes
+    while *bytes.get(pos)? == 0xFF {
+        pos += 1;
+    }
+    let marker = bytes[pos];
+    if matches!(marker, 0x00 | 0x01 | 0xD0..=0xD9) {
+        return None;
+    }
+
+    let length = u16::from_be_bytes([*bytes.get(pos + 1)?, *bytes.get(pos + 2)?]) as usize;
+    if length < 2 {
+        return None;
+    }
+    let end = pos + 1 + length;
+    Some((marker, bytes.get(pos + 3..end)?, end))
+}
+
+/// Position of the first occurrence of `needle`
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_tiff::{short, tiff, SHORT};
+    use super::*;
+    use image::codecs::jpeg::JpegEncoder;
+    use image::{ImageEncoder, RgbImage};
+
+    fn jpeg(width: u32, height: u32) -> Vec<u8> {
+        let img = RgbImage::from_fn(width, height, |x, y| {
+            image::Rgb([x as u8, y as u8, (x ^ y) as u8])
+        });
+        let mut out = Vec::new();
+        JpegEncoder::new(&mut out)
+            .write_image(img.as_raw(), width, height, image::ExtendedColorType::Rgb8)
+            .unwrap();
+        out
+    }
+
+    /// A stand-in for lossless sensor data: SOI, a SOF3 frame and a scan
+    fn lossless_jpeg() -> Vec<u8> {
+        let mut out = vec![0xFF, 0xD8];
+        out.extend_from_slice(&[0xFF, 0xC3, 0x00, 0x0B, 0x0E, 0x10, 0x00, 0x20, 0x00, 0x01]);
+        out.extend_from_slice(&[0x01, 0x11, 0x00]);
+        out.extend_from_slice(&[0xFF, 0xDA, 0x00, 0x08, 0x01, 0x01, 0x00, 0x01, 0x00, 0x00]);
+        out.extend_from_slice(&[0x12; 64]);
+        out.extend_from_slice(&[0xFF, 0xD9]);
+        out
+    }
+
+    #[test]
+    fn finds_largest_preview() {
+        let thumbnail = jpeg(64, 48);
+        let preview = jpeg(320, 240);
+        let mut file = b"II*\0 raw container ".to_vec();
+        file.extend_from_slice(&thumbnail);
+        file.extend_from_slice(&[0xAB; 100]);
+        file.extend_from_slice(&preview);
+        file.extend_from_slice(&lossless_jpeg());
+
+        let found = embedded_jpeg(&file).unwrap();
+
+        assert_eq!((found.width, found.height), (320, 240));
+        assert_eq!(found.data, &preview[..]);
+    }
+
+    #[test]
+    fn skips_lossless_sensor_data() {
+        let mut file = lossless_jpeg();
+        file.extend_from_slice(&[0xFF, 0xD8, 0xFF]);
+
+        assert_eq!(embedded_jpeg(&file), None);
+    }
+
+    #[test]
+    fn reads_exif_from_app1() {
+        let exif = tiff(&[(0x0112, SHORT, short(6))]);
+        let plain = jpeg(16, 16);
+        let mut with_exif = plain[..2].to_vec();
+        with_exif.extend_from_slice(&[0xFF, 0xE1]);
+        with_exif.extend_from_slice(&((exif.len() + 8) as u16).to_be_bytes());
+        with_exif.extend_from_slice(b"Exif\0\0");
+        with_exif.extend_from_slice(&exif);
+        with_exif.extend_from_slice(&plain[2..]);
+
+        assert_eq!(jpeg_exif(&with_exif), Some(&exif[..]));
+        assert_eq!(jpeg_exif(&plain), None);
+        // The EXIF segment doesn't hide the preview
+        assert_eq!(embedded_jpeg(&with_exif).unwrap().width, 16);
+    }
+}
@@ -1,7 +1,8 @@
 //! Human-readable explanations for duplicate matches.
 
-use crate::core::comparator::MatchType;
-use crate::core::hasher::HashAlgorithmKind;
+use crate::core::comparator::{DuplicateGroup, MatchResult, MatchType, REFERENCE_HASH_BITS};
+use crate::core::hasher::{CropRegion, DihedralTransform, HashAlgorithmKind};
+use crate::core::metadata::{MetadataDifference, PhotoMetadata};
 use crate::error::ReportError;
 use serde::{Deserialize, Serialize};
 
@@ -20,6 +21,17 @@ pub struct DuplicateExplanation {
     pub technical: TechnicalDetails,
     /// Human-friendly explanation
     pub human_readable: String,
+    /// Rotation or mirror needed to match (None for upright matches)
+    #[serde(default)]
+    pub transform: Option<DihedralTransform>,
+    /// Part of the original the cropped photo shows (None unless one photo
+    /// is a crop of the other)
+    #[serde(default)]
+    pub crop: Option<CropRegion>,
+    /// Metadata fields that differ between the photos (only filled in for
+    /// [`MatchType::SamePixelsDifferentMetadata`])
+    #[serde(default)]
+    pub metadata_differences: Vec<MetadataDifference>,
 }
 
 /// Technical details about the hash comparison
@@ -64,12 +76,82 @@ impl DuplicateExplanation {
                 total_bits,
             },
             human_readable,
+            transform: None,
+            crop: None,
+            metadata_differences: Vec::new(),
         }
     }
 
+    /// List the metadata fields that differ between the photos
+    ///
+    /// Appends them to the explanation, so it says what sets the copies
+    /// apart when their pixels are the same.
+    pub fn with_metadata_differences(mut self, differences: Vec<MetadataDifference>) -> Self {
+        if differences.is_empty() {
+            self.human_readable
+                .push_str(" None of the metadata fields we read differ.");
+        } else {
+            let listed: Vec<String> = differences.iter().map(|d| d.to_string()).collect();
+            self.human_readable
+                .push_str(&format!(" Differences: {}.", listed.join("; ")));
+        }
+        self.metadata_differences = differences;
+        self
+    }
+
+    /// Mark this as a rotated or mirrored copy
+    ///
+    /// Rewrites the summary and explanation to say so.
+    pub fn with_transform(mut self, transform: DihedralTransform) -> Self {
+        if transform == DihedralTransform::Identity {
+            return self;
+        }
+
+        let kind = if transform.is_mirrored() {
+            "mirrored"
+        } else {
+            "rotated"
+        };
+        self.summary = format!(
+            "These photos are a {} copy ({}, {:.0}% match)",
+            kind, transform, self.similarity_percent
+        );
+        self.human_readable = format!(
+            "One photo is a {} copy of the other: once it is {}, they match. {}",
+            kind, transform, self.human_readable
+        );
+        self.transform = Some(transform);
+        self
+    }
+
+    /// Mark this as a cropped copy
+    ///
+    /// Rewrites the summary and explanation to say which part of the
+    /// original the cropped photo shows.
+    pub fn with_crop(mut self, region: CropRegion) -> Self {
+        self.summary = format!(
+            "One photo is a cropped copy of the other ({:.0}% of the original, {:.0}% match)",
+            region.area() * 100.0,
+            self.similarity_percent
+        );
+        self.human_readable = format!(
+            "One photo is a cropped copy of the other: it shows roughly {} of the original. {}",
+            region, self.human_readable
+        );
+        self.crop = Some(region);
+        self
+    }
+
     /// Generate a one-line summary
     fn generate_summary(match_type: MatchType, similarity: f64) -> String {
         match match_type {
+            MatchType::Hardlinked => {
+                "These paths are hardlinks to one file - it's already stored once".to_string()
+            }
+            MatchType::ByteIdentical => "These files are bit-for-bit identical".to_string(),
+            MatchType::SamePixelsDifferentMetadata => {
+                "These photos have identical pixels but different metadata".to_string()
+            }
             MatchType::Exact => "These photos are identical".to_string(),
             MatchType::NearExact => {
                 format!("These photos are virtually identical ({:.0}% match)", similarity)
@@ -92,6 +174,19 @@ impl DuplicateExplanation {
     /// Generate a human-readable explanation
     fn generate_human_readable(match_type: MatchType, distance: u32, total_bits: u32) -> String {
         match match_type {
+            MatchType::Hardlinked => "These paths are hardlinks: different names for the \
+                 same file on disk, which is stored only once. Deleting one of them frees \
+                 no space, as the file stays until every path to it is removed."
+                .to_string(),
+            MatchType::ByteIdentical => "These files are bit-for-bit identical. \
+                 Every byte matches, so they are copies of the same file and \
+                 deleting all but one loses nothing."
+                .to_string(),
+            MatchType::SamePixelsDifferentMetadata => "These photos decode to exactly the same \
+                 pixels, but the files differ - usually only in metadata such as EXIF tags, \
+                 GPS position or the software that saved them. Check the differences before \
+                 deleting, in case one copy keeps information the other lost."
+                .to_string(),
             MatchType::Exact => {
                 "These photos produce the exact same visual fingerprint. \
                  They show identical content - they may be exact file copies, \
@@ -131,12 +226,23 @@ impl DuplicateExplanation {
 /// Reporter that generates detailed explanations
 pub struct DetailedReporter {
     algorithm: HashAlgorithmKind,
+    /// Bits in each hash, which match distances are counted in
+    hash_bits: u32,
 }
 
 impl DetailedReporter {
     /// Create a new detailed reporter
     pub fn new(algorithm: HashAlgorithmKind) -> Self {
-        Self { algorithm }
+        Self {
+            algorithm,
+            hash_bits: REFERENCE_HASH_BITS,
+        }
+    }
+
+    /// Set the hash size the matches were found with (the hash has size² bits)
+    pub fn hash_size(mut self, hash_size: u32) -> Self {
+        self.hash_bits = hash_size * hash_size;
+        self
     }
 
     /// Generate an explanation for a match
@@ -163,6 +269,70 @@ impl DetailedReporter {
             total_bits,
         ))
     }
+
+    /// Explain a match found by the comparator
+    ///
+    /// Calls out a rotated, mirrored or cropped copy when the match was
+    /// only found that way.
+    pub fn explain_match(&self, m: &MatchResult) -> DuplicateExplanation {
+        let mut explanation = DuplicateExplanation::new(
+            m.match_type,
+            m.distance,
+            m.similarity_percent,
+            self.algorithm,
+            String::new(),
+            String::new(),
+            self.hash_bits,
+        );
+        if let Some(transform) = m.transform {
+            explanation = explanation.with_transform(transform);
+        }
+        if let Some(crop) = &m.crop {
+            explanation = explanation.with_crop(crop.region);
+        }
+        explanation
+    }
+
+    /// Explain a duplicate group through its weakest match
+    ///
+    /// Groups without one, such as hardlinks, are explained as a match of
+    /// their type between identical fingerprints.
+    pub fn explain_group(&self, group: &DuplicateGroup) -> DuplicateExplanation {
+        match &group.weakest_match {
+            Some(m) => self.explain_match(m),
+            None => DuplicateExplanation::new(
+                group.match_type,
+                0,
+                100.0,
+                self.algorithm,
+                String::new(),
+                String::new(),
+                self.hash_bits,
+            ),
+        }
+    }
+
+    /// Explain a pair of photos with the same pixels, listing which of
+    /// their metadata fields differ
+    ///
+    /// Takes the metadata rather than the paths, so callers can pass what
+    /// the scan already read instead of reading each file again.
+    pub fn explain_same_pixels(
+        &self,
+        metadata_a: &PhotoMetadata,
+        metadata_b: &PhotoMetadata,
+    ) -> DuplicateExplanation {
+        DuplicateExplanation::new(
+            MatchType::SamePixelsDifferentMetadata,
+            0,
+            100.0,
+            self.algorithm,
+            String::new(),
+            String::new(),
+            0,
+        )
+        .with_metadata_differences(metadata_a.differences(metadata_b))
+    }
 }
 
 impl Default for DetailedReporter {
@@ -174,6 +344,8 @@ impl Default for DetailedReporter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::comparator::TransitiveGrouper;
+    use std::path::PathBuf;
 
     #[test]
     fn exact_match_summary_is_clear() {
@@ -190,6 +362,24 @@ mod tests {
         assert!(explanation.summary.contains("identical"));
     }
 
+    #[test]
+    fn byte_identical_says_bit_for_bit() {
+        let explanation = DuplicateExplanation::new(
+            MatchType::ByteIdentical,
+            0,
+            100.0,
+            HashAlgorithmKind::Difference,
+            "ff00".to_string(),
+            "ff00".to_string(),
+            16,
+        );
+
+        assert_eq!(explanation.summary, "These files are bit-for-bit identical");
+        assert!(explanation
+            .human_readable
+            .starts_with("These files are bit-for-bit identical."));
+    }
+
     #[test]
     fn near_exact_includes_percentage() {
         let explanation = DuplicateExplanation::new(
@@ -224,6 +414,151 @@ mod tests {
         assert_eq!(explanation.technical.hash_a, "deadbeef");
     }
 
+    #[test]
+    fn rotated_copy_is_called_out() {
+        let explanation = DuplicateExplanation::new(
+            MatchType::Exact,
+            0,
+            100.0,
+            HashAlgorithmKind::Difference,
+            "ff00".to_string(),
+            "ff00".to_string(),
+            16,
+        )
+        .with_transform(DihedralTransform::Rotate90);
+
+        assert!(explanation.summary.contains("rotated copy"));
+        assert!(explanation.human_readable.contains("rotated 90°"));
+        assert_eq!(explanation.transform, Some(DihedralTransform::Rotate90));
+    }
+
+    #[test]
+    fn cropped_copy_reports_region() {
+        let region = CropRegion {
+            x: 0.25,
+            y: 0.0,
+            width: 0.75,
+            height: 0.5,
+        };
+        let explanation = DuplicateExplanation::new(
+            MatchType::NearExact,
+            2,
+            96.875,
+            HashAlgorithmKind::CropResistant,
+            "ff00".to_string(),
+            "ff03".to_string(),
+            64,
+        )
+        .with_crop(region);
+
+        assert!(explanation.summary.contains("cropped copy"));
+        assert!(explanation.summary.contains("38% of the original"));
+        assert!(explanation.human_readable.contains("75% × 50%"));
+        assert!(explanation.human_readable.contains("25% from the left"));
+        assert_eq!(explanation.crop, Some(region));
+    }
+
+    #[test]
+    fn explain_match_calls_out_transform_and_crop() {
+        let reporter = DetailedReporter::new(HashAlgorithmKind::CropResistant);
+        let region = CropRegion {
+            x: 0.0,
+            y: 0.0,
+            width: 0.5,
+            height: 0.5,
+        };
+        let m = MatchResult {
+            photo_a: "/a.jpg".into(),
+            photo_b: "/b.jpg".into(),
+            distance: 3,
+            similarity_percent: 95.3125,
+            match_type: MatchType::NearExact,
+            confidence: None,
+            transform: Some(DihedralTransform::FlipHorizontal),
+            crop: Some(crate::core::comparator::CropMatch {
+                original: "/a.jpg".into(),
+                cropped: "/b.jpg".into(),
+                region,
+            }),
+        };
+
+        let explanation = reporter.explain_match(&m);
+
+        assert_eq!(
+            explanation.transform,
+            Some(DihedralTransform::FlipHorizontal)
+        );
+        assert_eq!(explanation.crop, Some(region));
+        assert!(explanation.summary.contains("cropped copy"));
+        assert!(explanation.human_readable.contains("mirrored copy"));
+        assert!(explanation.human_readable.contains("3 out of 64"));
+    }
+
+    #[test]
+    fn explain_group_counts_distance_in_hash_bits() {
+        let reporter = DetailedReporter::new(HashAlgorithmKind::Difference).hash_size(16);
+        let mut group = DuplicateGroup::new(
+            vec!["/a.jpg".into(), "/b.jpg".into()],
+            "/a.jpg".into(),
+            MatchType::Similar,
+        );
+        group.weakest_match = Some(MatchResult {
+            photo_a: "/a.jpg".into(),
+            photo_b: "/b.jpg".into(),
+            distance: 32,
+            similarity_percent: 87.5,
+            match_type: MatchType::Similar,
+            confidence: None,
+            transform: Some(DihedralTransform::Rotate180),
+            crop: None,
+        });
+
+        let explanation = reporter.explain_group(&group);
+
+        assert_eq!(explanation.hash_distance, 32);
+        assert_eq!(explanation.technical.total_bits, 256);
+        assert_eq!(explanation.similarity_percent, 87.5);
+        assert_eq!(explanation.transform, Some(DihedralTransform::Rotate180));
+        assert!(explanation.summary.contains("rotated copy"));
+    }
+
+    #[test]
+    fn explain_group_describes_its_weakest_pair() {
+        let pair = |a: &str, b: &str, distance, match_type, transform| MatchResult {
+            photo_a: a.into(),
+            photo_b: b.into(),
+            distance,
+            similarity_percent: 100.0 - distance as f64 * 1.5625,
+            match_type,
+            confidence: None,
+            transform,
+            crop: None,
+        };
+        let matches = vec![
+            pair("/a.jpg", "/b.jpg", 0, MatchType::ByteIdentical, None),
+            pair(
+                "/b.jpg",
+                "/c.jpg",
+                2,
+                MatchType::NearExact,
+                Some(DihedralTransform::Rotate90),
+            ),
+            pair("/c.jpg", "/d.jpg", 6, MatchType::Similar, None),
+        ];
+        let group = TransitiveGrouper::new().group(&matches).remove(0);
+
+        let explanation =
+            DetailedReporter::new(HashAlgorithmKind::Difference).explain_group(&group);
+
+        // C~D is the weakest pair, and it isn't rotated
+        assert_eq!(explanation.match_type, MatchType::Similar);
+        assert_eq!(explanation.hash_distance, 6);
+        assert_eq!(explanation.transform, None);
+        let weakest = group.weakest_match.unwrap();
+        assert_eq!(weakest.photo_a, PathBuf::from("/c.jpg"));
+        assert_eq!(weakest.photo_b, PathBuf::from("/d.jpg"));
+    }
+
     #[test]
     fn human_readable_is_non_technical() {
         let explanation = DuplicateExplanation::new(
@@ -255,4 +590,50 @@ mod tests {
         assert_eq!(explanation.match_type, MatchType::NearExact);
         assert_eq!(explanation.hash_distance, 3);
     }
+
+    #[test]
+    fn same_pixels_lists_metadata_differences() {
+        let explanation = DuplicateExplanation::new(
+            MatchType::SamePixelsDifferentMetadata,
+            0,
+            100.0,
+            HashAlgorithmKind::Difference,
+            String::new(),
+            String::new(),
+            0,
+        )
+        .with_metadata_differences(vec![MetadataDifference {
+            field: "Software".to_string(),
+            a: Some("Camera".to_string()),
+            b: Some("Editor".to_string()),
+        }]);
+
+        assert!(explanation.summary.contains("identical pixels"));
+        assert!(explanation
+            .human_readable
+            .ends_with("Differences: Software: Camera vs Editor."));
+        assert_eq!(explanation.metadata_differences.len(), 1);
+    }
+
+    #[test]
+    fn explain_same_pixels_compares_metadata() {
+        let reporter = DetailedReporter::default();
+        let original = PhotoMetadata {
+            camera_model: Some("X100V".to_string()),
+            ..Default::default()
+        };
+        let stripped = PhotoMetadata::default();
+
+        let explanation = reporter.explain_same_pixels(&original, &stripped);
+
+        assert_eq!(
+            explanation.match_type,
+            MatchType::SamePixelsDifferentMetadata
+        );
+        assert_eq!(explanation.metadata_differences.len(), 1);
+        assert!(explanation.human_readable.contains("X100V vs none"));
+
+        let unchanged = reporter.explain_same_pixels(&original, &original);
+        assert!(unchanged.metadata_differences.is_empty());
+    }
 }
@@ -264,7 +264,10 @@ pub fn export_html<W: Write>(
     // Groups
     for group in groups {
         let match_class = match group.match_type {
-            crate::core::comparator::MatchType::Exact => "match-exact",
+            crate::core::comparator::MatchType::Hardlinked
+            | crate::core::comparator::MatchType::ByteIdentical
+            | crate::core::comparator::MatchType::SamePixelsDifferentMetadata
+            | crate::core::comparator::MatchType::Exact => "match-exact",
             crate::core::comparator::MatchType::NearExact => "match-nearexact",
             _ => "match-similar",
         };
@@ -394,7 +397,12 @@ mod tests {
             representative: PathBuf::from("/photos/original.jpg"),
             match_type: MatchType::Exact,
             average_distance: 0.0,
+            max_distance: None,
             duplicate_size_bytes: 5_000_000,
+            confidence: None,
+            transform: None,
+            crop: None,
+            weakest_match: None,
         }
     }
 
@@ -19,6 +19,7 @@ pub use explanation::{DuplicateExplanation, DetailedReporter};
 pub use export::{export_csv, export_html, export_to_file, ExportFormat};
 pub use visualization::HashVisualizer;
 
+use crate::core::raw::is_raw_path;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::time::SystemTime;
@@ -93,6 +94,17 @@ pub fn select_representative(photos: &[PhotoInfo]) -> (PathBuf, KeepReason) {
         panic!("Cannot select from empty photo list");
     }
 
+    // A RAW original beats any copy rendered from it; choose among the RAWs
+    let raws: Vec<PhotoInfo> = photos
+        .iter()
+        .filter(|p| is_raw_path(&p.path))
+        .cloned()
+        .collect();
+    if !raws.is_empty() && raws.len() < photos.len() {
+        let (path, _) = select_representative(&raws);
+        return (path, KeepReason::OriginalFormat);
+    }
+
     // Strategy: Prefer highest resolution, then largest file size, then oldest
 
     // Find highest resolution
@@ -192,6 +204,20 @@ mod tests {
         assert_eq!(reason, KeepReason::LargestFileSize);
     }
 
+    #[test]
+    fn select_prefers_raw_original() {
+        let photos = vec![
+            create_photo_info("/IMG_0001.jpg", 9000, Some((6000, 4000))),
+            create_photo_info("/IMG_0001.CR2", 5000, Some((3000, 2000))),
+            create_photo_info("/IMG_0001_edit.dng", 4000, None),
+        ];
+
+        let (path, reason) = select_representative(&photos);
+
+        assert_eq!(path, PathBuf::from("/IMG_0001.CR2"));
+        assert_eq!(reason, KeepReason::OriginalFormat);
+    }
+
     #[test]
     fn keep_reason_display() {
         assert!(KeepReason::HighestResolution.to_string().contains("resolution"));
@@ -0,0 +1,253 @@
+//! Gitignore-style exclude and include patterns for the scanner.
+//!
+//! Patterns follow `.gitignore` syntax and are matched relative to the
+//! directory being scanned, so `@eaDir/` skips Synology thumbnail folders
+//! anywhere, and `/Backups/` only the one at the top of the scan.
+//!
+//! A directory can also hold a [`IGNORE_FILE_NAME`] file with more
+//! patterns, relative to that directory. As with `.gitignore`, the deepest
+//! file with a matching pattern decides, and its `!pattern` lines re-include
+//! what the configured patterns exclude. Excluded directories aren't
+//! entered at all.
+
+use super::ScanConfig;
+use crate::error::ScanError;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, RwLock};
+
+/// Name of the per-directory ignore file
+pub const IGNORE_FILE_NAME: &str = ".photodedupignore";
+
+/// Exclude rules for one scanned directory tree
+///
+/// Shared by the threads of a parallel walk, so ignore files and counts
+/// are behind locks.
+pub(super) struct PathRules {
+    root: PathBuf,
+    exclude: Gitignore,
+    include: Option<Gitignore>,
+    ignore_files: RwLock<HashMap<PathBuf, Gitignore>>,
+    /// Photos that matched a photo filter but were excluded
+    excluded_files: AtomicUsize,
+    /// Directories that were not entered
+    excluded_directories: AtomicUsize,
+    /// Ignore files that couldn't be read, with the error
+    errors: Mutex<Vec<(PathBuf, ScanError)>>,
+}
+
+/// What the rules of a finished directory tree excluded
+pub(super) struct RulesSummary {
+    pub(super) excluded_files: usize,
+    pub(super) excluded_directories: usize,
+    pub(super) errors: Vec<(PathBuf, ScanError)>,
+}
+
+impl PathRules {
+    /// Build the configured patterns for a scan of `root`.
+    ///
+    /// Fails if a configured pattern isn't valid. The ignore file of `root`
+    /// itself is loaded too.
+    pub(super) fn new(root: &Path, config: &ScanConfig) -> Result<Self, ScanError> {
+        let exclude = build_patterns(root, &config.exclude, "exclude patterns")?;
+        let include = if config.include.is_empty() {
+            None
+        } else {
+            Some(build_patterns(root, &config.include, "include patterns")?)
+        };
+
+        let rules = Self {
+            root: root.to_path_buf(),
+            exclude,
+            include,
+            ignore_files: RwLock::new(HashMap::new()),
+            excluded_files: AtomicUsize::new(0),
+            excluded_directories: AtomicUsize::new(0),
+            errors: Mutex::new(Vec::new()),
+        };
+        rules.load_ignore_file(root);
+        Ok(rules)
+    }
+
+    /// Load the ignore file of a directory, if it has one.
+    ///
+    /// Call this when entering a directory, before checking its entries.
+    /// Patterns that can't be parsed are skipped and recorded in `errors`.
+    pub(super) fn load_ignore_file(&self, dir: &Path) {
+        let path = dir.join(IGNORE_FILE_NAME);
+        if !path.is_file() {
+            return;
+        }
+
+        let mut builder = GitignoreBuilder::new(dir);
+        let mut error = builder.add(&path);
+        match builder.build() {
+            Ok(ignore) => {
+                let mut ignore_files = self.ignore_files.write().unwrap_or_else(|e| e.into_inner());
+                ignore_files.insert(dir.to_path_buf(), ignore);
+            }
+            Err(e) => error = Some(e),
+        }
+
+        if let Some(e) = error {
+            let error = ScanError::InvalidPattern {
+                origin: path.display().to_string(),
+                reason: e.to_string(),
+            };
+            let mut errors = self.errors.lock().unwrap_or_else(|e| e.into_inner());
+            errors.push((path, error));
+        }
+    }
+
+    /// Check a directory entry against the rules, counting it if excluded.
+    ///
+    /// `is_photo` says whether the entry would otherwise be scanned, so
+    /// only excluded photos are counted. It's only called for excluded files.
+    pub(super) fn admit(&self, path: &Path, is_dir: bool, is_photo: impl FnOnce() -> bool) -> bool {
+        if !self.is_excluded(path, is_dir) {
+            return true;
+        }
+        if is_dir {
+            self.excluded_directories.fetch_add(1, Ordering::Relaxed);
+        } else if is_photo() {
+            self.excluded_files.fetch_add(1, Ordering::Relaxed);
+        }
+        false
+    }
+
+    /// Finish the scan of the tree, taking what was excluded
+    pub(super) fn finish(self) -> RulesSummary {
+        RulesSummary {
+            excluded_files: self.excluded_files.into_inner(),
+            excluded_directories: self.excluded_directories.into_inner(),
+            errors: self.errors.into_inner().unwrap_or_else(|e| e.into_inner()),
+        }
+    }
+
+    /// Check if a path is excluded by the ignore files, the configured
+    /// exclude patterns, or (for files) by not matching an include pattern
+    fn is_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        if path == self.root {
+            return false;
+        }
+
+        let excluded = self
+            .ignore_file_match(path, is_dir)
+            .unwrap_or_else(|| self.exclude.matched(path, is_dir).is_ignore());
+        if excluded || is_dir {
+            return excluded;
+        }
+
+        self.include
+            .as_ref()
+            .is_some_and(|include| !include.matched_path_or_any_parents(path, false).is_ignore())
+    }
+
+    /// Whether the deepest ignore file with a matching pattern excludes
+    /// `path`, or None if no ignore file mentions it
+    fn ignore_file_match(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let ignore_files = self.ignore_files.read().unwrap_or_else(|e| e.into_inner());
+        for dir in path.ancestors().skip(1) {
+            if let Some(ignore) = ignore_files.get(dir) {
+                match ignore.matched(path, is_dir) {
+                    Match::Ignore(_) => return Some(true),
+                    Match::Whitelist(_) => return Some(false),
+                    Match::None => {}
+                }
+            }
+            if dir == self.root {
+                break;
+            }
+        }
+        None
+    }
+}
+
+/// Build gitignore-style patterns rooted at `root`
+fn build_patterns(root: &Path, patterns: &[String], origin: &str) -> Result<Gitignore, ScanError> {
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in patterns {
+        builder
+            .add_line(None, pattern)
+            .map_err(|e| ScanError::InvalidPattern {
+                origin: origin.to_string(),
+                reason: e.to_string(),
+            })?;
+    }
+    builder.build().map_err(|e| ScanError::InvalidPattern {
+        origin: origin.to_string(),
+        reason: e.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn rules(root: &Path, exclude: &[&str], include: &[&str]) -> PathRules {
+        let config = ScanConfig {
+            exclude: exclude.iter().map(|p| p.to_string()).collect(),
+            include: include.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        };
+        PathRules::new(root, &config).unwrap()
+    }
+
+    #[test]
+    fn excludes_matching_directories_anywhere() {
+        let root = Path::new("/photos");
+        let rules = rules(root, &["@eaDir/", "/Backups/", "*.lrprev"], &[]);
+
+        assert!(rules.is_excluded(&root.join("2024/@eaDir"), true));
+        assert!(rules.is_excluded(&root.join("Backups"), true));
+        assert!(!rules.is_excluded(&root.join("2024/Backups"), true));
+        assert!(rules.is_excluded(&root.join("Lightroom/a.lrprev"), false));
+        assert!(!rules.is_excluded(&root.join("2024/a.jpg"), false));
+    }
+
+    #[test]
+    fn include_patterns_only_limit_files() {
+        let root = Path::new("/photos");
+        let rules = rules(root, &[], &["*.jpg", "Raw/"]);
+
+        assert!(!rules.is_excluded(&root.join("2024"), true));
+        assert!(!rules.is_excluded(&root.join("2024/a.jpg"), false));
+        assert!(!rules.is_excluded(&root.join("Raw/2024/a.nef"), false));
+        assert!(rules.is_excluded(&root.join("2024/a.png"), false));
+    }
+
+    #[test]
+    fn deepest_ignore_file_decides() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let album = root.join("album");
+        fs::create_dir(&album).unwrap();
+        fs::write(root.join(IGNORE_FILE_NAME), "*.png\n").unwrap();
+        fs::write(album.join(IGNORE_FILE_NAME), "!keep.png\n").unwrap();
+
+        let rules = rules(root, &["*.gif"], &[]);
+        rules.load_ignore_file(&album);
+        assert!(rules.errors.lock().unwrap().is_empty());
+
+        assert!(rules.is_excluded(&root.join("a.png"), false));
+        assert!(rules.is_excluded(&album.join("b.png"), false));
+        assert!(!rules.is_excluded(&album.join("keep.png"), false));
+        assert!(rules.is_excluded(&album.join("c.gif"), false));
+    }
+
+    #[test]
+    fn invalid_pattern_is_an_error() {
+        let config = ScanConfig {
+            exclude: vec!["photos/{2023,2024".to_string()],
+            ..Default::default()
+        };
+
+        let error = PathRules::new(Path::new("/photos"), &config).err().unwrap();
+        assert!(error.to_string().contains("exclude patterns"));
+    }
+}
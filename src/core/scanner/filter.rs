@@ -1,6 +1,7 @@
 //! File filtering logic for the scanner.
 
 use super::ImageFormat;
+use crate::core::raw::RAW_EXTENSIONS;
 use std::path::Path;
 
 /// Filters files to determine if they are supported images
@@ -9,6 +10,8 @@ pub struct ImageFilter {
     extensions: std::collections::HashSet<String>,
     /// Whether to include hidden files
     include_hidden: bool,
+    /// Whether to read each file's magic bytes for its format
+    sniff_content: bool,
 }
 
 impl ImageFilter {
@@ -26,10 +29,14 @@ impl ImageFilter {
                 "bmp".to_string(),
                 "tiff".to_string(),
                 "tif".to_string(),
+                "avif".to_string(),
+                "jxl".to_string(),
             ]
             .into_iter()
+            .chain(RAW_EXTENSIONS.iter().map(|ext| ext.to_string()))
             .collect(),
             include_hidden: false,
+            sniff_content: false,
         }
     }
 
@@ -45,32 +52,71 @@ impl ImageFilter {
         self
     }
 
+    /// Read the magic bytes of files to find their format, so mislabeled
+    /// photos and photos without an image extension are found too
+    pub fn with_sniffing(mut self, sniff: bool) -> Self {
+        self.sniff_content = sniff;
+        self
+    }
+
     /// Check if a file should be included
     pub fn should_include(&self, path: &Path) -> bool {
+        self.detect(path).is_some()
+    }
+
+    /// Get the format of a file if it should be included.
+    ///
+    /// Without sniffing this only looks at the extension. With it, the
+    /// format comes from the file's contents (see
+    /// [`ImageFormat::from_content`]), and files without an accepted
+    /// extension are included if their contents are of an accepted format.
+    pub fn detect(&self, path: &Path) -> Option<ImageFormat> {
         // Check if hidden
         if !self.include_hidden {
             if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
                 if name.starts_with('.') {
-                    return false;
+                    return None;
                 }
             }
         }
 
         // Check extension
-        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-            let ext_lower = ext.to_lowercase();
-            self.extensions.contains(&ext_lower)
-        } else {
-            false
+        let accepted = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|ext| self.extensions.contains(&ext.to_lowercase()));
+
+        if !self.sniff_content {
+            return accepted.then(|| self.get_format(path));
         }
+
+        let format = ImageFormat::sniff(path);
+        (accepted || (format.is_supported() && self.accepts(format))).then_some(format)
+    }
+
+    /// Whether one of the accepted extensions is for `format`
+    fn accepts(&self, format: ImageFormat) -> bool {
+        self.extensions
+            .iter()
+            .any(|ext| ImageFormat::from_extension(ext) == format)
+    }
+
+    /// Describe what the filter accepts, so listings picked with another
+    /// filter aren't reused
+    pub fn fingerprint(&self) -> String {
+        let mut extensions: Vec<&str> = self.extensions.iter().map(String::as_str).collect();
+        extensions.sort_unstable();
+        format!(
+            "hidden={};sniff={};extensions={}",
+            self.include_hidden,
+            self.sniff_content,
+            extensions.join(",")
+        )
     }
 
     /// Get the image format for a path
     pub fn get_format(&self, path: &Path) -> ImageFormat {
-        path.extension()
-            .and_then(|e| e.to_str())
-            .map(ImageFormat::from_extension)
-            .unwrap_or(ImageFormat::Unknown)
+        ImageFormat::from_path(path)
     }
 }
 
@@ -97,6 +143,27 @@ mod tests {
         assert!(filter.should_include(Path::new("/photos/IMG_1234.HEIC")));
     }
 
+    #[test]
+    fn filter_includes_avif_and_jxl() {
+        let filter = ImageFilter::new();
+        assert!(filter.should_include(Path::new("/exports/photo.AVIF")));
+        assert_eq!(
+            filter.get_format(Path::new("/exports/photo.jxl")),
+            ImageFormat::Jxl
+        );
+    }
+
+    #[test]
+    fn filter_includes_camera_raw() {
+        let filter = ImageFilter::new();
+        assert!(filter.should_include(Path::new("/photos/DSC_0001.NEF")));
+        assert!(filter.should_include(Path::new("/photos/IMG_0001.cr3")));
+        assert_eq!(
+            filter.get_format(Path::new("/photos/DSCF0001.RAF")),
+            ImageFormat::Raw
+        );
+    }
+
     #[test]
     fn filter_excludes_non_images() {
         let filter = ImageFilter::new();
@@ -121,4 +188,32 @@ mod tests {
         let filter = ImageFilter::new();
         assert!(!filter.should_include(Path::new("/photos/no_extension")));
     }
+
+    #[test]
+    fn sniffing_filter_reads_format_from_contents() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let jpeg = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, 0x4A, 0x46];
+        for name in ["mislabeled.png", "no_extension", "photo.jpg"] {
+            std::fs::write(temp_dir.path().join(name), jpeg).unwrap();
+        }
+        std::fs::write(temp_dir.path().join("notes.txt"), "not a photo").unwrap();
+        let detect = |filter: &ImageFilter, name: &str| filter.detect(&temp_dir.path().join(name));
+
+        let filter = ImageFilter::new().with_sniffing(true);
+        assert_eq!(detect(&filter, "mislabeled.png"), Some(ImageFormat::Jpeg));
+        assert_eq!(detect(&filter, "no_extension"), Some(ImageFormat::Jpeg));
+        assert_eq!(detect(&filter, "notes.txt"), None);
+
+        let png_only = ImageFilter::new()
+            .with_extensions(vec!["png".to_string()])
+            .with_sniffing(true);
+        assert_eq!(detect(&png_only, "no_extension"), None);
+
+        let extension_only = ImageFilter::new();
+        assert_eq!(
+            detect(&extension_only, "mislabeled.png"),
+            Some(ImageFormat::Png)
+        );
+        assert_eq!(detect(&extension_only, "no_extension"), None);
+    }
 }
@@ -0,0 +1,493 @@
+//! Incremental directory walking that reuses the listings of unchanged
+//! directories.
+//!
+//! Adding, removing or renaming an entry updates the modification time of
+//! the directory it is in, so a directory whose time matches the one stored
+//! by the last scan still holds the same photos and subdirectories, and
+//! isn't read again, which makes rescanning a large, mostly unchanged
+//! library cheap.
+//!
+//! A photo edited in place doesn't touch its directory, so the photos of a
+//! reused listing are checked again on every scan. Their size, time and file
+//! ID are what the hash caches are keyed on, and a single `stat` each is
+//! still far cheaper than reading the directory.
+
+use super::exclude::{PathRules, IGNORE_FILE_NAME};
+use super::walker::{convert_io_error, image_filter, should_skip_directory, ScanContext};
+use super::{filter::ImageFilter, FileId, PhotoFile, PhotoScanner, ScanConfig, ScanResult};
+use crate::core::cache::{CacheBackend, ScanState};
+use crate::error::ScanError;
+use crate::events::{Event, EventSender, ScanEvent};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Directories changed this recently aren't stored, as a change in the same
+/// tick of a coarse filesystem clock would keep their time the same
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+/// Version of the stored listings, raised when photos gain a field (such as
+/// their file ID) that older listings don't have, so those are read again
+const LISTING_VERSION: u32 = 2;
+
+/// What a directory holds, as far as the scan is concerned
+struct Listing {
+    photos: Vec<PhotoFile>,
+    subdirectories: Vec<PathBuf>,
+    ignore_file: bool,
+}
+
+/// Scanner that keeps what each directory held in the cache, and only reads
+/// directories that changed since the last scan
+///
+/// Finds the same photos and sends the same events as
+/// [`WalkDirScanner`](super::WalkDirScanner), with directories visited in
+/// name order.
+pub struct IncrementalScanner<'a> {
+    config: ScanConfig,
+    filter: ImageFilter,
+    fingerprint: String,
+    cache: &'a dyn CacheBackend,
+}
+
+impl<'a> IncrementalScanner<'a> {
+    /// Create a scanner that keeps directory listings in `cache`
+    pub fn new(config: ScanConfig, cache: &'a dyn CacheBackend) -> Self {
+        let filter = image_filter(&config);
+        let fingerprint = format!(
+            "v{};{};symlinks={}",
+            LISTING_VERSION,
+            filter.fingerprint(),
+            config.follow_symlinks
+        );
+
+        Self {
+            config,
+            filter,
+            fingerprint,
+            cache,
+        }
+    }
+
+    /// Get the listing of a directory, from the cache if it hasn't changed.
+    ///
+    /// Listings that were read are added to `states` to store, unless the
+    /// directory just changed or couldn't be read in full.
+    fn list_directory(
+        &self,
+        dir: &Path,
+        ctx: &mut ScanContext,
+        states: &mut Vec<ScanState>,
+        reused: &mut usize,
+    ) -> Option<Listing> {
+        let modified = match fs::metadata(dir).and_then(|m| m.modified()) {
+            Ok(modified) => modified,
+            Err(e) => {
+                ctx.add_error(convert_io_error(e, dir), dir);
+                return None;
+            }
+        };
+
+        match self.cache.get_scan_state(dir) {
+            Ok(Some(state)) if state.modified == modified && state.filter == self.fingerprint => {
+                // A photo that can't be checked again is read with the rest
+                // of the directory
+                if let Some(photos) = refresh_photos(state.photos) {
+                    *reused += 1;
+                    return Some(Listing {
+                        photos,
+                        subdirectories: state.subdirectories,
+                        ignore_file: state.ignore_file,
+                    });
+                }
+            }
+            Ok(_) => {}
+            Err(e) => tracing::debug!("Failed to read scan state of {:?}: {}", dir, e),
+        }
+
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                ctx.add_error(convert_io_error(e, dir), dir);
+                return None;
+            }
+        };
+
+        let mut complete = true;
+        let mut entries: Vec<_> = entries
+            .filter_map(|entry| match entry {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    ctx.add_error(convert_io_error(e, dir), dir);
+                    complete = false;
+                    None
+                }
+            })
+            .collect();
+        entries.sort_by_key(|entry| entry.file_name());
+
+        let mut listing = Listing {
+            photos: Vec::new(),
+            subdirectories: Vec::new(),
+            ignore_file: false,
+        };
+        for entry in entries {
+            let path = entry.path();
+            let is_symlink = entry.file_type().is_ok_and(|t| t.is_symlink());
+
+            match fs::metadata(&path) {
+                Ok(metadata) if metadata.is_dir() => {
+                    if self.config.follow_symlinks || !is_symlink {
+                        listing.subdirectories.push(path);
+                    }
+                }
+                Ok(_) if entry.file_name() == IGNORE_FILE_NAME => {
+                    listing.ignore_file = true;
+                }
+                Ok(metadata) => {
+                    if let Some(format) = self.filter.detect(&path) {
+                        listing.photos.push(PhotoFile {
+                            format,
+                            size: metadata.len(),
+                            modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                            file_id: FileId::from_metadata(&metadata),
+                            path,
+                        });
+                    }
+                }
+                Err(e) => {
+                    if self.filter.should_include(&path) {
+                        ctx.add_error(
+                            ScanError::ReadDirectory {
+                                path: path.clone(),
+                                source: e,
+                            },
+                            &path,
+                        );
+                        complete = false;
+                    }
+                }
+            }
+        }
+
+        let settled = SystemTime::now()
+            .duration_since(modified)
+            .is_ok_and(|age| age >= RACY_WINDOW);
+        if complete && settled {
+            states.push(ScanState {
+                directory: dir.to_path_buf(),
+                last_scan_time: SystemTime::now(),
+                file_count: listing.photos.len(),
+                modified,
+                filter: self.fingerprint.clone(),
+                photos: listing.photos.clone(),
+                subdirectories: listing.subdirectories.clone(),
+                ignore_file: listing.ignore_file,
+            });
+        }
+
+        Some(listing)
+    }
+
+    /// Scan a single directory tree
+    #[tracing::instrument(skip(self, ctx, states, reused))]
+    fn scan_directory(
+        &self,
+        root: &Path,
+        ctx: &mut ScanContext,
+        states: &mut Vec<ScanState>,
+        reused: &mut usize,
+    ) -> Result<(), ScanError> {
+        if !root.exists() || !root.is_dir() {
+            return Err(ScanError::DirectoryNotFound {
+                path: root.to_path_buf(),
+            });
+        }
+
+        let rules = PathRules::new(root, &self.config)?;
+
+        // Directories reachable through more than one symlink are only
+        // scanned once, which also stops symlink loops
+        let mut visited = HashSet::new();
+        let mut stack = vec![(root.to_path_buf(), 0)];
+
+        while let Some((dir, depth)) = stack.pop() {
+            if self.config.follow_symlinks {
+                let real = fs::canonicalize(&dir).unwrap_or_else(|_| dir.clone());
+                if !visited.insert(real) {
+                    continue;
+                }
+            }
+
+            ctx.directories_scanned += 1;
+            if !should_skip_directory(&self.config, &dir, root) {
+                ctx.emit_progress(&dir);
+            }
+
+            // Entries of a directory are one level deeper than it
+            if self.config.max_depth.is_some_and(|max| depth >= max) {
+                continue;
+            }
+
+            let Some(listing) = self.list_directory(&dir, ctx, states, reused) else {
+                continue;
+            };
+            // Listings are stored unfiltered, and ignore files read on every
+            // scan, so changed patterns and limits apply to unchanged
+            // directories
+            if listing.ignore_file && dir != root {
+                rules.load_ignore_file(&dir);
+            }
+            for photo in listing.photos {
+                if rules.admit(&photo.path, false, || true) {
+                    ctx.add_photo_within(photo, &self.config.limits);
+                }
+            }
+            for subdirectory in listing.subdirectories.into_iter().rev() {
+                if rules.admit(&subdirectory, true, || false) {
+                    stack.push((subdirectory, depth + 1));
+                }
+            }
+        }
+
+        ctx.finish_rules(rules);
+        Ok(())
+    }
+}
+
+/// Check the photos of a reused listing again, for edits made in place.
+///
+/// Returns `None` if any of them is gone or is no longer a file.
+fn refresh_photos(photos: Vec<PhotoFile>) -> Option<Vec<PhotoFile>> {
+    photos
+        .into_iter()
+        .map(|photo| {
+            let metadata = fs::metadata(&photo.path).ok().filter(|m| m.is_file())?;
+            Some(PhotoFile {
+                size: metadata.len(),
+                modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                file_id: FileId::from_metadata(&metadata),
+                ..photo
+            })
+        })
+        .collect()
+}
+
+impl PhotoScanner for IncrementalScanner<'_> {
+    fn scan(&self, paths: &[PathBuf]) -> Result<ScanResult, ScanError> {
+        self.scan_with_events(paths, &crate::events::null_sender())
+    }
+
+    #[tracing::instrument(skip(self, events), fields(paths = ?paths))]
+    fn scan_with_events(
+        &self,
+        paths: &[PathBuf],
+        events: &EventSender,
+    ) -> Result<ScanResult, ScanError> {
+        tracing::info!("Starting incremental scan of {:?} paths", paths.len());
+        events.send(Event::Scan(ScanEvent::Started {
+            paths: paths.to_vec(),
+        }));
+
+        let mut ctx = ScanContext::new(Some(events));
+        let mut states = Vec::new();
+        let mut reused = 0;
+
+        for path in paths {
+            if let Err(e) = self.scan_directory(path, &mut ctx, &mut states, &mut reused) {
+                if matches!(e, ScanError::InvalidPattern { .. }) {
+                    return Err(e);
+                }
+                ctx.errors.push(e);
+            }
+        }
+
+        tracing::info!(
+            "Reused {} of {} directory listings",
+            reused,
+            ctx.directories_scanned
+        );
+        if let Err(e) = self.cache.set_scan_states(&states) {
+            tracing::warn!("Failed to store {} directory listings: {}", states.len(), e);
+        }
+
+        ctx.emit_completed();
+
+        Ok(ScanResult {
+            photos: ctx.photos,
+            errors: ctx.errors,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::cache::InMemoryCache;
+    use crate::core::scanner::ImageFormat;
+    use std::fs::File;
+
+    /// Move a directory's modification time back, out of the racy window
+    fn settle(dir: &Path) {
+        let past = SystemTime::now() - Duration::from_secs(60);
+        File::open(dir).unwrap().set_modified(past).unwrap();
+    }
+
+    fn paths(result: &ScanResult) -> Vec<PathBuf> {
+        result.photos.iter().map(|p| p.path.clone()).collect()
+    }
+
+    #[test]
+    fn finds_the_same_photos_as_walkdir_in_name_order() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("b/nested")).unwrap();
+        fs::create_dir(root.join("a")).unwrap();
+        for name in [
+            "z.jpg",
+            "a/1.png",
+            "b/2.jpg",
+            "b/nested/3.heic",
+            "notes.txt",
+        ] {
+            fs::write(root.join(name), [0xFF, 0xD8]).unwrap();
+        }
+
+        let cache = InMemoryCache::new();
+        let scanner = IncrementalScanner::new(ScanConfig::default(), &cache);
+        let result = scanner.scan(&[root.to_path_buf()]).unwrap();
+
+        // Photos come before the subdirectories of their directory
+        let names = ["z.jpg", "a/1.png", "b/2.jpg", "b/nested/3.heic"];
+        assert_eq!(paths(&result), names.map(|name| root.join(name)).to_vec());
+
+        let mut walked = paths(
+            &super::super::WalkDirScanner::new(ScanConfig::default())
+                .scan(&[root.to_path_buf()])
+                .unwrap(),
+        );
+        let mut found = paths(&result);
+        walked.sort();
+        found.sort();
+        assert_eq!(found, walked);
+    }
+
+    #[test]
+    fn reuses_unchanged_directories() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let album = root.join("album");
+        fs::create_dir(&album).unwrap();
+        fs::write(album.join("1.jpg"), [0xFF, 0xD8]).unwrap();
+        settle(&album);
+        settle(root);
+
+        let cache = InMemoryCache::new();
+        let scanner = IncrementalScanner::new(ScanConfig::default(), &cache);
+        scanner.scan(&[root.to_path_buf()]).unwrap();
+
+        // A stored listing is trusted while the directory is unchanged
+        let mut state = cache.get_scan_state(&album).unwrap().unwrap();
+        state.photos[0].format = ImageFormat::Png;
+        cache.set_scan_state(&state).unwrap();
+        let result = scanner.scan(&[root.to_path_buf()]).unwrap();
+        assert_eq!(result.photos[0].format, ImageFormat::Png);
+
+        // Adding a photo changes the directory, so it is read again
+        fs::write(album.join("2.jpg"), [0xFF, 0xD8]).unwrap();
+        let result = scanner.scan(&[root.to_path_buf()]).unwrap();
+        assert_eq!(result.photos.len(), 2);
+        assert_eq!(result.photos[0].size, 2);
+    }
+
+    #[test]
+    fn sees_photos_edited_in_place() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let photo = root.join("1.jpg");
+        fs::write(&photo, [0xFF, 0xD8]).unwrap();
+        settle(root);
+
+        let cache = InMemoryCache::new();
+        let scanner = IncrementalScanner::new(ScanConfig::default(), &cache);
+        scanner.scan(&[root.to_path_buf()]).unwrap();
+        assert!(cache.get_scan_state(root).unwrap().is_some());
+
+        // Writing over a photo leaves its directory's time alone
+        fs::write(&photo, [0xFF, 0xD8, 0xFF, 0xE0]).unwrap();
+        let edited = SystemTime::now() + Duration::from_secs(60);
+        File::options()
+            .write(true)
+            .open(&photo)
+            .unwrap()
+            .set_modified(edited)
+            .unwrap();
+        settle(root);
+
+        let result = scanner.scan(&[root.to_path_buf()]).unwrap();
+        assert_eq!(result.photos[0].size, 4);
+        assert_eq!(result.photos[0].modified, edited);
+    }
+
+    #[test]
+    fn rereads_directories_for_another_filter() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("photo.jpg"), [0xFF, 0xD8]).unwrap();
+        fs::write(root.join(".hidden.jpg"), [0xFF, 0xD8]).unwrap();
+        settle(root);
+
+        let cache = InMemoryCache::new();
+        let visible = IncrementalScanner::new(ScanConfig::default(), &cache);
+        assert_eq!(visible.scan(&[root.to_path_buf()]).unwrap().photos.len(), 1);
+
+        let config = ScanConfig {
+            include_hidden: true,
+            ..Default::default()
+        };
+        let hidden = IncrementalScanner::new(config, &cache);
+        assert_eq!(hidden.scan(&[root.to_path_buf()]).unwrap().photos.len(), 2);
+    }
+
+    #[test]
+    fn honors_max_depth() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("sub")).unwrap();
+        fs::write(root.join("top.jpg"), [0xFF, 0xD8]).unwrap();
+        fs::write(root.join("sub/deep.jpg"), [0xFF, 0xD8]).unwrap();
+
+        let config = ScanConfig {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        let cache = InMemoryCache::new();
+        let result = IncrementalScanner::new(config, &cache)
+            .scan(&[root.to_path_buf()])
+            .unwrap();
+
+        assert_eq!(result.photos.len(), 1);
+        assert!(result.photos[0].path.ends_with("top.jpg"));
+    }
+
+    #[test]
+    fn applies_ignore_files_of_unchanged_directories() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("a.jpg"), [0xFF, 0xD8]).unwrap();
+        fs::write(root.join("b.png"), [0xFF, 0xD8]).unwrap();
+        fs::write(root.join(IGNORE_FILE_NAME), "*.gif\n").unwrap();
+        settle(root);
+
+        let cache = InMemoryCache::new();
+        let scanner = IncrementalScanner::new(ScanConfig::default(), &cache);
+        assert_eq!(scanner.scan(&[root.to_path_buf()]).unwrap().photos.len(), 2);
+
+        // Editing the ignore file in place leaves the directory unchanged
+        fs::write(root.join(IGNORE_FILE_NAME), "*.png\n").unwrap();
+        settle(root);
+        let result = scanner.scan(&[root.to_path_buf()]).unwrap();
+        assert_eq!(paths(&result), vec![root.join("a.jpg")]);
+    }
+}
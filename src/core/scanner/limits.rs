@@ -0,0 +1,117 @@
+//! Size, date and dimension limits for scanned photos.
+//!
+//! Size and modified time come from the file system, so the scanners check
+//! them as they list each directory. Dimensions are only known once a photo
+//! is decoded: the duplicates pipeline and [`SimilarScanner`] check them
+//! against the decode they hash, and scans that never decode read them from
+//! the file header instead.
+//!
+//! [`SimilarScanner`]: crate::core::similar::SimilarScanner
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Limits a photo must be within to be scanned (all off by default)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanLimits {
+    /// Skip files smaller than this many bytes
+    pub min_size: Option<u64>,
+    /// Skip files larger than this many bytes
+    pub max_size: Option<u64>,
+    /// Skip files last modified before this time
+    pub modified_after: Option<SystemTime>,
+    /// Skip files last modified after this time
+    pub modified_before: Option<SystemTime>,
+    /// Skip photos narrower or shorter than this many pixels once decoded,
+    /// such as icons and thumbnails
+    pub min_dimension: Option<u32>,
+}
+
+impl ScanLimits {
+    /// Whether a file of this size and modified time is within the limits
+    pub fn admits_file(&self, size: u64, modified: SystemTime) -> bool {
+        self.min_size.is_none_or(|min| size >= min)
+            && self.max_size.is_none_or(|max| size <= max)
+            && self.modified_after.is_none_or(|after| modified >= after)
+            && self.modified_before.is_none_or(|before| modified <= before)
+    }
+
+    /// Whether a decoded photo of this size is within the dimension limit
+    pub fn admits_dimensions(&self, width: u32, height: u32) -> bool {
+        self.min_dimension
+            .is_none_or(|min| width.min(height) >= min)
+    }
+
+    /// Check the dimension limit against the file header, without decoding.
+    ///
+    /// Files whose header the image crate can't read, such as videos, RAW
+    /// and HEIC, are within the limit.
+    pub fn admits_header_dimensions(&self, path: &Path) -> bool {
+        if self.min_dimension.is_none() {
+            return true;
+        }
+        image::image_dimensions(path).map_or(true, |(width, height)| {
+            self.admits_dimensions(width, height)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn file_limits_are_inclusive() {
+        let day = |days: u64| SystemTime::UNIX_EPOCH + Duration::from_secs(days * 86_400);
+        let limits = ScanLimits {
+            min_size: Some(500),
+            max_size: Some(1000),
+            modified_after: Some(day(10)),
+            modified_before: Some(day(20)),
+            min_dimension: None,
+        };
+
+        assert!(limits.admits_file(500, day(10)));
+        assert!(limits.admits_file(1000, day(20)));
+        assert!(!limits.admits_file(499, day(15)));
+        assert!(!limits.admits_file(1001, day(15)));
+        assert!(!limits.admits_file(700, day(9)));
+        assert!(!limits.admits_file(700, day(21)));
+        assert!(ScanLimits::default().admits_file(0, SystemTime::UNIX_EPOCH));
+    }
+
+    #[test]
+    fn dimension_limit_applies_to_the_shorter_side() {
+        let limits = ScanLimits {
+            min_dimension: Some(200),
+            ..Default::default()
+        };
+
+        assert!(limits.admits_dimensions(200, 200));
+        assert!(limits.admits_dimensions(4000, 3000));
+        assert!(!limits.admits_dimensions(4000, 199));
+        assert!(!limits.admits_dimensions(64, 64));
+    }
+
+    #[test]
+    fn header_dimensions_skip_small_images() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let icon = temp_dir.path().join("icon.png");
+        let photo = temp_dir.path().join("photo.png");
+        let video = temp_dir.path().join("clip.mp4");
+        image::RgbImage::new(32, 32).save(&icon).unwrap();
+        image::RgbImage::new(320, 240).save(&photo).unwrap();
+        std::fs::write(&video, b"not an image").unwrap();
+        let limits = ScanLimits {
+            min_dimension: Some(200),
+            ..Default::default()
+        };
+
+        assert!(!limits.admits_header_dimensions(&icon));
+        assert!(limits.admits_header_dimensions(&photo));
+        assert!(limits.admits_header_dimensions(&video));
+    }
+}
@@ -0,0 +1,176 @@
+//! Photos whose extension doesn't match their contents.
+//!
+//! A scan with [`ScanConfig::sniff_content`](super::ScanConfig) records
+//! each photo's format from its magic bytes. Where that differs from the
+//! extension, as with a JPEG saved as `.png` or a photo with no extension
+//! from a messaging export, the file is mislabeled, and can be renamed to
+//! the extension of its real format.
+
+use super::{ImageFormat, PhotoFile};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// A photo whose extension names another format than its contents
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormatMismatch {
+    /// Path to the photo
+    pub path: PathBuf,
+    /// Format the extension names (`Unknown` without a known extension)
+    pub named_format: ImageFormat,
+    /// Format the magic bytes say
+    pub content_format: ImageFormat,
+}
+
+impl FormatMismatch {
+    /// Check a scanned photo, whose format was read from its contents
+    pub fn from_photo(photo: &PhotoFile) -> Option<Self> {
+        let named_format = ImageFormat::from_path(&photo.path);
+        if named_format.holds(photo.format) || photo.format.extension().is_none() {
+            return None;
+        }
+        Some(Self {
+            path: photo.path.clone(),
+            named_format,
+            content_format: photo.format,
+        })
+    }
+
+    /// Path the photo would have with the extension of its real format.
+    ///
+    /// A known image extension is replaced, keeping it upper case if it
+    /// was. Anything else after a dot is kept, so `IMG_0001.2023` becomes
+    /// `IMG_0001.2023.jpg`.
+    pub fn corrected_path(&self) -> PathBuf {
+        let extension = self.content_format.extension().unwrap_or_default();
+        let current = self.path.extension().and_then(|e| e.to_str());
+
+        match current {
+            Some(current) if self.named_format.is_supported() => {
+                if current.chars().all(|c| !c.is_ascii_lowercase()) {
+                    self.path.with_extension(extension.to_ascii_uppercase())
+                } else {
+                    self.path.with_extension(extension)
+                }
+            }
+            _ => {
+                let mut name = self
+                    .path
+                    .file_name()
+                    .map(OsString::from)
+                    .unwrap_or_default();
+                name.push(".");
+                name.push(extension);
+                self.path.with_file_name(name)
+            }
+        }
+    }
+}
+
+/// List the photos of a content-sniffing scan whose extension is wrong
+pub fn find_format_mismatches(photos: &[PhotoFile]) -> Vec<FormatMismatch> {
+    photos
+        .iter()
+        .filter_map(FormatMismatch::from_photo)
+        .collect()
+}
+
+/// Rename a mislabeled photo to [`FormatMismatch::corrected_path`].
+///
+/// Never replaces an existing file; that fails with
+/// [`io::ErrorKind::AlreadyExists`]. Returns the new path.
+pub fn rename_to_content_extension(mismatch: &FormatMismatch) -> io::Result<PathBuf> {
+    let target = mismatch.corrected_path();
+    if target.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", target.display()),
+        ));
+    }
+    fs::rename(&mismatch.path, &target)?;
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use std::time::SystemTime;
+    use tempfile::TempDir;
+
+    fn photo(path: &Path, format: ImageFormat) -> PhotoFile {
+        PhotoFile {
+            path: path.to_path_buf(),
+            size: 0,
+            modified: SystemTime::UNIX_EPOCH,
+            format,
+            file_id: None,
+        }
+    }
+
+    #[test]
+    fn lists_only_mislabeled_photos() {
+        let photos = [
+            photo(Path::new("/p/a.png"), ImageFormat::Jpeg),
+            photo(Path::new("/p/b.jpg"), ImageFormat::Jpeg),
+            photo(Path::new("/p/c.nef"), ImageFormat::Tiff),
+            photo(Path::new("/p/d"), ImageFormat::Heic),
+        ];
+
+        let mismatches = find_format_mismatches(&photos);
+
+        assert_eq!(mismatches.len(), 2);
+        assert_eq!(mismatches[0].named_format, ImageFormat::Png);
+        assert_eq!(mismatches[0].content_format, ImageFormat::Jpeg);
+        assert_eq!(mismatches[1].named_format, ImageFormat::Unknown);
+    }
+
+    #[test]
+    fn avif_with_generic_heif_brand_is_not_mislabeled() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("export.avif");
+        fs::write(&path, b"\0\0\0\x1cftypmif1\0\0\0\0mif1avifmiaf").unwrap();
+
+        let format = ImageFormat::sniff(&path);
+
+        assert_eq!(format, ImageFormat::Avif);
+        assert!(FormatMismatch::from_photo(&photo(&path, format)).is_none());
+    }
+
+    #[test]
+    fn corrected_path_keeps_case_and_unknown_suffixes() {
+        let path = |p: &str, format| {
+            FormatMismatch::from_photo(&photo(Path::new(p), format))
+                .unwrap()
+                .corrected_path()
+        };
+
+        assert_eq!(path("/p/a.png", ImageFormat::Jpeg), Path::new("/p/a.jpg"));
+        assert_eq!(path("/p/A.PNG", ImageFormat::Jpeg), Path::new("/p/A.JPG"));
+        assert_eq!(path("/p/IMG", ImageFormat::Heic), Path::new("/p/IMG.heic"));
+        assert_eq!(
+            path("/p/IMG_0001.2023", ImageFormat::Png),
+            Path::new("/p/IMG_0001.2023.png")
+        );
+    }
+
+    #[test]
+    fn rename_never_replaces_a_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let mislabeled = temp_dir.path().join("a.png");
+        fs::write(&mislabeled, b"jpeg").unwrap();
+        let mismatch = FormatMismatch::from_photo(&photo(&mislabeled, ImageFormat::Jpeg)).unwrap();
+
+        fs::write(temp_dir.path().join("a.jpg"), b"other").unwrap();
+        let error = rename_to_content_extension(&mismatch).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+
+        fs::remove_file(temp_dir.path().join("a.jpg")).unwrap();
+        let renamed = rename_to_content_extension(&mismatch).unwrap();
+        assert_eq!(renamed, temp_dir.path().join("a.jpg"));
+        assert_eq!(fs::read(renamed).unwrap(), b"jpeg");
+        assert!(!mislabeled.exists());
+    }
+}
@@ -10,6 +10,31 @@
 //! - GIF (.gif)
 //! - BMP (.bmp)
 //! - TIFF (.tiff, .tif)
+//! - AVIF (.avif) - decoded with the `avif` feature
+//! - JPEG XL (.jxl) - decoded with the `jxl` feature
+//! - Camera RAW (.cr2, .cr3, .nef, .arw, .raf, .orf, .dng)
+//!
+//! ## Content Sniffing
+//! With [`ScanConfig::sniff_content`] a photo's format comes from its magic
+//! bytes, so a JPEG saved as `.png` or a photo without an extension is
+//! classified by what it is. [`find_format_mismatches`] lists the photos
+//! whose extension is wrong.
+//!
+//! ## Excluding Paths
+//! Gitignore-style patterns in [`ScanConfig`] and per-directory
+//! [`IGNORE_FILE_NAME`] files skip files and whole directories.
+//!
+//! ## Limits
+//! [`ScanLimits`] skip photos by file size, modified time and, once
+//! decoded, by dimensions.
+//!
+//! ## Parallel Scans
+//! [`ParallelScanner`] reads directories on all cores, for huge trees on
+//! network mounts, and returns photos in the same order on every scan.
+//!
+//! ## Incremental Scans
+//! [`IncrementalScanner`] keeps each directory's listing in the cache and
+//! skips directories that haven't changed since the last scan.
 //!
 //! ## Example
 //! ```rust,ignore
@@ -19,16 +44,28 @@
 //! let photos = scanner.scan(&["/Users/photos".into()])?;
 //! ```
 
+mod exclude;
 mod filter;
+mod incremental;
+mod limits;
+mod mismatch;
+mod parallel;
 mod walker;
 
+pub use exclude::IGNORE_FILE_NAME;
 pub use filter::ImageFilter;
+pub use incremental::IncrementalScanner;
+pub use limits::ScanLimits;
+pub use mismatch::{find_format_mismatches, rename_to_content_extension, FormatMismatch};
+pub use parallel::ParallelScanner;
 pub use walker::{ScanConfig, WalkDirScanner};
 
+use crate::core::hasher::{read_file_header, sniff_image_format};
+use crate::core::raw::is_raw_extension;
 use crate::error::ScanError;
 use crate::events::EventSender;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 /// Represents a discovered photo file
@@ -42,6 +79,37 @@ pub struct PhotoFile {
     pub modified: SystemTime,
     /// Detected image format
     pub format: ImageFormat,
+    /// Device and inode, shared by every hardlink to the file (None where
+    /// the platform doesn't have them)
+    #[serde(default)]
+    pub file_id: Option<FileId>,
+}
+
+/// Identifies a file on disk, whichever path it is reached by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FileId {
+    /// Device the file is on
+    pub device: u64,
+    /// Inode number on that device
+    pub inode: u64,
+}
+
+impl FileId {
+    /// Read the device and inode from a file's metadata
+    #[cfg(unix)]
+    pub fn from_metadata(metadata: &std::fs::Metadata) -> Option<Self> {
+        use std::os::unix::fs::MetadataExt;
+        Some(Self {
+            device: metadata.dev(),
+            inode: metadata.ino(),
+        })
+    }
+
+    /// Inodes aren't available through std on other platforms
+    #[cfg(not(unix))]
+    pub fn from_metadata(_metadata: &std::fs::Metadata) -> Option<Self> {
+        None
+    }
 }
 
 /// Supported image formats
@@ -54,6 +122,10 @@ pub enum ImageFormat {
     Gif,
     Bmp,
     Tiff,
+    Avif,
+    Jxl,
+    /// Camera RAW (see [`crate::core::raw`])
+    Raw,
     Unknown,
 }
 
@@ -68,10 +140,65 @@ impl ImageFormat {
             "gif" => ImageFormat::Gif,
             "bmp" => ImageFormat::Bmp,
             "tiff" | "tif" => ImageFormat::Tiff,
+            "avif" => ImageFormat::Avif,
+            "jxl" => ImageFormat::Jxl,
+            ext if is_raw_extension(ext) => ImageFormat::Raw,
             _ => ImageFormat::Unknown,
         }
     }
 
+    /// Detect format from a path's extension
+    pub fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .map(ImageFormat::from_extension)
+            .unwrap_or(ImageFormat::Unknown)
+    }
+
+    /// Detect format from the magic bytes at the start of a file.
+    ///
+    /// The extension decides when the bytes agree with it or aren't
+    /// recognized, so a TIFF-based RAW file stays RAW.
+    pub fn from_content(path: &Path, header: &[u8]) -> Self {
+        let extension = Self::from_path(path);
+        match sniff_image_format(header) {
+            Some(content) if !extension.holds(content) => content,
+            _ => extension,
+        }
+    }
+
+    /// Read the start of a file and detect its format with
+    /// [`ImageFormat::from_content`], falling back to the extension if the
+    /// file can't be read
+    pub fn sniff(path: &Path) -> Self {
+        match read_file_header(path) {
+            Ok(header) => Self::from_content(path, &header),
+            Err(_) => Self::from_path(path),
+        }
+    }
+
+    /// Whether a file named for this format can hold `content`, the format
+    /// its magic bytes say
+    pub fn holds(&self, content: ImageFormat) -> bool {
+        *self == content || (*self == ImageFormat::Raw && content == ImageFormat::Tiff)
+    }
+
+    /// Usual file extension for this format
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            ImageFormat::Jpeg => Some("jpg"),
+            ImageFormat::Png => Some("png"),
+            ImageFormat::WebP => Some("webp"),
+            ImageFormat::Heic => Some("heic"),
+            ImageFormat::Gif => Some("gif"),
+            ImageFormat::Bmp => Some("bmp"),
+            ImageFormat::Tiff => Some("tif"),
+            ImageFormat::Avif => Some("avif"),
+            ImageFormat::Jxl => Some("jxl"),
+            ImageFormat::Raw | ImageFormat::Unknown => None,
+        }
+    }
+
     /// Check if this format is supported
     pub fn is_supported(&self) -> bool {
         !matches!(self, ImageFormat::Unknown)
@@ -132,4 +259,16 @@ mod tests {
         assert!(!ImageFormat::Unknown.is_supported());
         assert!(ImageFormat::Jpeg.is_supported());
     }
+
+    #[test]
+    fn content_overrides_a_contradicting_extension() {
+        let jpeg = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, 0x4A, 0x46];
+        let tiff = [0x49, 0x49, 0x2A, 0x00, 0x08, 0x00, 0x00, 0x00];
+        let detect = |name: &str, header: &[u8]| ImageFormat::from_content(Path::new(name), header);
+
+        assert_eq!(detect("photo.png", &jpeg), ImageFormat::Jpeg);
+        assert_eq!(detect("photo", &jpeg), ImageFormat::Jpeg);
+        assert_eq!(detect("DSC_0001.NEF", &tiff), ImageFormat::Raw);
+        assert_eq!(detect("photo.png", b"garbage!"), ImageFormat::Png);
+    }
 }
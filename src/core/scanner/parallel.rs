@@ -0,0 +1,460 @@
+//! Parallel directory walking for huge trees.
+//!
+//! Each directory is read by a rayon task, and its subdirectories become
+//! tasks of their own, so idle threads steal whole subtrees from busy ones.
+//! That keeps many directory reads in flight at once, which is what makes
+//! walks of network mounts with hundreds of thousands of folders fast, as
+//! most of a single-threaded walk there is spent waiting on round trips.
+//!
+//! Entries are sorted by name and what each task finds is put back together
+//! in walk order, so the photos come out in the same order however the work
+//! was split: the photos of a directory, then each of its subdirectories.
+
+use super::exclude::{PathRules, IGNORE_FILE_NAME};
+use super::walker::{
+    convert_io_error, image_filter, send_error, send_photo_found, send_progress,
+    should_skip_directory, symlink_loop_error, ScanContext,
+};
+use super::{filter::ImageFilter, FileId, PhotoFile, PhotoScanner, ScanConfig, ScanResult};
+use crate::error::ScanError;
+use crate::events::{Event, EventSender, ScanEvent};
+use rayon::prelude::*;
+use std::fs::{self, DirEntry};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+
+/// What the walk found in a directory and everything below it
+#[derive(Default)]
+struct Subtree {
+    photos: Vec<PhotoFile>,
+    errors: Vec<ScanError>,
+    directories: usize,
+    /// Photos outside the size and date limits
+    excluded_files: usize,
+    children: Vec<Subtree>,
+}
+
+impl Subtree {
+    /// Move everything found into `ctx`, in walk order
+    fn flatten_into(self, ctx: &mut ScanContext) {
+        ctx.photos.extend(self.photos);
+        ctx.errors.extend(self.errors);
+        ctx.directories_scanned += self.directories;
+        ctx.excluded_files += self.excluded_files;
+        for child in self.children {
+            child.flatten_into(ctx);
+        }
+    }
+}
+
+/// Counts and events shared by the threads of a walk
+struct Progress<'a> {
+    directories_scanned: AtomicUsize,
+    photos_found: AtomicUsize,
+    events: &'a EventSender,
+}
+
+impl Progress<'_> {
+    fn emit_progress(&self, current_path: &Path) {
+        send_progress(
+            self.events,
+            self.directories_scanned.load(Ordering::Relaxed),
+            self.photos_found.load(Ordering::Relaxed),
+            current_path,
+        );
+    }
+
+    fn add_photo(&self, photo: PhotoFile, subtree: &mut Subtree) {
+        self.photos_found.fetch_add(1, Ordering::Relaxed);
+        send_photo_found(self.events, &photo.path);
+        subtree.photos.push(photo);
+    }
+
+    fn add_error(&self, error: ScanError, path: &Path, subtree: &mut Subtree) {
+        send_error(self.events, &error, path);
+        subtree.errors.push(error);
+    }
+}
+
+/// A directory being walked and the ones above it, to stop symlink loops
+struct Ancestor<'a> {
+    path: PathBuf,
+    real_path: PathBuf,
+    parent: Option<&'a Ancestor<'a>>,
+}
+
+impl Ancestor<'_> {
+    /// The walked path of the ancestor at `real_path`, if there is one
+    fn find(&self, real_path: &Path) -> Option<&Path> {
+        if self.real_path == real_path {
+            Some(&self.path)
+        } else {
+            self.parent.and_then(|p| p.find(real_path))
+        }
+    }
+}
+
+/// Scanner that reads directories on all cores
+///
+/// Finds the same photos and sends the same events as
+/// [`WalkDirScanner`](super::WalkDirScanner), in a fixed order: directories
+/// are visited in name order, each directory's photos before its
+/// subdirectories. Events of different directories can interleave.
+pub struct ParallelScanner {
+    config: ScanConfig,
+    filter: ImageFilter,
+}
+
+impl ParallelScanner {
+    /// Create a new scanner with the given configuration
+    pub fn new(config: ScanConfig) -> Self {
+        let filter = image_filter(&config);
+        Self { config, filter }
+    }
+
+    /// Whether a directory entry is a directory to descend into
+    fn is_directory(&self, entry: &DirEntry) -> bool {
+        match entry.file_type() {
+            Ok(file_type) if file_type.is_symlink() => {
+                self.config.follow_symlinks && entry.path().is_dir()
+            }
+            Ok(file_type) => file_type.is_dir(),
+            Err(_) => false,
+        }
+    }
+
+    /// Walk a directory and, in parallel, its subdirectories
+    fn walk_directory(
+        &self,
+        dir: &Path,
+        depth: usize,
+        root: &Path,
+        rules: &PathRules,
+        progress: &Progress,
+        ancestor: Option<&Ancestor>,
+    ) -> Subtree {
+        // Only a directory's own ancestors can form a loop with it. Like
+        // walkdir, the link is reported and not followed
+        let here;
+        let ancestor = if self.config.follow_symlinks {
+            let real_path = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+            if let Some(looped) = ancestor.and_then(|a| a.find(&real_path)) {
+                let mut subtree = Subtree::default();
+                progress.add_error(symlink_loop_error(dir, looped), dir, &mut subtree);
+                return subtree;
+            }
+            here = Ancestor {
+                path: dir.to_path_buf(),
+                real_path,
+                parent: ancestor,
+            };
+            Some(&here)
+        } else {
+            None
+        };
+
+        let mut subtree = Subtree {
+            directories: 1,
+            ..Default::default()
+        };
+        progress.directories_scanned.fetch_add(1, Ordering::Relaxed);
+        if !should_skip_directory(&self.config, dir, root) {
+            progress.emit_progress(dir);
+        }
+
+        // Entries of a directory are one level deeper than it
+        if self.config.max_depth.is_some_and(|max| depth >= max) {
+            return subtree;
+        }
+
+        let mut entries = Vec::new();
+        match fs::read_dir(dir) {
+            Ok(read) => {
+                for entry in read {
+                    match entry {
+                        Ok(entry) => entries.push(entry),
+                        Err(e) => progress.add_error(convert_io_error(e, dir), dir, &mut subtree),
+                    }
+                }
+            }
+            Err(e) => {
+                progress.add_error(convert_io_error(e, dir), dir, &mut subtree);
+                return subtree;
+            }
+        }
+        entries.sort_by_key(|entry| entry.file_name());
+
+        if dir != root && entries.iter().any(|e| e.file_name() == IGNORE_FILE_NAME) {
+            rules.load_ignore_file(dir);
+        }
+
+        let mut subdirectories = Vec::new();
+        for entry in entries {
+            let path = entry.path();
+            if self.is_directory(&entry) {
+                if rules.admit(&path, true, || false) {
+                    subdirectories.push(path);
+                }
+                continue;
+            }
+
+            if !rules.admit(&path, false, || self.filter.should_include(&path)) {
+                continue;
+            }
+            let Some(format) = self.filter.detect(&path) else {
+                continue;
+            };
+            match fs::metadata(&path) {
+                Ok(metadata) => {
+                    let photo = PhotoFile {
+                        size: metadata.len(),
+                        modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                        format,
+                        file_id: FileId::from_metadata(&metadata),
+                        path,
+                    };
+                    if self.config.limits.admits_file(photo.size, photo.modified) {
+                        progress.add_photo(photo, &mut subtree);
+                    } else {
+                        subtree.excluded_files += 1;
+                    }
+                }
+                Err(e) => {
+                    let error = ScanError::ReadDirectory {
+                        path: path.clone(),
+                        source: e,
+                    };
+                    progress.add_error(error, &path, &mut subtree);
+                }
+            }
+        }
+
+        subtree.children = subdirectories
+            .par_iter()
+            .map(|subdirectory| {
+                self.walk_directory(subdirectory, depth + 1, root, rules, progress, ancestor)
+            })
+            .collect();
+        subtree
+    }
+
+    /// Scan a single directory tree
+    ///
+    /// Fails if the directory doesn't exist or an exclude pattern is invalid.
+    #[tracing::instrument(skip(self, ctx, progress))]
+    fn scan_directory(
+        &self,
+        root: &Path,
+        ctx: &mut ScanContext,
+        progress: &Progress,
+    ) -> Result<(), ScanError> {
+        if !root.exists() || !root.is_dir() {
+            return Err(ScanError::DirectoryNotFound {
+                path: root.to_path_buf(),
+            });
+        }
+
+        let rules = PathRules::new(root, &self.config)?;
+        let subtree = self.walk_directory(root, 0, root, &rules, progress, None);
+        subtree.flatten_into(ctx);
+        ctx.finish_rules(rules);
+        Ok(())
+    }
+}
+
+impl PhotoScanner for ParallelScanner {
+    fn scan(&self, paths: &[PathBuf]) -> Result<ScanResult, ScanError> {
+        self.scan_with_events(paths, &crate::events::null_sender())
+    }
+
+    #[tracing::instrument(skip(self, events), fields(paths = ?paths))]
+    fn scan_with_events(
+        &self,
+        paths: &[PathBuf],
+        events: &EventSender,
+    ) -> Result<ScanResult, ScanError> {
+        tracing::info!("Starting parallel scan of {:?} paths", paths.len());
+        events.send(Event::Scan(ScanEvent::Started {
+            paths: paths.to_vec(),
+        }));
+
+        let mut ctx = ScanContext::new(Some(events));
+        let progress = Progress {
+            directories_scanned: AtomicUsize::new(0),
+            photos_found: AtomicUsize::new(0),
+            events,
+        };
+
+        for path in paths {
+            if let Err(e) = self.scan_directory(path, &mut ctx, &progress) {
+                if matches!(e, ScanError::InvalidPattern { .. }) {
+                    return Err(e);
+                }
+                ctx.errors.push(e);
+            }
+        }
+
+        ctx.emit_completed();
+
+        Ok(ScanResult {
+            photos: ctx.photos,
+            errors: ctx.errors,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::WalkDirScanner;
+    use super::*;
+    use crate::events::EventChannel;
+    use tempfile::TempDir;
+
+    /// A tree wide and deep enough to be split between threads
+    fn create_tree(root: &Path) {
+        for album in 0..6 {
+            for day in 0..4 {
+                let dir = root.join(format!("album{}/day{}", album, day));
+                fs::create_dir_all(&dir).unwrap();
+                for photo in 0..3 {
+                    fs::write(dir.join(format!("{}.jpg", photo)), [0xFF, 0xD8, 0xFF, 0xE0])
+                        .unwrap();
+                }
+            }
+            fs::write(root.join(format!("album{}/cover.png", album)), b"png").unwrap();
+        }
+        fs::write(root.join("notes.txt"), b"not a photo").unwrap();
+    }
+
+    fn paths(result: &ScanResult) -> Vec<PathBuf> {
+        result.photos.iter().map(|p| p.path.clone()).collect()
+    }
+
+    #[test]
+    fn finds_the_same_photos_as_walkdir_in_walk_order() {
+        let temp_dir = TempDir::new().unwrap();
+        create_tree(temp_dir.path());
+        let roots = [temp_dir.path().to_path_buf()];
+
+        let result = ParallelScanner::new(ScanConfig::default())
+            .scan(&roots)
+            .unwrap();
+        let found = paths(&result);
+
+        assert_eq!(found.len(), 6 * 4 * 3 + 6);
+        assert_eq!(found[0], temp_dir.path().join("album0/cover.png"));
+        assert_eq!(found[1], temp_dir.path().join("album0/day0/0.jpg"));
+        assert_eq!(
+            found.last(),
+            Some(&temp_dir.path().join("album5/day3/2.jpg"))
+        );
+
+        let mut walked = paths(
+            &WalkDirScanner::new(ScanConfig::default())
+                .scan(&roots)
+                .unwrap(),
+        );
+        walked.sort();
+        let mut sorted = found.clone();
+        sorted.sort();
+        assert_eq!(sorted, walked);
+
+        for _ in 0..5 {
+            let again = ParallelScanner::new(ScanConfig::default())
+                .scan(&roots)
+                .unwrap();
+            assert_eq!(paths(&again), found);
+        }
+    }
+
+    #[test]
+    fn honors_depth_and_exclude_patterns() {
+        let temp_dir = TempDir::new().unwrap();
+        create_tree(temp_dir.path());
+        fs::write(
+            temp_dir.path().join("album1").join(IGNORE_FILE_NAME),
+            "day0/\n",
+        )
+        .unwrap();
+
+        let config = ScanConfig {
+            max_depth: Some(2),
+            exclude: vec!["album2/".to_string()],
+            ..Default::default()
+        };
+        let result = ParallelScanner::new(config)
+            .scan(&[temp_dir.path().to_path_buf()])
+            .unwrap();
+
+        let found = paths(&result);
+        assert_eq!(found.len(), 5);
+        assert!(found.iter().all(|p| p.ends_with("cover.png")));
+        assert!(!found.contains(&temp_dir.path().join("album2/cover.png")));
+    }
+
+    #[test]
+    fn sends_scan_events() {
+        let temp_dir = TempDir::new().unwrap();
+        create_tree(temp_dir.path());
+        let config = ScanConfig {
+            exclude: vec!["cover.png".to_string()],
+            ..Default::default()
+        };
+
+        let (sender, receiver) = EventChannel::new();
+        let result = ParallelScanner::new(config)
+            .scan_with_events(&[temp_dir.path().to_path_buf()], &sender)
+            .unwrap();
+        drop(sender);
+
+        let events: Vec<_> = receiver.iter().collect();
+        assert!(matches!(events[0], Event::Scan(ScanEvent::Started { .. })));
+        let found = events
+            .iter()
+            .filter(|e| matches!(e, Event::Scan(ScanEvent::PhotoFound { .. })))
+            .count();
+        let progress = events
+            .iter()
+            .filter(|e| matches!(e, Event::Scan(ScanEvent::Progress(_))))
+            .count();
+        assert_eq!(found, result.photos.len());
+        assert_eq!(progress, 1 + 6 + 6 * 4);
+        assert!(matches!(
+            events.last(),
+            Some(Event::Scan(ScanEvent::Completed {
+                total_photos: 72,
+                excluded_files: 6,
+                excluded_directories: 0,
+            }))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn stops_symlink_loops() {
+        let temp_dir = TempDir::new().unwrap();
+        let album = temp_dir.path().join("album");
+        fs::create_dir(&album).unwrap();
+        fs::write(album.join("photo.jpg"), [0xFF, 0xD8, 0xFF, 0xE0]).unwrap();
+        std::os::unix::fs::symlink(temp_dir.path(), album.join("loop")).unwrap();
+
+        let config = ScanConfig {
+            follow_symlinks: true,
+            ..Default::default()
+        };
+        let roots = [temp_dir.path().to_path_buf()];
+        let result = ParallelScanner::new(config.clone()).scan(&roots).unwrap();
+
+        assert_eq!(paths(&result), vec![album.join("photo.jpg")]);
+        // The loop is an error, as walkdir reports it
+        assert_eq!(result.errors.len(), 1);
+        assert!(matches!(
+            &result.errors[0],
+            ScanError::ReadDirectory { path, .. } if *path == album.join("loop")
+        ));
+        let walked = WalkDirScanner::new(config).scan(&roots).unwrap();
+        assert_eq!(walked.errors.len(), 1);
+        assert_eq!(result.errors[0].to_string(), walked.errors[0].to_string());
+    }
+}
@@ -1,6 +1,7 @@
 //! Directory walking implementation using walkdir.
 
-use super::{filter::ImageFilter, PhotoFile, PhotoScanner, ScanResult};
+use super::exclude::PathRules;
+use super::{filter::ImageFilter, FileId, PhotoFile, PhotoScanner, ScanLimits, ScanResult};
 use crate::error::ScanError;
 use crate::events::{Event, EventSender, ScanEvent, ScanProgress};
 use std::fs;
@@ -8,58 +9,82 @@ use std::path::{Path, PathBuf};
 use walkdir::{DirEntry, WalkDir};
 
 /// Holds mutable state during directory scanning
-struct ScanContext<'a> {
-    photos: Vec<PhotoFile>,
-    errors: Vec<ScanError>,
-    directories_scanned: usize,
+pub(super) struct ScanContext<'a> {
+    pub(super) photos: Vec<PhotoFile>,
+    pub(super) errors: Vec<ScanError>,
+    pub(super) directories_scanned: usize,
+    pub(super) excluded_files: usize,
+    pub(super) excluded_directories: usize,
     events: Option<&'a EventSender>,
 }
 
 impl<'a> ScanContext<'a> {
-    fn new(events: Option<&'a EventSender>) -> Self {
+    pub(super) fn new(events: Option<&'a EventSender>) -> Self {
         Self {
             photos: Vec::new(),
             errors: Vec::new(),
             directories_scanned: 0,
+            excluded_files: 0,
+            excluded_directories: 0,
             events,
         }
     }
 
-    fn emit_progress(&self, current_path: &Path) {
+    pub(super) fn emit_progress(&self, current_path: &Path) {
         if let Some(sender) = self.events {
-            sender.send(Event::Scan(ScanEvent::Progress(ScanProgress {
-                directories_scanned: self.directories_scanned,
-                photos_found: self.photos.len(),
-                current_path: current_path.to_path_buf(),
-            })));
+            send_progress(
+                sender,
+                self.directories_scanned,
+                self.photos.len(),
+                current_path,
+            );
         }
     }
 
-    fn emit_photo_found(&self, path: &Path) {
+    pub(super) fn add_photo(&mut self, photo: PhotoFile) {
         if let Some(sender) = self.events {
-            sender.send(Event::Scan(ScanEvent::PhotoFound {
-                path: path.to_path_buf(),
-            }));
+            send_photo_found(sender, &photo.path);
         }
+        self.photos.push(photo);
     }
 
-    fn emit_error(&self, path: &Path, message: &str) {
+    /// Add a photo if it's within the size and date limits, or count it as
+    /// excluded
+    pub(super) fn add_photo_within(&mut self, photo: PhotoFile, limits: &ScanLimits) {
+        if limits.admits_file(photo.size, photo.modified) {
+            self.add_photo(photo);
+        } else {
+            self.excluded_files += 1;
+        }
+    }
+
+    pub(super) fn add_error(&mut self, error: ScanError, path: &Path) {
         if let Some(sender) = self.events {
-            sender.send(Event::Scan(ScanEvent::Error {
-                path: path.to_path_buf(),
-                message: message.to_string(),
-            }));
+            send_error(sender, &error, path);
         }
+        self.errors.push(error);
     }
 
-    fn add_photo(&mut self, photo: PhotoFile) {
-        self.emit_photo_found(&photo.path);
-        self.photos.push(photo);
+    /// Count what the rules of a finished directory tree excluded, and
+    /// report the ignore files they couldn't read
+    pub(super) fn finish_rules(&mut self, rules: PathRules) {
+        let summary = rules.finish();
+        self.excluded_files += summary.excluded_files;
+        self.excluded_directories += summary.excluded_directories;
+        for (path, error) in summary.errors {
+            self.add_error(error, &path);
+        }
     }
 
-    fn add_error(&mut self, error: ScanError, path: &Path) {
-        self.emit_error(path, &error.to_string());
-        self.errors.push(error);
+    /// Send the completion event for everything scanned
+    pub(super) fn emit_completed(&self) {
+        if let Some(sender) = self.events {
+            sender.send(Event::Scan(ScanEvent::Completed {
+                total_photos: self.photos.len(),
+                excluded_files: self.excluded_files,
+                excluded_directories: self.excluded_directories,
+            }));
+        }
     }
 }
 
@@ -74,10 +99,112 @@ pub struct ScanConfig {
     pub max_depth: Option<usize>,
     /// Custom extensions to include (None = use defaults)
     pub extensions: Option<Vec<String>>,
-    /// Enable incremental mode (track scan state for faster subsequent scans)
+    /// Gitignore-style patterns for files and directories to skip, relative
+    /// to each scanned directory (see [`IGNORE_FILE_NAME`] for per-directory
+    /// patterns)
+    ///
+    /// [`IGNORE_FILE_NAME`]: super::IGNORE_FILE_NAME
+    pub exclude: Vec<String>,
+    /// Gitignore-style patterns a photo must match to be scanned (empty =
+    /// every photo)
+    pub include: Vec<String>,
+    /// Size, date and dimension limits a photo must be within
+    pub limits: ScanLimits,
+    /// Read the magic bytes of every file to find its format instead of
+    /// trusting the extension (see [`FormatMismatch`])
+    ///
+    /// [`FormatMismatch`]: super::FormatMismatch
+    pub sniff_content: bool,
+    /// Read directories on all cores with
+    /// [`ParallelScanner`](super::ParallelScanner) (incremental scans walk
+    /// one directory at a time regardless)
+    pub parallel: bool,
+    /// Enable incremental mode: reuse the listings of unchanged directories
+    /// and the matches between unchanged photos from the last scan (see
+    /// [`IncrementalScanner`](super::IncrementalScanner))
     pub incremental: bool,
 }
 
+/// Send the progress of a scan, as it reaches `current_path`
+pub(super) fn send_progress(
+    events: &EventSender,
+    directories_scanned: usize,
+    photos_found: usize,
+    current_path: &Path,
+) {
+    events.send(Event::Scan(ScanEvent::Progress(ScanProgress {
+        directories_scanned,
+        photos_found,
+        current_path: current_path.to_path_buf(),
+    })));
+}
+
+/// Send that a photo was found at `path`
+pub(super) fn send_photo_found(events: &EventSender, path: &Path) {
+    events.send(Event::Scan(ScanEvent::PhotoFound {
+        path: path.to_path_buf(),
+    }));
+}
+
+/// Send an error met reading `path`
+pub(super) fn send_error(events: &EventSender, error: &ScanError, path: &Path) {
+    events.send(Event::Scan(ScanEvent::Error {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    }));
+}
+
+/// Build the filter that picks out photos for a scan configuration
+pub(super) fn image_filter(config: &ScanConfig) -> ImageFilter {
+    let mut filter = ImageFilter::new()
+        .with_hidden(config.include_hidden)
+        .with_sniffing(config.sniff_content);
+
+    if let Some(ref extensions) = config.extensions {
+        filter = filter.with_extensions(extensions.clone());
+    }
+
+    filter
+}
+
+/// Check if a directory should be skipped (hidden directory check)
+pub(super) fn should_skip_directory(config: &ScanConfig, path: &Path, root: &Path) -> bool {
+    if config.include_hidden {
+        return false;
+    }
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    name.starts_with('.') && path != root
+}
+
+/// Convert an I/O error reading `path` to a ScanError
+pub(super) fn convert_io_error(error: std::io::Error, path: &Path) -> ScanError {
+    if error.kind() == std::io::ErrorKind::PermissionDenied {
+        ScanError::PermissionDenied {
+            path: path.to_path_buf(),
+        }
+    } else {
+        ScanError::ReadDirectory {
+            path: path.to_path_buf(),
+            source: error,
+        }
+    }
+}
+
+/// The error for a symlink at `path` to a directory it is inside of, worded
+/// as walkdir reports it
+pub(super) fn symlink_loop_error(path: &Path, ancestor: &Path) -> ScanError {
+    ScanError::ReadDirectory {
+        path: path.to_path_buf(),
+        source: std::io::Error::other(format!(
+            "File system loop found: {} points to an ancestor {}",
+            path.display(),
+            ancestor.display()
+        )),
+    }
+}
+
 /// Scanner implementation using the walkdir crate
 pub struct WalkDirScanner {
     config: ScanConfig,
@@ -87,32 +214,16 @@ pub struct WalkDirScanner {
 impl WalkDirScanner {
     /// Create a new scanner with the given configuration
     pub fn new(config: ScanConfig) -> Self {
-        let mut filter = ImageFilter::new().with_hidden(config.include_hidden);
-
-        if let Some(ref extensions) = config.extensions {
-            filter = filter.with_extensions(extensions.clone());
-        }
-
+        let filter = image_filter(&config);
         Self { config, filter }
     }
 
-    /// Check if a directory should be skipped (hidden directory check)
-    fn should_skip_directory(&self, path: &Path, root: &Path) -> bool {
-        if self.config.include_hidden {
-            return false;
-        }
-        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
-            return false;
-        };
-        name.starts_with('.') && path != root
-    }
-
     /// Process a directory entry
     fn process_directory(&self, entry: &DirEntry, root: &Path, ctx: &mut ScanContext) {
         let path = entry.path();
         ctx.directories_scanned += 1;
 
-        if !self.should_skip_directory(path, root) {
+        if !should_skip_directory(&self.config, path, root) {
             ctx.emit_progress(path);
         }
     }
@@ -121,9 +232,9 @@ impl WalkDirScanner {
     fn process_file(&self, entry: &DirEntry, ctx: &mut ScanContext) {
         let path = entry.path();
 
-        if !self.filter.should_include(path) {
+        let Some(format) = self.filter.detect(path) else {
             return;
-        }
+        };
 
         match fs::metadata(path) {
             Ok(metadata) => {
@@ -133,9 +244,10 @@ impl WalkDirScanner {
                     modified: metadata
                         .modified()
                         .unwrap_or(std::time::SystemTime::UNIX_EPOCH),
-                    format: self.filter.get_format(path),
+                    format,
+                    file_id: FileId::from_metadata(&metadata),
                 };
-                ctx.add_photo(photo);
+                ctx.add_photo_within(photo, &self.config.limits);
             }
             Err(e) => {
                 let error = ScanError::ReadDirectory {
@@ -169,38 +281,51 @@ impl WalkDirScanner {
     }
 
     /// Scan a single directory
-    #[tracing::instrument(skip(self, events))]
-    fn scan_directory(
-        &self,
-        root: &PathBuf,
-        events: Option<&EventSender>,
-    ) -> Result<(Vec<PhotoFile>, Vec<ScanError>), ScanError> {
+    ///
+    /// Fails if the directory doesn't exist or an exclude pattern is invalid.
+    #[tracing::instrument(skip(self, ctx))]
+    fn scan_directory(&self, root: &PathBuf, ctx: &mut ScanContext) -> Result<(), ScanError> {
         if !root.exists() || !root.is_dir() {
             return Err(ScanError::DirectoryNotFound { path: root.clone() });
         }
 
-        let mut ctx = ScanContext::new(events);
+        let rules = PathRules::new(root, &self.config)?;
+
         let mut walker = WalkDir::new(root).follow_links(self.config.follow_symlinks);
 
         if let Some(depth) = self.config.max_depth {
             walker = walker.max_depth(depth);
         }
 
-        for entry_result in walker {
+        // Excluded directories are pruned, so they are never read
+        let entries = walker.into_iter().filter_entry(|entry| {
+            let path = entry.path();
+            let is_dir = entry.file_type().is_dir();
+            if !rules.admit(path, is_dir, || !is_dir && self.filter.should_include(path)) {
+                return false;
+            }
+            if is_dir && entry.depth() > 0 {
+                rules.load_ignore_file(path);
+            }
+            true
+        });
+
+        for entry_result in entries {
             match entry_result {
                 Ok(entry) if entry.path().is_dir() => {
-                    self.process_directory(&entry, root, &mut ctx);
+                    self.process_directory(&entry, root, ctx);
                 }
                 Ok(entry) => {
-                    self.process_file(&entry, &mut ctx);
+                    self.process_file(&entry, ctx);
                 }
                 Err(e) => {
-                    self.handle_walk_error(e, &mut ctx);
+                    self.handle_walk_error(e, ctx);
                 }
             }
         }
 
-        Ok((ctx.photos, ctx.errors))
+        ctx.finish_rules(rules);
+        Ok(())
     }
 }
 
@@ -220,28 +345,22 @@ impl PhotoScanner for WalkDirScanner {
             paths: paths.to_vec(),
         }));
 
-        let mut all_photos = Vec::new();
-        let mut all_errors = Vec::new();
+        let mut ctx = ScanContext::new(Some(events));
 
         for path in paths {
-            match self.scan_directory(path, Some(events)) {
-                Ok((photos, errors)) => {
-                    all_photos.extend(photos);
-                    all_errors.extend(errors);
-                }
-                Err(e) => {
-                    all_errors.push(e);
+            if let Err(e) = self.scan_directory(path, &mut ctx) {
+                if matches!(e, ScanError::InvalidPattern { .. }) {
+                    return Err(e);
                 }
+                ctx.errors.push(e);
             }
         }
 
-        events.send(Event::Scan(ScanEvent::Completed {
-            total_photos: all_photos.len(),
-        }));
+        ctx.emit_completed();
 
         Ok(ScanResult {
-            photos: all_photos,
-            errors: all_errors,
+            photos: ctx.photos,
+            errors: ctx.errors,
         })
     }
 }
@@ -381,4 +500,128 @@ mod tests {
         let result = result.unwrap();
         assert!(!result.errors.is_empty());
     }
+
+    #[test]
+    fn scan_skips_excluded_paths_and_counts_them() {
+        use crate::events::EventChannel;
+
+        let temp_dir = TempDir::new().unwrap();
+        for dir in ["@eaDir", "album", "album/previews"] {
+            fs::create_dir(temp_dir.path().join(dir)).unwrap();
+        }
+        for name in [
+            "keep.jpg",
+            "@eaDir/thumb.jpg",
+            "album/photo.jpg",
+            "album/photo.png",
+            "album/previews/preview.jpg",
+        ] {
+            create_test_photo(&temp_dir, name);
+        }
+        fs::write(
+            temp_dir
+                .path()
+                .join("album")
+                .join(super::super::IGNORE_FILE_NAME),
+            "*.png\npreviews/\n",
+        )
+        .unwrap();
+
+        let config = ScanConfig {
+            exclude: vec!["@eaDir/".to_string()],
+            ..Default::default()
+        };
+        let (sender, receiver) = EventChannel::new();
+        let result = WalkDirScanner::new(config)
+            .scan_with_events(&[temp_dir.path().to_path_buf()], &sender)
+            .unwrap();
+        drop(sender);
+
+        let mut found: Vec<_> = result.photos.iter().map(|p| p.path.clone()).collect();
+        found.sort();
+        assert_eq!(
+            found,
+            vec![
+                temp_dir.path().join("album/photo.jpg"),
+                temp_dir.path().join("keep.jpg")
+            ]
+        );
+        let completed = receiver.iter().find_map(|event| match event {
+            Event::Scan(ScanEvent::Completed {
+                excluded_files,
+                excluded_directories,
+                ..
+            }) => Some((excluded_files, excluded_directories)),
+            _ => None,
+        });
+        assert_eq!(completed, Some((1, 2)));
+    }
+
+    #[test]
+    fn scan_can_sniff_formats_from_contents() {
+        let temp_dir = TempDir::new().unwrap();
+        let png = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+        fs::write(temp_dir.path().join("mislabeled.jpg"), png).unwrap();
+        fs::write(temp_dir.path().join("IMG-20240101-WA0001"), png).unwrap();
+
+        let scanner = WalkDirScanner::new(ScanConfig::default());
+        let result = scanner.scan(&[temp_dir.path().to_path_buf()]).unwrap();
+        assert_eq!(result.photos.len(), 1);
+        assert_eq!(result.photos[0].format, ImageFormat::Jpeg);
+
+        let config = ScanConfig {
+            sniff_content: true,
+            ..Default::default()
+        };
+        let result = WalkDirScanner::new(config)
+            .scan(&[temp_dir.path().to_path_buf()])
+            .unwrap();
+        assert_eq!(result.photos.len(), 2);
+        assert!(result.photos.iter().all(|p| p.format == ImageFormat::Png));
+    }
+
+    #[test]
+    fn scan_skips_photos_outside_the_limits() {
+        use std::time::{Duration, SystemTime};
+
+        let temp_dir = TempDir::new().unwrap();
+        let since = SystemTime::now() - Duration::from_secs(86_400);
+        fs::write(temp_dir.path().join("small.jpg"), [0; 10]).unwrap();
+        fs::write(temp_dir.path().join("large.jpg"), [0; 1000]).unwrap();
+        fs::write(temp_dir.path().join("old.jpg"), [0; 1000]).unwrap();
+        File::options()
+            .write(true)
+            .open(temp_dir.path().join("old.jpg"))
+            .unwrap()
+            .set_modified(since - Duration::from_secs(86_400))
+            .unwrap();
+
+        let config = ScanConfig {
+            limits: ScanLimits {
+                min_size: Some(100),
+                modified_after: Some(since),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = WalkDirScanner::new(config)
+            .scan(&[temp_dir.path().to_path_buf()])
+            .unwrap();
+
+        assert_eq!(result.photos.len(), 1);
+        assert_eq!(result.photos[0].path, temp_dir.path().join("large.jpg"));
+    }
+
+    #[test]
+    fn scan_rejects_invalid_exclude_pattern() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = ScanConfig {
+            exclude: vec!["{raw,jpg".to_string()],
+            ..Default::default()
+        };
+
+        let result = WalkDirScanner::new(config).scan(&[temp_dir.path().to_path_buf()]);
+
+        assert!(matches!(result, Err(ScanError::InvalidPattern { .. })));
+    }
 }
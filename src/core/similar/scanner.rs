@@ -1,6 +1,7 @@
 //! Scanner for finding similar (not exact duplicate) photos.
 
 use super::types::*;
+use crate::core::analysis::PhotoAnalyzer;
 use crate::core::comparator::{find_duplicate_pairs, MatchType, ThresholdStrategy, TransitiveGrouper};
 use crate::core::hasher::{HashAlgorithmKind, HasherConfig, ImageHashValue, PerceptualHash};
 use crate::core::scanner::{PhotoScanner, ScanConfig, WalkDirScanner};
@@ -30,7 +31,10 @@ impl SimilarScanner {
         on_progress("Scanning", 0, 0);
         let paths: Vec<PathBuf> = config.source_paths.iter().map(PathBuf::from).collect();
 
-        let scanner = WalkDirScanner::new(ScanConfig::default());
+        let scanner = WalkDirScanner::new(ScanConfig {
+            limits: config.limits,
+            ..Default::default()
+        });
         let scan_result = scanner.scan(&paths).map_err(|e| e.to_string())?;
 
         let photos = scan_result.photos;
@@ -55,6 +59,9 @@ impl SimilarScanner {
             Some("average") => HashAlgorithmKind::Average,
             Some("perceptual") => HashAlgorithmKind::Perceptual,
             Some("fusion") => HashAlgorithmKind::Fusion,
+            Some("wavelet") => HashAlgorithmKind::Wavelet,
+            Some("block-mean") => HashAlgorithmKind::BlockMean,
+            Some("crop") => HashAlgorithmKind::CropResistant,
             _ => HashAlgorithmKind::Perceptual, // Default to perceptual for similar detection
         };
 
@@ -66,10 +73,23 @@ impl SimilarScanner {
         let completed = Arc::new(AtomicUsize::new(0));
         let completed_clone = completed.clone();
 
+        // The dimension limit needs the full-size dimensions, which the
+        // analyzer reads from the same decode as the hash
+        let analyzer = PhotoAnalyzer::new(hasher.as_ref());
+        let limits = &config.limits;
         let hashes: Vec<(PathBuf, ImageHashValue)> = photos
             .par_iter()
             .filter_map(|photo| {
-                let result = hasher.hash_file(&photo.path).ok()?;
+                let result = if limits.min_dimension.is_some() {
+                    let analysis = analyzer.analyze_file(&photo.path).ok()?;
+                    let details = analysis.details;
+                    if !limits.admits_dimensions(details.width, details.height) {
+                        return None;
+                    }
+                    analysis.hash
+                } else {
+                    hasher.hash_file(&photo.path).ok()?
+                };
                 let _current = completed_clone.fetch_add(1, Ordering::Relaxed) + 1;
                 // Progress is updated but callback can't be easily called from par_iter
                 Some((photo.path.clone(), result))
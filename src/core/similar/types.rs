@@ -1,6 +1,7 @@
 //! Types for similar photo detection.
 
 use crate::core::comparator::MatchType;
+use crate::core::scanner::ScanLimits;
 use serde::{Deserialize, Serialize};
 
 /// Configuration for similar photo scan
@@ -13,8 +14,12 @@ pub struct SimilarConfig {
     /// Maximum hamming distance to include
     /// Default: 15 (includes Similar and MaybeSimilar)
     pub max_distance: u32,
-    /// Hash algorithm to use
+    /// Hash algorithm to use: "average", "perceptual", "fusion", "wavelet"
+    /// or "block-mean" (default: perceptual)
     pub algorithm: Option<String>,
+    /// Size, date and dimension limits a photo must be within
+    #[serde(default)]
+    pub limits: ScanLimits,
 }
 
 impl Default for SimilarConfig {
@@ -24,6 +29,7 @@ impl Default for SimilarConfig {
             min_distance: 5,  // Start from Similar (excludes exact duplicates)
             max_distance: 15, // Include MaybeSimilar
             algorithm: None,
+            limits: ScanLimits::default(),
         }
     }
 }
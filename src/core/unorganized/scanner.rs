@@ -1,6 +1,7 @@
 //! Scanner for finding unorganized media files.
 
 use super::types::*;
+use crate::core::scanner::ScanLimits;
 use rayon::prelude::*;
 use regex::Regex;
 use std::collections::HashMap;
@@ -8,7 +9,7 @@ use std::fs;
 use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Instant, UNIX_EPOCH};
 use walkdir::WalkDir;
 
 /// Scanner for finding unorganized files
@@ -33,7 +34,10 @@ impl UnorganizedScanner {
 
             for entry in WalkDir::new(source).into_iter().filter_map(|e| e.ok()) {
                 let path = entry.path();
-                if path.is_file() && Self::is_media_file(path) {
+                if path.is_file()
+                    && Self::is_media_file(path)
+                    && Self::within_limits(path, &config.limits)
+                {
                     let depth = Self::calculate_depth(path, source);
                     all_files.push((
                         path.display().to_string(),
@@ -168,8 +172,9 @@ impl UnorganizedScanner {
 
     fn is_media_file(path: &Path) -> bool {
         const MEDIA_EXTENSIONS: &[&str] = &[
-            "jpg", "jpeg", "png", "gif", "webp", "bmp", "tiff", "tif", "heic", "heif", "raw",
-            "cr2", "nef", "dng", "arw", "raf", "mp4", "mov", "avi", "mkv", "wmv", "webm", "m4v",
+            "jpg", "jpeg", "png", "gif", "webp", "bmp", "tiff", "tif", "heic", "heif", "avif",
+            "jxl", "raw", "cr2", "cr3", "nef", "dng", "arw", "raf", "orf", "mp4", "mov", "avi",
+            "mkv", "wmv", "webm", "m4v",
         ];
 
         path.extension()
@@ -178,6 +183,19 @@ impl UnorganizedScanner {
             .unwrap_or(false)
     }
 
+    /// Check a file against the limits, reading dimensions from its header
+    /// as nothing is decoded
+    fn within_limits(path: &Path, limits: &ScanLimits) -> bool {
+        if *limits == ScanLimits::default() {
+            return true;
+        }
+        let within = fs::metadata(path).is_ok_and(|metadata| {
+            let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+            limits.admits_file(metadata.len(), modified)
+        });
+        within && limits.admits_header_dimensions(path)
+    }
+
     fn calculate_depth(file_path: &Path, root: &Path) -> usize {
         let relative = file_path
             .parent()
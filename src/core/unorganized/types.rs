@@ -1,5 +1,6 @@
 //! Types for unorganized file detection.
 
+use crate::core::scanner::ScanLimits;
 use serde::{Deserialize, Serialize};
 
 /// Why a file is considered unorganized
@@ -68,6 +69,9 @@ pub struct UnorganizedConfig {
     pub check_generic_names: bool,
     /// Minimum folder depth to be considered "organized" (default: 2)
     pub min_depth: usize,
+    /// Size, date and dimension limits a file must be within
+    #[serde(default)]
+    pub limits: ScanLimits,
 }
 
 impl Default for UnorganizedConfig {
@@ -78,6 +82,7 @@ impl Default for UnorganizedConfig {
             check_date_pattern: true,
             check_generic_names: true,
             min_depth: 2,
+            limits: ScanLimits::default(),
         }
     }
 }
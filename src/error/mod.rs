@@ -52,6 +52,9 @@ pub enum ScanError {
         source: std::io::Error,
     },
 
+    #[error("Invalid pattern in {origin}: {reason}")]
+    InvalidPattern { origin: String, reason: String },
+
     #[error("Scan was cancelled")]
     Cancelled,
 }
@@ -71,6 +74,12 @@ pub enum HashError {
     #[error("Hash computation failed: {0}")]
     ComputationFailed(String),
 
+    #[error("Unsupported hash size: {size} (must be 8, 16 or 32)")]
+    UnsupportedHashSize { size: u32 },
+
+    #[error("{algorithm} can't be a fusion voter (only wHash and block-mean can)")]
+    InvalidFusionVoter { algorithm: String },
+
     #[error("Failed to open image file {path}: {source}")]
     IoError {
         path: PathBuf,
@@ -1,5 +1,6 @@
 //! Event type definitions for progress reporting.
 
+use crate::core::pipeline::{DetectionMode, TwoPhaseStats};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -47,7 +48,16 @@ pub enum ScanEvent {
     /// An error occurred but scanning continues
     Error { path: PathBuf, message: String },
     /// Scanning completed
-    Completed { total_photos: usize },
+    Completed {
+        total_photos: usize,
+        /// Photos skipped by exclude patterns, ignore files, or the size
+        /// and date limits
+        #[serde(default)]
+        excluded_files: usize,
+        /// Directories skipped by exclude patterns and ignore files
+        #[serde(default)]
+        excluded_directories: usize,
+    },
 }
 
 /// Progress information during scanning
@@ -137,12 +147,16 @@ pub enum PipelineEvent {
     Cancelled,
     /// Optimization pre-filtering completed
     OptimizationStats {
+        /// Detection mode the pre-filters ran for (perceptual skips nothing)
+        mode: DetectionMode,
         /// Photos skipped due to unique file size
         skipped_unique_size: usize,
         /// Photos skipped due to unique 4KB prefix
         skipped_unique_prefix: usize,
-        /// Photos remaining for hashing
+        /// Photos remaining for hashing after the byte-level filters
         candidates: usize,
+        /// How two-phase hashing screened those photos (None if it didn't run)
+        two_phase: Option<TwoPhaseStats>,
     },
     /// Pipeline encountered a fatal error
     Error { message: String },
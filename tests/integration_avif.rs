@@ -0,0 +1,60 @@
+//! Integration tests for AVIF support (`avif` feature, needs libdav1d).
+
+#![cfg(feature = "avif")]
+
+use duplicate_photo_cleaner::core::hasher::FastDecoder;
+use duplicate_photo_cleaner::core::pipeline::Pipeline;
+use image::codecs::avif::AvifEncoder;
+use image::ImageEncoder;
+use std::fs::File;
+use tempfile::TempDir;
+
+/// A photo-like gradient with some structure for the hashes to pick up
+fn photo() -> image::RgbImage {
+    image::RgbImage::from_fn(256, 192, |x, y| {
+        let wave = ((x as f32 / 11.0).sin() * (y as f32 / 7.0).cos() * 90.0) as i32;
+        let value = (wave + ((x / 32 + y / 24) % 4) as i32 * 30 + 80).clamp(0, 255) as u8;
+        image::Rgb([value, 255 - value, value / 2])
+    })
+}
+
+/// Save `img` as a high-quality AVIF
+fn save_avif(img: &image::RgbImage, path: &std::path::Path) {
+    AvifEncoder::new_with_speed_quality(File::create(path).unwrap(), 10, 90)
+        .write_image(
+            img.as_raw(),
+            img.width(),
+            img.height(),
+            image::ExtendedColorType::Rgb8,
+        )
+        .unwrap();
+}
+
+#[test]
+fn avif_decodes_to_original_dimensions() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("export.avif");
+    save_avif(&photo(), &path);
+
+    let image = FastDecoder::decode(&path).unwrap();
+
+    assert_eq!((image.width(), image.height()), (256, 192));
+}
+
+#[test]
+fn pipeline_matches_avif_export_with_its_png() {
+    let temp_dir = TempDir::new().unwrap();
+    let img = photo();
+    img.save(temp_dir.path().join("original.png")).unwrap();
+    save_avif(&img, &temp_dir.path().join("export.avif"));
+
+    let result = Pipeline::builder()
+        .paths(vec![temp_dir.path().to_path_buf()])
+        .build()
+        .run()
+        .unwrap();
+
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    assert_eq!(result.total_photos, 2);
+    assert_eq!(result.groups.len(), 1);
+}
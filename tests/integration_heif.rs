@@ -0,0 +1,59 @@
+//! Integration tests for native HEIC/HEIF support (`heif` feature).
+//!
+//! Runs against `tests/fixtures/heif/sample.heic`, an iPhone photo from the
+//! libheif-rs test data (CC BY-SA 4.0, see the license next to it).
+
+#![cfg(feature = "heif")]
+
+use duplicate_photo_cleaner::core::hasher::{DecodeOptions, FastDecoder};
+use duplicate_photo_cleaner::core::metadata::extract_metadata;
+use duplicate_photo_cleaner::core::pipeline::Pipeline;
+use duplicate_photo_cleaner::core::quality::QualityAnalyzer;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+fn fixture() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/heif/sample.heic")
+}
+
+#[test]
+fn pipeline_matches_heic_with_its_png_copy() {
+    let temp_dir = TempDir::new().unwrap();
+    let heic = temp_dir.path().join("IMG_0001.heic");
+    std::fs::copy(fixture(), &heic).unwrap();
+
+    // A downscaled PNG export of the same photo
+    FastDecoder::decode_with(&heic, &DecodeOptions::upright())
+        .unwrap()
+        .resize(826, 896, image::imageops::FilterType::Triangle)
+        .save(temp_dir.path().join("export.png"))
+        .unwrap();
+
+    let result = Pipeline::builder()
+        .paths(vec![temp_dir.path().to_path_buf()])
+        .build()
+        .run()
+        .unwrap();
+
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    assert_eq!(result.total_photos, 2);
+    assert_eq!(result.groups.len(), 1);
+    assert!(result.groups[0].photos.contains(&heic));
+}
+
+#[test]
+fn heic_metadata_uses_container_dimensions() {
+    let metadata = extract_metadata(&fixture());
+
+    assert_eq!(metadata.camera_make.as_deref(), Some("Apple"));
+    assert_eq!(metadata.camera_model.as_deref(), Some("iPhone 14 Pro"));
+    // The EXIF tags still say 4032x3024 from before the photo was edited
+    assert_eq!((metadata.width, metadata.height), (Some(1652), Some(1791)));
+}
+
+#[test]
+fn heic_quality_is_analyzed() {
+    let score = QualityAnalyzer::default().analyze_file(&fixture()).unwrap();
+
+    assert!(score.sharpness > 0.0);
+}
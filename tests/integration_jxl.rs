@@ -0,0 +1,42 @@
+//! Integration tests for JPEG XL support (`jxl` feature).
+
+#![cfg(feature = "jxl")]
+
+use duplicate_photo_cleaner::core::pipeline::Pipeline;
+use tempfile::TempDir;
+use zune_core::bit_depth::BitDepth;
+use zune_core::colorspace::ColorSpace;
+use zune_core::options::EncoderOptions;
+use zune_jpegxl::JxlSimpleEncoder;
+
+/// A photo-like gradient with some structure for the hashes to pick up
+fn photo() -> image::RgbImage {
+    image::RgbImage::from_fn(256, 192, |x, y| {
+        let wave = ((x as f32 / 11.0).sin() * (y as f32 / 7.0).cos() * 90.0) as i32;
+        let value = (wave + ((x / 32 + y / 24) % 4) as i32 * 30 + 80).clamp(0, 255) as u8;
+        image::Rgb([value, 255 - value, value / 2])
+    })
+}
+
+#[test]
+fn pipeline_matches_jxl_export_with_its_png() {
+    let temp_dir = TempDir::new().unwrap();
+    let img = photo();
+    img.save(temp_dir.path().join("original.png")).unwrap();
+
+    let options = EncoderOptions::new(256, 192, ColorSpace::RGB, BitDepth::Eight);
+    let jxl = JxlSimpleEncoder::new(img.as_raw(), options)
+        .encode()
+        .unwrap();
+    std::fs::write(temp_dir.path().join("export.jxl"), jxl).unwrap();
+
+    let result = Pipeline::builder()
+        .paths(vec![temp_dir.path().to_path_buf()])
+        .build()
+        .run()
+        .unwrap();
+
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    assert_eq!(result.total_photos, 2);
+    assert_eq!(result.groups.len(), 1);
+}
@@ -4,10 +4,25 @@
 //! - Empty directories
 //! - Nonexistent paths
 //! - Basic error handling
+//! - Rotated copies
+//! - Cropped copies
+//! - Larger hash sizes
+//! - Byte-identical copies
+//! - Pixel-identical copies with different bytes
+//! - Camera RAW files with an embedded preview
+//! - Preview scans from EXIF thumbnails
+//! - Incremental rescans
+//! - Mislabeled photos found by content sniffing
+//! - Hardlinked photos
+//! - Size and dimension limits
 
 use duplicate_photo_cleaner::core::cache::{CacheBackend, SqliteCache};
-use duplicate_photo_cleaner::core::hasher::HashAlgorithmKind;
-use duplicate_photo_cleaner::core::pipeline::Pipeline;
+use duplicate_photo_cleaner::core::comparator::{GroupingStrategy, MatchType};
+use duplicate_photo_cleaner::core::hasher::{DihedralTransform, HashAlgorithmKind};
+use duplicate_photo_cleaner::core::pipeline::{
+    OptimizationConfig, Pipeline, PreviewStats, ScanMode,
+};
+use duplicate_photo_cleaner::events::{Event, EventChannel, PipelineEvent};
 use std::fs::File;
 use std::io::Write;
 use std::path::PathBuf;
@@ -132,3 +147,644 @@ fn sqlite_cache_with_prune_works() {
     let stats = cache.stats().unwrap();
     assert_eq!(stats.total_entries, 0);
 }
+
+/// Save an image with no rotational symmetry, and a copy rotated 90°
+fn create_rotated_pair(dir: &std::path::Path) {
+    let img = image::RgbImage::from_fn(128, 96, |x, y| {
+        let value = if x < 40 && y < 24 { 255 } else { (x + y) as u8 };
+        image::Rgb([value, value / 2, 255 - value])
+    });
+    img.save(dir.join("original.png")).unwrap();
+    image::imageops::rotate90(&img)
+        .save(dir.join("rotated.png"))
+        .unwrap();
+}
+
+#[test]
+fn pipeline_matches_rotated_copies_when_enabled() {
+    let temp_dir = TempDir::new().unwrap();
+    create_rotated_pair(temp_dir.path());
+
+    let upright_only = Pipeline::builder()
+        .paths(vec![temp_dir.path().to_path_buf()])
+        .threshold(4)
+        .build()
+        .run()
+        .unwrap();
+    assert!(upright_only.groups.is_empty());
+
+    let with_rotations = Pipeline::builder()
+        .paths(vec![temp_dir.path().to_path_buf()])
+        .threshold(4)
+        .match_rotations(true)
+        .build()
+        .run()
+        .unwrap();
+
+    assert_eq!(with_rotations.groups.len(), 1);
+    let transform = with_rotations.groups[0].transform.unwrap();
+    assert!(matches!(
+        transform,
+        DihedralTransform::Rotate90 | DihedralTransform::Rotate270
+    ));
+}
+
+/// Write a detailed photo and a copy cropped to its bottom-right quarter
+fn create_cropped_pair(dir: &std::path::Path) {
+    let img = image::RgbImage::from_fn(320, 240, |x, y| {
+        let wave = ((x as f32 / 9.0).sin() * (y as f32 / 13.0).cos() * 100.0) as i32;
+        let value = (wave + ((x / 40 + y / 30) % 3) as i32 * 40 + 100).clamp(0, 255) as u8;
+        image::Rgb([value, value, value])
+    });
+    img.save(dir.join("original.png")).unwrap();
+    image::imageops::crop_imm(&img, 160, 120, 160, 120)
+        .to_image()
+        .save(dir.join("cropped.png"))
+        .unwrap();
+}
+
+#[test]
+fn pipeline_finds_cropped_copies_with_crop_resistant_hash() {
+    let temp_dir = TempDir::new().unwrap();
+    create_cropped_pair(temp_dir.path());
+
+    let global = Pipeline::builder()
+        .paths(vec![temp_dir.path().to_path_buf()])
+        .threshold(4)
+        .build()
+        .run()
+        .unwrap();
+    assert!(global.groups.is_empty());
+
+    let crop_resistant = Pipeline::builder()
+        .paths(vec![temp_dir.path().to_path_buf()])
+        .algorithm(HashAlgorithmKind::CropResistant)
+        .threshold(4)
+        .build()
+        .run()
+        .unwrap();
+
+    assert_eq!(crop_resistant.groups.len(), 1);
+    let crop = crop_resistant.groups[0].crop.as_ref().unwrap();
+    assert_eq!(crop.original, temp_dir.path().join("original.png"));
+    assert_eq!(crop.cropped, temp_dir.path().join("cropped.png"));
+    assert_eq!((crop.region.x, crop.region.y), (0.5, 0.5));
+}
+
+/// Save a photo and a half-size copy of it
+fn create_resized_pair(dir: &std::path::Path) {
+    let img = image::RgbImage::from_fn(256, 192, |x, y| {
+        let wave = ((x as f32 / 11.0).sin() * (y as f32 / 7.0).cos() * 90.0) as i32;
+        let value = (wave + ((x / 32 + y / 24) % 4) as i32 * 30 + 80).clamp(0, 255) as u8;
+        image::Rgb([value, 255 - value, value / 2])
+    });
+    img.save(dir.join("original.png")).unwrap();
+    image::imageops::resize(&img, 128, 96, image::imageops::FilterType::Triangle)
+        .save(dir.join("resized.png"))
+        .unwrap();
+}
+
+#[test]
+fn pipeline_uses_and_caches_configured_hash_size() {
+    let photo_dir = TempDir::new().unwrap();
+    create_resized_pair(photo_dir.path());
+    let cache_dir = TempDir::new().unwrap();
+    let cache_path = cache_dir.path().join("cache.db");
+
+    let result = Pipeline::builder()
+        .paths(vec![photo_dir.path().to_path_buf()])
+        .hash_size(16)
+        .threshold(8)
+        .cache(Box::new(SqliteCache::open(&cache_path).unwrap()))
+        .build()
+        .run()
+        .unwrap();
+    assert_eq!(result.groups.len(), 1);
+
+    let cache = SqliteCache::open(&cache_path).unwrap();
+    let path = photo_dir.path().join("original.png");
+    let metadata = std::fs::metadata(&path).unwrap();
+    let lookup = |hash_size| {
+        cache
+            .get(
+                &path,
+                HashAlgorithmKind::Difference,
+                hash_size,
+                metadata.len(),
+                metadata.modified().unwrap(),
+            )
+            .unwrap()
+    };
+
+    // 16x16 = 256 bits
+    assert_eq!(lookup(16).unwrap().hash.len(), 32);
+    assert!(lookup(8).is_none());
+}
+
+#[test]
+fn pipeline_caches_details_from_the_hashing_decode() {
+    let photo_dir = TempDir::new().unwrap();
+    create_resized_pair(photo_dir.path());
+    let cache_dir = TempDir::new().unwrap();
+    let cache_path = cache_dir.path().join("cache.db");
+    let run = || {
+        Pipeline::builder()
+            .paths(vec![photo_dir.path().to_path_buf()])
+            .cache(Box::new(SqliteCache::open(&cache_path).unwrap()))
+            .build()
+            .run()
+            .unwrap()
+    };
+
+    let result = run();
+    assert_eq!(result.groups.len(), 1);
+    let original = photo_dir.path().join("original.png");
+    let resized = photo_dir.path().join("resized.png");
+    assert_eq!(result.groups[0].representative, original);
+    let details = &result.details[&resized];
+    assert_eq!((details.width, details.height), (128, 96));
+    assert!(details.quality.sharpness > 0.0);
+
+    // A second run gets the same details from the cache
+    let rerun = run();
+    assert_eq!(rerun.cache_hits, 2);
+    let cached = &rerun.details[&resized];
+    assert_eq!((cached.width, cached.height), (128, 96));
+    assert!((cached.quality.overall - details.quality.overall).abs() < 1e-9);
+}
+
+#[test]
+fn two_phase_hashing_skips_photos_without_candidates() {
+    let photo_dir = TempDir::new().unwrap();
+    create_resized_pair(photo_dir.path());
+    // Bright on the left, dark on the right: far from the pair's aHash
+    image::RgbImage::from_fn(256, 192, |x, _| {
+        let value = if x < 128 { 230 } else { 20 };
+        image::Rgb([value, value, value])
+    })
+    .save(photo_dir.path().join("unrelated.png"))
+    .unwrap();
+
+    let (sender, receiver) = EventChannel::new();
+    let result = Pipeline::builder()
+        .paths(vec![photo_dir.path().to_path_buf()])
+        .algorithm(HashAlgorithmKind::Perceptual)
+        .optimization(OptimizationConfig {
+            min_photos_threshold: 0,
+            ..OptimizationConfig::default()
+        })
+        .build()
+        .run_with_events(&sender)
+        .unwrap();
+    drop(sender);
+
+    assert_eq!(result.groups.len(), 1);
+    assert_eq!(result.groups[0].photos.len(), 2);
+    let two_phase = receiver
+        .iter()
+        .find_map(|event| match event {
+            Event::Pipeline(PipelineEvent::OptimizationStats { two_phase, .. }) => two_phase,
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(two_phase.screened, 3);
+    assert_eq!(two_phase.candidates, 2);
+    assert_eq!(two_phase.skipped, 1);
+}
+
+#[test]
+fn pipeline_rejects_unsupported_hash_size() {
+    let temp_dir = TempDir::new().unwrap();
+    create_resized_pair(temp_dir.path());
+
+    let result = Pipeline::builder()
+        .paths(vec![temp_dir.path().to_path_buf()])
+        .hash_size(12)
+        .build()
+        .run();
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn pipeline_reports_byte_identical_copies() {
+    let temp_dir = TempDir::new().unwrap();
+    create_resized_pair(temp_dir.path());
+    std::fs::copy(
+        temp_dir.path().join("original.png"),
+        temp_dir.path().join("copy.png"),
+    )
+    .unwrap();
+
+    let result = Pipeline::builder()
+        .paths(vec![temp_dir.path().to_path_buf()])
+        .grouping(GroupingStrategy::CompleteLinkage)
+        .build()
+        .run()
+        .unwrap();
+
+    // The copy is byte-identical, but the resized photo only looks the same,
+    // so the group as a whole isn't
+    assert_eq!(result.groups.len(), 1);
+    assert_eq!(result.groups[0].photos.len(), 3);
+    assert_eq!(result.groups[0].match_type, MatchType::Exact);
+
+    std::fs::remove_file(temp_dir.path().join("resized.png")).unwrap();
+    let copies = Pipeline::builder()
+        .paths(vec![temp_dir.path().to_path_buf()])
+        .build()
+        .run()
+        .unwrap();
+    assert_eq!(copies.groups[0].match_type, MatchType::ByteIdentical);
+
+    let re_encoded = Pipeline::builder()
+        .paths(vec![temp_dir.path().to_path_buf()])
+        .byte_identical(false)
+        .build()
+        .run()
+        .unwrap();
+    assert_eq!(re_encoded.groups[0].match_type, MatchType::Exact);
+}
+
+#[test]
+fn pipeline_reports_same_pixels_with_different_bytes() {
+    use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+    use image::ImageEncoder;
+
+    let temp_dir = TempDir::new().unwrap();
+    create_resized_pair(temp_dir.path());
+    std::fs::remove_file(temp_dir.path().join("resized.png")).unwrap();
+
+    // Re-save the same pixels with different compression settings
+    let img = image::open(temp_dir.path().join("original.png"))
+        .unwrap()
+        .to_rgb8();
+    let file = File::create(temp_dir.path().join("resaved.png")).unwrap();
+    PngEncoder::new_with_quality(file, CompressionType::Fast, FilterType::NoFilter)
+        .write_image(
+            img.as_raw(),
+            img.width(),
+            img.height(),
+            image::ExtendedColorType::Rgb8,
+        )
+        .unwrap();
+    assert_ne!(
+        std::fs::read(temp_dir.path().join("original.png")).unwrap(),
+        std::fs::read(temp_dir.path().join("resaved.png")).unwrap()
+    );
+
+    let result = Pipeline::builder()
+        .paths(vec![temp_dir.path().to_path_buf()])
+        .build()
+        .run()
+        .unwrap();
+
+    assert_eq!(result.groups.len(), 1);
+    assert_eq!(
+        result.groups[0].match_type,
+        MatchType::SamePixelsDifferentMetadata
+    );
+}
+
+#[test]
+fn pipeline_keeps_raw_original_over_its_export() {
+    use image::codecs::jpeg::JpegEncoder;
+
+    let temp_dir = TempDir::new().unwrap();
+    create_resized_pair(temp_dir.path());
+    std::fs::remove_file(temp_dir.path().join("resized.png")).unwrap();
+
+    // A RAW-like file: an empty TIFF directory, then the camera's preview
+    let img = image::open(temp_dir.path().join("original.png")).unwrap();
+    let mut nef = b"II*\0\x08\0\0\0\0\0\0\0\0\0".to_vec();
+    JpegEncoder::new_with_quality(&mut nef, 90)
+        .encode_image(&img)
+        .unwrap();
+    let raw = temp_dir.path().join("DSC_0001.NEF");
+    std::fs::write(&raw, nef).unwrap();
+    assert!(
+        std::fs::metadata(&raw).unwrap().len()
+            < std::fs::metadata(temp_dir.path().join("original.png"))
+                .unwrap()
+                .len()
+    );
+
+    let result = Pipeline::builder()
+        .paths(vec![temp_dir.path().to_path_buf()])
+        .build()
+        .run()
+        .unwrap();
+
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    assert_eq!(result.total_photos, 2);
+    assert_eq!(result.groups.len(), 1);
+    assert_eq!(result.groups[0].representative, raw);
+}
+
+/// A busy test pattern; different seeds give unrelated images
+/// Move a directory's modification time back, as if it was written a while ago
+fn settle(dir: &std::path::Path) {
+    let past = std::time::SystemTime::now() - std::time::Duration::from_secs(60);
+    File::open(dir).unwrap().set_modified(past).unwrap();
+}
+
+#[test]
+fn incremental_scan_compares_new_photos_against_earlier_ones() {
+    let photo_dir = TempDir::new().unwrap();
+    let album = photo_dir.path().join("album");
+    std::fs::create_dir(&album).unwrap();
+    create_resized_pair(&album);
+    settle(&album);
+    settle(photo_dir.path());
+    let cache_dir = TempDir::new().unwrap();
+    let cache_path = cache_dir.path().join("cache.db");
+    let run = |incremental| {
+        Pipeline::builder()
+            .paths(vec![photo_dir.path().to_path_buf()])
+            .incremental(incremental)
+            .cache(Box::new(SqliteCache::open(&cache_path).unwrap()))
+            .build()
+            .run()
+            .unwrap()
+    };
+    let photos = |result: &duplicate_photo_cleaner::core::pipeline::PipelineResult| {
+        let mut photos: Vec<PathBuf> = result
+            .groups
+            .iter()
+            .flat_map(|g| g.photos.clone())
+            .collect();
+        photos.sort();
+        photos
+    };
+
+    let first = run(true);
+    assert_eq!(first.groups.len(), 1);
+    let cache = SqliteCache::open(&cache_path).unwrap();
+    let state = cache.get_scan_state(&album).unwrap().unwrap();
+    assert_eq!(state.file_count, 2);
+
+    // A new copy in a new directory matches the photos found before
+    let more = photo_dir.path().join("more");
+    std::fs::create_dir(&more).unwrap();
+    std::fs::copy(album.join("original.png"), more.join("copy.png")).unwrap();
+    let second = run(true);
+    assert_eq!(second.groups.len(), 1);
+    assert_eq!(second.groups[0].photos.len(), 3);
+    assert_eq!(photos(&second), photos(&run(false)));
+
+    // Matches of a deleted photo are dropped
+    std::fs::remove_file(album.join("resized.png")).unwrap();
+    let third = run(true);
+    assert_eq!(
+        photos(&third),
+        vec![album.join("original.png"), more.join("copy.png")]
+    );
+}
+
+fn pattern(width: u32, height: u32, seed: u32) -> image::RgbImage {
+    image::RgbImage::from_fn(width, height, |x, y| {
+        let (u, v) = (x * 256 / width, y * 192 / height);
+        let wave = ((u as f32 / (5 + seed) as f32).sin()
+            * (v as f32 / (9 + 2 * seed) as f32).cos()
+            * 90.0) as i32;
+        let value = (wave + (((u / 32) * seed + v / 24) % 4) as i32 * 30 + 80).clamp(0, 255) as u8;
+        image::Rgb([value, 255 - value, value / 2])
+    })
+}
+
+/// Encode `photo` as a JPEG whose EXIF data carries `thumbnail`
+fn jpeg_with_thumbnail(photo: &image::RgbImage, thumbnail: Option<&image::RgbImage>) -> Vec<u8> {
+    use image::codecs::jpeg::JpegEncoder;
+
+    let mut jpeg = Vec::new();
+    JpegEncoder::new(&mut jpeg).encode_image(photo).unwrap();
+    let Some(thumbnail) = thumbnail else {
+        return jpeg;
+    };
+    let mut thumbnail_jpeg = Vec::new();
+    JpegEncoder::new(&mut thumbnail_jpeg)
+        .encode_image(thumbnail)
+        .unwrap();
+
+    // An empty IFD0 at 8, then IFD1 at 14 pointing at the thumbnail at 44
+    let mut tiff = b"II*\0\x08\0\0\0\0\0\x0e\0\0\0\x02\0".to_vec();
+    for (tag, value) in [(0x0201u16, 44u32), (0x0202, thumbnail_jpeg.len() as u32)] {
+        tiff.extend_from_slice(&tag.to_le_bytes());
+        tiff.extend_from_slice(&4u16.to_le_bytes());
+        tiff.extend_from_slice(&1u32.to_le_bytes());
+        tiff.extend_from_slice(&value.to_le_bytes());
+    }
+    tiff.extend_from_slice(&0u32.to_le_bytes());
+    tiff.extend_from_slice(&thumbnail_jpeg);
+
+    let mut out = jpeg[..2].to_vec();
+    out.extend_from_slice(&[0xFF, 0xE1]);
+    out.extend_from_slice(&((tiff.len() + 8) as u16).to_be_bytes());
+    out.extend_from_slice(b"Exif\0\0");
+    out.extend_from_slice(&tiff);
+    out.extend_from_slice(&jpeg[2..]);
+    out
+}
+
+#[test]
+fn preview_scan_confirms_thumbnail_matches_in_full() {
+    let temp_dir = TempDir::new().unwrap();
+    let thumb = |img: &image::RgbImage| {
+        image::imageops::resize(img, 160, 120, image::imageops::FilterType::Triangle)
+    };
+    let write = |name: &str, photo: &image::RgbImage, thumbnail: Option<&image::RgbImage>| {
+        std::fs::write(
+            temp_dir.path().join(name),
+            jpeg_with_thumbnail(photo, thumbnail),
+        )
+        .unwrap();
+    };
+
+    // A photo and its resized copy, each with its own thumbnail
+    let original = pattern(256, 192, 1);
+    let resized =
+        image::imageops::resize(&original, 192, 144, image::imageops::FilterType::Triangle);
+    write("original.jpg", &original, Some(&thumb(&original)));
+    write("resized.jpg", &resized, Some(&thumb(&resized)));
+    // Two unrelated photos carrying the same stale thumbnail
+    let stale = thumb(&pattern(256, 192, 2));
+    write("edited.jpg", &pattern(256, 192, 3), Some(&stale));
+    write("other.jpg", &pattern(256, 192, 4), Some(&stale));
+    // A photo that matches nothing, and one without a thumbnail
+    write(
+        "unique.jpg",
+        &pattern(256, 192, 5),
+        Some(&thumb(&pattern(256, 192, 5))),
+    );
+    write("plain.jpg", &pattern(256, 192, 6), None);
+
+    let full = Pipeline::builder()
+        .paths(vec![temp_dir.path().to_path_buf()])
+        .build()
+        .run()
+        .unwrap();
+    let preview = Pipeline::builder()
+        .paths(vec![temp_dir.path().to_path_buf()])
+        .scan_mode(ScanMode::Preview)
+        .build()
+        .run()
+        .unwrap();
+
+    assert!(full.preview.is_none());
+    assert_eq!(full.groups.len(), 1);
+    assert_eq!(preview.groups.len(), 1);
+    let mut photos = preview.groups[0].photos.clone();
+    photos.sort();
+    assert_eq!(
+        photos,
+        vec![
+            temp_dir.path().join("original.jpg"),
+            temp_dir.path().join("resized.jpg")
+        ]
+    );
+    assert_eq!(
+        preview.preview,
+        Some(PreviewStats {
+            thumbnails_only: 1,
+            no_thumbnail: 1,
+            confirmed: 4,
+            rejected_pairs: 1,
+        })
+    );
+}
+
+#[test]
+fn sniffing_scan_matches_and_reports_mislabeled_photos() {
+    use duplicate_photo_cleaner::core::scanner::{rename_to_content_extension, ImageFormat};
+    use image::codecs::jpeg::JpegEncoder;
+
+    let temp_dir = TempDir::new().unwrap();
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, 90)
+        .encode_image(&pattern(320, 240, 3))
+        .unwrap();
+    for name in ["original.jpg", "shared.png", "IMG-20240101-WA0001"] {
+        std::fs::write(temp_dir.path().join(name), &jpeg).unwrap();
+    }
+
+    let result = Pipeline::builder()
+        .paths(vec![temp_dir.path().to_path_buf()])
+        .sniff_content(true)
+        .build()
+        .run()
+        .unwrap();
+
+    assert_eq!(result.total_photos, 3);
+    assert_eq!(result.groups.len(), 1);
+    assert_eq!(result.groups[0].photos.len(), 3);
+
+    let mut mismatches = result.format_mismatches;
+    mismatches.sort_by(|a, b| a.path.cmp(&b.path));
+    assert_eq!(mismatches.len(), 2);
+    assert!(mismatches
+        .iter()
+        .all(|m| m.content_format == ImageFormat::Jpeg));
+    assert_eq!(mismatches[1].named_format, ImageFormat::Png);
+
+    let renamed: Vec<_> = mismatches
+        .iter()
+        .map(|m| rename_to_content_extension(m).unwrap())
+        .collect();
+    assert_eq!(
+        renamed,
+        vec![
+            temp_dir.path().join("IMG-20240101-WA0001.jpg"),
+            temp_dir.path().join("shared.jpg"),
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn hardlinks_are_reported_as_already_deduplicated() {
+    use duplicate_photo_cleaner::core::comparator::MatchType;
+    use image::codecs::jpeg::JpegEncoder;
+
+    let temp_dir = TempDir::new().unwrap();
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, 90)
+        .encode_image(&pattern(320, 240, 4))
+        .unwrap();
+    let original = temp_dir.path().join("a_original.jpg");
+    let copy = temp_dir.path().join("b_copy.jpg");
+    std::fs::write(&original, &jpeg).unwrap();
+    std::fs::write(&copy, &jpeg).unwrap();
+    std::fs::hard_link(&original, temp_dir.path().join("c_link.jpg")).unwrap();
+    std::fs::hard_link(&copy, temp_dir.path().join("d_link.jpg")).unwrap();
+
+    let result = Pipeline::builder()
+        .paths(vec![temp_dir.path().to_path_buf()])
+        .build()
+        .run()
+        .unwrap();
+
+    assert_eq!(result.total_photos, 4);
+    assert_eq!(result.groups.len(), 3);
+
+    // Both copies have another link, so deleting either frees nothing
+    let copies = &result.groups[0];
+    assert_ne!(copies.match_type, MatchType::Hardlinked);
+    assert_eq!(copies.photos, vec![original.clone(), copy.clone()]);
+    assert_eq!(copies.duplicate_size_bytes, 0);
+
+    for (links, first, second) in [
+        (&result.groups[1], original, "c_link.jpg"),
+        (&result.groups[2], copy, "d_link.jpg"),
+    ] {
+        assert_eq!(links.match_type, MatchType::Hardlinked);
+        assert_eq!(links.photos, vec![first, temp_dir.path().join(second)]);
+        assert_eq!(links.duplicate_count(), 0);
+        assert_eq!(links.duplicate_size_bytes, 0);
+    }
+}
+
+#[test]
+fn limits_skip_small_files_and_icons() {
+    use duplicate_photo_cleaner::core::scanner::ScanLimits;
+
+    let temp_dir = TempDir::new().unwrap();
+    for (name, width, height, seed) in [("photo", 320, 240, 5), ("icon", 64, 64, 1)] {
+        let image = pattern(width, height, seed);
+        image
+            .save(temp_dir.path().join(format!("{}.png", name)))
+            .unwrap();
+        image
+            .save(temp_dir.path().join(format!("{}_copy.png", name)))
+            .unwrap();
+    }
+
+    let run = |limits| {
+        Pipeline::builder()
+            .paths(vec![temp_dir.path().to_path_buf()])
+            .limits(limits)
+            .build()
+            .run()
+            .unwrap()
+    };
+
+    let result = run(ScanLimits::default());
+    assert_eq!(result.groups.len(), 2);
+
+    let result = run(ScanLimits {
+        min_dimension: Some(100),
+        ..Default::default()
+    });
+    assert_eq!(result.total_photos, 4);
+    assert_eq!(result.groups.len(), 1);
+    assert!(result.groups[0].photos[0].ends_with("photo.png"));
+
+    let icon_size = std::fs::metadata(temp_dir.path().join("icon.png"))
+        .unwrap()
+        .len();
+    let result = run(ScanLimits {
+        min_size: Some(icon_size + 1),
+        ..Default::default()
+    });
+    assert_eq!(result.total_photos, 2);
+    assert_eq!(result.groups.len(), 1);
+}
@@ -37,6 +37,7 @@ fn test_unorganized_scanner_integration() {
         check_date_pattern: true,
         check_generic_names: true,
         min_depth: 2,
+        ..Default::default()
     };
 
     let result = UnorganizedScanner::scan(&config, |_, _| {}).unwrap();
@@ -1,9 +1,9 @@
 import { motion } from 'framer-motion'
-import { Cpu, Zap, Target, Shield, ChevronDown } from 'lucide-react'
+import { Cpu, Zap, Target, Shield, Crop, ChevronDown } from 'lucide-react'
 import { useState, useRef, useEffect } from 'react'
 import { Tooltip } from './Tooltip'
 
-export type Algorithm = 'average' | 'difference' | 'perceptual' | 'fusion'
+export type Algorithm = 'average' | 'difference' | 'perceptual' | 'fusion' | 'crop'
 
 interface AlgorithmSelectorProps {
     algorithm: Algorithm
@@ -57,6 +57,15 @@ const algorithms: AlgorithmOption[] = [
         color: 'text-emerald-400',
         bgColor: 'bg-emerald-400/10 border-emerald-400/30',
     },
+    {
+        id: 'crop',
+        name: 'Crops',
+        shortDesc: 'Cropped copies',
+        tooltip: 'Crop-Resistant Hash — Also finds cropped copies (social media crops, phone edits) and shows which part of the original they cover.',
+        icon: Crop,
+        color: 'text-orange-400',
+        bgColor: 'bg-orange-400/10 border-orange-400/30',
+    },
 ]
 
 export function AlgorithmSelector({ algorithm, onAlgorithmChange }: AlgorithmSelectorProps) {
@@ -140,6 +140,9 @@ export function DuplicateGroupCard({
           <h3 className="text-white text-lg font-bold tracking-tight truncate group-hover/card:text-brand-primary transition-colors">
             {getFileName(group.representative)}
           </h3>
+          <p className="text-xs text-text-muted truncate" title={group.explanation}>
+            {group.summary}
+          </p>
         </div>
 
         <div className="flex items-center gap-6">
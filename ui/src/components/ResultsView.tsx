@@ -79,7 +79,7 @@ export function ResultsView({ results, onNewScan }: ResultsViewProps) {
   const filteredAndSortedGroups = results.groups
     .filter(group => {
       // Filter by match type
-      if (filterBy === 'exact' && !(group.match_type.includes('Exact') && !group.match_type.includes('NearExact'))) return false
+      if (filterBy === 'exact' && !(group.match_type === 'Hardlinked' || group.match_type === 'ByteIdentical' || group.match_type === 'SamePixelsDifferentMetadata' || (group.match_type.includes('Exact') && !group.match_type.includes('NearExact')))) return false
       if (filterBy === 'near' && !group.match_type.includes('NearExact')) return false
       if (filterBy === 'similar' && !group.match_type.includes('Similar')) return false
 
@@ -92,9 +92,9 @@ export function ResultsView({ results, onNewScan }: ResultsViewProps) {
       if (sortBy === 'size') return b.duplicate_size_bytes - a.duplicate_size_bytes
       if (sortBy === 'photos') return b.photos.length - a.photos.length
       if (sortBy === 'type') {
-        const typeOrder = { 'Exact': 0, 'NearExact': 1, 'Similar': 2 }
-        const aOrder = typeOrder[a.match_type as keyof typeof typeOrder] ?? 3
-        const bOrder = typeOrder[b.match_type as keyof typeof typeOrder] ?? 3
+        const typeOrder = { 'Hardlinked': 0, 'ByteIdentical': 1, 'SamePixelsDifferentMetadata': 2, 'Exact': 3, 'NearExact': 4, 'Similar': 5 }
+        const aOrder = typeOrder[a.match_type as keyof typeof typeOrder] ?? 5
+        const bOrder = typeOrder[b.match_type as keyof typeof typeOrder] ?? 5
         return aOrder - bOrder
       }
       return 0
@@ -122,6 +122,7 @@ export function ScanProfiles({
         average: 'aHash',
         perceptual: 'pHash',
         fusion: 'Fusion',
+        crop: 'Crops',
     }
 
     return (
@@ -215,6 +215,7 @@ export function SettingsModal({ isOpen, onClose, onCacheCleared }: SettingsModal
                               <option value="average">Average</option>
                               <option value="perceptual">Perceptual (Accurate)</option>
                               <option value="fusion">Fusion (Best)</option>
+                              <option value="crop">Crop-Resistant</option>
                             </select>
                           </div>
                         </div>
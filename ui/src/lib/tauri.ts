@@ -86,7 +86,8 @@ export async function invoke<T>(command: string, args?: Record<string, unknown>)
           representative: '/mock/path/photo1.jpg',
           match_type: 'Exact',
           duplicate_count: 1,
-          duplicate_size_bytes: 1024 * 1024 * 4.2
+          duplicate_size_bytes: 1024 * 1024 * 4.2,
+          summary: 'These photos are identical'
         },
         {
           id: '2',
@@ -94,7 +95,8 @@ export async function invoke<T>(command: string, args?: Record<string, unknown>)
           representative: '/mock/path/img01.png',
           match_type: 'NearExact (99%)',
           duplicate_count: 2,
-          duplicate_size_bytes: 1024 * 1024 * 8.5
+          duplicate_size_bytes: 1024 * 1024 * 8.5,
+          summary: 'These photos are virtually identical (99% match)'
         },
         {
           id: '3',
@@ -102,7 +104,8 @@ export async function invoke<T>(command: string, args?: Record<string, unknown>)
           representative: '/mock/path/sunset.jpg',
           match_type: 'Similar (85%)',
           duplicate_count: 1,
-          duplicate_size_bytes: 1024 * 1024 * 3.1
+          duplicate_size_bytes: 1024 * 1024 * 3.1,
+          summary: 'These photos are very similar (85% match) - likely the same photo with different edits'
         }
       ],
       errors: []
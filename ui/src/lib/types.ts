@@ -17,8 +17,14 @@ export interface DuplicateGroup {
   photos: string[]
   representative: string
   match_type: string
+  confidence?: string | null
+  max_distance?: number | null
+  transform?: string | null
+  crop?: string | null
   duplicate_count: number
   duplicate_size_bytes: number
+  summary: string
+  explanation: string
 }
 
 export interface ScanProgress {